@@ -1,3 +1,5 @@
 fn main() {
+    // Migrations are embedded at compile time by `sqlx::migrate!`
+    println!("cargo:rerun-if-changed=migrations");
     tauri_build::build()
 }
@@ -0,0 +1,137 @@
+-- Baseline schema. Databases created before versioned migrations are brought up
+-- to this layout by Repository::upgrade_legacy_layout before it runs.
+
+-- Account configurations
+CREATE TABLE IF NOT EXISTS accounts (
+    id TEXT PRIMARY KEY,
+    provider TEXT NOT NULL,
+    name TEXT NOT NULL,
+    credentials_encrypted BLOB NOT NULL,
+    created_at INTEGER NOT NULL,
+    last_synced INTEGER,
+    needs_reauth INTEGER NOT NULL DEFAULT 0
+);
+
+-- Historical quota snapshots (account-level aggregates)
+CREATE TABLE IF NOT EXISTS quota_snapshots (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    account_id TEXT NOT NULL,
+    timestamp INTEGER NOT NULL,
+    tokens_input INTEGER,
+    tokens_output INTEGER,
+    cost_usd REAL,
+    quota_limit INTEGER,
+    quota_remaining INTEGER,
+    metadata TEXT,
+    rpm_limit INTEGER,
+    rpm_remaining INTEGER,
+    tpm_limit INTEGER,
+    tpm_remaining INTEGER,
+    requests_limit INTEGER,
+    requests_remaining INTEGER,
+    requests_reset_at INTEGER,
+    rpm_reset_at INTEGER,
+    tpm_reset_at INTEGER,
+    images_generated INTEGER,
+    audio_seconds REAL,
+    video_seconds REAL,
+    FOREIGN KEY (account_id) REFERENCES accounts(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_quota_snapshots_account_timestamp
+ON quota_snapshots(account_id, timestamp DESC);
+
+-- Per-model usage tracking
+CREATE TABLE IF NOT EXISTS model_usage (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    account_id TEXT NOT NULL,
+    model_name TEXT NOT NULL,
+    timestamp INTEGER NOT NULL,
+    tokens_input INTEGER DEFAULT 0,
+    tokens_output INTEGER DEFAULT 0,
+    cost_usd REAL DEFAULT 0.0,
+    request_count INTEGER DEFAULT 0,
+    tokens_cached_input INTEGER DEFAULT 0,
+    tokens_reasoning INTEGER DEFAULT 0,
+    images_generated INTEGER,
+    audio_seconds REAL,
+    video_seconds REAL,
+    FOREIGN KEY (account_id) REFERENCES accounts(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_model_usage_account_timestamp
+ON model_usage(account_id, timestamp DESC);
+
+CREATE INDEX IF NOT EXISTS idx_model_usage_model_name
+ON model_usage(model_name);
+
+-- Per-API-key usage tracking
+CREATE TABLE IF NOT EXISTS api_key_usage (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    account_id TEXT NOT NULL,
+    api_key_id TEXT NOT NULL,
+    timestamp INTEGER NOT NULL,
+    tokens_input INTEGER DEFAULT 0,
+    tokens_output INTEGER DEFAULT 0,
+    cost_usd REAL DEFAULT 0.0,
+    request_count INTEGER DEFAULT 0,
+    FOREIGN KEY (account_id) REFERENCES accounts(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_api_key_usage_account_timestamp
+ON api_key_usage(account_id, timestamp DESC);
+
+-- Notification state tracking
+CREATE TABLE IF NOT EXISTS notification_state (
+    account_id TEXT PRIMARY KEY,
+    last_75_percent_notified INTEGER,
+    last_90_percent_notified INTEGER,
+    last_95_percent_notified INTEGER,
+    FOREIGN KEY (account_id) REFERENCES accounts(id) ON DELETE CASCADE
+);
+
+-- Per-account settings (lookback window, billing timezone, ...)
+CREATE TABLE IF NOT EXISTS account_settings (
+    account_id TEXT NOT NULL,
+    key TEXT NOT NULL,
+    value TEXT NOT NULL,
+    PRIMARY KEY (account_id, key),
+    FOREIGN KEY (account_id) REFERENCES accounts(id) ON DELETE CASCADE
+);
+
+-- User pricing overrides, applied on top of the bundled/remote pricing table
+CREATE TABLE IF NOT EXISTS pricing_overrides (
+    provider TEXT NOT NULL,
+    model TEXT NOT NULL,
+    input_price REAL NOT NULL DEFAULT 0,
+    output_price REAL NOT NULL DEFAULT 0,
+    per_minute_price REAL NOT NULL DEFAULT 0,
+    PRIMARY KEY (provider, model)
+);
+
+-- Application settings
+CREATE TABLE IF NOT EXISTS settings (
+    key TEXT PRIMARY KEY,
+    value TEXT NOT NULL
+);
+
+-- Insert default settings
+INSERT OR IGNORE INTO settings (key, value) VALUES
+    ('refresh_interval_seconds', '300'),
+    ('notifications_enabled', 'true'),
+    ('threshold_75_enabled', 'true'),
+    ('threshold_90_enabled', 'true'),
+    ('threshold_95_enabled', 'true'),
+    ('quiet_hours_start', ''),
+    ('quiet_hours_end', ''),
+    ('data_retention_days', '90'),
+    ('retry_max_attempts', '3'),
+    ('connect_timeout_seconds', '10'),
+    ('read_timeout_seconds', '60'),
+    ('pricing_url', '');
+
+-- Current schema version, derived from the migrations sqlx has applied. A view
+-- rather than a table: sqlx already records every applied migration in
+-- _sqlx_migrations, and a separate table would need each migration to keep it in step.
+CREATE VIEW IF NOT EXISTS schema_version AS
+SELECT COALESCE(MAX(version), 0) AS version FROM _sqlx_migrations WHERE success = 1;
@@ -0,0 +1,17 @@
+-- Hourly and daily aggregates of quota_snapshots, so long-range history doesn't
+-- need every 5-minute snapshot. Values are those of the last snapshot in the bucket.
+CREATE TABLE IF NOT EXISTS usage_rollups (
+    account_id TEXT NOT NULL,
+    granularity TEXT NOT NULL CHECK (granularity IN ('hour', 'day')),
+    bucket_start INTEGER NOT NULL,
+    sample_count INTEGER NOT NULL,
+    tokens_input INTEGER,
+    tokens_output INTEGER,
+    cost_usd REAL,
+    quota_limit INTEGER,
+    quota_remaining INTEGER,
+    -- Lowest remaining quota seen during the bucket
+    min_quota_remaining INTEGER,
+    PRIMARY KEY (account_id, granularity, bucket_start),
+    FOREIGN KEY (account_id) REFERENCES accounts(id) ON DELETE CASCADE
+);
@@ -0,0 +1,20 @@
+-- Per-account history lookups. These shipped with the baseline schema but are
+-- restated so the query planner can rely on them regardless of install age.
+CREATE INDEX IF NOT EXISTS idx_quota_snapshots_account_timestamp
+ON quota_snapshots(account_id, timestamp DESC);
+
+CREATE INDEX IF NOT EXISTS idx_model_usage_account_timestamp
+ON model_usage(account_id, timestamp DESC);
+
+-- Range scans across all accounts: rollups, dashboard summaries, export and cleanup
+CREATE INDEX IF NOT EXISTS idx_quota_snapshots_timestamp
+ON quota_snapshots(timestamp);
+
+CREATE INDEX IF NOT EXISTS idx_model_usage_timestamp
+ON model_usage(timestamp);
+
+CREATE INDEX IF NOT EXISTS idx_api_key_usage_timestamp
+ON api_key_usage(timestamp);
+
+-- Give the planner statistics for the new indices
+ANALYZE;
@@ -0,0 +1,21 @@
+-- User-defined spend budgets, alerting even for providers without native limits
+CREATE TABLE IF NOT EXISTS budgets (
+    id TEXT PRIMARY KEY,
+    name TEXT NOT NULL,
+    scope TEXT NOT NULL CHECK (scope IN ('global', 'account', 'provider', 'model')),
+    -- Account id, provider id or model name; NULL for global budgets
+    target TEXT,
+    amount_usd REAL NOT NULL,
+    period TEXT NOT NULL CHECK (period IN ('daily', 'weekly', 'monthly')),
+    -- Periods are counted from this unix timestamp, e.g. a billing cycle start
+    reset_anchor INTEGER NOT NULL,
+    created_at INTEGER NOT NULL
+);
+
+-- Highest threshold already alerted for a budget in its current period
+CREATE TABLE IF NOT EXISTS budget_alerts (
+    budget_id TEXT PRIMARY KEY,
+    period_start INTEGER NOT NULL,
+    percent INTEGER NOT NULL,
+    FOREIGN KEY (budget_id) REFERENCES budgets(id) ON DELETE CASCADE
+);
@@ -0,0 +1,10 @@
+-- Free-form labels for attributing spend, e.g. "work", "personal", "client-x"
+CREATE TABLE IF NOT EXISTS account_tags (
+    account_id TEXT NOT NULL,
+    tag TEXT NOT NULL,
+    PRIMARY KEY (account_id, tag),
+    FOREIGN KEY (account_id) REFERENCES accounts(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_account_tags_tag
+ON account_tags(tag);
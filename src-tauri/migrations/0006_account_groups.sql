@@ -0,0 +1,19 @@
+-- Named groups of accounts, summarised and alerted on as a whole
+CREATE TABLE IF NOT EXISTS account_groups (
+    id TEXT PRIMARY KEY,
+    name TEXT NOT NULL UNIQUE,
+    -- Alert when the members' combined quota usage reaches this percentage
+    quota_threshold_percent REAL,
+    -- Alert when the members' combined current cost reaches this amount
+    cost_threshold_usd REAL,
+    last_notified_at INTEGER,
+    created_at INTEGER NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS account_group_members (
+    group_id TEXT NOT NULL,
+    account_id TEXT NOT NULL,
+    PRIMARY KEY (group_id, account_id),
+    FOREIGN KEY (group_id) REFERENCES account_groups(id) ON DELETE CASCADE,
+    FOREIGN KEY (account_id) REFERENCES accounts(id) ON DELETE CASCADE
+);
@@ -0,0 +1,15 @@
+-- Every notification the notifier has sent, so missed desktop toasts can be reviewed in-app
+CREATE TABLE IF NOT EXISTS notifications (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    -- NULL for budget and group alerts, which span several accounts
+    account_id TEXT,
+    -- Percentage that triggered the alert, where there is one
+    threshold REAL,
+    title TEXT NOT NULL,
+    message TEXT NOT NULL,
+    timestamp INTEGER NOT NULL,
+    acknowledged INTEGER NOT NULL DEFAULT 0,
+    FOREIGN KEY (account_id) REFERENCES accounts(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_notifications_timestamp ON notifications(timestamp DESC);
@@ -0,0 +1,14 @@
+-- Failed fetches per account, for diagnosing accounts that have silently gone stale
+CREATE TABLE IF NOT EXISTS fetch_errors (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    account_id TEXT NOT NULL,
+    provider TEXT NOT NULL,
+    -- See `QuonitorError::kind`
+    error_kind TEXT NOT NULL,
+    http_status INTEGER,
+    message TEXT NOT NULL,
+    timestamp INTEGER NOT NULL,
+    FOREIGN KEY (account_id) REFERENCES accounts(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_fetch_errors_account_timestamp ON fetch_errors(account_id, timestamp DESC);
@@ -0,0 +1,22 @@
+-- Settings are now parsed into a typed struct (services/settings.rs). Normalise
+-- values written by the old free-form setter so they parse cleanly.
+UPDATE settings SET value = TRIM(value);
+
+UPDATE settings SET value = 'true'
+WHERE key IN ('notifications_enabled', 'threshold_75_enabled', 'threshold_90_enabled', 'threshold_95_enabled')
+  AND LOWER(value) IN ('1', 'yes', 'on', 'true');
+
+UPDATE settings SET value = 'false'
+WHERE key IN ('notifications_enabled', 'threshold_75_enabled', 'threshold_90_enabled', 'threshold_95_enabled')
+  AND LOWER(value) IN ('0', 'no', 'off', 'false');
+
+-- Unset optional settings are now absent rather than empty
+DELETE FROM settings WHERE key IN ('quiet_hours_start', 'quiet_hours_end', 'pricing_url') AND value = '';
+
+-- Quiet hours used to be read by hour only; pad bare hours ("22") to HH:MM
+UPDATE settings SET value = printf('%02d:00', CAST(value AS INTEGER))
+WHERE key IN ('quiet_hours_start', 'quiet_hours_end')
+  AND value NOT LIKE '%:%' AND value GLOB '[0-9]*' AND CAST(value AS INTEGER) BETWEEN 0 AND 23;
+
+UPDATE settings SET value = printf('%02d:%s', CAST(substr(value, 1, instr(value, ':') - 1) AS INTEGER), substr(value, instr(value, ':') + 1))
+WHERE key IN ('quiet_hours_start', 'quiet_hours_end') AND instr(value, ':') = 2;
@@ -0,0 +1,8 @@
+-- Each database maintenance run, for the weekly schedule and the settings screen
+CREATE TABLE IF NOT EXISTS maintenance_log (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    ran_at INTEGER NOT NULL,
+    integrity_ok INTEGER NOT NULL,
+    size_before INTEGER NOT NULL,
+    size_after INTEGER NOT NULL
+);
@@ -0,0 +1,13 @@
+-- The single data_retention_days setting is split per data type (services/settings.rs);
+-- carry an existing value over to the two kinds of raw history it used to cover
+INSERT OR IGNORE INTO settings (key, value)
+SELECT 'retention_snapshot_days', MAX(CAST(value AS INTEGER), 32) FROM settings
+WHERE key = 'data_retention_days' AND CAST(value AS INTEGER) > 0;
+
+INSERT OR IGNORE INTO settings (key, value)
+SELECT 'retention_model_usage_days', CAST(value AS INTEGER) FROM settings
+WHERE key = 'data_retention_days' AND CAST(value AS INTEGER) > 0;
+
+DELETE FROM settings WHERE key = 'data_retention_days';
+
+CREATE INDEX IF NOT EXISTS idx_usage_rollups_bucket_start ON usage_rollups(bucket_start);
@@ -0,0 +1,35 @@
+-- Usage consumed between consecutive snapshots of an account. Providers report running
+-- totals that reset each period, so a drop means a reset and the delta is the new total.
+CREATE TABLE IF NOT EXISTS usage_deltas (
+    snapshot_id INTEGER PRIMARY KEY,
+    account_id TEXT NOT NULL,
+    timestamp INTEGER NOT NULL,
+    interval_seconds INTEGER NOT NULL,
+    tokens_input INTEGER,
+    tokens_output INTEGER,
+    cost_usd REAL,
+    FOREIGN KEY (snapshot_id) REFERENCES quota_snapshots(id) ON DELETE CASCADE,
+    FOREIGN KEY (account_id) REFERENCES accounts(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_usage_deltas_account_timestamp ON usage_deltas(account_id, timestamp);
+
+-- Backfill from existing history
+INSERT OR IGNORE INTO usage_deltas (snapshot_id, account_id, timestamp, interval_seconds, tokens_input, tokens_output, cost_usd)
+SELECT id, account_id, timestamp, timestamp - prev_timestamp,
+       CASE WHEN tokens_input IS NULL OR prev_input IS NULL THEN NULL
+            WHEN tokens_input >= prev_input THEN tokens_input - prev_input ELSE tokens_input END,
+       CASE WHEN tokens_output IS NULL OR prev_output IS NULL THEN NULL
+            WHEN tokens_output >= prev_output THEN tokens_output - prev_output ELSE tokens_output END,
+       CASE WHEN cost_usd IS NULL OR prev_cost IS NULL THEN NULL
+            WHEN cost_usd >= prev_cost THEN cost_usd - prev_cost ELSE cost_usd END
+FROM (
+    SELECT id, account_id, timestamp, tokens_input, tokens_output, cost_usd,
+           LAG(timestamp) OVER w AS prev_timestamp,
+           LAG(tokens_input) OVER w AS prev_input,
+           LAG(tokens_output) OVER w AS prev_output,
+           LAG(cost_usd) OVER w AS prev_cost
+    FROM quota_snapshots
+    WINDOW w AS (PARTITION BY account_id ORDER BY timestamp, id)
+)
+WHERE prev_timestamp IS NOT NULL;
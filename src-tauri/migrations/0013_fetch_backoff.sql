@@ -0,0 +1,11 @@
+-- Consecutive fetch failures per account. Scheduled fetches skip the account until
+-- `next_attempt_at`, which backs off exponentially and, once the circuit breaker has
+-- tripped, stays a full cool-down away. A successful fetch removes the row.
+CREATE TABLE IF NOT EXISTS fetch_backoff (
+    account_id TEXT PRIMARY KEY,
+    consecutive_failures INTEGER NOT NULL,
+    next_attempt_at INTEGER NOT NULL,
+    -- When the circuit breaker tripped; NULL while it's closed
+    tripped_at INTEGER,
+    FOREIGN KEY (account_id) REFERENCES accounts(id) ON DELETE CASCADE
+);
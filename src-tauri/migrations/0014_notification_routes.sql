@@ -0,0 +1,16 @@
+-- Routing rules deciding which channels an alert goes to. An alert goes to the union of
+-- the channels of every rule it matches, or to every channel when it matches none.
+CREATE TABLE IF NOT EXISTS notification_routes (
+    id TEXT PRIMARY KEY,
+    name TEXT NOT NULL,
+    -- Inclusive bounds on the alert's threshold percentage; NULL leaves it open
+    min_threshold REAL,
+    max_threshold REAL,
+    -- The alert's account, or an account carrying this tag; NULL matches any
+    account_id TEXT,
+    account_tag TEXT,
+    -- Comma separated: desktop, webhook, email, push
+    channels TEXT NOT NULL,
+    created_at INTEGER NOT NULL,
+    FOREIGN KEY (account_id) REFERENCES accounts(id) ON DELETE CASCADE
+);
@@ -0,0 +1,14 @@
+-- Per-account notification preferences; accounts without a row use the defaults
+CREATE TABLE IF NOT EXISTS account_notification_prefs (
+    account_id TEXT PRIMARY KEY,
+    -- 0 silences every alert about the account
+    enabled INTEGER NOT NULL DEFAULT 1,
+    -- Usage percentages for the warning, caution and critical alerts in place of
+    -- 75, 90 and 95; NULL keeps the default
+    warning_percent REAL,
+    caution_percent REAL,
+    critical_percent REAL,
+    -- Comma separated channels used instead of the routing rules; NULL leaves it to them
+    channels TEXT,
+    FOREIGN KEY (account_id) REFERENCES accounts(id) ON DELETE CASCADE
+);
@@ -0,0 +1,2 @@
+-- Alerts about the account are held back until this unix timestamp; NULL when not snoozed
+ALTER TABLE account_notification_prefs ADD COLUMN snoozed_until INTEGER;
@@ -0,0 +1,6 @@
+-- Period in which a budget's projected overrun was last alerted, once per period
+CREATE TABLE IF NOT EXISTS budget_forecast_alerts (
+    budget_id TEXT PRIMARY KEY,
+    period_start INTEGER NOT NULL,
+    FOREIGN KEY (budget_id) REFERENCES budgets(id) ON DELETE CASCADE
+);
@@ -0,0 +1,3 @@
+-- Spend per hour above which the account is alerted on, in place of the global setting;
+-- NULL keeps it, 0 turns it off for the account
+ALTER TABLE account_notification_prefs ADD COLUMN burn_rate_usd_per_hour REAL;
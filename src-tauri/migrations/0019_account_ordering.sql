@@ -0,0 +1,10 @@
+-- Position in the account list, lowest first, and whether the account is pinned above
+-- the others. Existing accounts keep the order they were added in.
+ALTER TABLE accounts ADD COLUMN sort_order INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE accounts ADD COLUMN favorite INTEGER NOT NULL DEFAULT 0;
+
+UPDATE accounts SET sort_order = (
+    SELECT COUNT(*) FROM accounts earlier
+    WHERE earlier.created_at < accounts.created_at
+       OR (earlier.created_at = accounts.created_at AND earlier.id < accounts.id)
+);
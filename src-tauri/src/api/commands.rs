@@ -1,21 +1,56 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
 use std::sync::Arc;
 use chrono::Utc;
 use uuid::Uuid;
-use tauri::State;
+use tauri::{AppHandle, State};
+use tauri_plugin_dialog::DialogExt;
 use serde::{Deserialize, Serialize};
 
-use crate::db::{Repository, Account, Credentials, QuotaSnapshot, ModelUsage};
-use crate::services::{Aggregator, Cache, Scheduler};
-use crate::crypto::CryptoService;
-use crate::providers::QuotaData;
+use crate::db::cipher::{self, EncryptionState};
+use crate::db::profiles::{self, Profile, ProfileList};
+use crate::db::location::{self, DataLocation, LocationSource};
+use crate::db::{Repository, Account, Credentials, QuotaSnapshot, ModelUsage, ApiKeyUsage, PricingOverride, UsageSummary, Budget, TagSpend, AccountGroup, GroupSummary, NotificationRecord, FetchError, FetchBackoff, UsageDelta, NotificationRoute, AccountNotificationPrefs, AccountUpdate};
+use crate::services::{Aggregator, Cache, Connectivity, Scheduler, PricingService, RollupService, SnapshotService, MaintenanceService, Settings};
+use crate::services::cache::{AccountStatus, CacheEntry, QuotaSummary};
+use crate::services::maintenance::MaintenanceReport;
+use crate::services::snapshots::SnapshotInfo;
+use crate::services::rollup::HistoryPage;
+use crate::services::backup;
+use crate::services::schedule;
+use crate::services::{email, webhook};
+use crate::services::push;
+use crate::services::routing;
+use crate::services::notifier::Alert;
+use crate::services::connectivity::ConnectivityStatus;
+use crate::services::credentials::{self, AccountImport, CredentialImport};
+use crate::services::budgets::{self, BudgetStatus};
+use crate::services::export::usage_csv;
+use crate::services::report;
+use crate::services::import::{self, ColumnMapping, ImportFormat, ImportSummary};
+use crate::crypto::{CryptoService, KeyStorage};
+use crate::os_auth;
+use crate::providers::{QuotaData, ProviderInfo, ProviderRegistry};
+use crate::providers::status::ProviderStatus;
+use crate::providers::google::{GoogleProvider, GoogleAuthConfig};
 use crate::error::{QuonitorError, Result};
 
 pub struct AppState {
     pub repo: Arc<Repository>,
     pub aggregator: Arc<Aggregator>,
+    pub providers: Arc<ProviderRegistry>,
     pub cache: Arc<Cache>,
     pub scheduler: Arc<Scheduler>,
     pub crypto: Arc<CryptoService>,
+    pub pricing: Arc<PricingService>,
+    pub rollups: Arc<RollupService>,
+    pub snapshots: Arc<SnapshotService>,
+    pub maintenance: Arc<MaintenanceService>,
+    pub connectivity: Arc<Connectivity>,
+    pub data_dir: PathBuf,
+    pub data_source: LocationSource,
+    // Directory of the active profile's database
+    pub profile_dir: PathBuf,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -25,6 +60,61 @@ pub struct AddAccountRequest {
     pub credentials: Credentials,
 }
 
+// Outcome of `test_credentials`; a failure is reported here rather than as an error
+#[derive(Debug, Serialize)]
+pub struct CredentialTest {
+    pub success: bool,
+    // `QuonitorError::kind` and the provider's HTTP status, when it failed
+    pub error_kind: Option<String>,
+    pub http_status: Option<u16>,
+    pub message: Option<String>,
+    // What the provider returned, when it succeeded
+    pub quota: Option<QuotaData>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct LockState {
+    // A master password protects the credentials
+    pub enabled: bool,
+    // Credentials can't be decrypted, so nothing is fetched
+    pub locked: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BudgetRequest {
+    // Omit to create a new budget
+    pub id: Option<String>,
+    pub name: String,
+    pub scope: String,
+    pub target: Option<String>,
+    pub amount_usd: f64,
+    pub period: String,
+    // Defaults to the start of the current UTC day
+    pub reset_anchor: Option<i64>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct NotificationRouteRequest {
+    // Omit to create a new rule
+    pub id: Option<String>,
+    pub name: String,
+    pub min_threshold: Option<f64>,
+    pub max_threshold: Option<f64>,
+    pub account_id: Option<String>,
+    pub account_tag: Option<String>,
+    pub channels: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AccountGroupRequest {
+    // Omit to create a new group
+    pub id: Option<String>,
+    pub name: String,
+    pub account_ids: Vec<String>,
+    pub quota_threshold_percent: Option<f64>,
+    pub cost_threshold_usd: Option<f64>,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct AccountResponse {
     pub id: String,
@@ -32,6 +122,9 @@ pub struct AccountResponse {
     pub name: String,
     pub created_at: i64,
     pub last_synced: Option<i64>,
+    pub needs_reauth: bool,
+    pub sort_order: i64,
+    pub favorite: bool,
 }
 
 impl From<Account> for AccountResponse {
@@ -42,6 +135,9 @@ impl From<Account> for AccountResponse {
             name: account.name,
             created_at: account.created_at,
             last_synced: account.last_synced,
+            needs_reauth: account.needs_reauth,
+            sort_order: account.sort_order,
+            favorite: account.favorite,
         }
     }
 }
@@ -56,7 +152,7 @@ pub async fn add_account(
     let initial_quota = state.aggregator.validate_credentials(&request.provider, &request.credentials).await?;
 
     let creds_json = serde_json::to_string(&request.credentials)?;
-    let encrypted_creds = state.crypto.encrypt(&creds_json)?;
+    let encrypted_creds = state.crypto.encrypt(&account_id, &request.provider, &creds_json)?;
 
     let account = Account {
         id: account_id.clone(),
@@ -65,6 +161,9 @@ pub async fn add_account(
         credentials_encrypted: encrypted_creds,
         created_at: Utc::now().timestamp(),
         last_synced: None,
+        needs_reauth: false,
+        sort_order: 0,
+        favorite: false,
     };
 
     state.repo.insert_account(&account).await
@@ -75,21 +174,71 @@ pub async fn add_account(
     
     state.cache.set(account_id.clone(), quota_to_store).await;
 
+    fetch_in_background(&state, &account_id, "new account");
+
+    Ok(AccountResponse::from(account))
+}
+
+// Fetches the account with its own settings and caches the result, without holding up
+// the command
+fn fetch_in_background(state: &AppState, account_id: &str, what: &'static str) {
     tokio::spawn({
         let aggregator = state.aggregator.clone();
         let cache = state.cache.clone();
-        let id = account_id.clone();
+        let id = account_id.to_string();
         async move {
             match aggregator.fetch_account_quota(&id).await {
                 Ok(quota) => {
                     cache.set(quota.account_id.clone(), quota).await;
                 }
                 Err(e) => {
-                    tracing::error!("Failed to fetch quota for new account: {}", e);
+                    tracing::error!("Failed to fetch quota for {}: {}", what, e);
                 }
             }
         }
     });
+}
+
+// Adds an account with a copy of another's provider and credentials, to track the same
+// key under different settings or budgets. Nothing else is copied.
+#[tauri::command]
+pub async fn duplicate_account(
+    account_id: String,
+    new_name: String,
+    state: State<'_, AppState>,
+) -> Result<AccountResponse> {
+    let name = new_name.trim().to_string();
+    if name.is_empty() {
+        return Err(QuonitorError::Config("Account name is required".to_string()));
+    }
+
+    let source = state.repo.get_account(&account_id).await
+        .map_err(|e| QuonitorError::Database(e))?
+        .ok_or_else(|| QuonitorError::Config(format!("Account {} not found", account_id)))?;
+
+    // Credentials are bound to their account, so they're encrypted afresh for the copy
+    let creds_json = state.crypto.decrypt(&source.id, &source.provider, &source.credentials_encrypted)?;
+    let new_id = Uuid::new_v4().to_string();
+    let credentials_encrypted = state.crypto.encrypt(&new_id, &source.provider, &creds_json)?;
+
+    let account = Account {
+        id: new_id.clone(),
+        provider: source.provider,
+        name,
+        credentials_encrypted,
+        created_at: Utc::now().timestamp(),
+        last_synced: None,
+        needs_reauth: source.needs_reauth,
+        sort_order: 0,
+        favorite: false,
+    };
+
+    state.repo.insert_account(&account).await
+        .map_err(|e| QuonitorError::Database(e))?;
+
+    if !account.needs_reauth {
+        fetch_in_background(&state, &new_id, "duplicated account");
+    }
 
     Ok(AccountResponse::from(account))
 }
@@ -105,6 +254,120 @@ pub async fn remove_account(
     Ok(())
 }
 
+// Runs the provider's validation without storing anything, for the add-account form
+#[tauri::command]
+pub async fn test_credentials(
+    provider: String,
+    credentials: Credentials,
+    state: State<'_, AppState>,
+) -> Result<CredentialTest> {
+    Ok(match state.aggregator.validate_credentials(&provider, &credentials).await {
+        Ok(quota) => CredentialTest {
+            success: true,
+            error_kind: None,
+            http_status: None,
+            message: None,
+            quota: Some(quota),
+        },
+        Err(e) => CredentialTest {
+            success: false,
+            error_kind: Some(e.kind().to_string()),
+            http_status: e.http_status(),
+            message: Some(e.to_string()),
+            quota: None,
+        },
+    })
+}
+
+// Swaps in rotated credentials, keeping the account's history. The new credentials
+// must work before anything is stored.
+#[tauri::command]
+pub async fn update_account_credentials(
+    account_id: String,
+    credentials: Credentials,
+    state: State<'_, AppState>,
+) -> Result<AccountResponse> {
+    let account = state.repo.get_account(&account_id).await
+        .map_err(|e| QuonitorError::Database(e))?
+        .ok_or_else(|| QuonitorError::Config(format!("Account {} not found", account_id)))?;
+
+    state.aggregator.validate_credentials(&account.provider, &credentials).await?;
+
+    let creds_json = serde_json::to_string(&credentials)?;
+    let encrypted_creds = state.crypto.encrypt(&account_id, &account.provider, &creds_json)?;
+    state.repo.update_account_credentials(&account_id, &encrypted_creds).await
+        .map_err(|e| QuonitorError::Database(e))?;
+
+    // Failures with the old credentials no longer hold off fetching
+    state.repo.clear_fetch_backoff(&account_id).await
+        .map_err(|e| QuonitorError::Database(e))?;
+
+    // Fetched again rather than caching the validation result, which ignores the
+    // account's own settings such as its lookback window
+    fetch_in_background(&state, &account_id, "updated credentials");
+
+    let account = state.repo.get_account(&account_id).await
+        .map_err(|e| QuonitorError::Database(e))?
+        .ok_or_else(|| QuonitorError::Config(format!("Account {} not found", account_id)))?;
+    Ok(AccountResponse::from(account))
+}
+
+// Renames, retags, regroups or changes settings of an account in place
+#[tauri::command]
+pub async fn update_account(
+    account_id: String,
+    mut update: AccountUpdate,
+    state: State<'_, AppState>,
+) -> Result<AccountResponse> {
+    if let Some(name) = &mut update.name {
+        *name = name.trim().to_string();
+        if name.is_empty() {
+            return Err(QuonitorError::Config("Account name is required".to_string()));
+        }
+    }
+    if let Some(tags) = &mut update.tags {
+        *tags = normalize_tags(tags);
+    }
+    if let Some(group_ids) = &mut update.group_ids {
+        let groups = state.repo.get_account_groups().await
+            .map_err(|e| QuonitorError::Database(e))?;
+        if let Some(unknown) = group_ids.iter().find(|id| !groups.iter().any(|group| &group.id == *id)) {
+            return Err(QuonitorError::Config(format!("Unknown account group: {}", unknown)));
+        }
+        group_ids.sort();
+        group_ids.dedup();
+    }
+    for (key, value) in &update.settings {
+        validate_account_setting(key, value)?;
+    }
+
+    state.repo.update_account(&account_id, &update).await
+        .map_err(|e| QuonitorError::Database(e))?;
+
+    if update.settings.contains_key(schedule::ACCOUNT_SETTING) {
+        state.scheduler.reschedule();
+    }
+
+    let account = state.repo.get_account(&account_id).await
+        .map_err(|e| QuonitorError::Database(e))?
+        .ok_or_else(|| QuonitorError::Config(format!("Account {} not found", account_id)))?;
+    Ok(AccountResponse::from(account))
+}
+
+// Saves the list order; accounts left out follow, in their current order
+#[tauri::command]
+pub async fn reorder_accounts(
+    account_ids: Vec<String>,
+    state: State<'_, AppState>,
+) -> Result<Vec<AccountResponse>> {
+    state.repo.reorder_accounts(&account_ids).await
+        .map_err(|e| QuonitorError::Database(e))?;
+
+    let accounts = state.repo.get_all_accounts().await
+        .map_err(|e| QuonitorError::Database(e))?;
+    Ok(accounts.into_iter().map(AccountResponse::from).collect())
+}
+
 #[tauri::command]
 pub async fn get_all_accounts(
     state: State<'_, AppState>,
@@ -114,27 +377,100 @@ pub async fn get_all_accounts(
     Ok(accounts.into_iter().map(AccountResponse::from).collect())
 }
 
+#[tauri::command]
+pub async fn list_providers(
+    state: State<'_, AppState>,
+) -> Result<Vec<ProviderInfo>> {
+    Ok(state.providers.list_providers())
+}
+
+#[tauri::command]
+pub async fn check_provider_status(
+    provider: String,
+    state: State<'_, AppState>,
+) -> Result<ProviderStatus> {
+    state.aggregator.check_provider_status(&provider).await
+}
+
 #[tauri::command]
 pub async fn get_all_quotas(
     state: State<'_, AppState>,
-) -> Result<Vec<QuotaData>> {
-    Ok(state.cache.get_all().await)
+) -> Result<Vec<CacheEntry>> {
+    let stale_after = stale_after(&state.repo).await?;
+    Ok(state.cache.get_all(stale_after).await)
 }
 
 #[tauri::command]
 pub async fn get_quota(
     account_id: String,
     state: State<'_, AppState>,
-) -> Result<Option<QuotaData>> {
-    Ok(state.cache.get(&account_id).await)
+) -> Result<Option<CacheEntry>> {
+    let stale_after = stale_after(&state.repo).await?;
+    Ok(state.cache.get(&account_id, stale_after).await)
+}
+
+// Recent usage deltas from memory, oldest first, for the account list's trend charts
+#[tauri::command]
+pub async fn get_sparkline(
+    account_id: String,
+    state: State<'_, AppState>,
+) -> Result<Vec<UsageDelta>> {
+    Ok(state.cache.get_sparkline(&account_id).await)
+}
+
+// Each account's own warning and critical thresholds apply
+#[tauri::command]
+pub async fn get_quota_summary(
+    state: State<'_, AppState>,
+) -> Result<QuotaSummary> {
+    let mut thresholds = HashMap::new();
+    for account in state.repo.get_all_accounts().await.map_err(|e| QuonitorError::Database(e))? {
+        let prefs = state.repo.get_account_notification_prefs(&account.id).await
+            .map_err(|e| QuonitorError::Database(e))?
+            .unwrap_or_else(|| AccountNotificationPrefs::defaults(&account.id));
+        let (warning, _, critical) = prefs.thresholds();
+        thresholds.insert(account.id, (warning, critical));
+    }
+
+    let defaults = AccountNotificationPrefs::defaults("").thresholds();
+    Ok(state.cache.get_summary(|account_id| {
+        thresholds.get(account_id).copied().unwrap_or((defaults.0, defaults.2))
+    }).await)
+}
+
+// Falls back to the stored last sync time for accounts not fetched successfully this run
+#[tauri::command]
+pub async fn get_account_status(
+    account_id: String,
+    state: State<'_, AppState>,
+) -> Result<AccountStatus> {
+    let mut status = state.cache.get_status(&account_id).await;
+    if status.last_success_at.is_none() {
+        status.last_success_at = state.repo.get_account(&account_id).await
+            .map_err(|e| QuonitorError::Database(e))?
+            .and_then(|account| account.last_synced);
+    }
+    Ok(status)
+}
+
+// Cached data is stale once two refreshes have been missed. With adaptive polling the
+// slowest interval counts, since idle accounts are fetched that rarely.
+async fn stale_after(repo: &Repository) -> Result<i64> {
+    let settings = Settings::load(repo).await?;
+    let interval = if settings.adaptive_polling {
+        settings.adaptive_max_interval_seconds
+    } else {
+        settings.refresh_interval_seconds
+    };
+    Ok(interval as i64 * 2)
 }
 
+// True when the refresh joined one already in progress instead of starting another
 #[tauri::command]
 pub async fn refresh_now(
     state: State<'_, AppState>,
-) -> Result<()> {
-    state.scheduler.run_fetch_cycle().await;
-    Ok(())
+) -> Result<bool> {
+    Ok(state.scheduler.run_fetch_cycle().await)
 }
 
 #[tauri::command]
@@ -151,10 +487,32 @@ pub async fn refresh_account(
 pub async fn get_historical_snapshots(
     account_id: String,
     days: u32,
+    page: Option<HistoryPage>,
     state: State<'_, AppState>,
 ) -> Result<Vec<QuotaSnapshot>> {
+    state.rollups.history(&account_id, days, page.unwrap_or_default()).await
+}
+
+#[tauri::command]
+pub async fn get_usage_deltas(
+    account_id: String,
+    days: u32,
+    state: State<'_, AppState>,
+) -> Result<Vec<UsageDelta>> {
     let since = Utc::now().timestamp() - (days as i64 * 86400);
-    state.repo.get_snapshots_since(&account_id, since).await
+    state.repo.get_usage_deltas(&account_id, since).await
+        .map_err(|e| QuonitorError::Database(e))
+}
+
+#[tauri::command]
+pub async fn get_dashboard_summary(
+    account_ids: Vec<String>,
+    days: u32,
+    state: State<'_, AppState>,
+) -> Result<UsageSummary> {
+    let until = Utc::now().timestamp();
+    let since = until - (days as i64 * 86400);
+    state.repo.get_usage_summary(&account_ids, since, until).await
         .map_err(|e| QuonitorError::Database(e))
 }
 
@@ -162,46 +520,1024 @@ pub async fn get_historical_snapshots(
 pub async fn get_model_usage_history(
     account_id: String,
     days: u32,
+    page: Option<HistoryPage>,
     state: State<'_, AppState>,
 ) -> Result<Vec<ModelUsage>> {
     let since = Utc::now().timestamp() - (days as i64 * 86400);
-    state.repo.get_model_usage_since(&account_id, since).await
-        .map_err(|e| QuonitorError::Database(e))
+    let page = page.unwrap_or_default();
+    let (limit, offset) = page.query_bounds();
+    let usage = state.repo.get_model_usage_since(&account_id, since, limit, offset).await
+        .map_err(|e| QuonitorError::Database(e))?;
+    Ok(page.finish(usage, |u| u.model_name.as_str(), |u| u.timestamp))
 }
 
 #[tauri::command]
-pub async fn get_setting(
-    key: String,
+pub async fn get_api_key_usage_history(
+    account_id: String,
+    days: u32,
+    page: Option<HistoryPage>,
+    state: State<'_, AppState>,
+) -> Result<Vec<ApiKeyUsage>> {
+    let since = Utc::now().timestamp() - (days as i64 * 86400);
+    let page = page.unwrap_or_default();
+    let (limit, offset) = page.query_bounds();
+    let usage = state.repo.get_api_key_usage_since(&account_id, since, limit, offset).await
+        .map_err(|e| QuonitorError::Database(e))?;
+    Ok(page.finish(usage, |u| u.api_key_id.as_str(), |u| u.timestamp))
+}
+
+// Asks where to save, then writes snapshots and model usage for the range as CSV.
+// Returns the written path, or None if the save dialog was cancelled.
+#[tauri::command]
+pub async fn export_usage_csv(
+    account_ids: Vec<String>,
+    start: i64,
+    end: i64,
+    app: AppHandle,
     state: State<'_, AppState>,
 ) -> Result<Option<String>> {
-    state.repo.get_setting(&key).await
-        .map_err(|e| QuonitorError::Database(e))
+    if end <= start {
+        return Err(QuonitorError::Config("Export range end must be after its start".to_string()));
+    }
+
+    let rows = state.repo.get_usage_export(&account_ids, start, end).await
+        .map_err(|e| QuonitorError::Database(e))?;
+
+    let (tx, rx) = tokio::sync::oneshot::channel();
+    app.dialog()
+        .file()
+        .add_filter("CSV", &["csv"])
+        .set_file_name("quonitor-usage.csv")
+        .save_file(move |path| {
+            let _ = tx.send(path);
+        });
+
+    let Some(path) = rx.await.ok().flatten() else {
+        return Ok(None);
+    };
+    let path = path.into_path()
+        .map_err(|e| QuonitorError::Config(format!("Invalid export path: {}", e)))?;
+
+    tokio::fs::write(&path, usage_csv(&rows)).await?;
+    tracing::info!("Exported {} usage rows to {}", rows.len(), path.display());
+
+    Ok(Some(path.display().to_string()))
 }
 
+// Asks where to save, then writes the statement for `period` (a month, YYYY-MM) as JSON,
+// CSV or HTML. No accounts means every account. Returns the written path, or None if the
+// save dialog was cancelled.
 #[tauri::command]
-pub async fn set_setting(
-    key: String,
-    value: String,
+pub async fn generate_report(
+    period: String,
+    account_ids: Vec<String>,
+    format: String,
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<Option<String>> {
+    let format = report::Format::parse(&format)?;
+    let statement = report::build(&state.repo, &period, &account_ids).await?;
+    let content = report::render(&statement, format)?;
+
+    let (tx, rx) = tokio::sync::oneshot::channel();
+    app.dialog()
+        .file()
+        .add_filter("Statement", &[format.extension()])
+        .set_file_name(format!("quonitor-statement-{}.{}", statement.period, format.extension()))
+        .save_file(move |path| {
+            let _ = tx.send(path);
+        });
+
+    let Some(path) = rx.await.ok().flatten() else {
+        return Ok(None);
+    };
+    let path = path.into_path()
+        .map_err(|e| QuonitorError::Config(format!("Invalid report path: {}", e)))?;
+
+    tokio::fs::write(&path, content).await?;
+    tracing::info!("Wrote the {} statement for {} accounts to {}", statement.period, statement.accounts.len(), path.display());
+
+    Ok(Some(path.display().to_string()))
+}
+
+// Imports a provider usage export (CSV, or a JSON array of rows) picked by the user
+// into the account's history. Returns None if the user cancels.
+#[tauri::command]
+pub async fn import_usage(
+    account_id: String,
+    mapping: Option<ColumnMapping>,
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<Option<ImportSummary>> {
+    let (tx, rx) = tokio::sync::oneshot::channel();
+    app.dialog()
+        .file()
+        .add_filter("Usage export", &["csv", "json"])
+        .pick_file(move |path| {
+            let _ = tx.send(path);
+        });
+
+    let Some(path) = rx.await.ok().flatten() else {
+        return Ok(None);
+    };
+    let path = path.into_path()
+        .map_err(|e| QuonitorError::Config(format!("Invalid import path: {}", e)))?;
+
+    let format = match path.extension().and_then(|e| e.to_str()) {
+        Some(ext) if ext.eq_ignore_ascii_case("json") => ImportFormat::Json,
+        _ => ImportFormat::Csv,
+    };
+    let content = tokio::fs::read_to_string(&path).await?;
+
+    let summary = import::import_usage(
+        &state.repo,
+        &state.pricing,
+        &account_id,
+        &content,
+        format,
+        &mapping.unwrap_or_default(),
+    ).await?;
+    tracing::info!(
+        "Imported {} days of usage for {} from {}",
+        summary.days_imported, account_id, path.display()
+    );
+
+    Ok(Some(summary))
+}
+
+// Writes a backup archive to a user-chosen file; credentials are encrypted with `passphrase`
+#[tauri::command]
+pub async fn backup_database(
+    passphrase: String,
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<Option<String>> {
+    os_auth::require(&state.repo, "back up account credentials").await?;
+
+    let (tx, rx) = tokio::sync::oneshot::channel();
+    app.dialog()
+        .file()
+        .add_filter("Quonitor backup", &["qbak"])
+        .set_file_name(&format!("quonitor-{}.qbak", Utc::now().format("%Y-%m-%d")))
+        .save_file(move |path| {
+            let _ = tx.send(path);
+        });
+
+    let Some(path) = rx.await.ok().flatten() else {
+        return Ok(None);
+    };
+    let path = path.into_path()
+        .map_err(|e| QuonitorError::Config(format!("Invalid backup path: {}", e)))?;
+
+    let accounts = backup::create_backup(&state.repo, &state.crypto, &passphrase, &path).await?;
+    tracing::info!("Backed up {} accounts to {}", accounts, path.display());
+
+    Ok(Some(path.display().to_string()))
+}
+
+// Replaces all local data with a user-chosen backup archive.
+// Returns the number of restored accounts, or None if the dialog was cancelled.
+#[tauri::command]
+pub async fn restore_database(
+    passphrase: String,
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<Option<usize>> {
+    let (tx, rx) = tokio::sync::oneshot::channel();
+    app.dialog()
+        .file()
+        .add_filter("Quonitor backup", &["qbak"])
+        .pick_file(move |path| {
+            let _ = tx.send(path);
+        });
+
+    let Some(path) = rx.await.ok().flatten() else {
+        return Ok(None);
+    };
+    let path = path.into_path()
+        .map_err(|e| QuonitorError::Config(format!("Invalid backup path: {}", e)))?;
+
+    let accounts = backup::restore_backup(&state.repo, &state.crypto, &passphrase, &path).await?;
+    credentials::bind_to_accounts(&state.repo, &state.crypto).await?;
+
+    // Everything cached or loaded from the old data is stale now
+    state.cache.clear().await;
+    if let Err(e) = state.pricing.reload_overrides().await {
+        tracing::warn!("Failed to reload pricing overrides after restore: {}", e);
+    }
+    let scheduler = state.scheduler.clone();
+    tokio::spawn(async move {
+        scheduler.run_fetch_cycle().await;
+    });
+
+    Ok(Some(accounts))
+}
+
+// Seals the chosen accounts (all when omitted) and their credentials under the
+// passphrase. Returns the written path, or None if the save dialog was cancelled.
+#[tauri::command]
+pub async fn export_credentials(
+    passphrase: String,
+    account_ids: Option<Vec<String>>,
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<Option<String>> {
+    os_auth::require(&state.repo, "export account credentials").await?;
+
+    let (tx, rx) = tokio::sync::oneshot::channel();
+    app.dialog()
+        .file()
+        .add_filter("Quonitor credentials", &["qcred"])
+        .set_file_name("quonitor-accounts.qcred")
+        .save_file(move |path| {
+            let _ = tx.send(path);
+        });
+
+    let Some(path) = rx.await.ok().flatten() else {
+        return Ok(None);
+    };
+    let path = path.into_path()
+        .map_err(|e| QuonitorError::Config(format!("Invalid export path: {}", e)))?;
+
+    let account_ids = account_ids.unwrap_or_default();
+    let accounts = credentials::export_credentials(&state.repo, &state.crypto, &passphrase, &account_ids, &path).await?;
+    tracing::info!("Exported {} accounts to {}", accounts, path.display());
+
+    Ok(Some(path.display().to_string()))
+}
+
+// Adds the accounts listed in a plain JSON file picked by the user, all or none; see
+// `credentials::import_accounts`. Returns None if the file dialog was cancelled.
+#[tauri::command]
+pub async fn import_accounts(
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<Option<AccountImport>> {
+    let (tx, rx) = tokio::sync::oneshot::channel();
+    app.dialog()
+        .file()
+        .add_filter("Account list", &["json"])
+        .pick_file(move |path| {
+            let _ = tx.send(path);
+        });
+
+    let Some(path) = rx.await.ok().flatten() else {
+        return Ok(None);
+    };
+    let path = path.into_path()
+        .map_err(|e| QuonitorError::Config(format!("Invalid import path: {}", e)))?;
+
+    let json = tokio::fs::read_to_string(&path).await?;
+    let report = credentials::import_accounts(&state.repo, &state.crypto, &state.aggregator, &json).await?;
+
+    if report.imported > 0 {
+        let scheduler = state.scheduler.clone();
+        tokio::spawn(async move {
+            scheduler.run_fetch_cycle().await;
+        });
+    }
+
+    Ok(Some(report))
+}
+
+// Returns None if the file dialog was cancelled
+#[tauri::command]
+pub async fn import_credentials(
+    passphrase: String,
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<Option<CredentialImport>> {
+    let (tx, rx) = tokio::sync::oneshot::channel();
+    app.dialog()
+        .file()
+        .add_filter("Quonitor credentials", &["qcred"])
+        .pick_file(move |path| {
+            let _ = tx.send(path);
+        });
+
+    let Some(path) = rx.await.ok().flatten() else {
+        return Ok(None);
+    };
+    let path = path.into_path()
+        .map_err(|e| QuonitorError::Config(format!("Invalid import path: {}", e)))?;
+
+    let summary = credentials::import_credentials(&state.repo, &state.crypto, &passphrase, &path).await?;
+
+    if summary.imported > 0 {
+        let scheduler = state.scheduler.clone();
+        tokio::spawn(async move {
+            scheduler.run_fetch_cycle().await;
+        });
+    }
+
+    Ok(Some(summary))
+}
+
+#[tauri::command]
+pub async fn maintain_database(
+    state: State<'_, AppState>,
+) -> Result<MaintenanceReport> {
+    state.maintenance.run().await
+}
+
+#[tauri::command]
+pub async fn list_backups(
+    state: State<'_, AppState>,
+) -> Result<Vec<SnapshotInfo>> {
+    state.snapshots.list().await
+}
+
+// Restores one of the automatic backups from `list_backups`
+#[tauri::command]
+pub async fn restore_backup(
+    name: String,
     state: State<'_, AppState>,
 ) -> Result<()> {
-    state.repo.set_setting(&key, &value).await
-        .map_err(|e| QuonitorError::Database(e))?;
+    state.snapshots.restore(&name).await?;
+    // Backups from before credentials were bound to their account
+    credentials::bind_to_accounts(&state.repo, &state.crypto).await?;
 
-    // Handle special settings
-    if key == "refresh_interval_seconds" {
-        if let Ok(seconds) = value.parse::<u64>() {
-            state.scheduler.set_interval(seconds).await;
-        }
+    state.cache.clear().await;
+    if let Err(e) = state.pricing.reload_overrides().await {
+        tracing::warn!("Failed to reload pricing overrides after restore: {}", e);
     }
+    let scheduler = state.scheduler.clone();
+    tokio::spawn(async move {
+        scheduler.run_fetch_cycle().await;
+    });
 
     Ok(())
 }
 
 #[tauri::command]
-pub async fn cleanup_old_data(
-    days: i64,
+pub async fn get_profiles(
+    state: State<'_, AppState>,
+) -> Result<ProfileList> {
+    Ok(profiles::load(&state.data_dir))
+}
+
+#[tauri::command]
+pub async fn create_profile(
+    name: String,
+    state: State<'_, AppState>,
+) -> Result<Profile> {
+    profiles::create(&state.data_dir, &name)
+        .map_err(|e| QuonitorError::Config(e.to_string()))
+}
+
+// Every service holds the open database, so switching restarts the app into the new profile
+#[tauri::command]
+pub async fn switch_profile(
+    profile_id: String,
+    app: AppHandle,
     state: State<'_, AppState>,
 ) -> Result<()> {
-    state.repo.cleanup_old_data(days).await
-        .map_err(|e| QuonitorError::Database(e))
+    if profiles::load(&state.data_dir).active == profile_id {
+        return Ok(());
+    }
+
+    profiles::set_active(&state.data_dir, &profile_id)
+        .map_err(|e| QuonitorError::Config(e.to_string()))?;
+
+    state.scheduler.stop().await;
+    state.repo.close().await;
+    app.restart()
+}
+
+#[tauri::command]
+pub async fn delete_profile(
+    profile_id: String,
+    state: State<'_, AppState>,
+) -> Result<()> {
+    profiles::delete(&state.data_dir, &profile_id)
+        .map_err(|e| QuonitorError::Config(e.to_string()))
+}
+
+#[tauri::command]
+pub async fn get_data_location(
+    state: State<'_, AppState>,
+) -> Result<DataLocation> {
+    Ok(location::current(&state.data_dir, state.data_source))
+}
+
+// Lets the user pick the new folder; `reset` goes back to the default location instead.
+// Like switching profiles this restarts the app: the change, and the move with
+// `move_data`, happen before the database and key file are opened again.
+#[tauri::command]
+pub async fn set_data_location(
+    reset: bool,
+    move_data: bool,
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<()> {
+    let current = location::current(&state.data_dir, state.data_source);
+
+    let target = if reset {
+        None
+    } else {
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        app.dialog()
+            .file()
+            .pick_folder(move |path| {
+                let _ = tx.send(path);
+            });
+
+        let Some(path) = rx.await.ok().flatten() else {
+            return Ok(());
+        };
+        Some(path.into_path()
+            .map_err(|e| QuonitorError::Config(format!("Invalid data directory: {}", e)))?)
+    };
+
+    location::request_change(&current, target.as_deref(), move_data)
+        .map_err(|e| QuonitorError::Config(e.to_string()))?;
+    if location::current(&state.data_dir, state.data_source).pending.is_none() {
+        return Ok(());
+    }
+
+    state.scheduler.stop().await;
+    state.repo.close().await;
+    app.restart()
+}
+
+#[tauri::command]
+pub async fn get_lock_state(
+    state: State<'_, AppState>,
+) -> Result<LockState> {
+    Ok(LockState {
+        enabled: state.crypto.has_master_password(),
+        locked: state.crypto.is_locked(),
+    })
+}
+
+#[tauri::command]
+pub async fn lock(
+    state: State<'_, AppState>,
+) -> Result<()> {
+    state.crypto.lock()
+}
+
+// Fetching resumes right away rather than at the next scheduled cycle
+#[tauri::command]
+pub async fn unlock(
+    passphrase: String,
+    state: State<'_, AppState>,
+) -> Result<()> {
+    state.crypto.unlock(&passphrase)?;
+    credentials::bind_to_accounts(&state.repo, &state.crypto).await?;
+
+    let scheduler = state.scheduler.clone();
+    tokio::spawn(async move {
+        scheduler.run_fetch_cycle().await;
+    });
+
+    Ok(())
+}
+
+// Sets, changes or (with `new_passphrase` omitted) removes the master password
+#[tauri::command]
+pub async fn set_master_password(
+    current: Option<String>,
+    new_passphrase: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<LockState> {
+    credentials::set_master_password(&state.repo, &state.crypto, current.as_deref(), new_passphrase.as_deref()).await?;
+
+    Ok(LockState {
+        enabled: state.crypto.has_master_password(),
+        locked: state.crypto.is_locked(),
+    })
+}
+
+#[tauri::command]
+pub async fn get_key_storage(
+    state: State<'_, AppState>,
+) -> Result<KeyStorage> {
+    Ok(CryptoService::key_storage(&state.data_dir))
+}
+
+// Also records the first-run choice when made with the current mode
+#[tauri::command]
+pub async fn set_keyring_only(
+    enabled: bool,
+    state: State<'_, AppState>,
+) -> Result<KeyStorage> {
+    CryptoService::set_keyring_only(&state.data_dir, enabled)?;
+    Ok(CryptoService::key_storage(&state.data_dir))
+}
+
+#[tauri::command]
+pub async fn get_database_encryption(
+    state: State<'_, AppState>,
+) -> Result<EncryptionState> {
+    Ok(cipher::state(&state.profile_dir))
+}
+
+// Encryption is applied on the next start, before the database is opened
+#[tauri::command]
+pub async fn enable_database_encryption(
+    state: State<'_, AppState>,
+) -> Result<EncryptionState> {
+    match cipher::state(&state.profile_dir) {
+        EncryptionState::Disabled => {
+            cipher::request_encryption(&state.profile_dir)
+                .map_err(|e| QuonitorError::Config(e.to_string()))?;
+            Ok(EncryptionState::Pending)
+        }
+        current => Ok(current),
+    }
+}
+
+#[tauri::command]
+pub async fn get_account_errors(
+    account_id: String,
+    limit: Option<i64>,
+    state: State<'_, AppState>,
+) -> Result<Vec<FetchError>> {
+    state.repo.get_fetch_errors(&account_id, limit.unwrap_or(50)).await
+        .map_err(|e| QuonitorError::Database(e))
+}
+
+// None unless the account's recent fetches failed
+#[tauri::command]
+pub async fn get_account_backoff(
+    account_id: String,
+    state: State<'_, AppState>,
+) -> Result<Option<FetchBackoff>> {
+    state.repo.get_fetch_backoff(&account_id).await
+        .map_err(|e| QuonitorError::Database(e))
+}
+
+// As of the last probe; changes are also emitted as `connectivity-changed`
+#[tauri::command]
+pub async fn get_connectivity(
+    state: State<'_, AppState>,
+) -> Result<ConnectivityStatus> {
+    Ok(state.connectivity.status())
+}
+
+#[tauri::command]
+pub async fn get_notification_history(
+    limit: Option<i64>,
+    unacknowledged_only: Option<bool>,
+    state: State<'_, AppState>,
+) -> Result<Vec<NotificationRecord>> {
+    state.repo.get_notifications(limit.unwrap_or(100), unacknowledged_only.unwrap_or(false)).await
+        .map_err(|e| QuonitorError::Database(e))
+}
+
+#[tauri::command]
+pub async fn acknowledge_notification(
+    notification_id: i64,
+    state: State<'_, AppState>,
+) -> Result<()> {
+    state.repo.acknowledge_notification(notification_id).await
+        .map_err(|e| QuonitorError::Database(e))
+}
+
+#[tauri::command]
+pub async fn get_account_tags(
+    state: State<'_, AppState>,
+) -> Result<HashMap<String, Vec<String>>> {
+    state.repo.get_all_account_tags().await
+        .map_err(|e| QuonitorError::Database(e))
+}
+
+#[tauri::command]
+pub async fn set_account_tags(
+    account_id: String,
+    tags: Vec<String>,
+    state: State<'_, AppState>,
+) -> Result<Vec<String>> {
+    let tags = normalize_tags(&tags);
+    state.repo.set_account_tags(&account_id, &tags).await
+        .map_err(|e| QuonitorError::Database(e))?;
+
+    Ok(tags)
+}
+
+// Trimmed, without blanks or duplicates, sorted
+fn normalize_tags(tags: &[String]) -> Vec<String> {
+    let mut tags: Vec<String> = tags.iter()
+        .map(|t| t.trim().to_string())
+        .filter(|t| !t.is_empty())
+        .collect();
+    tags.sort();
+    tags.dedup();
+    tags
+}
+
+#[tauri::command]
+pub async fn get_spend_by_tag(
+    days: u32,
+    state: State<'_, AppState>,
+) -> Result<Vec<TagSpend>> {
+    let until = Utc::now().timestamp();
+    let since = until - (days as i64 * 86400);
+    state.repo.get_spend_by_tag(since, until).await
+        .map_err(|e| QuonitorError::Database(e))
+}
+
+#[tauri::command]
+pub async fn get_account_groups(
+    state: State<'_, AppState>,
+) -> Result<Vec<AccountGroup>> {
+    state.repo.get_account_groups().await
+        .map_err(|e| QuonitorError::Database(e))
+}
+
+#[tauri::command]
+pub async fn save_account_group(
+    request: AccountGroupRequest,
+    state: State<'_, AppState>,
+) -> Result<AccountGroup> {
+    let name = request.name.trim().to_string();
+    if name.is_empty() {
+        return Err(QuonitorError::Config("Group name is required".to_string()));
+    }
+
+    let group = AccountGroup {
+        id: request.id.unwrap_or_else(|| Uuid::new_v4().to_string()),
+        name,
+        account_ids: request.account_ids,
+        quota_threshold_percent: request.quota_threshold_percent,
+        cost_threshold_usd: request.cost_threshold_usd,
+        last_notified_at: None,
+        created_at: Utc::now().timestamp(),
+    };
+
+    state.repo.upsert_account_group(&group).await
+        .map_err(|e| QuonitorError::Database(e))?;
+
+    Ok(group)
+}
+
+#[tauri::command]
+pub async fn remove_account_group(
+    group_id: String,
+    state: State<'_, AppState>,
+) -> Result<()> {
+    state.repo.delete_account_group(&group_id).await
+        .map_err(|e| QuonitorError::Database(e))
+}
+
+#[tauri::command]
+pub async fn get_group_summary(
+    group_id: String,
+    state: State<'_, AppState>,
+) -> Result<GroupSummary> {
+    state.repo.get_group_summary(&group_id).await
+        .map_err(|e| QuonitorError::Database(e))
+}
+
+#[tauri::command]
+pub async fn get_budgets(
+    state: State<'_, AppState>,
+) -> Result<Vec<BudgetStatus>> {
+    budgets::all_statuses(&state.repo).await
+}
+
+#[tauri::command]
+pub async fn set_budget(
+    request: BudgetRequest,
+    state: State<'_, AppState>,
+) -> Result<Budget> {
+    let now = Utc::now().timestamp();
+    let budget = Budget {
+        id: request.id.unwrap_or_else(|| Uuid::new_v4().to_string()),
+        name: request.name,
+        target: if request.scope == "global" { None } else { request.target },
+        scope: request.scope,
+        amount_usd: request.amount_usd,
+        period: request.period,
+        reset_anchor: request.reset_anchor.unwrap_or(now - now.rem_euclid(86400)),
+        created_at: now,
+    };
+    budgets::validate(&budget)?;
+
+    state.repo.upsert_budget(&budget).await
+        .map_err(|e| QuonitorError::Database(e))?;
+
+    Ok(budget)
+}
+
+#[tauri::command]
+pub async fn remove_budget(
+    id: String,
+    state: State<'_, AppState>,
+) -> Result<()> {
+    state.repo.delete_budget(&id).await
+        .map_err(|e| QuonitorError::Database(e))
+}
+
+#[tauri::command]
+pub async fn get_account_notification_prefs(
+    account_id: String,
+    state: State<'_, AppState>,
+) -> Result<AccountNotificationPrefs> {
+    let prefs = state.repo.get_account_notification_prefs(&account_id).await
+        .map_err(|e| QuonitorError::Database(e))?;
+    Ok(prefs.unwrap_or_else(|| AccountNotificationPrefs::defaults(&account_id)))
+}
+
+#[tauri::command]
+pub async fn set_account_notification_prefs(
+    prefs: AccountNotificationPrefs,
+    state: State<'_, AppState>,
+) -> Result<()> {
+    let thresholds = [prefs.warning_percent, prefs.caution_percent, prefs.critical_percent];
+    if thresholds.iter().flatten().any(|percent| !(1.0..=100.0).contains(percent)) {
+        return Err(QuonitorError::Config("Thresholds must be between 1 and 100%".to_string()));
+    }
+    let (warning, caution, critical) = prefs.thresholds();
+    if warning > caution || caution > critical {
+        return Err(QuonitorError::Config(
+            "Thresholds must rise from warning to caution to critical".to_string(),
+        ));
+    }
+    if let Some(channels) = &prefs.channels {
+        routing::validate_channels(channels)?;
+    }
+    if prefs.burn_rate_usd_per_hour.is_some_and(|limit| !(0.0..=100_000.0).contains(&limit)) {
+        return Err(QuonitorError::Config("Burn rate alert must be between $0 and $100,000 an hour".to_string()));
+    }
+
+    state.repo.set_account_notification_prefs(&prefs).await
+        .map_err(|e| QuonitorError::Database(e))
+}
+
+// Holds back the account's alerts for `duration_minutes`; 0 ends the snooze
+#[tauri::command]
+pub async fn snooze_notifications(
+    account_id: String,
+    duration_minutes: i64,
+    state: State<'_, AppState>,
+) -> Result<Option<i64>> {
+    if !(0..=525_600).contains(&duration_minutes) {
+        return Err(QuonitorError::Config("Snooze for at most a year".to_string()));
+    }
+    let until = (duration_minutes > 0).then(|| Utc::now().timestamp() + duration_minutes * 60);
+
+    state.repo.set_account_snooze(&account_id, until).await
+        .map_err(|e| QuonitorError::Database(e))?;
+
+    Ok(until)
+}
+
+#[tauri::command]
+pub async fn mute_account(
+    account_id: String,
+    muted: bool,
+    state: State<'_, AppState>,
+) -> Result<()> {
+    state.repo.set_account_muted(&account_id, muted).await
+        .map_err(|e| QuonitorError::Database(e))
+}
+
+#[tauri::command]
+pub async fn get_notification_routes(
+    state: State<'_, AppState>,
+) -> Result<Vec<NotificationRoute>> {
+    state.repo.get_notification_routes().await
+        .map_err(|e| QuonitorError::Database(e))
+}
+
+#[tauri::command]
+pub async fn save_notification_route(
+    request: NotificationRouteRequest,
+    state: State<'_, AppState>,
+) -> Result<NotificationRoute> {
+    let route = NotificationRoute {
+        id: request.id.unwrap_or_else(|| Uuid::new_v4().to_string()),
+        name: request.name.trim().to_string(),
+        min_threshold: request.min_threshold,
+        max_threshold: request.max_threshold,
+        account_id: request.account_id.filter(|id| !id.is_empty()),
+        account_tag: request.account_tag.map(|tag| tag.trim().to_string()).filter(|tag| !tag.is_empty()),
+        channels: request.channels,
+        created_at: Utc::now().timestamp(),
+    };
+    routing::validate(&route)?;
+
+    state.repo.upsert_notification_route(&route).await
+        .map_err(|e| QuonitorError::Database(e))?;
+
+    Ok(route)
+}
+
+#[tauri::command]
+pub async fn remove_notification_route(
+    id: String,
+    state: State<'_, AppState>,
+) -> Result<()> {
+    state.repo.delete_notification_route(&id).await
+        .map_err(|e| QuonitorError::Database(e))
+}
+
+#[tauri::command]
+pub async fn get_all_settings(
+    state: State<'_, AppState>,
+) -> Result<Settings> {
+    Settings::load(&state.repo).await
+}
+
+// Network timeouts only apply to provider clients built at the next launch
+#[tauri::command]
+pub async fn update_settings(
+    settings: Settings,
+    state: State<'_, AppState>,
+) -> Result<Settings> {
+    let previous = Settings::load(&state.repo).await?;
+    // Otherwise turning the gate off would be a way around it
+    if previous.require_os_auth && !settings.require_os_auth {
+        os_auth::verify("turn off OS authentication").await?;
+    }
+    settings.save(&state.repo).await?;
+
+    if settings.refresh_interval_seconds != previous.refresh_interval_seconds {
+        state.scheduler.set_interval(settings.refresh_interval_seconds).await;
+    }
+    if settings.refresh_schedule != previous.refresh_schedule
+        || settings.adaptive_polling != previous.adaptive_polling
+        || settings.blackout_windows != previous.blackout_windows
+    {
+        state.scheduler.reschedule();
+    }
+    crate::providers::retry::set_max_attempts(settings.retry_max_attempts);
+
+    Ok(settings)
+}
+
+#[tauri::command]
+pub async fn has_smtp_password(
+    state: State<'_, AppState>,
+) -> Result<bool> {
+    email::has_password(&state.repo).await
+}
+
+// Omit `password` to remove it
+#[tauri::command]
+pub async fn set_smtp_password(
+    password: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<()> {
+    email::set_password(&state.repo, &state.crypto, password.as_deref()).await
+}
+
+#[tauri::command]
+pub async fn has_webhook_secret(
+    state: State<'_, AppState>,
+) -> Result<bool> {
+    webhook::has_secret(&state.repo).await
+}
+
+// Omit `secret` to remove it
+#[tauri::command]
+pub async fn set_webhook_secret(
+    secret: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<()> {
+    webhook::set_secret(&state.repo, &state.crypto, secret.as_deref()).await
+}
+
+// Sends a sample alert with the saved settings, whatever the threshold
+#[tauri::command]
+pub async fn send_test_email(
+    state: State<'_, AppState>,
+) -> Result<()> {
+    let settings = Settings::load(&state.repo).await?;
+    let alert = Alert {
+        summary: "Test Alert".to_string(),
+        body: "Email alerts from Quonitor are working.".to_string(),
+        timestamp: Utc::now().timestamp(),
+        ..Default::default()
+    };
+    email::deliver(&state.repo, &state.crypto, &settings, &alert).await
+}
+
+#[tauri::command]
+pub async fn get_account_settings(
+    account_id: String,
+    state: State<'_, AppState>,
+) -> Result<HashMap<String, String>> {
+    state.repo.get_account_settings(&account_id).await
+        .map_err(|e| QuonitorError::Database(e))
+}
+
+#[tauri::command]
+pub async fn set_account_setting(
+    account_id: String,
+    key: String,
+    value: String,
+    state: State<'_, AppState>,
+) -> Result<()> {
+    validate_account_setting(&key, &value)?;
+
+    state.repo.set_account_setting(&account_id, &key, &value).await
+        .map_err(|e| QuonitorError::Database(e))?;
+
+    if key == schedule::ACCOUNT_SETTING {
+        state.scheduler.reschedule();
+    }
+    Ok(())
+}
+
+// Validates the settings providers rely on so a typo can't break fetching
+fn validate_account_setting(key: &str, value: &str) -> Result<()> {
+    match key {
+        "lookback_days" => {
+            value.parse::<u32>()
+                .map_err(|_| QuonitorError::Config(format!("Invalid lookback_days: {}", value)))?;
+        }
+        "billing_timezone" => {
+            value.parse::<chrono_tz::Tz>()
+                .map_err(|_| QuonitorError::Config(format!("Unknown timezone: {}", value)))?;
+        }
+        // Empty means the account follows the global schedule
+        schedule::ACCOUNT_SETTING if !value.trim().is_empty() => {
+            schedule::parse(value)?;
+        }
+        // Empty means the account uses the global push targets
+        push::ACCOUNT_SETTING => {
+            push::parse(value)?;
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_pricing_overrides(
+    state: State<'_, AppState>,
+) -> Result<Vec<PricingOverride>> {
+    state.repo.get_pricing_overrides().await
+        .map_err(|e| QuonitorError::Database(e))
+}
+
+#[tauri::command]
+pub async fn set_pricing_override(
+    pricing: PricingOverride,
+    state: State<'_, AppState>,
+) -> Result<()> {
+    if pricing.model.trim().is_empty() {
+        return Err(QuonitorError::Config("Pricing override needs a model pattern".to_string()));
+    }
+
+    state.repo.set_pricing_override(&pricing).await
+        .map_err(|e| QuonitorError::Database(e))?;
+    state.pricing.reload_overrides().await
+}
+
+#[tauri::command]
+pub async fn remove_pricing_override(
+    provider: String,
+    model: String,
+    state: State<'_, AppState>,
+) -> Result<()> {
+    state.repo.delete_pricing_override(&provider, &model).await
+        .map_err(|e| QuonitorError::Database(e))?;
+    state.pricing.reload_overrides().await
+}
+
+#[tauri::command]
+pub async fn refresh_pricing(
+    state: State<'_, AppState>,
+) -> Result<bool> {
+    state.pricing.refresh().await
+}
+
+// Applies the retention settings now rather than at the next hourly pass
+#[tauri::command]
+pub async fn cleanup_old_data(
+    state: State<'_, AppState>,
+) -> Result<u64> {
+    state.maintenance.cleanup().await
+}
+
+#[tauri::command]
+pub async fn google_auth_start(
+    client_id: String,
+    client_secret: String,
+    redirect_uri: String,
+) -> Result<(String, String)> {
+    GoogleProvider::get_auth_url(&GoogleAuthConfig {
+        client_id,
+        client_secret,
+        redirect_uri,
+    })
+}
+
+#[tauri::command]
+pub async fn google_auth_finish(
+    client_id: String,
+    client_secret: String,
+    redirect_uri: String,
+    code: String,
+) -> Result<Credentials> {
+    let config = GoogleAuthConfig {
+        client_id,
+        client_secret,
+        redirect_uri,
+    };
+    GoogleProvider::exchange_code(&config, code).await
 }
@@ -1,99 +1,434 @@
 use aes_gcm::{
-    aead::{Aead, KeyInit, OsRng},
+    aead::{Aead, AeadCore, KeyInit, OsRng, Payload},
     Aes256Gcm, Nonce,
 };
+use argon2::Argon2;
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
 use base64::{engine::general_purpose, Engine as _};
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
 use crate::error::{QuonitorError, Result};
 
 const NONCE_SIZE: usize = 12;
+const SALT_SIZE: usize = 16;
+const TAG_SIZE: usize = 16;
+// Version byte of credential blobs, which are version || nonce || ciphertext. The
+// oldest blobs are bare ciphertext under an all-zero nonce.
+const NONCE_PREFIXED: u8 = 1;
+// Authenticated with the account id and provider as associated data
+const ACCOUNT_BOUND: u8 = 2;
+// Per profile: the credentials key, sealed with the master password
+const PASSWORD_FILE: &str = "credentials.key";
+const KEY_FILE: &str = "master.key";
+const KEYRING_SERVICE: &str = "quonitor";
+const KEYRING_USER: &str = "master_key";
+// Where the user chose to keep the master key; absent until they've chosen
+const KEY_STORAGE_FILE: &str = "key-storage.json";
+
+#[derive(Debug, Clone, Serialize)]
+pub struct KeyStorage {
+    // The master key lives only in the system keyring
+    pub keyring_only: bool,
+    // `master.key` exists in the data directory
+    pub key_file: bool,
+    // The first-run choice has been made
+    pub chosen: bool,
+}
+
+#[derive(Serialize, Deserialize)]
+struct StoredKeyStorage {
+    keyring_only: bool,
+}
 
 pub struct CryptoService {
+    // Encrypts account credentials: the master key, or with a master password set the
+    // key unwrapped by it. None while locked.
+    cipher: RwLock<Option<Aes256Gcm>>,
+    master_cipher: Aes256Gcm,
+    database_key: String,
+    password_file: PathBuf,
+}
+
+// A new credentials key from `begin_rekey`. It only takes effect with `finish_rekey`,
+// once the credentials re-encrypted with it are stored.
+pub struct Rekey {
     cipher: Aes256Gcm,
+    // Whether the new key is sealed in a staged key file, or is the master key
+    staged: bool,
+}
+
+impl Rekey {
+    pub fn encrypt(&self, account_id: &str, provider: &str, data: &str) -> Result<Vec<u8>> {
+        CryptoService::seal(&self.cipher, account_id, provider, data)
+    }
 }
 
 impl CryptoService {
-    pub fn new() -> Result<Self> {
+    // The master key (and database key) are per data dir; the master password is per
+    // profile, like the credentials it protects. With one set, this starts locked.
+    pub fn new(data_dir: &Path, profile_dir: &Path) -> Result<Self> {
         // In production, this key should be derived from a master key stored securely
         // For now, we'll use a key from the OS keyring or environment
-        let key = Self::get_or_create_master_key()?;
-        let cipher = Aes256Gcm::new(&key.into());
+        let key = Self::get_or_create_master_key(data_dir)?;
+        let master_cipher = Aes256Gcm::new(&key.into());
+        let database_key = Self::derive_database_key(&key)?;
+        let password_file = profile_dir.join(PASSWORD_FILE);
+        let cipher = (!password_file.exists()).then(|| master_cipher.clone());
+
+        Ok(Self {
+            cipher: RwLock::new(cipher),
+            master_cipher,
+            database_key,
+            password_file,
+        })
+    }
+
+    pub fn has_master_password(&self) -> bool {
+        self.password_file.exists()
+    }
+
+    pub fn is_locked(&self) -> bool {
+        !matches!(self.cipher.read().as_deref(), Ok(Some(_)))
+    }
+
+    // Forgets the unwrapped key; credentials can't be used until `unlock`
+    pub fn lock(&self) -> Result<()> {
+        if !self.has_master_password() {
+            return Err(QuonitorError::Config("No master password is set".to_string()));
+        }
+        *self.write_cipher()? = None;
+        Ok(())
+    }
+
+    pub fn unlock(&self, passphrase: &str) -> Result<()> {
+        let cipher = self.password_cipher(passphrase)?;
+        *self.write_cipher()? = Some(cipher);
+        Ok(())
+    }
+
+    // Checks `current` (when a master password is set) and prepares a key for
+    // `new_passphrase`, or for the master key alone when None. The caller re-encrypts
+    // every credential with it and stores them before calling `finish_rekey`.
+    pub fn begin_rekey(&self, current: Option<&str>, new_passphrase: Option<&str>) -> Result<Rekey> {
+        if self.has_master_password() {
+            let current = current
+                .ok_or_else(|| QuonitorError::Auth("The current master password is required".to_string()))?;
+            self.password_cipher(current)?;
+        }
+
+        let Some(passphrase) = new_passphrase else {
+            return Ok(Rekey { cipher: self.master_cipher.clone(), staged: false });
+        };
+        if passphrase.chars().count() < 8 {
+            return Err(QuonitorError::Config("Master password must be at least 8 characters".to_string()));
+        }
+
+        // Written before any credential is re-encrypted, so the new key is on disk
+        // whatever happens after the database commits
+        let key = Aes256Gcm::generate_key(OsRng);
+        std::fs::write(self.staged_file(), Self::seal_with_passphrase(&key, passphrase)?)?;
+        Ok(Rekey { cipher: Aes256Gcm::new(&key), staged: true })
+    }
+
+    // Moves the staged key file into place (or removes the key file) and switches to the new key
+    pub fn finish_rekey(&self, rekey: Rekey) -> Result<()> {
+        if rekey.staged {
+            std::fs::rename(self.staged_file(), &self.password_file)?;
+        } else if self.password_file.exists() {
+            std::fs::remove_file(&self.password_file)?;
+        }
+
+        *self.write_cipher()? = Some(rekey.cipher);
+        Ok(())
+    }
+
+    fn staged_file(&self) -> PathBuf {
+        self.password_file.with_extension("key.new")
+    }
+
+    fn password_cipher(&self, passphrase: &str) -> Result<Aes256Gcm> {
+        let sealed = std::fs::read(&self.password_file)
+            .map_err(|_| QuonitorError::Config("No master password is set".to_string()))?;
+        let key = Self::open_with_passphrase(&sealed, passphrase)
+            .map_err(|_| QuonitorError::Auth("Wrong master password".to_string()))?;
+        if key.len() != 32 {
+            return Err(QuonitorError::Encryption("Invalid credentials key file".to_string()));
+        }
+        Aes256Gcm::new_from_slice(&key)
+            .map_err(|e| QuonitorError::Encryption(format!("Invalid credentials key: {}", e)))
+    }
+
+    fn cipher(&self) -> Result<Aes256Gcm> {
+        self.cipher.read()
+            .map_err(|_| QuonitorError::Encryption("Key store poisoned".to_string()))?
+            .clone()
+            .ok_or(QuonitorError::Locked)
+    }
 
-        Ok(Self { cipher })
+    fn write_cipher(&self) -> Result<std::sync::RwLockWriteGuard<'_, Option<Aes256Gcm>>> {
+        self.cipher.write()
+            .map_err(|_| QuonitorError::Encryption("Key store poisoned".to_string()))
     }
 
-    fn get_or_create_master_key() -> Result<[u8; 32]> {
+    // SQLCipher raw key (`x'<hex>'`), derived from the master key so it never has to be
+    // stored separately. The fixed salt only separates it from other uses of the key.
+    fn derive_database_key(master_key: &[u8; 32]) -> Result<String> {
+        let mut key = [0u8; 32];
+        Argon2::default()
+            .hash_password_into(master_key, b"quonitor-database-key", &mut key)
+            .map_err(|e| QuonitorError::Encryption(format!("Key derivation failed: {}", e)))?;
+
+        let hex: String = key.iter().map(|b| format!("{:02x}", b)).collect();
+        Ok(format!("x'{}'", hex))
+    }
+
+    pub fn database_key(&self) -> &str {
+        &self.database_key
+    }
+
+    fn get_or_create_master_key(data_dir: &Path) -> Result<[u8; 32]> {
+        let key_path = data_dir.join(KEY_FILE);
+        let keyring_only = Self::key_storage(data_dir).keyring_only;
+
         // Try to get key from keyring first
-        if let Ok(entry) = keyring::Entry::new("quonitor", "master_key") {
-            if let Ok(key_str) = entry.get_password() {
-                if let Ok(key_bytes) = general_purpose::STANDARD.decode(&key_str) {
-                    let mut key = [0u8; 32];
-                    if key_bytes.len() == 32 {
-                        key.copy_from_slice(&key_bytes);
-                        return Ok(key);
-                    }
-                }
+        if let Some(key) = Self::keyring_key() {
+            if keyring_only && key_path.exists() {
+                Self::remove_key_file(&key_path);
             }
+            return Ok(key);
         }
 
         // Fallback: Try file-based key in app data directory
-        let data_dir = dirs::data_local_dir()
-            .map(|p| p.join("quonitor"))
-            .ok_or_else(|| QuonitorError::Encryption("Failed to get data directory".to_string()))?;
-            
-        std::fs::create_dir_all(&data_dir)
-            .map_err(|e| QuonitorError::Encryption(format!("Failed to create data dir: {}", e)))?;
-            
-        let key_path = data_dir.join("master.key");
-        
-        if key_path.exists() {
-            // Read existing key from file
-            let key_str = std::fs::read_to_string(&key_path)
-                .map_err(|e| QuonitorError::Encryption(format!("Failed to read key file: {}", e)))?;
-                
-            let key_bytes = general_purpose::STANDARD
-                .decode(key_str.trim())
-                .map_err(|e| QuonitorError::Encryption(format!("Failed to decode file key: {}", e)))?;
-
-            let mut key = [0u8; 32];
-            if key_bytes.len() == 32 {
-                key.copy_from_slice(&key_bytes);
-                return Ok(key);
+        if let Some(key) = Self::file_key(&key_path)? {
+            // Strict mode with the key still in a file (restored, or the keyring was
+            // reset): move it into the keyring, or refuse to start on it
+            if keyring_only {
+                Self::store_in_keyring(&key)?;
+                Self::remove_key_file(&key_path);
             }
+            return Ok(key);
         }
 
         // Generate new key if neither found
-        let key = Aes256Gcm::generate_key(OsRng);
-        let key_str = general_purpose::STANDARD.encode(&key);
+        let key: [u8; 32] = Aes256Gcm::generate_key(OsRng).into();
+
+        if keyring_only {
+            Self::store_in_keyring(&key)?;
+            return Ok(key);
+        }
 
         // Try to save to keyring
-        if let Ok(entry) = keyring::Entry::new("quonitor", "master_key") {
-            let _ = entry.set_password(&key_str);
+        if let Ok(entry) = keyring::Entry::new(KEYRING_SERVICE, KEYRING_USER) {
+            let _ = entry.set_password(&general_purpose::STANDARD.encode(key));
         }
 
         // Always save to file as backup/primary
-        std::fs::write(&key_path, &key_str)
+        std::fs::write(&key_path, general_purpose::STANDARD.encode(key))
             .map_err(|e| QuonitorError::Encryption(format!("Failed to write key file: {}", e)))?;
 
-        Ok(key.into())
+        Ok(key)
+    }
+
+    pub fn key_storage(data_dir: &Path) -> KeyStorage {
+        let chosen = std::fs::read_to_string(data_dir.join(KEY_STORAGE_FILE))
+            .ok()
+            .and_then(|json| serde_json::from_str::<StoredKeyStorage>(&json).ok());
+
+        KeyStorage {
+            keyring_only: chosen.as_ref().is_some_and(|c| c.keyring_only),
+            key_file: data_dir.join(KEY_FILE).exists(),
+            chosen: chosen.is_some(),
+        }
+    }
+
+    // Keyring-only mode keeps the master key out of the data directory. Turning it on
+    // copies the key into the keyring if needed and deletes `master.key` only once the
+    // keyring hands the key back; turning it off writes the file again.
+    pub fn set_keyring_only(data_dir: &Path, enabled: bool) -> Result<()> {
+        let key_path = data_dir.join(KEY_FILE);
+
+        if enabled {
+            // The keyring is read first at startup, so when it has a key that's the one in use
+            if Self::keyring_key().is_none() {
+                let key = Self::file_key(&key_path)?
+                    .ok_or_else(|| QuonitorError::Encryption("No master key found".to_string()))?;
+                Self::store_in_keyring(&key)?;
+            }
+            if key_path.exists() {
+                std::fs::remove_file(&key_path)
+                    .map_err(|e| QuonitorError::Encryption(format!("Failed to remove key file: {}", e)))?;
+            }
+        } else if !key_path.exists() {
+            let key = Self::keyring_key()
+                .ok_or_else(|| QuonitorError::Encryption("The system keyring has no master key".to_string()))?;
+            std::fs::write(&key_path, general_purpose::STANDARD.encode(key))
+                .map_err(|e| QuonitorError::Encryption(format!("Failed to write key file: {}", e)))?;
+        }
+
+        let json = serde_json::to_string(&StoredKeyStorage { keyring_only: enabled })?;
+        std::fs::write(data_dir.join(KEY_STORAGE_FILE), json)?;
+        Ok(())
+    }
+
+    fn keyring_key() -> Option<[u8; 32]> {
+        let entry = keyring::Entry::new(KEYRING_SERVICE, KEYRING_USER).ok()?;
+        let key_bytes = general_purpose::STANDARD.decode(entry.get_password().ok()?).ok()?;
+        key_bytes.try_into().ok()
+    }
+
+    fn file_key(key_path: &Path) -> Result<Option<[u8; 32]>> {
+        if !key_path.exists() {
+            return Ok(None);
+        }
+
+        let key_str = std::fs::read_to_string(key_path)
+            .map_err(|e| QuonitorError::Encryption(format!("Failed to read key file: {}", e)))?;
+        let key_bytes = general_purpose::STANDARD
+            .decode(key_str.trim())
+            .map_err(|e| QuonitorError::Encryption(format!("Failed to decode file key: {}", e)))?;
+
+        Ok(key_bytes.try_into().ok())
+    }
+
+    // Writes the key and reads it back, since some keyring backends accept writes they can't persist
+    fn store_in_keyring(key: &[u8; 32]) -> Result<()> {
+        let unavailable = |detail: String| QuonitorError::Encryption(format!(
+            "The system keyring is unavailable ({}) and keyring-only mode needs it. \
+             Unlock or install a keyring (Secret Service, Keychain, Credential Manager), \
+             or delete {} from the data directory to store the key in a file again.",
+            detail, KEY_STORAGE_FILE
+        ));
+
+        let entry = keyring::Entry::new(KEYRING_SERVICE, KEYRING_USER)
+            .map_err(|e| unavailable(e.to_string()))?;
+        entry.set_password(&general_purpose::STANDARD.encode(key))
+            .map_err(|e| unavailable(e.to_string()))?;
+
+        match Self::keyring_key() {
+            Some(stored) if &stored == key => Ok(()),
+            _ => Err(unavailable("the key could not be read back".to_string())),
+        }
+    }
+
+    fn remove_key_file(key_path: &Path) {
+        if let Err(e) = std::fs::remove_file(key_path) {
+            tracing::warn!("Failed to remove {}: {}", key_path.display(), e);
+        }
+    }
+
+    // Encrypts an account's credentials, bound to that account
+    pub fn encrypt(&self, account_id: &str, provider: &str, data: &str) -> Result<Vec<u8>> {
+        Self::seal(&self.cipher()?, account_id, provider, data)
     }
 
-    pub fn encrypt(&self, data: &str) -> Result<Vec<u8>> {
-        let nonce = Nonce::from_slice(&[0u8; NONCE_SIZE]); // In production, use random nonce per encryption
+    fn seal(cipher: &Aes256Gcm, account_id: &str, provider: &str, data: &str) -> Result<Vec<u8>> {
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let aad = Self::aad(account_id, provider);
 
-        let ciphertext = self.cipher
-            .encrypt(nonce, data.as_bytes())
+        let ciphertext = cipher
+            .encrypt(&nonce, Payload { msg: data.as_bytes(), aad: &aad })
             .map_err(|e| QuonitorError::Encryption(format!("Encryption failed: {}", e)))?;
 
-        Ok(ciphertext)
+        let mut sealed = Vec::with_capacity(1 + NONCE_SIZE + ciphertext.len());
+        sealed.push(ACCOUNT_BOUND);
+        sealed.extend_from_slice(&nonce);
+        sealed.extend_from_slice(&ciphertext);
+        Ok(sealed)
     }
 
-    pub fn decrypt(&self, encrypted_data: &[u8]) -> Result<String> {
-        let nonce = Nonce::from_slice(&[0u8; NONCE_SIZE]);
+    // For data that leaves this machine (backups): the key comes from the passphrase
+    // via Argon2id instead of the local master key. Output is salt || nonce || ciphertext.
+    pub fn seal_with_passphrase(data: &[u8], passphrase: &str) -> Result<Vec<u8>> {
+        let mut salt = [0u8; SALT_SIZE];
+        OsRng.fill_bytes(&mut salt);
+        let cipher = Self::passphrase_cipher(passphrase, &salt)?;
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
 
-        let plaintext = self.cipher
-            .decrypt(nonce, encrypted_data)
+        let ciphertext = cipher
+            .encrypt(&nonce, data)
+            .map_err(|e| QuonitorError::Encryption(format!("Encryption failed: {}", e)))?;
+
+        let mut sealed = Vec::with_capacity(SALT_SIZE + NONCE_SIZE + ciphertext.len());
+        sealed.extend_from_slice(&salt);
+        sealed.extend_from_slice(&nonce);
+        sealed.extend_from_slice(&ciphertext);
+        Ok(sealed)
+    }
+
+    pub fn open_with_passphrase(sealed: &[u8], passphrase: &str) -> Result<Vec<u8>> {
+        if sealed.len() < SALT_SIZE + NONCE_SIZE {
+            return Err(QuonitorError::Encryption("Sealed data is truncated".to_string()));
+        }
+        let (salt, rest) = sealed.split_at(SALT_SIZE);
+        let (nonce, ciphertext) = rest.split_at(NONCE_SIZE);
+        let cipher = Self::passphrase_cipher(passphrase, salt)?;
+
+        // A wrong passphrase and tampered data look the same to AES-GCM
+        cipher
+            .decrypt(Nonce::from_slice(nonce), ciphertext)
+            .map_err(|_| QuonitorError::Encryption("Wrong passphrase or corrupted data".to_string()))
+    }
+
+    fn passphrase_cipher(passphrase: &str, salt: &[u8]) -> Result<Aes256Gcm> {
+        let mut key = [0u8; 32];
+        Argon2::default()
+            .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+            .map_err(|e| QuonitorError::Encryption(format!("Key derivation failed: {}", e)))?;
+
+        Ok(Aes256Gcm::new(&key.into()))
+    }
+
+    // Only accepts blobs bound to this account and provider, so one account's
+    // credentials copied over another's fail instead of being used for it
+    pub fn decrypt(&self, account_id: &str, provider: &str, encrypted_data: &[u8]) -> Result<String> {
+        let cipher = self.cipher()?;
+
+        if !Self::is_bound(encrypted_data) || encrypted_data.len() < 1 + NONCE_SIZE + TAG_SIZE {
+            return Err(QuonitorError::Encryption(format!(
+                "Credentials for account {} are not bound to it", account_id
+            )));
+        }
+        let (nonce, ciphertext) = encrypted_data[1..].split_at(NONCE_SIZE);
+        let aad = Self::aad(account_id, provider);
+
+        let plaintext = cipher
+            .decrypt(Nonce::from_slice(nonce), Payload { msg: ciphertext, aad: &aad })
+            .map_err(|_| QuonitorError::Encryption(format!(
+                "Credentials for account {} failed authentication; they may have been tampered with", account_id
+            )))?;
+        Self::utf8(plaintext)
+    }
+
+    pub fn is_bound(encrypted_data: &[u8]) -> bool {
+        encrypted_data.first() == Some(&ACCOUNT_BOUND)
+    }
+
+    // Decrypts a blob from before credentials were bound to their account, for
+    // re-encrypting it with `encrypt`
+    pub fn decrypt_unbound(&self, encrypted_data: &[u8]) -> Result<String> {
+        let cipher = self.cipher()?;
+
+        // An unversioned blob can start with the version byte too; only a successful
+        // decryption tells them apart
+        if encrypted_data.len() >= 1 + NONCE_SIZE + TAG_SIZE && encrypted_data[0] == NONCE_PREFIXED {
+            let (nonce, ciphertext) = encrypted_data[1..].split_at(NONCE_SIZE);
+            if let Ok(plaintext) = cipher.decrypt(Nonce::from_slice(nonce), ciphertext) {
+                return Self::utf8(plaintext);
+            }
+        }
+
+        let plaintext = cipher
+            .decrypt(Nonce::from_slice(&[0u8; NONCE_SIZE]), encrypted_data)
             .map_err(|e| QuonitorError::Encryption(format!("Decryption failed: {}", e)))?;
+        Self::utf8(plaintext)
+    }
+
+    fn aad(account_id: &str, provider: &str) -> Vec<u8> {
+        format!("quonitor-credentials\0{}\0{}", account_id, provider).into_bytes()
+    }
 
+    fn utf8(plaintext: Vec<u8>) -> Result<String> {
         String::from_utf8(plaintext)
             .map_err(|e| QuonitorError::Encryption(format!("Invalid UTF-8: {}", e)))
     }
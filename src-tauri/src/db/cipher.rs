@@ -0,0 +1,114 @@
+use std::path::{Path, PathBuf};
+use anyhow::{bail, Context, Result};
+use sqlx::SqlitePool;
+
+const PLAIN_DB: &str = "quonitor.db";
+const ENCRYPTED_DB: &str = "quonitor.encrypted.db";
+// Dropped by `request_encryption`; the conversion itself runs on the next start,
+// before anything has the plaintext database open
+const MARKER: &str = "encrypt-database";
+
+// Whether this build links SQLCipher; without it `PRAGMA key` is silently ignored
+pub const AVAILABLE: bool = cfg!(feature = "sqlcipher");
+
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EncryptionState {
+    Unavailable,
+    Disabled,
+    // Requested, applied on next start
+    Pending,
+    Enabled,
+}
+
+pub fn state(data_dir: &Path) -> EncryptionState {
+    if data_dir.join(ENCRYPTED_DB).exists() {
+        EncryptionState::Enabled
+    } else if !AVAILABLE {
+        EncryptionState::Unavailable
+    } else if data_dir.join(MARKER).exists() {
+        EncryptionState::Pending
+    } else {
+        EncryptionState::Disabled
+    }
+}
+
+pub fn request_encryption(data_dir: &Path) -> Result<()> {
+    if !AVAILABLE {
+        bail!("This build does not include SQLCipher support");
+    }
+    std::fs::write(data_dir.join(MARKER), b"").context("Failed to record encryption request")?;
+    Ok(())
+}
+
+// Picks the database file to open, converting the plaintext one first if encryption
+// was requested. Returns the path and, for an encrypted database, the key to open it with.
+pub async fn prepare(data_dir: &Path, key: &str) -> Result<(PathBuf, Option<String>)> {
+    let plain = data_dir.join(PLAIN_DB);
+    let encrypted = data_dir.join(ENCRYPTED_DB);
+    let marker = data_dir.join(MARKER);
+
+    if encrypted.exists() {
+        if !AVAILABLE {
+            bail!("The database is encrypted but this build has no SQLCipher support");
+        }
+        return Ok((encrypted, Some(key.to_string())));
+    }
+
+    if !AVAILABLE || !marker.exists() {
+        return Ok((plain, None));
+    }
+
+    if plain.exists() {
+        tracing::info!("Encrypting database");
+        export_encrypted(&plain, &encrypted, key).await?;
+        remove_plaintext(&plain);
+    }
+    let _ = std::fs::remove_file(&marker);
+
+    Ok((encrypted, Some(key.to_string())))
+}
+
+async fn export_encrypted(plain: &Path, encrypted: &Path, key: &str) -> Result<()> {
+    let pool = SqlitePool::connect(&format!("sqlite://{}", plain.display()))
+        .await
+        .context("Failed to open plaintext database")?;
+
+    // sqlcipher_export copies schema and data, including the migration history
+    let result = async {
+        sqlx::query("ATTACH DATABASE ? AS encrypted KEY ?")
+            .bind(encrypted.display().to_string())
+            .bind(key)
+            .execute(&pool)
+            .await
+            .context("Failed to create encrypted database")?;
+        sqlx::query("SELECT sqlcipher_export('encrypted')")
+            .execute(&pool)
+            .await
+            .context("Failed to copy data into encrypted database")?;
+        sqlx::query("DETACH DATABASE encrypted")
+            .execute(&pool)
+            .await
+            .context("Failed to detach encrypted database")?;
+        Ok(())
+    }
+    .await;
+    pool.close().await;
+
+    if result.is_err() {
+        // Leave the plaintext database as the source of truth
+        let _ = std::fs::remove_file(encrypted);
+    }
+    result
+}
+
+fn remove_plaintext(plain: &Path) {
+    for suffix in ["", "-wal", "-shm"] {
+        let path = PathBuf::from(format!("{}{}", plain.display(), suffix));
+        if let Err(e) = std::fs::remove_file(&path) {
+            if e.kind() != std::io::ErrorKind::NotFound {
+                tracing::warn!("Failed to remove plaintext database file {}: {}", path.display(), e);
+            }
+        }
+    }
+}
@@ -0,0 +1,224 @@
+use std::path::{Path, PathBuf};
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+
+// Where the data directory (databases, master key, profiles, backups, config) lives.
+// A custom location is recorded in a pointer file in the default directory, which
+// stays put so the next start can find it. `--data-dir` and portable mode override it.
+const LOCATION_FILE: &str = "location.json";
+const DATA_DIR_FLAG: &str = "--data-dir";
+// Next to the executable; its presence makes `<exe dir>/data` the data directory
+const PORTABLE_MARKER: &str = "portable";
+const PORTABLE_DIR: &str = "data";
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LocationSource {
+    Default,
+    Custom,
+    Flag,
+    Portable,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DataLocation {
+    pub path: PathBuf,
+    pub source: LocationSource,
+    // Requested, applied on next start
+    pub pending: Option<PathBuf>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Pointer {
+    // None means the default directory
+    data_dir: Option<PathBuf>,
+    // Switch to this directory on the next start, moving the data there if `move_data`
+    pending: Option<PathBuf>,
+    #[serde(default)]
+    move_data: bool,
+}
+
+pub fn default_dir() -> Result<PathBuf> {
+    dirs::data_local_dir()
+        .map(|p| p.join("quonitor"))
+        .context("Failed to get app data directory")
+}
+
+// Resolves the data directory for this run, applying a requested move first. Runs
+// before anything opens the database or key file.
+pub fn resolve() -> Result<DataLocation> {
+    let (path, source) = if let Some(path) = flag_dir() {
+        (path, LocationSource::Flag)
+    } else if let Some(path) = portable_dir() {
+        (path, LocationSource::Portable)
+    } else {
+        let default = default_dir()?;
+        std::fs::create_dir_all(&default).context("Failed to create data directory")?;
+        let path = apply_pending(&default)?;
+        let source = if path == default { LocationSource::Default } else { LocationSource::Custom };
+        (path, source)
+    };
+
+    std::fs::create_dir_all(&path)
+        .with_context(|| format!("Failed to create data directory {}", path.display()))?;
+    Ok(DataLocation { path, source, pending: None })
+}
+
+// The location in use plus any change waiting for a restart
+pub fn current(path: &Path, source: LocationSource) -> DataLocation {
+    let pending = match source {
+        LocationSource::Default | LocationSource::Custom => default_dir()
+            .ok()
+            .and_then(|default| load(&default).pending),
+        _ => None,
+    };
+    DataLocation { path: path.to_path_buf(), source, pending }
+}
+
+// Records a switch to `target` (None for the default directory) for the next start.
+// With `move_data` the current data is moved there, which needs an empty target;
+// without it the target's existing data (e.g. a synced folder) is used as is.
+pub fn request_change(current: &DataLocation, target: Option<&Path>, move_data: bool) -> Result<()> {
+    match current.source {
+        LocationSource::Flag => bail!("The data directory is set with {} for this run", DATA_DIR_FLAG),
+        LocationSource::Portable => bail!("Portable installs keep their data next to the app"),
+        _ => {}
+    }
+
+    let default = default_dir()?;
+    let target = target.map(Path::to_path_buf).unwrap_or_else(|| default.clone());
+    if !target.is_absolute() {
+        bail!("Data directory must be an absolute path");
+    }
+
+    let mut pointer = load(&default);
+    if target == current.path {
+        pointer.pending = None;
+        return save(&default, &pointer);
+    }
+    if target.starts_with(&current.path) {
+        bail!("Data directory can't be inside the current one");
+    }
+    if move_data && !is_empty(&target, &default)? {
+        bail!("{} is not empty; choose an empty folder to move the data into", target.display());
+    }
+
+    pointer.pending = Some(target);
+    pointer.move_data = move_data;
+    save(&default, &pointer)
+}
+
+fn flag_dir() -> Option<PathBuf> {
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        if arg == DATA_DIR_FLAG {
+            return args.next().map(PathBuf::from);
+        }
+        if let Some(value) = arg.strip_prefix(DATA_DIR_FLAG).and_then(|rest| rest.strip_prefix('=')) {
+            return Some(PathBuf::from(value));
+        }
+    }
+    None
+}
+
+fn portable_dir() -> Option<PathBuf> {
+    let exe_dir = std::env::current_exe().ok()?.parent()?.to_path_buf();
+    exe_dir.join(PORTABLE_MARKER).exists().then(|| exe_dir.join(PORTABLE_DIR))
+}
+
+fn apply_pending(default: &Path) -> Result<PathBuf> {
+    let mut pointer = load(default);
+    let current = pointer.data_dir.clone().unwrap_or_else(|| default.to_path_buf());
+    let Some(target) = pointer.pending.take() else {
+        return Ok(current);
+    };
+
+    if pointer.move_data {
+        tracing::info!("Moving data from {} to {}", current.display(), target.display());
+        if let Err(e) = move_contents(&current, &target) {
+            // Keep running from the old location; the request is dropped so a bad
+            // target doesn't fail every start
+            tracing::error!("Failed to move data directory: {:#}", e);
+            pointer.move_data = false;
+            save(default, &pointer)?;
+            return Ok(current);
+        }
+    }
+
+    pointer.data_dir = (target != default).then(|| target.clone());
+    pointer.move_data = false;
+    save(default, &pointer)?;
+    Ok(target)
+}
+
+// Moves everything except the pointer file. Renames where possible; across devices
+// (a USB stick) everything is copied first and the originals removed only once all
+// copies succeeded.
+fn move_contents(from: &Path, to: &Path) -> Result<()> {
+    std::fs::create_dir_all(to).context("Failed to create the new data directory")?;
+
+    let mut copied = Vec::new();
+    for entry in std::fs::read_dir(from).context("Failed to read the data directory")? {
+        let entry = entry?;
+        if entry.file_name() == LOCATION_FILE {
+            continue;
+        }
+        let dest = to.join(entry.file_name());
+        if std::fs::rename(entry.path(), &dest).is_err() {
+            copy_recursive(&entry.path(), &dest)
+                .with_context(|| format!("Failed to copy {}", entry.path().display()))?;
+            copied.push(entry.path());
+        }
+    }
+
+    for path in copied {
+        let removed = if path.is_dir() { std::fs::remove_dir_all(&path) } else { std::fs::remove_file(&path) };
+        if let Err(e) = removed {
+            tracing::warn!("Failed to remove {} after moving it: {}", path.display(), e);
+        }
+    }
+    Ok(())
+}
+
+fn copy_recursive(from: &Path, to: &Path) -> Result<()> {
+    if from.is_dir() {
+        std::fs::create_dir_all(to)?;
+        for entry in std::fs::read_dir(from)? {
+            let entry = entry?;
+            copy_recursive(&entry.path(), &to.join(entry.file_name()))?;
+        }
+    } else {
+        std::fs::copy(from, to)?;
+    }
+    Ok(())
+}
+
+// The default directory always holds the pointer file, which doesn't count
+fn is_empty(dir: &Path, default: &Path) -> Result<bool> {
+    match std::fs::read_dir(dir) {
+        Ok(entries) => {
+            for entry in entries {
+                let entry = entry?;
+                if !(dir == default && entry.file_name() == LOCATION_FILE) {
+                    return Ok(false);
+                }
+            }
+            Ok(true)
+        }
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(true),
+        Err(e) => Err(e.into()),
+    }
+}
+
+fn load(default: &Path) -> Pointer {
+    std::fs::read_to_string(default.join(LOCATION_FILE))
+        .ok()
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default()
+}
+
+fn save(default: &Path, pointer: &Pointer) -> Result<()> {
+    let json = serde_json::to_string_pretty(pointer)?;
+    std::fs::write(default.join(LOCATION_FILE), json).context("Failed to save data location")?;
+    Ok(())
+}
@@ -1,5 +1,9 @@
 pub mod models;
 pub mod repository;
+pub mod cipher;
+pub mod profiles;
+pub mod location;
+mod online_backup;
 
 pub use models::*;
 pub use repository::Repository;
@@ -1,3 +1,4 @@
+use std::collections::HashMap;
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -9,6 +10,10 @@ pub struct Account {
     pub credentials_encrypted: Vec<u8>,
     pub created_at: i64,
     pub last_synced: Option<i64>,
+    pub needs_reauth: bool,
+    // Favorites come first, then accounts by `sort_order`
+    pub sort_order: i64,
+    pub favorite: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -22,6 +27,29 @@ pub struct QuotaSnapshot {
     pub quota_limit: Option<i64>,
     pub quota_remaining: Option<i64>,
     pub metadata: Option<String>,
+    pub rpm_limit: Option<i64>,
+    pub rpm_remaining: Option<i64>,
+    pub tpm_limit: Option<i64>,
+    pub tpm_remaining: Option<i64>,
+    pub requests_limit: Option<i64>,
+    pub requests_remaining: Option<i64>,
+    pub requests_reset_at: Option<i64>,
+    pub rpm_reset_at: Option<i64>,
+    pub tpm_reset_at: Option<i64>,
+    pub images_generated: Option<i64>,
+    pub audio_seconds: Option<f64>,
+    pub video_seconds: Option<f64>,
+}
+
+// Usage consumed since the account's previous snapshot, for burn-rate charts
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UsageDelta {
+    pub account_id: String,
+    pub timestamp: i64,
+    pub interval_seconds: i64,
+    pub tokens_input: Option<i64>,
+    pub tokens_output: Option<i64>,
+    pub cost_usd: Option<f64>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -34,6 +62,23 @@ pub struct ModelUsage {
     pub tokens_output: i64,
     pub cost_usd: f64,
     pub request_count: i64,
+    pub tokens_cached_input: i64,
+    pub tokens_reasoning: i64,
+    pub images_generated: Option<i64>,
+    pub audio_seconds: Option<f64>,
+    pub video_seconds: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApiKeyUsage {
+    pub id: Option<i64>,
+    pub account_id: String,
+    pub api_key_id: String,
+    pub timestamp: i64,
+    pub tokens_input: i64,
+    pub tokens_output: i64,
+    pub cost_usd: f64,
+    pub request_count: i64,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -44,6 +89,108 @@ pub struct NotificationState {
     pub last_95_percent_notified: Option<i64>,
 }
 
+// See `account_notification_prefs`; the defaults when an account has no row
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AccountNotificationPrefs {
+    pub account_id: String,
+    pub enabled: bool,
+    pub warning_percent: Option<f64>,
+    pub caution_percent: Option<f64>,
+    pub critical_percent: Option<f64>,
+    // Replaces the routing rules for the account's alerts when set
+    pub channels: Option<Vec<String>>,
+    // No alerts until then; expires by itself
+    pub snoozed_until: Option<i64>,
+    // Replaces `Settings::burn_rate_usd_per_hour` when set
+    pub burn_rate_usd_per_hour: Option<f64>,
+}
+
+impl AccountNotificationPrefs {
+    pub fn defaults(account_id: &str) -> Self {
+        Self {
+            account_id: account_id.to_string(),
+            enabled: true,
+            warning_percent: None,
+            caution_percent: None,
+            critical_percent: None,
+            channels: None,
+            snoozed_until: None,
+            burn_rate_usd_per_hour: None,
+        }
+    }
+
+    // Muted, or snoozed until after `now`
+    pub fn is_silenced(&self, now: i64) -> bool {
+        !self.enabled || self.snoozed_until.is_some_and(|until| until > now)
+    }
+
+    // Warning, caution and critical percentages
+    pub fn thresholds(&self) -> (f64, f64, f64) {
+        (
+            self.warning_percent.unwrap_or(75.0),
+            self.caution_percent.unwrap_or(90.0),
+            self.critical_percent.unwrap_or(95.0),
+        )
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NotificationRecord {
+    pub id: i64,
+    pub account_id: Option<String>,
+    pub threshold: Option<f64>,
+    pub title: String,
+    pub message: String,
+    pub timestamp: i64,
+    pub acknowledged: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FetchError {
+    pub id: Option<i64>,
+    pub account_id: String,
+    pub provider: String,
+    pub error_kind: String,
+    pub http_status: Option<i64>,
+    pub message: String,
+    pub timestamp: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FetchBackoff {
+    pub account_id: String,
+    pub consecutive_failures: i64,
+    // Scheduled fetches skip the account until then
+    pub next_attempt_at: i64,
+    // Set while the circuit breaker is open
+    pub tripped_at: Option<i64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Budget {
+    pub id: String,
+    pub name: String,
+    // "global", "account", "provider" or "model"
+    pub scope: String,
+    // Account id, provider id or model name; None for global budgets
+    pub target: Option<String>,
+    pub amount_usd: f64,
+    // "daily", "weekly" or "monthly"
+    pub period: String,
+    pub reset_anchor: i64,
+    pub created_at: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PricingOverride {
+    pub provider: String,
+    // Matched as a substring of the model (or instance type) name
+    pub model: String,
+    pub input_price: f64,
+    pub output_price: f64,
+    pub per_minute_price: f64,
+}
+
 #[allow(dead_code)]
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Setting {
@@ -51,11 +198,29 @@ pub struct Setting {
     pub value: String,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct Credentials {
     pub api_key: Option<String>,
     pub oauth_token: Option<String>,
     pub oauth_refresh_token: Option<String>,
+    // Organization admin key, used by providers that gate billing data behind it
+    #[serde(default)]
+    pub admin_key: Option<String>,
+    // Organization slug for org-scoped usage (e.g. GitHub Copilot billing)
+    #[serde(default)]
+    pub organization: Option<String>,
+    // Google Cloud service account key (JSON) and `project.dataset.table` of the billing export
+    #[serde(default)]
+    pub service_account_json: Option<String>,
+    #[serde(default)]
+    pub billing_export_table: Option<String>,
+    // OAuth token expiry (unix seconds) and the client used to obtain it, for refreshing
+    #[serde(default)]
+    pub oauth_expires_at: Option<i64>,
+    #[serde(default)]
+    pub oauth_client_id: Option<String>,
+    #[serde(default)]
+    pub oauth_client_secret: Option<String>,
 }
 
 impl Credentials {
@@ -63,17 +228,175 @@ impl Credentials {
     pub fn new_api_key(api_key: String) -> Self {
         Self {
             api_key: Some(api_key),
-            oauth_token: None,
-            oauth_refresh_token: None,
+            ..Default::default()
         }
     }
 
     #[allow(dead_code)]
     pub fn new_oauth(token: String, refresh_token: Option<String>) -> Self {
         Self {
-            api_key: None,
             oauth_token: Some(token),
             oauth_refresh_token: refresh_token,
+            ..Default::default()
         }
     }
 }
+
+// Rows older than each timestamp (unix seconds) are deleted by `cleanup_old_data`
+#[derive(Debug, Clone, Copy)]
+pub struct RetentionCutoffs {
+    pub snapshots: i64,
+    // Per-model and per-API-key usage
+    pub model_usage: i64,
+    pub rollups: i64,
+    // Notification and fetch error history
+    pub history: i64,
+}
+
+// One line of a usage export: either an account-level snapshot or a per-model entry
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UsageExportRow {
+    // "snapshot" or "model"
+    pub record_type: String,
+    pub account_id: String,
+    pub account_name: String,
+    pub provider: String,
+    pub timestamp: i64,
+    pub model_name: Option<String>,
+    pub tokens_input: Option<i64>,
+    pub tokens_output: Option<i64>,
+    pub cost_usd: Option<f64>,
+    pub request_count: Option<i64>,
+    pub quota_limit: Option<i64>,
+    pub quota_remaining: Option<i64>,
+}
+
+// Dashboard totals over a range, computed in SQL from each account's closing snapshot per day
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UsageSummary {
+    pub since: i64,
+    pub until: i64,
+    pub days: i64,
+    pub total_tokens_input: i64,
+    pub total_tokens_output: i64,
+    pub total_cost_usd: f64,
+    pub avg_daily_tokens_input: f64,
+    pub avg_daily_tokens_output: f64,
+    pub avg_daily_cost_usd: f64,
+    pub daily: Vec<DailyUsage>,
+    // Highest spend first
+    pub by_provider: Vec<ProviderSpend>,
+    pub top_models: Vec<ModelSpend>,
+    // Month-to-date spend (UTC months, up to `until`) against the same days of the
+    // month before; the change is None when nothing was spent then
+    pub month_to_date_cost_usd: f64,
+    pub previous_month_cost_usd: f64,
+    pub month_over_month_percent: Option<f64>,
+}
+
+// One account's usage over a statement period
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AccountSpend {
+    pub account_id: String,
+    pub account_name: String,
+    pub provider: String,
+    pub tokens_input: i64,
+    pub tokens_output: i64,
+    pub cost_usd: f64,
+}
+
+// One model's usage on one account over a statement period
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModelSpendDetail {
+    pub account_id: String,
+    pub model_name: String,
+    pub tokens_input: i64,
+    pub tokens_output: i64,
+    pub request_count: i64,
+    pub cost_usd: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProviderSpend {
+    pub provider: String,
+    pub account_count: i64,
+    pub cost_usd: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModelSpend {
+    pub model_name: String,
+    pub cost_usd: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DailyUsage {
+    // UTC date, YYYY-MM-DD
+    pub day: String,
+    pub tokens_input: i64,
+    pub tokens_output: i64,
+    pub cost_usd: f64,
+    pub account_count: i64,
+}
+
+// Spend attributed to one tag; accounts with several tags count towards each
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TagSpend {
+    pub tag: String,
+    pub account_count: i64,
+    pub tokens_input: i64,
+    pub tokens_output: i64,
+    pub cost_usd: f64,
+}
+
+// Changes to an existing account; a None part is left as it is
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AccountUpdate {
+    pub name: Option<String>,
+    pub favorite: Option<bool>,
+    // Replace the account's tags and group memberships
+    pub tags: Option<Vec<String>>,
+    pub group_ids: Option<Vec<String>>,
+    // Set alongside the account's other settings
+    #[serde(default)]
+    pub settings: HashMap<String, String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AccountGroup {
+    pub id: String,
+    pub name: String,
+    pub account_ids: Vec<String>,
+    pub quota_threshold_percent: Option<f64>,
+    pub cost_threshold_usd: Option<f64>,
+    pub last_notified_at: Option<i64>,
+    pub created_at: i64,
+}
+
+// Sends matching alerts to `channels`; see `services::routing`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NotificationRoute {
+    pub id: String,
+    pub name: String,
+    pub min_threshold: Option<f64>,
+    pub max_threshold: Option<f64>,
+    pub account_id: Option<String>,
+    pub account_tag: Option<String>,
+    // "desktop", "webhook", "email" and/or "push"
+    pub channels: Vec<String>,
+    pub created_at: i64,
+}
+
+// Combined latest snapshot of a group's members. Quota totals only include
+// members that report both a limit and a remaining amount.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GroupSummary {
+    pub group_id: String,
+    pub account_count: i64,
+    pub tokens_input: i64,
+    pub tokens_output: i64,
+    pub cost_usd: f64,
+    pub quota_limit: Option<i64>,
+    pub quota_remaining: Option<i64>,
+    pub percent_used: Option<f64>,
+}
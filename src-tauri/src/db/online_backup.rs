@@ -0,0 +1,98 @@
+use std::ffi::{CStr, CString};
+use std::os::raw::c_char;
+use std::path::Path;
+use std::ptr::{self, NonNull};
+use anyhow::{bail, Context, Result};
+use libsqlite3_sys as ffi;
+
+// Pages copied per backup step; between steps other connections can keep writing
+const PAGES_PER_STEP: i32 = 1024;
+// How long to keep retrying a step while the other side is locked (in 100ms sleeps)
+const MAX_BUSY_RETRIES: u32 = 50;
+const MAIN: &[u8] = b"main\0";
+
+pub enum Direction {
+    // Copy the live database into the file
+    ToFile,
+    // Replace the live database's contents with the file's
+    FromFile,
+}
+
+// Copies between an open connection and the database file at `path` with SQLite's
+// online backup API, which yields a consistent image even while the database is in
+// use, unlike copying the file (and its WAL) directly. `key` must be the live
+// database's SQLCipher key, if any: SQLCipher only backs up between databases
+// sharing a key.
+pub fn copy(live: NonNull<ffi::sqlite3>, path: &Path, key: Option<&str>, direction: Direction) -> Result<()> {
+    let c_path = CString::new(path.to_string_lossy().as_bytes()).context("Invalid backup path")?;
+    let flags = match direction {
+        Direction::ToFile => ffi::SQLITE_OPEN_READWRITE | ffi::SQLITE_OPEN_CREATE,
+        Direction::FromFile => ffi::SQLITE_OPEN_READONLY,
+    };
+
+    let mut file = ptr::null_mut();
+    // SAFETY: `file` is only used while open and closed on every path below; `live` is
+    // a valid handle the caller holds exclusively for the duration of this call
+    unsafe {
+        let rc = ffi::sqlite3_open_v2(c_path.as_ptr(), &mut file, flags, ptr::null());
+        let result = if rc != ffi::SQLITE_OK {
+            Err(anyhow::anyhow!("Failed to open {}: {}", path.display(), error_message(file)))
+        } else {
+            apply_key(file, key).and_then(|_| match direction {
+                Direction::ToFile => run(live.as_ptr(), file),
+                Direction::FromFile => run(file, live.as_ptr()),
+            })
+        };
+        ffi::sqlite3_close(file);
+        result
+    }
+}
+
+unsafe fn apply_key(db: *mut ffi::sqlite3, key: Option<&str>) -> Result<()> {
+    let Some(key) = key else {
+        return Ok(());
+    };
+
+    let sql = CString::new(format!("PRAGMA key = \"{}\"", key))?;
+    let rc = ffi::sqlite3_exec(db, sql.as_ptr(), None, ptr::null_mut(), ptr::null_mut());
+    if rc != ffi::SQLITE_OK {
+        bail!("Failed to key backup file: {}", error_message(db));
+    }
+    Ok(())
+}
+
+unsafe fn run(source: *mut ffi::sqlite3, dest: *mut ffi::sqlite3) -> Result<()> {
+    let main = MAIN.as_ptr() as *const c_char;
+    let backup = ffi::sqlite3_backup_init(dest, main, source, main);
+    if backup.is_null() {
+        bail!("Failed to start backup: {}", error_message(dest));
+    }
+
+    let mut retries = 0;
+    let step = loop {
+        match ffi::sqlite3_backup_step(backup, PAGES_PER_STEP) {
+            ffi::SQLITE_OK => continue,
+            ffi::SQLITE_BUSY | ffi::SQLITE_LOCKED if retries < MAX_BUSY_RETRIES => {
+                retries += 1;
+                ffi::sqlite3_sleep(100);
+            }
+            rc => break rc,
+        }
+    };
+
+    let finish = ffi::sqlite3_backup_finish(backup);
+    if step != ffi::SQLITE_DONE {
+        bail!("Backup failed: {}", CStr::from_ptr(ffi::sqlite3_errstr(step)).to_string_lossy());
+    }
+    if finish != ffi::SQLITE_OK {
+        bail!("Backup failed: {}", error_message(dest));
+    }
+    Ok(())
+}
+
+unsafe fn error_message(db: *mut ffi::sqlite3) -> String {
+    if db.is_null() {
+        return "out of memory".to_string();
+    }
+    CStr::from_ptr(ffi::sqlite3_errmsg(db)).to_string_lossy().into_owned()
+}
@@ -0,0 +1,128 @@
+use std::path::{Path, PathBuf};
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+
+// Profiles are independent databases (accounts, settings, history) under one data
+// directory. The default profile keeps the original location, so existing installs
+// become it unchanged; every other profile lives in `profiles/<id>/`.
+const PROFILES_FILE: &str = "profiles.json";
+const PROFILES_DIR: &str = "profiles";
+pub const DEFAULT_PROFILE: &str = "default";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Profile {
+    pub id: String,
+    pub name: String,
+    pub created_at: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProfileList {
+    // Opened at startup; changing it takes effect on the next start
+    pub active: String,
+    pub profiles: Vec<Profile>,
+}
+
+impl Default for ProfileList {
+    fn default() -> Self {
+        Self {
+            active: DEFAULT_PROFILE.to_string(),
+            profiles: vec![Profile {
+                id: DEFAULT_PROFILE.to_string(),
+                name: "Default".to_string(),
+                created_at: 0,
+            }],
+        }
+    }
+}
+
+pub fn load(data_dir: &Path) -> ProfileList {
+    let list = std::fs::read_to_string(data_dir.join(PROFILES_FILE))
+        .ok()
+        .and_then(|json| serde_json::from_str::<ProfileList>(&json).ok())
+        .unwrap_or_default();
+
+    // A hand-edited file could point at a profile that no longer exists
+    if list.profiles.iter().any(|p| p.id == list.active) {
+        list
+    } else {
+        ProfileList { active: DEFAULT_PROFILE.to_string(), ..list }
+    }
+}
+
+fn save(data_dir: &Path, list: &ProfileList) -> Result<()> {
+    let json = serde_json::to_string_pretty(list)?;
+    std::fs::write(data_dir.join(PROFILES_FILE), json).context("Failed to save profiles")?;
+    Ok(())
+}
+
+pub fn dir(data_dir: &Path, id: &str) -> PathBuf {
+    if id == DEFAULT_PROFILE {
+        data_dir.to_path_buf()
+    } else {
+        data_dir.join(PROFILES_DIR).join(id)
+    }
+}
+
+// Directory holding the active profile's database, created if needed
+pub fn active_dir(data_dir: &Path) -> Result<PathBuf> {
+    let dir = dir(data_dir, &load(data_dir).active);
+    std::fs::create_dir_all(&dir).context("Failed to create profile directory")?;
+    Ok(dir)
+}
+
+pub fn create(data_dir: &Path, name: &str) -> Result<Profile> {
+    let name = name.trim();
+    if name.is_empty() {
+        bail!("Profile name is required");
+    }
+
+    let mut list = load(data_dir);
+    if list.profiles.iter().any(|p| p.name.eq_ignore_ascii_case(name)) {
+        bail!("A profile named {} already exists", name);
+    }
+
+    let profile = Profile {
+        id: uuid::Uuid::new_v4().to_string(),
+        name: name.to_string(),
+        created_at: chrono::Utc::now().timestamp(),
+    };
+    list.profiles.push(profile.clone());
+    save(data_dir, &list)?;
+
+    Ok(profile)
+}
+
+pub fn set_active(data_dir: &Path, id: &str) -> Result<()> {
+    let mut list = load(data_dir);
+    if !list.profiles.iter().any(|p| p.id == id) {
+        bail!("Profile {} not found", id);
+    }
+
+    list.active = id.to_string();
+    save(data_dir, &list)
+}
+
+// Removes the profile and its database. The default and active profiles can't be deleted.
+pub fn delete(data_dir: &Path, id: &str) -> Result<()> {
+    let mut list = load(data_dir);
+    if id == DEFAULT_PROFILE {
+        bail!("The default profile can't be deleted");
+    }
+    if id == list.active {
+        bail!("Switch to another profile before deleting this one");
+    }
+    if !list.profiles.iter().any(|p| p.id == id) {
+        bail!("Profile {} not found", id);
+    }
+
+    list.profiles.retain(|p| p.id != id);
+    save(data_dir, &list)?;
+
+    let dir = dir(data_dir, id);
+    if dir.exists() {
+        std::fs::remove_dir_all(&dir).context("Failed to delete profile data")?;
+    }
+
+    Ok(())
+}
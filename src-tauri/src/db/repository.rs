@@ -1,48 +1,446 @@
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::time::Duration;
 use anyhow::{Context, Result};
+use chrono::Datelike;
 use sqlx::{SqlitePool, Row};
+use sqlx::sqlite::{SqliteConnectOptions, SqliteJournalMode, SqlitePoolOptions, SqliteSynchronous};
 use super::models::*;
+use super::online_backup::{self, Direction};
+
+// SQLite allows one writer at a time, so a handful of connections covers the
+// scheduler, the UI and background jobs without piling up lock contention
+const MAX_CONNECTIONS: u32 = 5;
+const BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+// Models listed in the dashboard summary
+const DASHBOARD_TOP_MODELS: i64 = 5;
 
 pub struct Repository {
     pool: SqlitePool,
+    // SQLCipher key; attached plaintext files then need an explicit empty key
+    key: Option<String>,
 }
 
 impl Repository {
-    pub async fn new(database_url: &str) -> Result<Self> {
-        let pool = SqlitePool::connect(database_url)
+    // `key` opens a SQLCipher database (see `db::cipher`); None for a plaintext one
+    pub async fn new(database_url: &str, key: Option<&str>) -> Result<Self> {
+        // WAL lets UI reads proceed while the scheduler writes; the busy timeout makes
+        // competing writers wait their turn instead of failing with "database is locked"
+        let options = SqliteConnectOptions::from_str(database_url)
+            .context("Invalid database URL")?
+            .create_if_missing(true)
+            .journal_mode(SqliteJournalMode::Wal)
+            .synchronous(SqliteSynchronous::Normal)
+            .busy_timeout(BUSY_TIMEOUT)
+            .foreign_keys(true);
+        // sqlx issues `key` before any other pragma, as SQLCipher requires
+        let options = match key {
+            Some(key) => options.pragma("key", format!("\"{}\"", key)),
+            None => options,
+        };
+
+        let pool = SqlitePoolOptions::new()
+            .max_connections(MAX_CONNECTIONS)
+            .connect_with(options)
             .await
             .context("Failed to connect to database")?;
 
-        // Run migrations
-        sqlx::query(include_str!("schema.sql"))
-            .execute(&pool)
+        let repo = Self { pool, key: key.map(str::to_string) };
+        repo.upgrade_legacy_layout().await?;
+
+        sqlx::migrate!()
+            .run(&repo.pool)
             .await
             .context("Failed to run database migrations")?;
 
-        Ok(Self { pool })
+        Ok(repo)
     }
 
-    // Account operations
-    pub async fn insert_account(&self, account: &Account) -> Result<()> {
+    // Databases created before versioned migrations replayed a schema file on every
+    // start and patched it up in place. Bring those up to the baseline migration's
+    // layout once, so 0001 applies cleanly and later migrations can assume it.
+    async fn upgrade_legacy_layout(&self) -> Result<()> {
+        let migrated: Option<String> = sqlx::query_scalar(
+            "SELECT name FROM sqlite_master WHERE type = 'table' AND name = '_sqlx_migrations'"
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .context("Failed to inspect migration state")?;
+
+        let has_accounts: Option<String> = sqlx::query_scalar(
+            "SELECT name FROM sqlite_master WHERE type = 'table' AND name = 'accounts'"
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .context("Failed to inspect accounts table")?;
+
+        if migrated.is_some() || has_accounts.is_none() {
+            return Ok(());
+        }
+
+        tracing::info!("Upgrading pre-migration database layout");
+        self.drop_provider_check().await?;
+
+        // Columns added after the initial release
+        for column in [
+            "rpm_limit", "rpm_remaining", "tpm_limit", "tpm_remaining",
+            "requests_limit", "requests_remaining", "requests_reset_at", "rpm_reset_at", "tpm_reset_at",
+        ] {
+            self.ensure_column("quota_snapshots", column, "INTEGER").await?;
+        }
+        self.ensure_column("accounts", "needs_reauth", "INTEGER NOT NULL DEFAULT 0").await?;
+        self.ensure_column("model_usage", "tokens_cached_input", "INTEGER DEFAULT 0").await?;
+        self.ensure_column("model_usage", "tokens_reasoning", "INTEGER DEFAULT 0").await?;
+        for table in ["quota_snapshots", "model_usage"] {
+            self.ensure_column(table, "images_generated", "INTEGER").await?;
+            self.ensure_column(table, "audio_seconds", "REAL").await?;
+            self.ensure_column(table, "video_seconds", "REAL").await?;
+        }
+
+        Ok(())
+    }
+
+    // `CREATE TABLE IF NOT EXISTS` leaves existing tables untouched, so columns
+    // introduced later have to be added explicitly
+    async fn ensure_column(&self, table: &str, column: &str, definition: &str) -> Result<()> {
+        let exists: Option<String> = sqlx::query_scalar(
+            "SELECT name FROM pragma_table_info(?) WHERE name = ?"
+        )
+        .bind(table)
+        .bind(column)
+        .fetch_optional(&self.pool)
+        .await
+        .context("Failed to inspect table columns")?;
+
+        if exists.is_none() {
+            sqlx::query(&format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, definition))
+                .execute(&self.pool)
+                .await
+                .with_context(|| format!("Failed to add column {}.{}", table, column))?;
+        }
+
+        Ok(())
+    }
+
+    // Older databases pin `accounts.provider` to a fixed list via a CHECK constraint,
+    // which rejects any provider added later. SQLite can't drop a constraint in place,
+    // so the table is rebuilt once without it.
+    async fn drop_provider_check(&self) -> Result<()> {
+        let table_sql: Option<String> = sqlx::query_scalar(
+            "SELECT sql FROM sqlite_master WHERE type = 'table' AND name = 'accounts'"
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .context("Failed to inspect accounts table")?;
+
+        if !table_sql.map_or(false, |sql| sql.contains("CHECK (provider IN")) {
+            return Ok(());
+        }
+
+        // Foreign keys must be off while the old table is dropped, otherwise the
+        // cascade would wipe every snapshot. The pragma is per-connection.
+        let mut conn = self.pool.acquire().await.context("Failed to acquire connection")?;
+
+        sqlx::query("PRAGMA foreign_keys = OFF")
+            .execute(&mut *conn)
+            .await
+            .context("Failed to disable foreign keys")?;
+
+        let result = sqlx::query(
+            "BEGIN;
+             CREATE TABLE accounts_new (
+                 id TEXT PRIMARY KEY,
+                 provider TEXT NOT NULL,
+                 name TEXT NOT NULL,
+                 credentials_encrypted BLOB NOT NULL,
+                 created_at INTEGER NOT NULL,
+                 last_synced INTEGER
+             );
+             INSERT INTO accounts_new SELECT id, provider, name, credentials_encrypted, created_at, last_synced FROM accounts;
+             DROP TABLE accounts;
+             ALTER TABLE accounts_new RENAME TO accounts;
+             COMMIT;"
+        )
+        .execute(&mut *conn)
+        .await
+        .context("Failed to rebuild accounts table");
+
+        if result.is_err() {
+            let _ = sqlx::query("ROLLBACK").execute(&mut *conn).await;
+        }
+
+        sqlx::query("PRAGMA foreign_keys = ON")
+            .execute(&mut *conn)
+            .await
+            .context("Failed to re-enable foreign keys")?;
+
+        result?;
+        Ok(())
+    }
+
+    // Writes a consistent copy of the database to `path`, with credentials blanked out
+    // since they're only readable with this machine's master key
+    pub async fn backup_to(&self, path: &std::path::Path) -> Result<()> {
+        if self.key.is_some() {
+            // VACUUM INTO would keep the local key; backups carry their own protection
+            let mut conn = self.pool.acquire().await.context("Failed to acquire connection")?;
+            sqlx::query("ATTACH DATABASE ? AS plaintext KEY ''")
+                .bind(path.display().to_string())
+                .execute(&mut *conn)
+                .await
+                .context("Failed to create database copy")?;
+            let exported = sqlx::query("SELECT sqlcipher_export('plaintext')")
+                .execute(&mut *conn)
+                .await
+                .context("Failed to copy database");
+            let _ = sqlx::query("DETACH DATABASE plaintext").execute(&mut *conn).await;
+            exported?;
+        } else {
+            sqlx::query("VACUUM INTO ?")
+                .bind(path.display().to_string())
+                .execute(&self.pool)
+                .await
+                .context("Failed to copy database")?;
+        }
+
+        let copy = SqlitePool::connect(&format!("sqlite://{}", path.display()))
+            .await
+            .context("Failed to open database copy")?;
+        sqlx::query("UPDATE accounts SET credentials_encrypted = X''")
+            .execute(&copy)
+            .await
+            .context("Failed to scrub credentials from database copy")?;
+        // Secrets kept in settings: the SMTP password, the webhook signing secret and
+        // its plain-text predecessor
+        sqlx::query("DELETE FROM settings WHERE key IN ('smtp_password', 'webhook_signing_secret', 'webhook_secret')")
+            .execute(&copy)
+            .await
+            .context("Failed to scrub secrets from database copy")?;
+        sqlx::query("VACUUM")
+            .execute(&copy)
+            .await
+            .context("Failed to compact database copy")?;
+        copy.close().await;
+
+        Ok(())
+    }
+
+    // Replaces every table's contents with those of the database at `path`, which
+    // must already be migrated to this schema (open it with `Repository::new` first)
+    pub async fn restore_from(&self, path: &std::path::Path) -> Result<()> {
+        let tables: Vec<String> = sqlx::query_scalar(
+            "SELECT name FROM sqlite_master
+             WHERE type = 'table' AND name NOT LIKE 'sqlite_%' AND name != '_sqlx_migrations'"
+        )
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to list tables")?;
+
+        // Same reasoning as drop_provider_check: no cascades while tables are emptied
+        let mut conn = self.pool.acquire().await.context("Failed to acquire connection")?;
+
+        sqlx::query("PRAGMA foreign_keys = OFF")
+            .execute(&mut *conn)
+            .await
+            .context("Failed to disable foreign keys")?;
+
+        let result = async {
+            // An encrypted main database would otherwise try its own key on the backup
+            let attach = if self.key.is_some() {
+                "ATTACH DATABASE ? AS backup KEY ''"
+            } else {
+                "ATTACH DATABASE ? AS backup"
+            };
+            sqlx::query(attach)
+                .bind(path.display().to_string())
+                .execute(&mut *conn)
+                .await
+                .context("Failed to attach backup")?;
+
+            sqlx::query("BEGIN").execute(&mut *conn).await.context("Failed to begin restore")?;
+            for table in &tables {
+                // Column order can differ between databases that grew columns at
+                // different times, so copy by name
+                let columns: Vec<String> = sqlx::query_scalar("SELECT name FROM pragma_table_info(?)")
+                    .bind(table)
+                    .fetch_all(&mut *conn)
+                    .await
+                    .context("Failed to inspect table columns")?;
+                let columns = columns.join(", ");
+
+                sqlx::query(&format!("DELETE FROM main.{}", table))
+                    .execute(&mut *conn)
+                    .await
+                    .with_context(|| format!("Failed to clear {}", table))?;
+                sqlx::query(&format!(
+                    "INSERT INTO main.{table} ({columns}) SELECT {columns} FROM backup.{table}",
+                    table = table,
+                    columns = columns
+                ))
+                .execute(&mut *conn)
+                .await
+                .with_context(|| format!("Failed to restore {}", table))?;
+            }
+            sqlx::query("COMMIT").execute(&mut *conn).await.context("Failed to commit restore")?;
+            Ok::<(), anyhow::Error>(())
+        }
+        .await;
+
+        if result.is_err() {
+            let _ = sqlx::query("ROLLBACK").execute(&mut *conn).await;
+        }
+        let _ = sqlx::query("DETACH DATABASE backup").execute(&mut *conn).await;
+
+        sqlx::query("PRAGMA foreign_keys = ON")
+            .execute(&mut *conn)
+            .await
+            .context("Failed to re-enable foreign keys")?;
+
+        result
+    }
+
+    // Online backup of the whole database, credentials included, encrypted with the
+    // same key as the live database. For local recovery; see `backup_to` for exports.
+    pub async fn snapshot_to(&self, path: &std::path::Path) -> Result<()> {
+        let mut conn = self.pool.acquire().await.context("Failed to acquire connection")?;
+        let mut handle = conn.lock_handle().await.context("Failed to lock connection")?;
+        // The copy can take a while on a large database and sleeps while it's busy; the
+        // locked handle can't leave this task, so run it in place instead of spawning
+        tokio::task::block_in_place(|| {
+            online_backup::copy(handle.as_raw_handle(), path, self.key.as_deref(), Direction::ToFile)
+        })
+    }
+
+    // Replaces the database with a snapshot taken by `snapshot_to`, then migrates it
+    // in case it predates the current schema
+    pub async fn restore_snapshot(&self, path: &std::path::Path) -> Result<()> {
+        {
+            let mut conn = self.pool.acquire().await.context("Failed to acquire connection")?;
+            let mut handle = conn.lock_handle().await.context("Failed to lock connection")?;
+            tokio::task::block_in_place(|| {
+                online_backup::copy(handle.as_raw_handle(), path, self.key.as_deref(), Direction::FromFile)
+            })?;
+        }
+
+        sqlx::migrate!()
+            .run(&self.pool)
+            .await
+            .context("Failed to run database migrations")?;
+
+        Ok(())
+    }
+
+    // Maintenance operations
+    // Size of the main database file in bytes, from its page count
+    pub async fn database_size(&self) -> Result<i64> {
+        let size: i64 = sqlx::query_scalar(
+            "SELECT page_count * page_size FROM pragma_page_count(), pragma_page_size()"
+        )
+        .fetch_one(&self.pool)
+        .await
+        .context("Failed to read database size")?;
+
+        Ok(size)
+    }
+
+    // Returns the problems found; empty when the database is intact
+    pub async fn integrity_check(&self) -> Result<Vec<String>> {
+        let rows: Vec<String> = sqlx::query_scalar("PRAGMA integrity_check")
+            .fetch_all(&self.pool)
+            .await
+            .context("Failed to run integrity check")?;
+
+        Ok(rows.into_iter().filter(|row| row != "ok").collect())
+    }
+
+    pub async fn vacuum(&self) -> Result<()> {
+        sqlx::query("VACUUM")
+            .execute(&self.pool)
+            .await
+            .context("Failed to vacuum database")?;
+        // VACUUM goes through the WAL; fold it back in so the file actually shrinks
+        sqlx::query("PRAGMA wal_checkpoint(TRUNCATE)")
+            .execute(&self.pool)
+            .await
+            .context("Failed to checkpoint database")?;
+
+        Ok(())
+    }
+
+    pub async fn analyze(&self) -> Result<()> {
+        sqlx::query("ANALYZE")
+            .execute(&self.pool)
+            .await
+            .context("Failed to analyze database")?;
+
+        Ok(())
+    }
+
+    pub async fn log_maintenance(&self, ran_at: i64, integrity_ok: bool, size_before: i64, size_after: i64) -> Result<()> {
         sqlx::query(
-            "INSERT INTO accounts (id, provider, name, credentials_encrypted, created_at, last_synced)
-             VALUES (?, ?, ?, ?, ?, ?)"
+            "INSERT INTO maintenance_log (ran_at, integrity_ok, size_before, size_after) VALUES (?, ?, ?, ?)"
         )
-        .bind(&account.id)
-        .bind(&account.provider)
-        .bind(&account.name)
-        .bind(&account.credentials_encrypted)
-        .bind(account.created_at)
-        .bind(account.last_synced)
+        .bind(ran_at)
+        .bind(integrity_ok)
+        .bind(size_before)
+        .bind(size_after)
         .execute(&self.pool)
         .await
-        .context("Failed to insert account")?;
+        .context("Failed to record maintenance run")?;
+
+        Ok(())
+    }
+
+    pub async fn get_last_maintenance(&self) -> Result<Option<i64>> {
+        let ran_at: Option<i64> = sqlx::query_scalar("SELECT MAX(ran_at) FROM maintenance_log")
+            .fetch_one(&self.pool)
+            .await
+            .context("Failed to fetch last maintenance run")?;
+
+        Ok(ran_at)
+    }
+
+    pub async fn close(&self) {
+        self.pool.close().await;
+    }
+
+    // Account operations
+    // New accounts go after every other account; `account.sort_order` is ignored
+    pub async fn insert_account(&self, account: &Account) -> Result<()> {
+        self.insert_accounts(std::slice::from_ref(account)).await
+    }
+
+    // Adds all of the accounts, in order, or none of them
+    pub async fn insert_accounts(&self, accounts: &[Account]) -> Result<()> {
+        let mut tx = self.pool.begin().await.context("Failed to begin transaction")?;
 
+        for account in accounts {
+            sqlx::query(
+                "INSERT INTO accounts (id, provider, name, credentials_encrypted, created_at, last_synced, needs_reauth, sort_order, favorite)
+                 VALUES (?, ?, ?, ?, ?, ?, ?, (SELECT COALESCE(MAX(sort_order) + 1, 0) FROM accounts), ?)"
+            )
+            .bind(&account.id)
+            .bind(&account.provider)
+            .bind(&account.name)
+            .bind(&account.credentials_encrypted)
+            .bind(account.created_at)
+            .bind(account.last_synced)
+            .bind(account.needs_reauth)
+            .bind(account.favorite)
+            .execute(&mut *tx)
+            .await
+            .context("Failed to insert account")?;
+        }
+
+        tx.commit().await.context("Failed to save accounts")?;
         Ok(())
     }
 
     pub async fn get_all_accounts(&self) -> Result<Vec<Account>> {
         let accounts = sqlx::query_as::<_, Account>(
-            "SELECT id, provider, name, credentials_encrypted, created_at, last_synced FROM accounts"
+            "SELECT id, provider, name, credentials_encrypted, created_at, last_synced, needs_reauth, sort_order, favorite
+             FROM accounts
+             ORDER BY favorite DESC, sort_order, created_at"
         )
         .fetch_all(&self.pool)
         .await
@@ -53,7 +451,7 @@ impl Repository {
 
     pub async fn get_account(&self, id: &str) -> Result<Option<Account>> {
         let account = sqlx::query_as::<_, Account>(
-            "SELECT id, provider, name, credentials_encrypted, created_at, last_synced
+            "SELECT id, provider, name, credentials_encrypted, created_at, last_synced, needs_reauth, sort_order, favorite
              FROM accounts WHERE id = ?"
         )
         .bind(id)
@@ -64,6 +462,108 @@ impl Repository {
         Ok(account)
     }
 
+    // Applies every part of the update or none of it
+    pub async fn update_account(&self, id: &str, update: &AccountUpdate) -> Result<()> {
+        let mut tx = self.pool.begin().await.context("Failed to begin transaction")?;
+
+        let exists = sqlx::query("SELECT 1 FROM accounts WHERE id = ?")
+            .bind(id)
+            .fetch_optional(&mut *tx)
+            .await
+            .context("Failed to fetch account")?;
+        if exists.is_none() {
+            anyhow::bail!("Account {} not found", id);
+        }
+
+        if let Some(name) = &update.name {
+            sqlx::query("UPDATE accounts SET name = ? WHERE id = ?")
+                .bind(name)
+                .bind(id)
+                .execute(&mut *tx)
+                .await
+                .context("Failed to rename account")?;
+        }
+
+        if let Some(favorite) = update.favorite {
+            sqlx::query("UPDATE accounts SET favorite = ? WHERE id = ?")
+                .bind(favorite)
+                .bind(id)
+                .execute(&mut *tx)
+                .await
+                .context("Failed to update account favorite")?;
+        }
+
+        if let Some(tags) = &update.tags {
+            sqlx::query("DELETE FROM account_tags WHERE account_id = ?")
+                .bind(id)
+                .execute(&mut *tx)
+                .await
+                .context("Failed to clear account tags")?;
+
+            for tag in tags {
+                sqlx::query("INSERT OR IGNORE INTO account_tags (account_id, tag) VALUES (?, ?)")
+                    .bind(id)
+                    .bind(tag)
+                    .execute(&mut *tx)
+                    .await
+                    .context("Failed to add account tag")?;
+            }
+        }
+
+        if let Some(group_ids) = &update.group_ids {
+            sqlx::query("DELETE FROM account_group_members WHERE account_id = ?")
+                .bind(id)
+                .execute(&mut *tx)
+                .await
+                .context("Failed to clear account group memberships")?;
+
+            for group_id in group_ids {
+                sqlx::query("INSERT OR IGNORE INTO account_group_members (group_id, account_id) VALUES (?, ?)")
+                    .bind(group_id)
+                    .bind(id)
+                    .execute(&mut *tx)
+                    .await
+                    .context("Failed to add account group member")?;
+            }
+        }
+
+        for (key, value) in &update.settings {
+            sqlx::query("INSERT OR REPLACE INTO account_settings (account_id, key, value) VALUES (?, ?, ?)")
+                .bind(id)
+                .bind(key)
+                .bind(value)
+                .execute(&mut *tx)
+                .await
+                .context("Failed to set account setting")?;
+        }
+
+        tx.commit().await.context("Failed to update account")?;
+        Ok(())
+    }
+
+    // Numbers the listed accounts in order, ahead of any left out
+    pub async fn reorder_accounts(&self, account_ids: &[String]) -> Result<()> {
+        let mut tx = self.pool.begin().await.context("Failed to begin transaction")?;
+
+        sqlx::query("UPDATE accounts SET sort_order = sort_order + ?")
+            .bind(account_ids.len() as i64)
+            .execute(&mut *tx)
+            .await
+            .context("Failed to shift account order")?;
+
+        for (position, id) in account_ids.iter().enumerate() {
+            sqlx::query("UPDATE accounts SET sort_order = ? WHERE id = ?")
+                .bind(position as i64)
+                .bind(id)
+                .execute(&mut *tx)
+                .await
+                .context("Failed to set account order")?;
+        }
+
+        tx.commit().await.context("Failed to reorder accounts")?;
+        Ok(())
+    }
+
     pub async fn delete_account(&self, id: &str) -> Result<()> {
         sqlx::query("DELETE FROM accounts WHERE id = ?")
             .bind(id)
@@ -85,12 +585,57 @@ impl Repository {
         Ok(())
     }
 
+    pub async fn update_account_credentials(&self, id: &str, credentials_encrypted: &[u8]) -> Result<()> {
+        sqlx::query("UPDATE accounts SET credentials_encrypted = ?, needs_reauth = 0 WHERE id = ?")
+            .bind(credentials_encrypted)
+            .bind(id)
+            .execute(&self.pool)
+            .await
+            .context("Failed to update account credentials")?;
+
+        Ok(())
+    }
+
+    // Swaps every listed account's credentials in one transaction, for re-keying
+    pub async fn replace_account_credentials(&self, credentials: &[(String, Vec<u8>)]) -> Result<()> {
+        let mut tx = self.pool.begin().await.context("Failed to begin transaction")?;
+
+        for (id, encrypted) in credentials {
+            sqlx::query("UPDATE accounts SET credentials_encrypted = ? WHERE id = ?")
+                .bind(encrypted)
+                .bind(id)
+                .execute(&mut *tx)
+                .await
+                .context("Failed to update account credentials")?;
+        }
+
+        tx.commit().await.context("Failed to commit credentials")?;
+        Ok(())
+    }
+
+    pub async fn set_account_needs_reauth(&self, id: &str, needs_reauth: bool) -> Result<()> {
+        sqlx::query("UPDATE accounts SET needs_reauth = ? WHERE id = ?")
+            .bind(needs_reauth)
+            .bind(id)
+            .execute(&self.pool)
+            .await
+            .context("Failed to update account re-auth flag")?;
+
+        Ok(())
+    }
+
     // Quota snapshot operations
+    // Also records the usage consumed since the account's previous snapshot
     pub async fn insert_quota_snapshot(&self, snapshot: &QuotaSnapshot) -> Result<()> {
-        sqlx::query(
+        let mut tx = self.pool.begin().await.context("Failed to begin transaction")?;
+
+        let snapshot_id = sqlx::query(
             "INSERT INTO quota_snapshots
-             (account_id, timestamp, tokens_input, tokens_output, cost_usd, quota_limit, quota_remaining, metadata)
-             VALUES (?, ?, ?, ?, ?, ?, ?, ?)"
+             (account_id, timestamp, tokens_input, tokens_output, cost_usd, quota_limit, quota_remaining, metadata,
+              rpm_limit, rpm_remaining, tpm_limit, tpm_remaining,
+              requests_limit, requests_remaining, requests_reset_at, rpm_reset_at, tpm_reset_at,
+              images_generated, audio_seconds, video_seconds)
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)"
         )
         .bind(&snapshot.account_id)
         .bind(snapshot.timestamp)
@@ -100,19 +645,119 @@ impl Repository {
         .bind(snapshot.quota_limit)
         .bind(snapshot.quota_remaining)
         .bind(&snapshot.metadata)
-        .execute(&self.pool)
+        .bind(snapshot.rpm_limit)
+        .bind(snapshot.rpm_remaining)
+        .bind(snapshot.tpm_limit)
+        .bind(snapshot.tpm_remaining)
+        .bind(snapshot.requests_limit)
+        .bind(snapshot.requests_remaining)
+        .bind(snapshot.requests_reset_at)
+        .bind(snapshot.rpm_reset_at)
+        .bind(snapshot.tpm_reset_at)
+        .bind(snapshot.images_generated)
+        .bind(snapshot.audio_seconds)
+        .bind(snapshot.video_seconds)
+        .execute(&mut *tx)
+        .await
+        .context("Failed to insert quota snapshot")?
+        .last_insert_rowid();
+
+        // A drop in a running total means the provider's period reset
+        sqlx::query(
+            "INSERT INTO usage_deltas (snapshot_id, account_id, timestamp, interval_seconds, tokens_input, tokens_output, cost_usd)
+             SELECT cur.id, cur.account_id, cur.timestamp, cur.timestamp - prev.timestamp,
+                    CASE WHEN cur.tokens_input IS NULL OR prev.tokens_input IS NULL THEN NULL
+                         WHEN cur.tokens_input >= prev.tokens_input THEN cur.tokens_input - prev.tokens_input
+                         ELSE cur.tokens_input END,
+                    CASE WHEN cur.tokens_output IS NULL OR prev.tokens_output IS NULL THEN NULL
+                         WHEN cur.tokens_output >= prev.tokens_output THEN cur.tokens_output - prev.tokens_output
+                         ELSE cur.tokens_output END,
+                    CASE WHEN cur.cost_usd IS NULL OR prev.cost_usd IS NULL THEN NULL
+                         WHEN cur.cost_usd >= prev.cost_usd THEN cur.cost_usd - prev.cost_usd
+                         ELSE cur.cost_usd END
+             FROM quota_snapshots cur
+             JOIN quota_snapshots prev ON prev.id = (
+                 SELECT id FROM quota_snapshots INDEXED BY idx_quota_snapshots_account_timestamp
+                 WHERE account_id = cur.account_id AND timestamp <= cur.timestamp AND id != cur.id
+                 ORDER BY timestamp DESC, id DESC
+                 LIMIT 1
+             )
+             WHERE cur.id = ?"
+        )
+        .bind(snapshot_id)
+        .execute(&mut *tx)
         .await
-        .context("Failed to insert quota snapshot")?;
+        .context("Failed to record usage delta")?;
 
+        tx.commit().await.context("Failed to insert quota snapshot")?;
         Ok(())
     }
 
+    // UTC dates (YYYY-MM-DD) in [since, until) on which the account has snapshots
+    pub async fn get_snapshot_days(&self, account_id: &str, since: i64, until: i64) -> Result<Vec<String>> {
+        let days = sqlx::query_scalar(
+            "SELECT DISTINCT strftime('%Y-%m-%d', timestamp, 'unixepoch')
+             FROM quota_snapshots
+             WHERE account_id = ? AND timestamp >= ? AND timestamp < ?"
+        )
+        .bind(account_id)
+        .bind(since)
+        .bind(until)
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to fetch snapshot days")?;
+
+        Ok(days)
+    }
+
+    // Oldest first
+    pub async fn get_usage_deltas(&self, account_id: &str, since: i64) -> Result<Vec<UsageDelta>> {
+        let deltas = sqlx::query_as::<_, UsageDelta>(
+            "SELECT account_id, timestamp, interval_seconds, tokens_input, tokens_output, cost_usd
+             FROM usage_deltas
+             WHERE account_id = ? AND timestamp >= ?
+             ORDER BY timestamp ASC"
+        )
+        .bind(account_id)
+        .bind(since)
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to fetch usage deltas")?;
+
+        Ok(deltas)
+    }
+
+    // The newest `limit` deltas, oldest first
+    pub async fn get_recent_usage_deltas(&self, account_id: &str, limit: i64) -> Result<Vec<UsageDelta>> {
+        let deltas = sqlx::query_as::<_, UsageDelta>(
+            "SELECT * FROM (
+                 SELECT account_id, timestamp, interval_seconds, tokens_input, tokens_output, cost_usd
+                 FROM usage_deltas
+                 WHERE account_id = ?
+                 ORDER BY timestamp DESC
+                 LIMIT ?
+             ) ORDER BY timestamp ASC"
+        )
+        .bind(account_id)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to fetch recent usage deltas")?;
+
+        Ok(deltas)
+    }
+
     #[allow(dead_code)]
+    // Pinned to the (account_id, timestamp DESC) index: a single index seek rather than
+    // letting the planner pick the timestamp-only index and scan every account's rows
     pub async fn get_latest_snapshot(&self, account_id: &str) -> Result<Option<QuotaSnapshot>> {
         let snapshot = sqlx::query_as::<_, QuotaSnapshot>(
             "SELECT id, account_id, timestamp, tokens_input, tokens_output, cost_usd,
-                    quota_limit, quota_remaining, metadata
-             FROM quota_snapshots
+                    quota_limit, quota_remaining, metadata,
+                    rpm_limit, rpm_remaining, tpm_limit, tpm_remaining,
+                    requests_limit, requests_remaining, requests_reset_at, rpm_reset_at, tpm_reset_at,
+                    images_generated, audio_seconds, video_seconds
+             FROM quota_snapshots INDEXED BY idx_quota_snapshots_account_timestamp
              WHERE account_id = ?
              ORDER BY timestamp DESC
              LIMIT 1"
@@ -125,16 +770,23 @@ impl Repository {
         Ok(snapshot)
     }
 
-    pub async fn get_snapshots_since(&self, account_id: &str, since: i64) -> Result<Vec<QuotaSnapshot>> {
+    // Oldest first; `limit` None returns everything from `offset` on
+    pub async fn get_snapshots_since(&self, account_id: &str, since: i64, limit: Option<u32>, offset: u32) -> Result<Vec<QuotaSnapshot>> {
         let snapshots = sqlx::query_as::<_, QuotaSnapshot>(
             "SELECT id, account_id, timestamp, tokens_input, tokens_output, cost_usd,
-                    quota_limit, quota_remaining, metadata
+                    quota_limit, quota_remaining, metadata,
+                    rpm_limit, rpm_remaining, tpm_limit, tpm_remaining,
+                    requests_limit, requests_remaining, requests_reset_at, rpm_reset_at, tpm_reset_at,
+                    images_generated, audio_seconds, video_seconds
              FROM quota_snapshots
              WHERE account_id = ? AND timestamp >= ?
-             ORDER BY timestamp ASC"
+             ORDER BY timestamp ASC, id ASC
+             LIMIT ? OFFSET ?"
         )
         .bind(account_id)
         .bind(since)
+        .bind(limit.map_or(-1, i64::from))
+        .bind(offset)
         .fetch_all(&self.pool)
         .await
         .context("Failed to fetch snapshots")?;
@@ -142,12 +794,105 @@ impl Repository {
         Ok(snapshots)
     }
 
+    // Aggregates complete `bucket_seconds` buckets from `since` onwards into usage_rollups.
+    // Totals and remaining come from the closing (latest) snapshot of each bucket.
+    pub async fn rollup_snapshots(&self, granularity: &str, bucket_seconds: i64, since: i64) -> Result<u64> {
+        let current_bucket = chrono::Utc::now().timestamp() / bucket_seconds * bucket_seconds;
+
+        let result = sqlx::query(
+            "INSERT OR REPLACE INTO usage_rollups
+             (account_id, granularity, bucket_start, sample_count, tokens_input, tokens_output,
+              cost_usd, quota_limit, quota_remaining, min_quota_remaining)
+             SELECT account_id, ?, bucket_start, sample_count, tokens_input, tokens_output,
+                    cost_usd, quota_limit, quota_remaining, min_quota_remaining
+             FROM (
+                 SELECT account_id, bucket_start, tokens_input, tokens_output, cost_usd, quota_limit, quota_remaining,
+                        COUNT(*) OVER bucket AS sample_count,
+                        MIN(quota_remaining) OVER bucket AS min_quota_remaining,
+                        ROW_NUMBER() OVER (bucket ORDER BY timestamp DESC) AS recency
+                 FROM (
+                     SELECT *, (timestamp / ?) * ? AS bucket_start
+                     FROM quota_snapshots
+                     WHERE timestamp >= ? AND timestamp < ?
+                 )
+                 WINDOW bucket AS (PARTITION BY account_id, bucket_start)
+             )
+             WHERE recency = 1"
+        )
+        .bind(granularity)
+        .bind(bucket_seconds)
+        .bind(bucket_seconds)
+        .bind(since / bucket_seconds * bucket_seconds)
+        .bind(current_bucket)
+        .execute(&self.pool)
+        .await
+        .context("Failed to roll up snapshots")?;
+
+        Ok(result.rows_affected())
+    }
+
+    pub async fn get_latest_rollup_start(&self, granularity: &str) -> Result<Option<i64>> {
+        let latest: Option<i64> = sqlx::query_scalar(
+            "SELECT MAX(bucket_start) FROM usage_rollups WHERE granularity = ?"
+        )
+        .bind(granularity)
+        .fetch_one(&self.pool)
+        .await
+        .context("Failed to fetch latest rollup")?;
+
+        Ok(latest)
+    }
+
+    // Rollups shaped like snapshots, so charts can take either
+    pub async fn get_rollups_since(&self, account_id: &str, granularity: &str, since: i64, limit: Option<u32>, offset: u32) -> Result<Vec<QuotaSnapshot>> {
+        let rows = sqlx::query(
+            "SELECT account_id, bucket_start, tokens_input, tokens_output, cost_usd, quota_limit, quota_remaining
+             FROM usage_rollups
+             WHERE account_id = ? AND granularity = ? AND bucket_start >= ?
+             ORDER BY bucket_start ASC
+             LIMIT ? OFFSET ?"
+        )
+        .bind(account_id)
+        .bind(granularity)
+        .bind(since)
+        .bind(limit.map_or(-1, i64::from))
+        .bind(offset)
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to fetch rollups")?;
+
+        Ok(rows.into_iter().map(|r| QuotaSnapshot {
+            id: None,
+            account_id: r.get("account_id"),
+            timestamp: r.get("bucket_start"),
+            tokens_input: r.get("tokens_input"),
+            tokens_output: r.get("tokens_output"),
+            cost_usd: r.get("cost_usd"),
+            quota_limit: r.get("quota_limit"),
+            quota_remaining: r.get("quota_remaining"),
+            metadata: None,
+            rpm_limit: None,
+            rpm_remaining: None,
+            tpm_limit: None,
+            tpm_remaining: None,
+            requests_limit: None,
+            requests_remaining: None,
+            requests_reset_at: None,
+            rpm_reset_at: None,
+            tpm_reset_at: None,
+            images_generated: None,
+            audio_seconds: None,
+            video_seconds: None,
+        }).collect())
+    }
+
     // Model usage operations
     pub async fn insert_model_usage(&self, usage: &ModelUsage) -> Result<()> {
         sqlx::query(
             "INSERT INTO model_usage
-             (account_id, model_name, timestamp, tokens_input, tokens_output, cost_usd, request_count)
-             VALUES (?, ?, ?, ?, ?, ?, ?)"
+             (account_id, model_name, timestamp, tokens_input, tokens_output, cost_usd, request_count,
+              tokens_cached_input, tokens_reasoning, images_generated, audio_seconds, video_seconds)
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)"
         )
         .bind(&usage.account_id)
         .bind(&usage.model_name)
@@ -156,6 +901,11 @@ impl Repository {
         .bind(usage.tokens_output)
         .bind(usage.cost_usd)
         .bind(usage.request_count)
+        .bind(usage.tokens_cached_input)
+        .bind(usage.tokens_reasoning)
+        .bind(usage.images_generated)
+        .bind(usage.audio_seconds)
+        .bind(usage.video_seconds)
         .execute(&self.pool)
         .await
         .context("Failed to insert model usage")?;
@@ -163,15 +913,19 @@ impl Repository {
         Ok(())
     }
 
-    pub async fn get_model_usage_since(&self, account_id: &str, since: i64) -> Result<Vec<ModelUsage>> {
+    pub async fn get_model_usage_since(&self, account_id: &str, since: i64, limit: Option<u32>, offset: u32) -> Result<Vec<ModelUsage>> {
         let usage = sqlx::query_as::<_, ModelUsage>(
-            "SELECT id, account_id, model_name, timestamp, tokens_input, tokens_output, cost_usd, request_count
+            "SELECT id, account_id, model_name, timestamp, tokens_input, tokens_output, cost_usd, request_count,
+                    tokens_cached_input, tokens_reasoning, images_generated, audio_seconds, video_seconds
              FROM model_usage
              WHERE account_id = ? AND timestamp >= ?
-             ORDER BY timestamp ASC"
+             ORDER BY timestamp ASC, id ASC
+             LIMIT ? OFFSET ?"
         )
         .bind(account_id)
         .bind(since)
+        .bind(limit.map_or(-1, i64::from))
+        .bind(offset)
         .fetch_all(&self.pool)
         .await
         .context("Failed to fetch model usage")?;
@@ -179,77 +933,1168 @@ impl Repository {
         Ok(usage)
     }
 
-    // Notification state operations
-    pub async fn get_notification_state(&self, account_id: &str) -> Result<Option<NotificationState>> {
-        let state = sqlx::query_as::<_, NotificationState>(
-            "SELECT account_id, last_75_percent_notified, last_90_percent_notified, last_95_percent_notified
-             FROM notification_state
-             WHERE account_id = ?"
-        )
-        .bind(account_id)
-        .fetch_optional(&self.pool)
-        .await
-        .context("Failed to fetch notification state")?;
-
-        Ok(state)
-    }
+    // Snapshots and model usage in [start, end), oldest first. An empty account list exports every account.
+    pub async fn get_usage_export(&self, account_ids: &[String], start: i64, end: i64) -> Result<Vec<UsageExportRow>> {
+        let account_filter = account_filter("a.id", account_ids);
 
-    pub async fn update_notification_state(&self, state: &NotificationState) -> Result<()> {
-        sqlx::query(
-            "INSERT OR REPLACE INTO notification_state
-             (account_id, last_75_percent_notified, last_90_percent_notified, last_95_percent_notified)
-             VALUES (?, ?, ?, ?)"
-        )
-        .bind(&state.account_id)
-        .bind(state.last_75_percent_notified)
-        .bind(state.last_90_percent_notified)
-        .bind(state.last_95_percent_notified)
-        .execute(&self.pool)
-        .await
-        .context("Failed to update notification state")?;
+        let sql = format!(
+            "SELECT 'snapshot' AS record_type, a.id AS account_id, a.name AS account_name, a.provider,
+                    s.timestamp, NULL AS model_name, s.tokens_input, s.tokens_output, s.cost_usd,
+                    NULL AS request_count, s.quota_limit, s.quota_remaining
+             FROM quota_snapshots s JOIN accounts a ON a.id = s.account_id
+             WHERE s.timestamp >= ? AND s.timestamp < ? {filter}
+             UNION ALL
+             SELECT 'model', a.id, a.name, a.provider,
+                    m.timestamp, m.model_name, m.tokens_input, m.tokens_output, m.cost_usd,
+                    m.request_count, NULL, NULL
+             FROM model_usage m JOIN accounts a ON a.id = m.account_id
+             WHERE m.timestamp >= ? AND m.timestamp < ? {filter}
+             ORDER BY timestamp ASC, account_id, record_type DESC",
+            filter = account_filter
+        );
 
-        Ok(())
-    }
+        let mut query = sqlx::query_as::<_, UsageExportRow>(&sql).bind(start).bind(end);
+        for id in account_ids {
+            query = query.bind(id);
+        }
+        query = query.bind(start).bind(end);
+        for id in account_ids {
+            query = query.bind(id);
+        }
 
-    // Settings operations
-    pub async fn get_setting(&self, key: &str) -> Result<Option<String>> {
-        let row = sqlx::query("SELECT value FROM settings WHERE key = ?")
-            .bind(key)
-            .fetch_optional(&self.pool)
+        let rows = query
+            .fetch_all(&self.pool)
             .await
-            .context("Failed to fetch setting")?;
+            .context("Failed to fetch usage for export")?;
 
-        Ok(row.map(|r| r.get("value")))
+        Ok(rows)
     }
 
-    pub async fn set_setting(&self, key: &str, value: &str) -> Result<()> {
-        sqlx::query("INSERT OR REPLACE INTO settings (key, value) VALUES (?, ?)")
-            .bind(key)
-            .bind(value)
-            .execute(&self.pool)
+    // Totals, daily averages and per-day buckets for the dashboard. Snapshots repeat the
+    // provider's usage window, so each account contributes its last snapshot of each day.
+    pub async fn get_usage_summary(&self, account_ids: &[String], since: i64, until: i64) -> Result<UsageSummary> {
+        let daily_sql = format!(
+            "{closing}
+             SELECT day,
+                    COALESCE(SUM(tokens_input), 0) AS tokens_input,
+                    COALESCE(SUM(tokens_output), 0) AS tokens_output,
+                    COALESCE(SUM(cost_usd), 0.0) AS cost_usd,
+                    COUNT(*) AS account_count
+             FROM closing
+             GROUP BY day
+             ORDER BY day ASC",
+            closing = closing_snapshots_cte(account_ids)
+        );
+
+        let mut query = sqlx::query(&daily_sql).bind(since).bind(until);
+        for id in account_ids {
+            query = query.bind(id);
+        }
+        let daily_rows = query
+            .fetch_all(&self.pool)
             .await
-            .context("Failed to set setting")?;
+            .context("Failed to fetch daily usage")?;
 
-        Ok(())
-    }
+        let daily: Vec<DailyUsage> = daily_rows.into_iter().map(|r| DailyUsage {
+            day: r.get("day"),
+            tokens_input: r.get("tokens_input"),
+            tokens_output: r.get("tokens_output"),
+            cost_usd: r.get("cost_usd"),
+            account_count: r.get("account_count"),
+        }).collect();
 
-    pub async fn cleanup_old_data(&self, days: i64) -> Result<()> {
-        let cutoff = chrono::Utc::now().timestamp() - (days * 86400);
+        let totals_sql = format!(
+            "{closing},
+             per_day AS (
+                 SELECT day, SUM(tokens_input) AS tokens_input, SUM(tokens_output) AS tokens_output,
+                        SUM(cost_usd) AS cost_usd
+                 FROM closing
+                 GROUP BY day
+             )
+             SELECT COUNT(*) AS days,
+                    COALESCE(SUM(tokens_input), 0) AS total_tokens_input,
+                    COALESCE(SUM(tokens_output), 0) AS total_tokens_output,
+                    COALESCE(SUM(cost_usd), 0.0) AS total_cost_usd,
+                    COALESCE(AVG(tokens_input), 0.0) AS avg_daily_tokens_input,
+                    COALESCE(AVG(tokens_output), 0.0) AS avg_daily_tokens_output,
+                    COALESCE(AVG(cost_usd), 0.0) AS avg_daily_cost_usd
+             FROM per_day",
+            closing = closing_snapshots_cte(account_ids)
+        );
 
-        sqlx::query("DELETE FROM quota_snapshots WHERE timestamp < ?")
-            .bind(cutoff)
-            .execute(&self.pool)
+        let mut query = sqlx::query(&totals_sql).bind(since).bind(until);
+        for id in account_ids {
+            query = query.bind(id);
+        }
+        let totals = query
+            .fetch_one(&self.pool)
             .await
-            .context("Failed to cleanup quota snapshots")?;
+            .context("Failed to fetch usage totals")?;
 
-        sqlx::query("DELETE FROM model_usage WHERE timestamp < ?")
-            .bind(cutoff)
-            .execute(&self.pool)
+        let by_provider_sql = format!(
+            "{closing}
+             SELECT a.provider,
+                    COUNT(DISTINCT c.account_id) AS account_count,
+                    COALESCE(SUM(c.cost_usd), 0.0) AS cost_usd
+             FROM closing c JOIN accounts a ON a.id = c.account_id
+             GROUP BY a.provider
+             ORDER BY cost_usd DESC",
+            closing = closing_snapshots_cte(account_ids)
+        );
+
+        let mut query = sqlx::query(&by_provider_sql).bind(since).bind(until);
+        for id in account_ids {
+            query = query.bind(id);
+        }
+        let by_provider = query
+            .fetch_all(&self.pool)
+            .await
+            .context("Failed to fetch spend by provider")?
+            .into_iter()
+            .map(|r| ProviderSpend {
+                provider: r.get("provider"),
+                account_count: r.get("account_count"),
+                cost_usd: r.get("cost_usd"),
+            })
+            .collect();
+
+        let top_models = self.get_top_models(account_ids, since, until, DASHBOARD_TOP_MODELS).await?
+            .into_iter()
+            .map(|(model_name, cost_usd)| ModelSpend { model_name, cost_usd })
+            .collect();
+
+        // The previous month's span is cut short at its end, so March 31st compares
+        // against all of February
+        let until_date = chrono::DateTime::from_timestamp(until, 0).unwrap_or_default().date_naive();
+        let month_start = until_date.with_day(1).unwrap_or(until_date);
+        let previous_month_start = month_start.checked_sub_months(chrono::Months::new(1)).unwrap_or(month_start);
+        let month_start = month_start.and_time(chrono::NaiveTime::MIN).and_utc().timestamp();
+        let previous_month_start = previous_month_start.and_time(chrono::NaiveTime::MIN).and_utc().timestamp();
+        let previous_until = (previous_month_start + (until - month_start)).min(month_start);
+
+        let month_to_date_cost_usd = self.get_total_spend(account_ids, month_start, until).await?;
+        let previous_month_cost_usd = self.get_total_spend(account_ids, previous_month_start, previous_until).await?;
+        let month_over_month_percent = (previous_month_cost_usd > 0.0)
+            .then(|| (month_to_date_cost_usd - previous_month_cost_usd) / previous_month_cost_usd * 100.0);
+
+        Ok(UsageSummary {
+            since,
+            until,
+            days: totals.get("days"),
+            total_tokens_input: totals.get("total_tokens_input"),
+            total_tokens_output: totals.get("total_tokens_output"),
+            total_cost_usd: totals.get("total_cost_usd"),
+            avg_daily_tokens_input: totals.get("avg_daily_tokens_input"),
+            avg_daily_tokens_output: totals.get("avg_daily_tokens_output"),
+            avg_daily_cost_usd: totals.get("avg_daily_cost_usd"),
+            daily,
+            by_provider,
+            top_models,
+            month_to_date_cost_usd,
+            previous_month_cost_usd,
+            month_over_month_percent,
+        })
+    }
+
+    // Per-account usage over [since, until) for statements, highest spend first. Accounts
+    // with no snapshots in the range are left out.
+    pub async fn get_account_spend(&self, account_ids: &[String], since: i64, until: i64) -> Result<Vec<AccountSpend>> {
+        let sql = format!(
+            "{closing}
+             SELECT a.id AS account_id, a.name AS account_name, a.provider,
+                    COALESCE(SUM(c.tokens_input), 0) AS tokens_input,
+                    COALESCE(SUM(c.tokens_output), 0) AS tokens_output,
+                    COALESCE(SUM(c.cost_usd), 0.0) AS cost_usd
+             FROM closing c JOIN accounts a ON a.id = c.account_id
+             GROUP BY a.id
+             ORDER BY cost_usd DESC, a.name",
+            closing = closing_snapshots_cte(account_ids)
+        );
+
+        let mut query = sqlx::query(&sql).bind(since).bind(until);
+        for id in account_ids {
+            query = query.bind(id);
+        }
+        let rows = query
+            .fetch_all(&self.pool)
+            .await
+            .context("Failed to fetch spend by account")?;
+
+        Ok(rows.into_iter().map(|r| AccountSpend {
+            account_id: r.get("account_id"),
+            account_name: r.get("account_name"),
+            provider: r.get("provider"),
+            tokens_input: r.get("tokens_input"),
+            tokens_output: r.get("tokens_output"),
+            cost_usd: r.get("cost_usd"),
+        }).collect())
+    }
+
+    // Per-account, per-model usage over [since, until) from each day's closing model
+    // usage, highest spend first
+    pub async fn get_model_spend(&self, account_ids: &[String], since: i64, until: i64) -> Result<Vec<ModelSpendDetail>> {
+        let sql = format!(
+            "SELECT account_id, model_name,
+                    COALESCE(SUM(tokens_input), 0) AS tokens_input,
+                    COALESCE(SUM(tokens_output), 0) AS tokens_output,
+                    COALESCE(SUM(request_count), 0) AS request_count,
+                    COALESCE(SUM(cost_usd), 0.0) AS cost_usd
+             FROM (
+                 SELECT account_id, model_name, strftime('%Y-%m-%d', timestamp, 'unixepoch') AS day,
+                        MAX(timestamp), tokens_input, tokens_output, request_count, cost_usd
+                 FROM model_usage
+                 WHERE timestamp >= ? AND timestamp < ? {}
+                 GROUP BY account_id, model_name, day
+             )
+             GROUP BY account_id, model_name
+             ORDER BY cost_usd DESC, model_name",
+            account_filter("account_id", account_ids)
+        );
+
+        let mut query = sqlx::query(&sql).bind(since).bind(until);
+        for id in account_ids {
+            query = query.bind(id);
+        }
+        let rows = query
+            .fetch_all(&self.pool)
+            .await
+            .context("Failed to fetch spend by model")?;
+
+        Ok(rows.into_iter().map(|r| ModelSpendDetail {
+            account_id: r.get("account_id"),
+            model_name: r.get("model_name"),
+            tokens_input: r.get("tokens_input"),
+            tokens_output: r.get("tokens_output"),
+            request_count: r.get("request_count"),
+            cost_usd: r.get("cost_usd"),
+        }).collect())
+    }
+
+    // Spend over [since, until) from each account's closing snapshot per day
+    async fn get_total_spend(&self, account_ids: &[String], since: i64, until: i64) -> Result<f64> {
+        let sql = format!(
+            "{closing}
+             SELECT COALESCE(SUM(cost_usd), 0.0) FROM closing",
+            closing = closing_snapshots_cte(account_ids)
+        );
+
+        let mut query = sqlx::query_scalar::<_, f64>(&sql).bind(since).bind(until);
+        for id in account_ids {
+            query = query.bind(id);
+        }
+        let spend = query
+            .fetch_one(&self.pool)
+            .await
+            .context("Failed to fetch total spend")?;
+
+        Ok(spend)
+    }
+
+    // Spend per account over [since, until), highest first, from each day's closing snapshot
+    pub async fn get_spend_by_account(&self, since: i64, until: i64) -> Result<Vec<(String, f64)>> {
+        let sql = format!(
+            "{closing}
+             SELECT account_id, COALESCE(SUM(cost_usd), 0.0) AS cost_usd
+             FROM closing
+             GROUP BY account_id
+             ORDER BY cost_usd DESC",
+            closing = closing_snapshots_cte(&[])
+        );
+
+        let rows = sqlx::query(&sql)
+            .bind(since)
+            .bind(until)
+            .fetch_all(&self.pool)
+            .await
+            .context("Failed to fetch spend by account")?;
+
+        Ok(rows.into_iter().map(|r| (r.get("account_id"), r.get("cost_usd"))).collect())
+    }
+
+    // The `limit` models with the highest spend over [since, until), across the given
+    // accounts or all of them
+    pub async fn get_top_models(&self, account_ids: &[String], since: i64, until: i64, limit: i64) -> Result<Vec<(String, f64)>> {
+        let sql = format!(
+            "SELECT model_name, SUM(cost_usd) AS cost_usd FROM (
+                 SELECT account_id, model_name, strftime('%Y-%m-%d', timestamp, 'unixepoch') AS day,
+                        MAX(timestamp), cost_usd
+                 FROM model_usage
+                 WHERE timestamp >= ? AND timestamp < ? {}
+                 GROUP BY account_id, model_name, day
+             )
+             GROUP BY model_name
+             ORDER BY cost_usd DESC
+             LIMIT ?",
+            account_filter("account_id", account_ids)
+        );
+
+        let mut query = sqlx::query(&sql).bind(since).bind(until);
+        for id in account_ids {
+            query = query.bind(id);
+        }
+        let rows = query
+            .bind(limit)
+            .fetch_all(&self.pool)
+            .await
+            .context("Failed to fetch top models")?;
+
+        Ok(rows.into_iter().map(|r| (r.get("model_name"), r.get("cost_usd"))).collect())
+    }
+
+    // API key usage operations
+    pub async fn insert_api_key_usage(&self, usage: &ApiKeyUsage) -> Result<()> {
+        sqlx::query(
+            "INSERT INTO api_key_usage
+             (account_id, api_key_id, timestamp, tokens_input, tokens_output, cost_usd, request_count)
+             VALUES (?, ?, ?, ?, ?, ?, ?)"
+        )
+        .bind(&usage.account_id)
+        .bind(&usage.api_key_id)
+        .bind(usage.timestamp)
+        .bind(usage.tokens_input)
+        .bind(usage.tokens_output)
+        .bind(usage.cost_usd)
+        .bind(usage.request_count)
+        .execute(&self.pool)
+        .await
+        .context("Failed to insert API key usage")?;
+
+        Ok(())
+    }
+
+    pub async fn get_api_key_usage_since(&self, account_id: &str, since: i64, limit: Option<u32>, offset: u32) -> Result<Vec<ApiKeyUsage>> {
+        let usage = sqlx::query_as::<_, ApiKeyUsage>(
+            "SELECT id, account_id, api_key_id, timestamp, tokens_input, tokens_output, cost_usd, request_count
+             FROM api_key_usage
+             WHERE account_id = ? AND timestamp >= ?
+             ORDER BY timestamp ASC, id ASC
+             LIMIT ? OFFSET ?"
+        )
+        .bind(account_id)
+        .bind(since)
+        .bind(limit.map_or(-1, i64::from))
+        .bind(offset)
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to fetch API key usage")?;
+
+        Ok(usage)
+    }
+
+    // Notification state operations
+    pub async fn get_notification_state(&self, account_id: &str) -> Result<Option<NotificationState>> {
+        let state = sqlx::query_as::<_, NotificationState>(
+            "SELECT account_id, last_75_percent_notified, last_90_percent_notified, last_95_percent_notified
+             FROM notification_state
+             WHERE account_id = ?"
+        )
+        .bind(account_id)
+        .fetch_optional(&self.pool)
+        .await
+        .context("Failed to fetch notification state")?;
+
+        Ok(state)
+    }
+
+    pub async fn update_notification_state(&self, state: &NotificationState) -> Result<()> {
+        sqlx::query(
+            "INSERT OR REPLACE INTO notification_state
+             (account_id, last_75_percent_notified, last_90_percent_notified, last_95_percent_notified)
+             VALUES (?, ?, ?, ?)"
+        )
+        .bind(&state.account_id)
+        .bind(state.last_75_percent_notified)
+        .bind(state.last_90_percent_notified)
+        .bind(state.last_95_percent_notified)
+        .execute(&self.pool)
+        .await
+        .context("Failed to update notification state")?;
+
+        Ok(())
+    }
+
+    // Fetch error operations
+    pub async fn get_account_notification_prefs(&self, account_id: &str) -> Result<Option<AccountNotificationPrefs>> {
+        let prefs = sqlx::query_as::<_, AccountNotificationPrefs>(
+            "SELECT account_id, enabled, warning_percent, caution_percent, critical_percent, channels, snoozed_until,
+                    burn_rate_usd_per_hour
+             FROM account_notification_prefs
+             WHERE account_id = ?"
+        )
+        .bind(account_id)
+        .fetch_optional(&self.pool)
+        .await
+        .context("Failed to fetch notification preferences")?;
+
+        Ok(prefs)
+    }
+
+    pub async fn set_account_notification_prefs(&self, prefs: &AccountNotificationPrefs) -> Result<()> {
+        sqlx::query(
+            "INSERT OR REPLACE INTO account_notification_prefs
+             (account_id, enabled, warning_percent, caution_percent, critical_percent, channels, snoozed_until,
+              burn_rate_usd_per_hour)
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?)"
+        )
+        .bind(&prefs.account_id)
+        .bind(prefs.enabled)
+        .bind(prefs.warning_percent)
+        .bind(prefs.caution_percent)
+        .bind(prefs.critical_percent)
+        .bind(prefs.channels.as_ref().map(|channels| channels.join(",")))
+        .bind(prefs.snoozed_until)
+        .bind(prefs.burn_rate_usd_per_hour)
+        .execute(&self.pool)
+        .await
+        .context("Failed to save notification preferences")?;
+
+        Ok(())
+    }
+
+    // None ends a snooze
+    pub async fn set_account_snooze(&self, account_id: &str, until: Option<i64>) -> Result<()> {
+        sqlx::query(
+            "INSERT INTO account_notification_prefs (account_id, snoozed_until) VALUES (?, ?)
+             ON CONFLICT (account_id) DO UPDATE SET snoozed_until = excluded.snoozed_until"
+        )
+        .bind(account_id)
+        .bind(until)
+        .execute(&self.pool)
+        .await
+        .context("Failed to snooze notifications")?;
+
+        Ok(())
+    }
+
+    pub async fn set_account_muted(&self, account_id: &str, muted: bool) -> Result<()> {
+        sqlx::query(
+            "INSERT INTO account_notification_prefs (account_id, enabled) VALUES (?, ?)
+             ON CONFLICT (account_id) DO UPDATE SET enabled = excluded.enabled"
+        )
+        .bind(account_id)
+        .bind(!muted)
+        .execute(&self.pool)
+        .await
+        .context("Failed to mute account")?;
+
+        Ok(())
+    }
+
+    pub async fn insert_fetch_error(&self, error: &FetchError) -> Result<()> {
+        sqlx::query(
+            "INSERT INTO fetch_errors (account_id, provider, error_kind, http_status, message, timestamp)
+             VALUES (?, ?, ?, ?, ?, ?)"
+        )
+        .bind(&error.account_id)
+        .bind(&error.provider)
+        .bind(&error.error_kind)
+        .bind(error.http_status)
+        .bind(&error.message)
+        .bind(error.timestamp)
+        .execute(&self.pool)
+        .await
+        .context("Failed to record fetch error")?;
+
+        Ok(())
+    }
+
+    pub async fn get_fetch_backoffs(&self) -> Result<Vec<FetchBackoff>> {
+        let backoffs = sqlx::query_as::<_, FetchBackoff>(
+            "SELECT account_id, consecutive_failures, next_attempt_at, tripped_at FROM fetch_backoff"
+        )
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to fetch backoff state")?;
+
+        Ok(backoffs)
+    }
+
+    pub async fn get_fetch_backoff(&self, account_id: &str) -> Result<Option<FetchBackoff>> {
+        let backoff = sqlx::query_as::<_, FetchBackoff>(
+            "SELECT account_id, consecutive_failures, next_attempt_at, tripped_at
+             FROM fetch_backoff
+             WHERE account_id = ?"
+        )
+        .bind(account_id)
+        .fetch_optional(&self.pool)
+        .await
+        .context("Failed to fetch backoff state")?;
+
+        Ok(backoff)
+    }
+
+    pub async fn set_fetch_backoff(&self, backoff: &FetchBackoff) -> Result<()> {
+        sqlx::query(
+            "INSERT OR REPLACE INTO fetch_backoff (account_id, consecutive_failures, next_attempt_at, tripped_at)
+             VALUES (?, ?, ?, ?)"
+        )
+        .bind(&backoff.account_id)
+        .bind(backoff.consecutive_failures)
+        .bind(backoff.next_attempt_at)
+        .bind(backoff.tripped_at)
+        .execute(&self.pool)
+        .await
+        .context("Failed to update backoff state")?;
+
+        Ok(())
+    }
+
+    pub async fn clear_fetch_backoff(&self, account_id: &str) -> Result<()> {
+        sqlx::query("DELETE FROM fetch_backoff WHERE account_id = ?")
+            .bind(account_id)
+            .execute(&self.pool)
+            .await
+            .context("Failed to clear backoff state")?;
+
+        Ok(())
+    }
+
+    // Newest first
+    pub async fn get_fetch_errors(&self, account_id: &str, limit: i64) -> Result<Vec<FetchError>> {
+        let errors = sqlx::query_as::<_, FetchError>(
+            "SELECT id, account_id, provider, error_kind, http_status, message, timestamp
+             FROM fetch_errors
+             WHERE account_id = ?
+             ORDER BY timestamp DESC, id DESC
+             LIMIT ?"
+        )
+        .bind(account_id)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to fetch account errors")?;
+
+        Ok(errors)
+    }
+
+    // Notification history operations
+    pub async fn insert_notification(
+        &self,
+        account_id: Option<&str>,
+        threshold: Option<f64>,
+        title: &str,
+        message: &str,
+        timestamp: i64,
+    ) -> Result<()> {
+        sqlx::query(
+            "INSERT INTO notifications (account_id, threshold, title, message, timestamp)
+             VALUES (?, ?, ?, ?, ?)"
+        )
+        .bind(account_id)
+        .bind(threshold)
+        .bind(title)
+        .bind(message)
+        .bind(timestamp)
+        .execute(&self.pool)
+        .await
+        .context("Failed to record notification")?;
+
+        Ok(())
+    }
+
+    // Newest first
+    pub async fn get_notifications(&self, limit: i64, unacknowledged_only: bool) -> Result<Vec<NotificationRecord>> {
+        let notifications = sqlx::query_as::<_, NotificationRecord>(
+            "SELECT id, account_id, threshold, title, message, timestamp, acknowledged
+             FROM notifications
+             WHERE acknowledged = 0 OR ? = 0
+             ORDER BY timestamp DESC, id DESC
+             LIMIT ?"
+        )
+        .bind(unacknowledged_only)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to fetch notification history")?;
+
+        Ok(notifications)
+    }
+
+    pub async fn acknowledge_notification(&self, id: i64) -> Result<()> {
+        sqlx::query("UPDATE notifications SET acknowledged = 1 WHERE id = ?")
+            .bind(id)
+            .execute(&self.pool)
+            .await
+            .context("Failed to acknowledge notification")?;
+
+        Ok(())
+    }
+
+    // Tag operations
+    pub async fn get_all_account_tags(&self) -> Result<HashMap<String, Vec<String>>> {
+        let rows = sqlx::query("SELECT account_id, tag FROM account_tags ORDER BY tag")
+            .fetch_all(&self.pool)
+            .await
+            .context("Failed to fetch account tags")?;
+
+        let mut tags: HashMap<String, Vec<String>> = HashMap::new();
+        for row in rows {
+            tags.entry(row.get("account_id")).or_default().push(row.get("tag"));
+        }
+        Ok(tags)
+    }
+
+    // Replaces the account's tags with `tags`
+    pub async fn set_account_tags(&self, account_id: &str, tags: &[String]) -> Result<()> {
+        let mut tx = self.pool.begin().await.context("Failed to begin transaction")?;
+
+        sqlx::query("DELETE FROM account_tags WHERE account_id = ?")
+            .bind(account_id)
+            .execute(&mut *tx)
             .await
-            .context("Failed to cleanup model usage")?;
+            .context("Failed to clear account tags")?;
+
+        for tag in tags {
+            sqlx::query("INSERT OR IGNORE INTO account_tags (account_id, tag) VALUES (?, ?)")
+                .bind(account_id)
+                .bind(tag)
+                .execute(&mut *tx)
+                .await
+                .context("Failed to add account tag")?;
+        }
 
+        tx.commit().await.context("Failed to save account tags")?;
         Ok(())
     }
+
+    pub async fn get_spend_by_tag(&self, since: i64, until: i64) -> Result<Vec<TagSpend>> {
+        let sql = format!(
+            "{closing}
+             SELECT t.tag,
+                    COUNT(DISTINCT c.account_id) AS account_count,
+                    COALESCE(SUM(c.tokens_input), 0) AS tokens_input,
+                    COALESCE(SUM(c.tokens_output), 0) AS tokens_output,
+                    COALESCE(SUM(c.cost_usd), 0.0) AS cost_usd
+             FROM closing c JOIN account_tags t ON t.account_id = c.account_id
+             GROUP BY t.tag
+             ORDER BY cost_usd DESC",
+            closing = closing_snapshots_cte(&[])
+        );
+
+        let rows = sqlx::query(&sql)
+            .bind(since)
+            .bind(until)
+            .fetch_all(&self.pool)
+            .await
+            .context("Failed to fetch spend by tag")?;
+
+        Ok(rows.into_iter().map(|r| TagSpend {
+            tag: r.get("tag"),
+            account_count: r.get("account_count"),
+            tokens_input: r.get("tokens_input"),
+            tokens_output: r.get("tokens_output"),
+            cost_usd: r.get("cost_usd"),
+        }).collect())
+    }
+
+    // Account group operations
+    pub async fn get_account_groups(&self) -> Result<Vec<AccountGroup>> {
+        let rows = sqlx::query(
+            "SELECT id, name, quota_threshold_percent, cost_threshold_usd, last_notified_at, created_at
+             FROM account_groups ORDER BY name"
+        )
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to fetch account groups")?;
+
+        let members = sqlx::query("SELECT group_id, account_id FROM account_group_members")
+            .fetch_all(&self.pool)
+            .await
+            .context("Failed to fetch account group members")?;
+
+        let mut account_ids: HashMap<String, Vec<String>> = HashMap::new();
+        for member in members {
+            account_ids.entry(member.get("group_id")).or_default().push(member.get("account_id"));
+        }
+
+        Ok(rows.into_iter().map(|r| {
+            let id: String = r.get("id");
+            AccountGroup {
+                account_ids: account_ids.remove(&id).unwrap_or_default(),
+                id,
+                name: r.get("name"),
+                quota_threshold_percent: r.get("quota_threshold_percent"),
+                cost_threshold_usd: r.get("cost_threshold_usd"),
+                last_notified_at: r.get("last_notified_at"),
+                created_at: r.get("created_at"),
+            }
+        }).collect())
+    }
+
+    // Creates or updates the group and replaces its member list
+    pub async fn upsert_account_group(&self, group: &AccountGroup) -> Result<()> {
+        let mut tx = self.pool.begin().await.context("Failed to begin transaction")?;
+
+        sqlx::query(
+            "INSERT INTO account_groups (id, name, quota_threshold_percent, cost_threshold_usd, created_at)
+             VALUES (?, ?, ?, ?, ?)
+             ON CONFLICT (id) DO UPDATE SET
+                 name = excluded.name,
+                 quota_threshold_percent = excluded.quota_threshold_percent,
+                 cost_threshold_usd = excluded.cost_threshold_usd"
+        )
+        .bind(&group.id)
+        .bind(&group.name)
+        .bind(group.quota_threshold_percent)
+        .bind(group.cost_threshold_usd)
+        .bind(group.created_at)
+        .execute(&mut *tx)
+        .await
+        .context("Failed to save account group")?;
+
+        sqlx::query("DELETE FROM account_group_members WHERE group_id = ?")
+            .bind(&group.id)
+            .execute(&mut *tx)
+            .await
+            .context("Failed to clear account group members")?;
+
+        for account_id in &group.account_ids {
+            sqlx::query("INSERT OR IGNORE INTO account_group_members (group_id, account_id) VALUES (?, ?)")
+                .bind(&group.id)
+                .bind(account_id)
+                .execute(&mut *tx)
+                .await
+                .context("Failed to add account group member")?;
+        }
+
+        tx.commit().await.context("Failed to save account group")?;
+        Ok(())
+    }
+
+    pub async fn delete_account_group(&self, id: &str) -> Result<()> {
+        sqlx::query("DELETE FROM account_groups WHERE id = ?")
+            .bind(id)
+            .execute(&self.pool)
+            .await
+            .context("Failed to delete account group")?;
+
+        Ok(())
+    }
+
+    pub async fn set_group_notified(&self, id: &str, timestamp: i64) -> Result<()> {
+        sqlx::query("UPDATE account_groups SET last_notified_at = ? WHERE id = ?")
+            .bind(timestamp)
+            .bind(id)
+            .execute(&self.pool)
+            .await
+            .context("Failed to update account group notification time")?;
+
+        Ok(())
+    }
+
+    pub async fn get_group_summary(&self, group_id: &str) -> Result<GroupSummary> {
+        let row = sqlx::query(
+            "SELECT COUNT(s.id) AS account_count,
+                    COALESCE(SUM(s.tokens_input), 0) AS tokens_input,
+                    COALESCE(SUM(s.tokens_output), 0) AS tokens_output,
+                    COALESCE(SUM(s.cost_usd), 0.0) AS cost_usd,
+                    SUM(CASE WHEN s.quota_limit > 0 AND s.quota_remaining IS NOT NULL THEN s.quota_limit END) AS quota_limit,
+                    SUM(CASE WHEN s.quota_limit > 0 AND s.quota_remaining IS NOT NULL THEN s.quota_remaining END) AS quota_remaining
+             FROM account_group_members m
+             JOIN quota_snapshots s ON s.id = (
+                 SELECT id FROM quota_snapshots INDEXED BY idx_quota_snapshots_account_timestamp
+                 WHERE account_id = m.account_id
+                 ORDER BY timestamp DESC
+                 LIMIT 1
+             )
+             WHERE m.group_id = ?"
+        )
+        .bind(group_id)
+        .fetch_one(&self.pool)
+        .await
+        .context("Failed to compute group summary")?;
+
+        let quota_limit: Option<i64> = row.get("quota_limit");
+        let quota_remaining: Option<i64> = row.get("quota_remaining");
+        let percent_used = match (quota_limit, quota_remaining) {
+            (Some(limit), Some(remaining)) if limit > 0 => {
+                Some((limit - remaining) as f64 / limit as f64 * 100.0)
+            }
+            _ => None,
+        };
+
+        Ok(GroupSummary {
+            group_id: group_id.to_string(),
+            account_count: row.get("account_count"),
+            tokens_input: row.get("tokens_input"),
+            tokens_output: row.get("tokens_output"),
+            cost_usd: row.get("cost_usd"),
+            quota_limit,
+            quota_remaining,
+            percent_used,
+        })
+    }
+
+    // Notification route operations
+    pub async fn get_notification_routes(&self) -> Result<Vec<NotificationRoute>> {
+        let routes = sqlx::query_as::<_, NotificationRoute>(
+            "SELECT id, name, min_threshold, max_threshold, account_id, account_tag, channels, created_at
+             FROM notification_routes ORDER BY name"
+        )
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to fetch notification routes")?;
+
+        Ok(routes)
+    }
+
+    pub async fn upsert_notification_route(&self, route: &NotificationRoute) -> Result<()> {
+        sqlx::query(
+            "INSERT INTO notification_routes
+                 (id, name, min_threshold, max_threshold, account_id, account_tag, channels, created_at)
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+             ON CONFLICT (id) DO UPDATE SET
+                 name = excluded.name, min_threshold = excluded.min_threshold,
+                 max_threshold = excluded.max_threshold, account_id = excluded.account_id,
+                 account_tag = excluded.account_tag, channels = excluded.channels"
+        )
+        .bind(&route.id)
+        .bind(&route.name)
+        .bind(route.min_threshold)
+        .bind(route.max_threshold)
+        .bind(&route.account_id)
+        .bind(&route.account_tag)
+        .bind(route.channels.join(","))
+        .bind(route.created_at)
+        .execute(&self.pool)
+        .await
+        .context("Failed to save notification route")?;
+
+        Ok(())
+    }
+
+    pub async fn delete_notification_route(&self, id: &str) -> Result<()> {
+        sqlx::query("DELETE FROM notification_routes WHERE id = ?")
+            .bind(id)
+            .execute(&self.pool)
+            .await
+            .context("Failed to delete notification route")?;
+
+        Ok(())
+    }
+
+    // Budget operations
+    pub async fn get_budgets(&self) -> Result<Vec<Budget>> {
+        let budgets = sqlx::query_as::<_, Budget>(
+            "SELECT id, name, scope, target, amount_usd, period, reset_anchor, created_at
+             FROM budgets ORDER BY name"
+        )
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to fetch budgets")?;
+
+        Ok(budgets)
+    }
+
+    pub async fn upsert_budget(&self, budget: &Budget) -> Result<()> {
+        sqlx::query(
+            "INSERT INTO budgets (id, name, scope, target, amount_usd, period, reset_anchor, created_at)
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+             ON CONFLICT (id) DO UPDATE SET
+                 name = excluded.name, scope = excluded.scope, target = excluded.target,
+                 amount_usd = excluded.amount_usd, period = excluded.period,
+                 reset_anchor = excluded.reset_anchor"
+        )
+        .bind(&budget.id)
+        .bind(&budget.name)
+        .bind(&budget.scope)
+        .bind(&budget.target)
+        .bind(budget.amount_usd)
+        .bind(&budget.period)
+        .bind(budget.reset_anchor)
+        .bind(budget.created_at)
+        .execute(&self.pool)
+        .await
+        .context("Failed to save budget")?;
+
+        // A changed budget starts alerting afresh
+        sqlx::query("DELETE FROM budget_alerts WHERE budget_id = ?")
+            .bind(&budget.id)
+            .execute(&self.pool)
+            .await
+            .context("Failed to reset budget alerts")?;
+        sqlx::query("DELETE FROM budget_forecast_alerts WHERE budget_id = ?")
+            .bind(&budget.id)
+            .execute(&self.pool)
+            .await
+            .context("Failed to reset budget alerts")?;
+
+        Ok(())
+    }
+
+    pub async fn delete_budget(&self, id: &str) -> Result<()> {
+        sqlx::query("DELETE FROM budgets WHERE id = ?")
+            .bind(id)
+            .execute(&self.pool)
+            .await
+            .context("Failed to delete budget")?;
+
+        Ok(())
+    }
+
+    // Spend since `since` for a budget scope. Snapshots repeat the provider's usage
+    // window, so like the dashboard summary this sums each last-of-the-day value.
+    pub async fn get_spend_since(&self, scope: &str, target: Option<&str>, since: i64) -> Result<f64> {
+        let spend: Option<f64> = if scope == "model" {
+            sqlx::query_scalar(
+                "SELECT SUM(cost_usd) FROM (
+                     SELECT account_id, model_name, strftime('%Y-%m-%d', timestamp, 'unixepoch') AS day,
+                            MAX(timestamp), cost_usd
+                     FROM model_usage
+                     WHERE timestamp >= ? AND model_name = ?
+                     GROUP BY account_id, model_name, day
+                 )"
+            )
+            .bind(since)
+            .bind(target)
+            .fetch_one(&self.pool)
+            .await
+            .context("Failed to compute model spend")?
+        } else {
+            let filter = match scope {
+                "account" => "AND a.id = ?",
+                "provider" => "AND a.provider = ?",
+                _ => "",
+            };
+            let sql = format!(
+                "SELECT SUM(cost_usd) FROM (
+                     SELECT s.account_id, strftime('%Y-%m-%d', s.timestamp, 'unixepoch') AS day,
+                            MAX(s.timestamp), s.cost_usd
+                     FROM quota_snapshots s JOIN accounts a ON a.id = s.account_id
+                     WHERE s.timestamp >= ? {}
+                     GROUP BY s.account_id, day
+                 )",
+                filter
+            );
+
+            let mut query = sqlx::query_scalar(&sql).bind(since);
+            if !filter.is_empty() {
+                query = query.bind(target);
+            }
+            query
+                .fetch_one(&self.pool)
+                .await
+                .context("Failed to compute spend")?
+        };
+
+        Ok(spend.unwrap_or(0.0))
+    }
+
+    // (period_start, percent) of the last alert sent for a budget
+    pub async fn get_budget_alert(&self, budget_id: &str) -> Result<Option<(i64, i64)>> {
+        let row = sqlx::query("SELECT period_start, percent FROM budget_alerts WHERE budget_id = ?")
+            .bind(budget_id)
+            .fetch_optional(&self.pool)
+            .await
+            .context("Failed to fetch budget alert")?;
+
+        Ok(row.map(|r| (r.get("period_start"), r.get("percent"))))
+    }
+
+    pub async fn set_budget_alert(&self, budget_id: &str, period_start: i64, percent: i64) -> Result<()> {
+        sqlx::query("INSERT OR REPLACE INTO budget_alerts (budget_id, period_start, percent) VALUES (?, ?, ?)")
+            .bind(budget_id)
+            .bind(period_start)
+            .bind(percent)
+            .execute(&self.pool)
+            .await
+            .context("Failed to record budget alert")?;
+
+        Ok(())
+    }
+
+    // period_start of the last forecast alert sent for a budget
+    pub async fn get_budget_forecast_alert(&self, budget_id: &str) -> Result<Option<i64>> {
+        let period_start = sqlx::query_scalar("SELECT period_start FROM budget_forecast_alerts WHERE budget_id = ?")
+            .bind(budget_id)
+            .fetch_optional(&self.pool)
+            .await
+            .context("Failed to fetch budget forecast alert")?;
+
+        Ok(period_start)
+    }
+
+    pub async fn set_budget_forecast_alert(&self, budget_id: &str, period_start: i64) -> Result<()> {
+        sqlx::query("INSERT OR REPLACE INTO budget_forecast_alerts (budget_id, period_start) VALUES (?, ?)")
+            .bind(budget_id)
+            .bind(period_start)
+            .execute(&self.pool)
+            .await
+            .context("Failed to record budget forecast alert")?;
+
+        Ok(())
+    }
+
+    // Settings operations
+    pub async fn get_all_settings(&self) -> Result<HashMap<String, String>> {
+        let rows = sqlx::query("SELECT key, value FROM settings")
+            .fetch_all(&self.pool)
+            .await
+            .context("Failed to fetch settings")?;
+
+        Ok(rows.into_iter().map(|r| (r.get("key"), r.get("value"))).collect())
+    }
+
+    // Writes all values atomically; None deletes the key
+    pub async fn replace_settings(&self, values: &[(&str, Option<String>)]) -> Result<()> {
+        let mut tx = self.pool.begin().await.context("Failed to begin transaction")?;
+
+        for (key, value) in values {
+            match value {
+                Some(value) => {
+                    sqlx::query("INSERT OR REPLACE INTO settings (key, value) VALUES (?, ?)")
+                        .bind(key)
+                        .bind(value)
+                        .execute(&mut *tx)
+                        .await
+                        .context("Failed to set setting")?;
+                }
+                None => {
+                    sqlx::query("DELETE FROM settings WHERE key = ?")
+                        .bind(key)
+                        .execute(&mut *tx)
+                        .await
+                        .context("Failed to clear setting")?;
+                }
+            }
+        }
+
+        tx.commit().await.context("Failed to save settings")?;
+        Ok(())
+    }
+
+    // `pattern` is a SQL LIKE pattern over setting keys
+    pub async fn get_settings_matching(&self, pattern: &str) -> Result<HashMap<String, String>> {
+        let rows = sqlx::query("SELECT key, value FROM settings WHERE key LIKE ?")
+            .bind(pattern)
+            .fetch_all(&self.pool)
+            .await
+            .context("Failed to fetch settings")?;
+
+        Ok(rows.into_iter().map(|r| (r.get("key"), r.get("value"))).collect())
+    }
+
+    pub async fn get_account_settings(&self, account_id: &str) -> Result<HashMap<String, String>> {
+        let rows = sqlx::query("SELECT key, value FROM account_settings WHERE account_id = ?")
+            .bind(account_id)
+            .fetch_all(&self.pool)
+            .await
+            .context("Failed to fetch account settings")?;
+
+        Ok(rows.into_iter().map(|r| (r.get("key"), r.get("value"))).collect())
+    }
+
+    // account_id -> value of one setting, for the accounts that have it
+    pub async fn get_account_setting_values(&self, key: &str) -> Result<HashMap<String, String>> {
+        let rows = sqlx::query("SELECT account_id, value FROM account_settings WHERE key = ?")
+            .bind(key)
+            .fetch_all(&self.pool)
+            .await
+            .context("Failed to fetch account settings")?;
+
+        Ok(rows.into_iter().map(|r| (r.get("account_id"), r.get("value"))).collect())
+    }
+
+    pub async fn set_account_setting(&self, account_id: &str, key: &str, value: &str) -> Result<()> {
+        sqlx::query("INSERT OR REPLACE INTO account_settings (account_id, key, value) VALUES (?, ?, ?)")
+            .bind(account_id)
+            .bind(key)
+            .bind(value)
+            .execute(&self.pool)
+            .await
+            .context("Failed to set account setting")?;
+
+        Ok(())
+    }
+
+    // Pricing override operations
+    pub async fn get_pricing_overrides(&self) -> Result<Vec<PricingOverride>> {
+        let overrides = sqlx::query_as::<_, PricingOverride>(
+            "SELECT provider, model, input_price, output_price, per_minute_price
+             FROM pricing_overrides"
+        )
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to fetch pricing overrides")?;
+
+        Ok(overrides)
+    }
+
+    pub async fn set_pricing_override(&self, pricing: &PricingOverride) -> Result<()> {
+        sqlx::query(
+            "INSERT OR REPLACE INTO pricing_overrides
+             (provider, model, input_price, output_price, per_minute_price)
+             VALUES (?, ?, ?, ?, ?)"
+        )
+        .bind(&pricing.provider)
+        .bind(&pricing.model)
+        .bind(pricing.input_price)
+        .bind(pricing.output_price)
+        .bind(pricing.per_minute_price)
+        .execute(&self.pool)
+        .await
+        .context("Failed to set pricing override")?;
+
+        Ok(())
+    }
+
+    pub async fn delete_pricing_override(&self, provider: &str, model: &str) -> Result<()> {
+        sqlx::query("DELETE FROM pricing_overrides WHERE provider = ? AND model = ?")
+            .bind(provider)
+            .bind(model)
+            .execute(&self.pool)
+            .await
+            .context("Failed to delete pricing override")?;
+
+        Ok(())
+    }
+
+    // Returns the number of rows deleted
+    pub async fn cleanup_old_data(&self, cutoffs: &RetentionCutoffs) -> Result<u64> {
+        let tables = [
+            ("quota_snapshots", "timestamp", cutoffs.snapshots),
+            ("model_usage", "timestamp", cutoffs.model_usage),
+            ("api_key_usage", "timestamp", cutoffs.model_usage),
+            ("usage_rollups", "bucket_start", cutoffs.rollups),
+            ("notifications", "timestamp", cutoffs.history),
+            ("fetch_errors", "timestamp", cutoffs.history),
+        ];
+
+        let mut deleted = 0;
+        for (table, column, cutoff) in tables {
+            deleted += sqlx::query(&format!("DELETE FROM {} WHERE {} < ?", table, column))
+                .bind(cutoff)
+                .execute(&self.pool)
+                .await
+                .with_context(|| format!("Failed to clean up {}", table))?
+                .rows_affected();
+        }
+
+        Ok(deleted)
+    }
+}
+
+// Each account's last snapshot of each UTC day in [since, until); binds since, until, then account ids
+fn closing_snapshots_cte(account_ids: &[String]) -> String {
+    format!(
+        "WITH closing AS (
+             SELECT account_id, strftime('%Y-%m-%d', timestamp, 'unixepoch') AS day, MAX(timestamp),
+                    tokens_input, tokens_output, cost_usd
+             FROM quota_snapshots
+             WHERE timestamp >= ? AND timestamp < ? {}
+             GROUP BY account_id, day
+         )",
+        account_filter("account_id", account_ids)
+    )
+}
+
+// `AND <column> IN (?, ...)` for a non-empty account selection; empty means every account
+fn account_filter(column: &str, account_ids: &[String]) -> String {
+    if account_ids.is_empty() {
+        String::new()
+    } else {
+        format!("AND {} IN ({})", column, vec!["?"; account_ids.len()].join(", "))
+    }
 }
 
 // Implement sqlx::FromRow for custom types
@@ -262,6 +2107,9 @@ impl sqlx::FromRow<'_, sqlx::sqlite::SqliteRow> for Account {
             credentials_encrypted: row.try_get("credentials_encrypted")?,
             created_at: row.try_get("created_at")?,
             last_synced: row.try_get("last_synced")?,
+            needs_reauth: row.try_get("needs_reauth")?,
+            sort_order: row.try_get("sort_order")?,
+            favorite: row.try_get("favorite")?,
         })
     }
 }
@@ -278,6 +2126,31 @@ impl sqlx::FromRow<'_, sqlx::sqlite::SqliteRow> for QuotaSnapshot {
             quota_limit: row.try_get("quota_limit")?,
             quota_remaining: row.try_get("quota_remaining")?,
             metadata: row.try_get("metadata")?,
+            rpm_limit: row.try_get("rpm_limit")?,
+            rpm_remaining: row.try_get("rpm_remaining")?,
+            tpm_limit: row.try_get("tpm_limit")?,
+            tpm_remaining: row.try_get("tpm_remaining")?,
+            requests_limit: row.try_get("requests_limit")?,
+            requests_remaining: row.try_get("requests_remaining")?,
+            requests_reset_at: row.try_get("requests_reset_at")?,
+            rpm_reset_at: row.try_get("rpm_reset_at")?,
+            tpm_reset_at: row.try_get("tpm_reset_at")?,
+            images_generated: row.try_get("images_generated")?,
+            audio_seconds: row.try_get("audio_seconds")?,
+            video_seconds: row.try_get("video_seconds")?,
+        })
+    }
+}
+
+impl sqlx::FromRow<'_, sqlx::sqlite::SqliteRow> for UsageDelta {
+    fn from_row(row: &sqlx::sqlite::SqliteRow) -> sqlx::Result<Self> {
+        Ok(UsageDelta {
+            account_id: row.try_get("account_id")?,
+            timestamp: row.try_get("timestamp")?,
+            interval_seconds: row.try_get("interval_seconds")?,
+            tokens_input: row.try_get("tokens_input")?,
+            tokens_output: row.try_get("tokens_output")?,
+            cost_usd: row.try_get("cost_usd")?,
         })
     }
 }
@@ -293,6 +2166,26 @@ impl sqlx::FromRow<'_, sqlx::sqlite::SqliteRow> for ModelUsage {
             tokens_output: row.try_get("tokens_output")?,
             cost_usd: row.try_get("cost_usd")?,
             request_count: row.try_get("request_count")?,
+            tokens_cached_input: row.try_get("tokens_cached_input")?,
+            tokens_reasoning: row.try_get("tokens_reasoning")?,
+            images_generated: row.try_get("images_generated")?,
+            audio_seconds: row.try_get("audio_seconds")?,
+            video_seconds: row.try_get("video_seconds")?,
+        })
+    }
+}
+
+impl sqlx::FromRow<'_, sqlx::sqlite::SqliteRow> for ApiKeyUsage {
+    fn from_row(row: &sqlx::sqlite::SqliteRow) -> sqlx::Result<Self> {
+        Ok(ApiKeyUsage {
+            id: row.try_get("id")?,
+            account_id: row.try_get("account_id")?,
+            api_key_id: row.try_get("api_key_id")?,
+            timestamp: row.try_get("timestamp")?,
+            tokens_input: row.try_get("tokens_input")?,
+            tokens_output: row.try_get("tokens_output")?,
+            cost_usd: row.try_get("cost_usd")?,
+            request_count: row.try_get("request_count")?,
         })
     }
 }
@@ -307,3 +2200,120 @@ impl sqlx::FromRow<'_, sqlx::sqlite::SqliteRow> for NotificationState {
         })
     }
 }
+
+impl sqlx::FromRow<'_, sqlx::sqlite::SqliteRow> for AccountNotificationPrefs {
+    fn from_row(row: &sqlx::sqlite::SqliteRow) -> sqlx::Result<Self> {
+        let channels: Option<String> = row.try_get("channels")?;
+        Ok(AccountNotificationPrefs {
+            account_id: row.try_get("account_id")?,
+            enabled: row.try_get("enabled")?,
+            warning_percent: row.try_get("warning_percent")?,
+            caution_percent: row.try_get("caution_percent")?,
+            critical_percent: row.try_get("critical_percent")?,
+            channels: channels.map(|channels| channels.split(',').filter(|c| !c.is_empty()).map(str::to_string).collect()),
+            snoozed_until: row.try_get("snoozed_until")?,
+            burn_rate_usd_per_hour: row.try_get("burn_rate_usd_per_hour")?,
+        })
+    }
+}
+
+impl sqlx::FromRow<'_, sqlx::sqlite::SqliteRow> for FetchError {
+    fn from_row(row: &sqlx::sqlite::SqliteRow) -> sqlx::Result<Self> {
+        Ok(FetchError {
+            id: row.try_get("id")?,
+            account_id: row.try_get("account_id")?,
+            provider: row.try_get("provider")?,
+            error_kind: row.try_get("error_kind")?,
+            http_status: row.try_get("http_status")?,
+            message: row.try_get("message")?,
+            timestamp: row.try_get("timestamp")?,
+        })
+    }
+}
+
+impl sqlx::FromRow<'_, sqlx::sqlite::SqliteRow> for FetchBackoff {
+    fn from_row(row: &sqlx::sqlite::SqliteRow) -> sqlx::Result<Self> {
+        Ok(FetchBackoff {
+            account_id: row.try_get("account_id")?,
+            consecutive_failures: row.try_get("consecutive_failures")?,
+            next_attempt_at: row.try_get("next_attempt_at")?,
+            tripped_at: row.try_get("tripped_at")?,
+        })
+    }
+}
+
+impl sqlx::FromRow<'_, sqlx::sqlite::SqliteRow> for NotificationRecord {
+    fn from_row(row: &sqlx::sqlite::SqliteRow) -> sqlx::Result<Self> {
+        Ok(NotificationRecord {
+            id: row.try_get("id")?,
+            account_id: row.try_get("account_id")?,
+            threshold: row.try_get("threshold")?,
+            title: row.try_get("title")?,
+            message: row.try_get("message")?,
+            timestamp: row.try_get("timestamp")?,
+            acknowledged: row.try_get("acknowledged")?,
+        })
+    }
+}
+
+impl sqlx::FromRow<'_, sqlx::sqlite::SqliteRow> for NotificationRoute {
+    fn from_row(row: &sqlx::sqlite::SqliteRow) -> sqlx::Result<Self> {
+        let channels: String = row.try_get("channels")?;
+        Ok(NotificationRoute {
+            id: row.try_get("id")?,
+            name: row.try_get("name")?,
+            min_threshold: row.try_get("min_threshold")?,
+            max_threshold: row.try_get("max_threshold")?,
+            account_id: row.try_get("account_id")?,
+            account_tag: row.try_get("account_tag")?,
+            channels: channels.split(',').filter(|c| !c.is_empty()).map(str::to_string).collect(),
+            created_at: row.try_get("created_at")?,
+        })
+    }
+}
+
+impl sqlx::FromRow<'_, sqlx::sqlite::SqliteRow> for Budget {
+    fn from_row(row: &sqlx::sqlite::SqliteRow) -> sqlx::Result<Self> {
+        Ok(Budget {
+            id: row.try_get("id")?,
+            name: row.try_get("name")?,
+            scope: row.try_get("scope")?,
+            target: row.try_get("target")?,
+            amount_usd: row.try_get("amount_usd")?,
+            period: row.try_get("period")?,
+            reset_anchor: row.try_get("reset_anchor")?,
+            created_at: row.try_get("created_at")?,
+        })
+    }
+}
+
+impl sqlx::FromRow<'_, sqlx::sqlite::SqliteRow> for PricingOverride {
+    fn from_row(row: &sqlx::sqlite::SqliteRow) -> sqlx::Result<Self> {
+        Ok(PricingOverride {
+            provider: row.try_get("provider")?,
+            model: row.try_get("model")?,
+            input_price: row.try_get("input_price")?,
+            output_price: row.try_get("output_price")?,
+            per_minute_price: row.try_get("per_minute_price")?,
+        })
+    }
+}
+
+impl sqlx::FromRow<'_, sqlx::sqlite::SqliteRow> for UsageExportRow {
+    fn from_row(row: &sqlx::sqlite::SqliteRow) -> sqlx::Result<Self> {
+        Ok(UsageExportRow {
+            record_type: row.try_get("record_type")?,
+            account_id: row.try_get("account_id")?,
+            account_name: row.try_get("account_name")?,
+            provider: row.try_get("provider")?,
+            timestamp: row.try_get("timestamp")?,
+            model_name: row.try_get("model_name")?,
+            tokens_input: row.try_get("tokens_input")?,
+            tokens_output: row.try_get("tokens_output")?,
+            cost_usd: row.try_get("cost_usd")?,
+            request_count: row.try_get("request_count")?,
+            quota_limit: row.try_get("quota_limit")?,
+            quota_remaining: row.try_get("quota_remaining")?,
+        })
+    }
+}
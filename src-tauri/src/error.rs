@@ -1,21 +1,31 @@
 use serde::Serialize;
+use crate::redact::redact;
 
 #[derive(Debug, thiserror::Error)]
 pub enum QuonitorError {
     #[error("Database error: {0}")]
     Database(#[from] anyhow::Error),
 
-    #[error("Provider error: {0}")]
+    // Provider and network errors can carry response bodies or URLs with secrets in
+    // them; they're masked wherever the error is displayed, stored or sent to the UI
+    #[error("Provider error: {}", redact(.0))]
     Provider(String),
 
-    #[error("Authentication error: {0}")]
+    #[error("Authentication error: {}", redact(.0))]
     Auth(String),
 
     #[error("Encryption error: {0}")]
     Encryption(String),
 
-    #[error("Network error: {0}")]
-    Network(#[from] reqwest::Error),
+    // A master password is set and hasn't been entered
+    #[error("Credentials are locked")]
+    Locked,
+
+    #[error("Network error: {}", redact(&.0.to_string()))]
+    Network(reqwest::Error),
+
+    #[error("Request timed out: {}", redact(.0))]
+    Timeout(String),
 
     #[error("Serialization error: {0}")]
     Serialization(#[from] serde_json::Error),
@@ -30,6 +40,52 @@ pub enum QuonitorError {
     Tauri(#[from] tauri::Error),
 }
 
+// Timeouts get their own variant so a slow provider reads differently from a dead one
+impl From<reqwest::Error> for QuonitorError {
+    fn from(e: reqwest::Error) -> Self {
+        if e.is_timeout() {
+            QuonitorError::Timeout(e.to_string())
+        } else {
+            QuonitorError::Network(e)
+        }
+    }
+}
+
+impl QuonitorError {
+    // Short machine-readable category, stored alongside logged fetch errors
+    pub fn kind(&self) -> &'static str {
+        match self {
+            QuonitorError::Database(_) => "database",
+            QuonitorError::Provider(_) => "provider",
+            QuonitorError::Auth(_) => "auth",
+            QuonitorError::Encryption(_) => "encryption",
+            QuonitorError::Locked => "locked",
+            QuonitorError::Network(_) => "network",
+            QuonitorError::Timeout(_) => "timeout",
+            QuonitorError::Serialization(_) => "serialization",
+            QuonitorError::Io(_) => "io",
+            QuonitorError::Config(_) => "config",
+            QuonitorError::Tauri(_) => "tauri",
+        }
+    }
+
+    // Providers report non-success responses as e.g. "OpenAI API error (429 Too Many
+    // Requests): ...", so the status is recovered from the first parenthesised code
+    pub fn http_status(&self) -> Option<u16> {
+        match self {
+            QuonitorError::Network(e) => e.status().map(|s| s.as_u16()),
+            QuonitorError::Provider(message) | QuonitorError::Auth(message) => {
+                message.match_indices('(').find_map(|(i, _)| {
+                    let code = message.get(i + 1..i + 4)?;
+                    let status = code.parse::<u16>().ok()?;
+                    (100..600).contains(&status).then_some(status)
+                })
+            }
+            _ => None,
+        }
+    }
+}
+
 impl Serialize for QuonitorError {
     fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
     where
@@ -4,10 +4,13 @@
 mod db;
 mod error;
 mod crypto;
+mod os_auth;
+mod power;
 mod providers;
 mod services;
 mod api;
 mod tray;
+mod redact;
 
 use std::sync::Arc;
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
@@ -15,7 +18,7 @@ use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 use db::Repository;
 use crypto::CryptoService;
 use providers::ProviderRegistry;
-use services::{Aggregator, Notifier, Cache, Scheduler};
+use services::{Aggregator, Notifier, Cache, Connectivity, Scheduler, TokenRefresher, PricingService, RollupService, SnapshotService, MaintenanceService, Settings};
 use api::{AppState, commands::*};
 
 #[tokio::main]
@@ -29,34 +32,54 @@ async fn main() {
             tracing_subscriber::EnvFilter::try_from_default_env()
                 .unwrap_or_else(|_| "quonitor=info,tower_http=debug".into()),
         )
-        .with(tracing_subscriber::fmt::layer())
+        .with(tracing_subscriber::fmt::layer().with_writer(redact::RedactingStdout))
         .init();
 
-    // Get data directory
-    let data_dir = dirs::data_local_dir()
-        .map(|p| p.join("quonitor"))
-        .expect("Failed to get app data directory");
+    // Get data directory: --data-dir, portable mode, a custom location or the default
+    let location = db::location::resolve().expect("Failed to prepare data directory");
+    let data_dir = location.path.clone();
+    tracing::info!("Using data directory {}", data_dir.display());
 
-    std::fs::create_dir_all(&data_dir).expect("Failed to create data directory");
+    // Each profile has its own database; everything else in the data dir is shared
+    let profile_dir = db::profiles::active_dir(&data_dir)
+        .expect("Failed to prepare profile directory");
 
-    let db_path = data_dir.join("quonitor.db");
+    // Initialize crypto service
+    let crypto = Arc::new(
+        CryptoService::new(&data_dir, &profile_dir)
+            .expect("Failed to initialize crypto service")
+    );
+
+    let (db_path, db_key) = db::cipher::prepare(&profile_dir, crypto.database_key())
+        .await
+        .expect("Failed to prepare database");
     let db_url = format!("sqlite://{}?mode=rwc", db_path.display());
 
     // Initialize database
     let repo = Arc::new(
-        Repository::new(&db_url)
+        Repository::new(&db_url, db_key.as_deref())
             .await
             .expect("Failed to initialize database")
     );
 
-    // Initialize crypto service
-    let crypto = Arc::new(
-        CryptoService::new()
-            .expect("Failed to initialize crypto service")
-    );
+    // Credentials written before they were bound to their account; when locked this
+    // does nothing and runs again after the unlock
+    if let Err(e) = services::credentials::bind_to_accounts(&repo, &crypto).await {
+        tracing::error!("Failed to re-encrypt credentials: {}", e);
+    }
+
+    // Initialize pricing
+    let pricing = Arc::new(PricingService::new(repo.clone(), data_dir.join("pricing.json")));
+    if let Err(e) = pricing.reload_overrides().await {
+        tracing::warn!("Failed to load pricing overrides: {}", e);
+    }
 
     // Initialize providers
-    let providers = Arc::new(ProviderRegistry::new());
+    let timeouts = providers::timeouts::TimeoutConfig::load(&repo).await;
+    let mut registry = ProviderRegistry::new(pricing.clone(), timeouts);
+    registry.register_config_providers(&data_dir.join("providers.json"));
+    registry.register_plugins(&data_dir.join("plugins"));
+    let providers = Arc::new(registry);
 
     // Initialize services
     let cache = Arc::new(Cache::new());
@@ -64,41 +87,71 @@ async fn main() {
         repo.clone(),
         providers.clone(),
         crypto.clone(),
+        cache.clone(),
     ));
-    let notifier = Arc::new(Notifier::new(repo.clone()));
-
-    // Get refresh interval from settings
-    let interval = repo
-        .get_setting("refresh_interval_seconds")
-        .await
-        .ok()
-        .flatten()
-        .and_then(|s| s.parse::<u64>().ok())
-        .unwrap_or(300);
+    let notifier = Arc::new(Notifier::new(repo.clone(), crypto.clone()));
+    let token_refresher = Arc::new(TokenRefresher::new(repo.clone(), crypto.clone()));
+    let rollups = Arc::new(RollupService::new(repo.clone()));
+    let snapshots = Arc::new(SnapshotService::new(repo.clone(), &profile_dir));
+    let maintenance = Arc::new(MaintenanceService::new(repo.clone()));
+    let connectivity = Arc::new(Connectivity::new());
+
+    let settings = Settings::load(&repo).await.unwrap_or_else(|e| {
+        tracing::warn!("Failed to load settings, using defaults: {}", e);
+        Settings::default()
+    });
+    providers::retry::set_max_attempts(settings.retry_max_attempts);
 
     let scheduler = Arc::new(Scheduler::new(
+        repo.clone(),
         aggregator.clone(),
         notifier.clone(),
         cache.clone(),
-        interval,
+        connectivity.clone(),
+        token_refresher,
+        rollups.clone(),
+        snapshots.clone(),
+        maintenance.clone(),
+        settings.refresh_interval_seconds,
     ));
 
     // Create app state
     let app_state = AppState {
         repo,
         aggregator,
-        cache,
+        providers,
+        cache: cache.clone(),
         scheduler: scheduler.clone(),
         crypto,
+        pricing: pricing.clone(),
+        rollups,
+        snapshots,
+        maintenance,
+        connectivity: connectivity.clone(),
+        data_dir,
+        data_source: location.source,
+        profile_dir,
     };
 
     tauri::Builder::default()
         .plugin(tauri_plugin_shell::init())
+        .plugin(tauri_plugin_dialog::init())
         .manage(app_state)
         .setup(move |app| {
             // Create system tray
             let _tray = tray::create_tray(&app.handle())?;
 
+            connectivity.attach(app.handle().clone());
+            cache.attach(app.handle().clone());
+
+            // Pull the latest pricing table in the background
+            let pricing_clone = pricing.clone();
+            tauri::async_runtime::spawn(async move {
+                if let Err(e) = pricing_clone.refresh().await {
+                    tracing::warn!("Failed to refresh pricing table: {}", e);
+                }
+            });
+
             // Start scheduler
             let scheduler_clone = scheduler.clone();
             tauri::async_runtime::spawn(async move {
@@ -108,14 +161,89 @@ async fn main() {
             Ok(())
         })
         .invoke_handler(tauri::generate_handler![
-            api::commands::get_accounts,
+            api::commands::get_all_accounts,
+            api::commands::list_providers,
+            api::commands::check_provider_status,
             api::commands::add_account,
+            api::commands::test_credentials,
             api::commands::remove_account,
+            api::commands::update_account,
+            api::commands::update_account_credentials,
+            api::commands::reorder_accounts,
+            api::commands::duplicate_account,
+            api::commands::get_account_status,
+            api::commands::get_all_quotas,
+            api::commands::get_quota,
+            api::commands::refresh_account,
+            api::commands::get_quota_summary,
+            api::commands::get_sparkline,
             api::commands::get_historical_snapshots,
+            api::commands::get_dashboard_summary,
+            api::commands::get_usage_deltas,
             api::commands::get_model_usage_history,
-            api::commands::set_setting,
-            api::commands::get_setting,
+            api::commands::get_api_key_usage_history,
+            api::commands::export_usage_csv,
+            api::commands::generate_report,
+            api::commands::import_usage,
+            api::commands::backup_database,
+            api::commands::restore_database,
+            api::commands::export_credentials,
+            api::commands::import_credentials,
+            api::commands::import_accounts,
+            api::commands::get_account_errors,
+            api::commands::get_account_backoff,
+            api::commands::get_connectivity,
+            api::commands::get_notification_history,
+            api::commands::acknowledge_notification,
+            api::commands::get_account_tags,
+            api::commands::set_account_tags,
+            api::commands::get_spend_by_tag,
+            api::commands::get_account_groups,
+            api::commands::save_account_group,
+            api::commands::remove_account_group,
+            api::commands::get_group_summary,
+            api::commands::get_budgets,
+            api::commands::set_budget,
+            api::commands::remove_budget,
+            api::commands::maintain_database,
+            api::commands::cleanup_old_data,
+            api::commands::list_backups,
+            api::commands::restore_backup,
+            api::commands::get_profiles,
+            api::commands::create_profile,
+            api::commands::switch_profile,
+            api::commands::delete_profile,
+            api::commands::get_data_location,
+            api::commands::set_data_location,
+            api::commands::get_lock_state,
+            api::commands::lock,
+            api::commands::unlock,
+            api::commands::set_master_password,
+            api::commands::get_key_storage,
+            api::commands::set_keyring_only,
+            api::commands::get_database_encryption,
+            api::commands::enable_database_encryption,
+            api::commands::get_account_notification_prefs,
+            api::commands::set_account_notification_prefs,
+            api::commands::snooze_notifications,
+            api::commands::mute_account,
+            api::commands::get_notification_routes,
+            api::commands::save_notification_route,
+            api::commands::remove_notification_route,
+            api::commands::get_all_settings,
+            api::commands::update_settings,
+            api::commands::has_smtp_password,
+            api::commands::set_smtp_password,
+            api::commands::has_webhook_secret,
+            api::commands::set_webhook_secret,
+            api::commands::send_test_email,
+            api::commands::get_account_settings,
+            api::commands::set_account_setting,
             api::commands::refresh_now,
+            api::commands::get_pricing_overrides,
+            api::commands::set_pricing_override,
+            api::commands::remove_pricing_override,
+            api::commands::refresh_pricing,
             api::commands::google_auth_start,
             api::commands::google_auth_finish,
         ])
@@ -0,0 +1,95 @@
+use crate::db::Repository;
+use crate::error::{QuonitorError, Result};
+use crate::services::Settings;
+
+// `verify`, when the `require_os_auth` setting is on
+pub async fn require(repo: &Repository, reason: &str) -> Result<()> {
+    if Settings::load(repo).await?.require_os_auth {
+        verify(reason).await?;
+    }
+    Ok(())
+}
+
+// Asks the user to confirm their identity with the platform's own prompt (Windows
+// Hello, the macOS administrator dialog, polkit on Linux) before secrets leave the
+// app. Fails closed: no prompt available counts as a refusal.
+pub async fn verify(reason: &str) -> Result<()> {
+    let reason = reason.to_string();
+    let outcome = tokio::task::spawn_blocking(move || prompt(&reason))
+        .await
+        .map_err(|e| QuonitorError::Auth(format!("OS authentication failed: {}", e)))?;
+
+    match outcome {
+        Ok(true) => Ok(()),
+        Ok(false) => Err(QuonitorError::Auth("OS authentication was cancelled or failed".to_string())),
+        Err(e) => Err(QuonitorError::Auth(format!("OS authentication is unavailable: {}", e))),
+    }
+}
+
+// Ok(false) when the user cancelled or failed the prompt, Err when there's no prompt to show
+#[cfg(windows)]
+fn prompt(reason: &str) -> std::result::Result<bool, String> {
+    use windows::core::HSTRING;
+    use windows::Security::Credentials::UI::{
+        UserConsentVerificationResult, UserConsentVerifier, UserConsentVerifierAvailability,
+    };
+
+    let availability = UserConsentVerifier::CheckAvailabilityAsync()
+        .and_then(|op| op.get())
+        .map_err(|e| e.to_string())?;
+    if availability != UserConsentVerifierAvailability::Available {
+        return Err("Windows Hello is not set up".to_string());
+    }
+
+    let result = UserConsentVerifier::RequestVerificationAsync(&HSTRING::from(reason))
+        .and_then(|op| op.get())
+        .map_err(|e| e.to_string())?;
+    Ok(result == UserConsentVerificationResult::Verified)
+}
+
+// The administrator prompt, which offers Touch ID where the system allows it
+#[cfg(target_os = "macos")]
+fn prompt(reason: &str) -> std::result::Result<bool, String> {
+    let script = format!(
+        "do shell script \"true\" with prompt \"Quonitor: {}\" with administrator privileges",
+        reason.replace('\\', "").replace('"', "'")
+    );
+    let output = std::process::Command::new("osascript")
+        .args(["-e", &script])
+        .output()
+        .map_err(|e| e.to_string())?;
+
+    if output.status.success() {
+        return Ok(true);
+    }
+    // -128 is "User canceled"
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    if stderr.contains("-128") || stderr.contains("-60005") {
+        Ok(false)
+    } else {
+        Err(stderr.trim().to_string())
+    }
+}
+
+// polkit's authentication agent; needs one running, which desktop sessions provide
+#[cfg(all(unix, not(target_os = "macos")))]
+fn prompt(_reason: &str) -> std::result::Result<bool, String> {
+    let status = std::process::Command::new("pkexec")
+        .arg("--disable-internal-agent")
+        .arg("/bin/true")
+        .status()
+        .map_err(|e| format!("pkexec not found ({})", e))?;
+
+    match status.code() {
+        Some(0) => Ok(true),
+        // Dismissed, or authentication failed
+        Some(126) => Ok(false),
+        Some(127) => Err("no polkit authentication agent".to_string()),
+        _ => Err(format!("pkexec exited with {}", status)),
+    }
+}
+
+#[cfg(not(any(windows, unix)))]
+fn prompt(_reason: &str) -> std::result::Result<bool, String> {
+    Err("not supported on this platform".to_string())
+}
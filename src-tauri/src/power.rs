@@ -0,0 +1,103 @@
+// Whether the machine is running on battery or a metered connection, for stretching
+// the refresh interval. Anything that can't be determined counts as unconstrained, so
+// a missing API never slows polling down.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PowerStatus {
+    pub on_battery: bool,
+    pub metered: bool,
+}
+
+pub async fn status() -> PowerStatus {
+    tokio::task::spawn_blocking(|| PowerStatus {
+        on_battery: on_battery().unwrap_or(false),
+        metered: metered().unwrap_or(false),
+    })
+    .await
+    .unwrap_or_default()
+}
+
+#[cfg(windows)]
+fn on_battery() -> Option<bool> {
+    use windows::Win32::System::Power::{GetSystemPowerStatus, SYSTEM_POWER_STATUS};
+
+    let mut status = SYSTEM_POWER_STATUS::default();
+    unsafe { GetSystemPowerStatus(&mut status) }.ok()?;
+    // 0 offline, 1 online, 255 unknown
+    match status.ACLineStatus {
+        0 => Some(true),
+        1 => Some(false),
+        _ => None,
+    }
+}
+
+#[cfg(windows)]
+fn metered() -> Option<bool> {
+    use windows::Networking::Connectivity::{NetworkCostType, NetworkInformation};
+
+    let profile = NetworkInformation::GetInternetConnectionProfile().ok()?;
+    let cost = profile.GetConnectionCost().ok()?;
+    let cost_type = cost.NetworkCostType().ok()?;
+    Some(cost_type == NetworkCostType::Fixed || cost_type == NetworkCostType::Variable)
+}
+
+#[cfg(target_os = "macos")]
+fn on_battery() -> Option<bool> {
+    let output = std::process::Command::new("pmset").args(["-g", "batt"]).output().ok()?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    // First line: "Now drawing from 'Battery Power'" or "'AC Power'"
+    let source = stdout.lines().next()?;
+    Some(source.contains("Battery Power"))
+}
+
+// macOS only exposes "expensive" paths through the Network framework
+#[cfg(target_os = "macos")]
+fn metered() -> Option<bool> {
+    None
+}
+
+// On battery when there is a battery and no mains supply is online
+#[cfg(all(unix, not(target_os = "macos")))]
+fn on_battery() -> Option<bool> {
+    let mut has_battery = false;
+    for entry in std::fs::read_dir("/sys/class/power_supply").ok()?.flatten() {
+        let read = |name: &str| std::fs::read_to_string(entry.path().join(name)).unwrap_or_default();
+        match read("type").trim() {
+            "Mains" if read("online").trim() == "1" => return Some(false),
+            "Battery" => has_battery = true,
+            _ => {}
+        }
+    }
+    has_battery.then_some(true)
+}
+
+// NetworkManager's overall metered state: 1 yes, 3 guessed yes (e.g. a phone hotspot)
+#[cfg(all(unix, not(target_os = "macos")))]
+fn metered() -> Option<bool> {
+    let output = std::process::Command::new("busctl")
+        .args([
+            "get-property",
+            "org.freedesktop.NetworkManager",
+            "/org/freedesktop/NetworkManager",
+            "org.freedesktop.NetworkManager",
+            "Metered",
+        ])
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    // "u 4"
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let value: u32 = stdout.split_whitespace().nth(1)?.parse().ok()?;
+    Some(value == 1 || value == 3)
+}
+
+#[cfg(not(any(windows, unix)))]
+fn on_battery() -> Option<bool> {
+    None
+}
+
+#[cfg(not(any(windows, unix)))]
+fn metered() -> Option<bool> {
+    None
+}
@@ -0,0 +1,121 @@
+use async_trait::async_trait;
+use chrono::Utc;
+use serde::Deserialize;
+use super::{QuotaProvider, QuotaData, ModelData, FetchOptions, AuthMethod};
+use super::retry::SendWithRetry;
+use super::status::StatusEndpoint;
+use crate::db::Credentials;
+use crate::error::{QuonitorError, Result};
+
+const CODEWHISPERER_URL: &str = "https://codewhisperer.us-east-1.amazonaws.com/";
+
+pub struct AmazonQProvider {
+    client: reqwest::Client,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct UsageLimitsResponse {
+    #[serde(default)]
+    limits: Vec<UsageLimit>,
+    #[serde(default)]
+    next_reset_date: Option<f64>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct UsageLimit {
+    #[serde(rename = "type")]
+    limit_type: String,
+    #[serde(default)]
+    current_usage: i64,
+    #[serde(default)]
+    total_usage_limit: i64,
+}
+
+impl AmazonQProvider {
+    pub fn new(client: reqwest::Client) -> Self {
+        Self {
+            client,
+        }
+    }
+}
+
+#[async_trait]
+impl QuotaProvider for AmazonQProvider {
+    async fn fetch_quota(&self, credentials: &Credentials, _options: &FetchOptions) -> Result<QuotaData> {
+        // Amazon Q Developer authenticates with an AWS Builder ID / IAM Identity Center bearer token
+        let token = credentials.oauth_token.as_ref()
+            .ok_or_else(|| QuonitorError::Auth("Amazon Q requires a Builder ID or Identity Center token".to_string()))?;
+
+        let response = self.client
+            .post(CODEWHISPERER_URL)
+            .bearer_auth(token)
+            .header("Content-Type", "application/x-amz-json-1.0")
+            .header("X-Amz-Target", "AmazonCodeWhispererService.GetUsageLimits")
+            .body("{}")
+            .send_with_retry()
+            .await?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let error_text = response.text().await.unwrap_or_else(|_| "Unknown error".to_string());
+            return Err(QuonitorError::Provider(format!(
+                "Amazon Q API error ({}): {}",
+                status, error_text
+            )));
+        }
+
+        let usage: UsageLimitsResponse = response.json().await?;
+
+        // The monthly agentic request allowance is the limit users actually hit
+        let agentic = usage.limits.iter()
+            .find(|l| l.limit_type == "AGENTIC_REQUEST");
+
+        let model_breakdown = usage.limits.iter()
+            .map(|limit| ModelData {
+                model_name: limit.limit_type.to_lowercase(),
+                tokens_input: 0,
+                tokens_output: 0,
+                cost_usd: 0.0,
+                request_count: limit.current_usage,
+                ..Default::default()
+            })
+            .collect();
+
+        let metadata = usage.next_reset_date
+            .and_then(|ts| chrono::DateTime::from_timestamp(ts as i64, 0))
+            .map(|reset| format!("Resets {}", reset.format("%Y-%m-%d")));
+
+        let now = Utc::now();
+
+        Ok(QuotaData {
+            account_id: String::new(), // Will be set by caller
+            timestamp: now.timestamp(),
+            tokens_input: None,
+            tokens_output: None,
+            cost_usd: None,
+            quota_limit: agentic.map(|l| l.total_usage_limit),
+            quota_remaining: agentic.map(|l| (l.total_usage_limit - l.current_usage).max(0)),
+            model_breakdown,
+            metadata,
+            ..Default::default()
+        })
+    }
+
+    fn supports_oauth(&self) -> bool {
+        true
+    }
+
+    fn provider_name(&self) -> &'static str {
+        "Amazon Q Developer"
+    }
+
+    fn auth_methods(&self) -> Vec<AuthMethod> {
+        vec![AuthMethod::OAuth]
+    }
+
+    fn status_endpoint(&self) -> Option<StatusEndpoint> {
+        Some(StatusEndpoint::Ping("https://codewhisperer.us-east-1.amazonaws.com/".to_string()))
+    }
+}
@@ -1,12 +1,17 @@
+use std::sync::Arc;
 use async_trait::async_trait;
 use chrono::Utc;
 use serde::Deserialize;
-use super::{QuotaProvider, QuotaData, ModelData};
+use super::{QuotaProvider, QuotaData, ModelData, RateLimits, FetchOptions};
+use super::retry::SendWithRetry;
+use super::status::StatusEndpoint;
 use crate::db::Credentials;
+use crate::services::PricingService;
 use crate::error::{QuonitorError, Result};
 
 pub struct AnthropicProvider {
     client: reqwest::Client,
+    pricing: Arc<PricingService>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -27,34 +32,100 @@ struct UsageDataPoint {
     model: Option<String>,
 }
 
+#[derive(Debug, Deserialize)]
+struct SubscriptionUsageResponse {
+    #[serde(default)]
+    five_hour: Option<UsageWindow>,
+    #[serde(default)]
+    seven_day: Option<UsageWindow>,
+    #[serde(default)]
+    seven_day_opus: Option<UsageWindow>,
+}
+
+#[derive(Debug, Deserialize)]
+struct UsageWindow {
+    // Percentage of the window's allowance already consumed
+    #[serde(default)]
+    utilization: f64,
+    #[serde(default)]
+    resets_at: Option<String>,
+}
+
 impl AnthropicProvider {
-    pub fn new() -> Self {
+    pub fn new(client: reqwest::Client, pricing: Arc<PricingService>) -> Self {
         Self {
-            client: reqwest::Client::new(),
+            client,
+            pricing,
         }
     }
 
-    fn calculate_anthropic_cost(model: &str, input_tokens: i64, output_tokens: i64) -> f64 {
-        // Pricing per million tokens (as of 2026)
-        let (input_price, output_price) = match model {
-            m if m.contains("opus") => (15.00, 75.00),
-            m if m.contains("sonnet") => (3.00, 15.00),
-            m if m.contains("haiku") => (0.25, 1.25),
-            _ => (3.00, 15.00), // Default to Sonnet pricing
-        };
+    fn calculate_anthropic_cost(&self, model: &str, input_tokens: i64, output_tokens: i64) -> f64 {
+        self.pricing.token_cost("anthropic", model, input_tokens, output_tokens)
+    }
 
-        let input_cost = (input_tokens as f64 / 1_000_000.0) * input_price;
-        let output_cost = (output_tokens as f64 / 1_000_000.0) * output_price;
+    // Claude Pro/Max subscriptions expose rolling 5-hour and weekly limits as
+    // utilization percentages. The most constrained window drives the quota.
+    async fn fetch_subscription_quota(&self, token: &str) -> Result<QuotaData> {
+        let response = self.client
+            .get("https://api.anthropic.com/api/oauth/usage")
+            .bearer_auth(token)
+            .header("anthropic-beta", "oauth-2025-04-20")
+            .send_with_retry()
+            .await?;
 
-        input_cost + output_cost
+        if !response.status().is_success() {
+            let status = response.status();
+            let error_text = response.text().await.unwrap_or_else(|_| "Unknown error".to_string());
+            return Err(QuonitorError::Provider(format!(
+                "Anthropic API error ({}): {}",
+                status, error_text
+            )));
+        }
+
+        let usage: SubscriptionUsageResponse = response.json().await?;
+
+        let windows = [
+            ("5-hour session", usage.five_hour.as_ref()),
+            ("weekly", usage.seven_day.as_ref()),
+            ("weekly Opus", usage.seven_day_opus.as_ref()),
+        ];
+
+        let mut metadata = Vec::new();
+        let mut max_utilization = None::<f64>;
+
+        for (label, window) in windows.into_iter().filter_map(|(l, w)| w.map(|w| (l, w))) {
+            let reset = window.resets_at.as_deref().unwrap_or("unknown");
+            metadata.push(format!("{}: {:.0}% used, resets {}", label, window.utilization, reset));
+            max_utilization = Some(max_utilization.map_or(window.utilization, |m| m.max(window.utilization)));
+        }
+
+        let now = Utc::now();
+
+        Ok(QuotaData {
+            account_id: String::new(), // Will be set by caller
+            timestamp: now.timestamp(),
+            tokens_input: None,
+            tokens_output: None,
+            cost_usd: None,
+            // Limits are reported as percentages, so the quota is expressed out of 100
+            quota_limit: max_utilization.map(|_| 100),
+            quota_remaining: max_utilization.map(|u| (100.0 - u).round().max(0.0) as i64),
+            model_breakdown: vec![],
+            metadata: if metadata.is_empty() { None } else { Some(metadata.join("; ")) },
+            ..Default::default()
+        })
     }
 }
 
 #[async_trait]
 impl QuotaProvider for AnthropicProvider {
-    async fn fetch_quota(&self, credentials: &Credentials) -> Result<QuotaData> {
+    async fn fetch_quota(&self, credentials: &Credentials, _options: &FetchOptions) -> Result<QuotaData> {
+        if let Some(token) = credentials.oauth_token.as_ref() {
+            return self.fetch_subscription_quota(token).await;
+        }
+
         let api_key = credentials.api_key.as_ref()
-            .ok_or_else(|| QuonitorError::Auth("Anthropic requires API key".to_string()))?;
+            .ok_or_else(|| QuonitorError::Auth("Anthropic requires API key or OAuth token".to_string()))?;
 
         // Anthropic does not currently provide a public API for retrieving historical usage/cost.
         // We validate the key by listing models, and return 0 usage.
@@ -66,7 +137,7 @@ impl QuotaProvider for AnthropicProvider {
             .header("x-api-key", api_key)
             .header("anthropic-version", "2023-06-01")
             .header("Content-Type", "application/json")
-            .send()
+            .send_with_retry()
             .await?;
 
         if !response.status().is_success() {
@@ -78,6 +149,8 @@ impl QuotaProvider for AnthropicProvider {
             )));
         }
 
+        let rate_limits = RateLimits::from_headers(response.headers());
+
         // Key is valid if we got here.
         // Return placeholder data since we can't fetch real usage.
         let now = Utc::now();
@@ -92,14 +165,25 @@ impl QuotaProvider for AnthropicProvider {
             quota_remaining: None,
             model_breakdown: vec![],
             metadata: Some("Anthropic API does not support usage tracking yet".to_string()),
+            rpm_limit: rate_limits.rpm_limit,
+            rpm_remaining: rate_limits.rpm_remaining,
+            tpm_limit: rate_limits.tpm_limit,
+            tpm_remaining: rate_limits.tpm_remaining,
+            rpm_reset_at: rate_limits.rpm_reset_at,
+            tpm_reset_at: rate_limits.tpm_reset_at,
+            ..Default::default()
         })
     }
 
     fn supports_oauth(&self) -> bool {
-        false
+        true
     }
 
     fn provider_name(&self) -> &'static str {
         "Anthropic"
     }
+
+    fn status_endpoint(&self) -> Option<StatusEndpoint> {
+        Some(StatusEndpoint::StatusPage("https://status.anthropic.com/api/v2/status.json".to_string()))
+    }
 }
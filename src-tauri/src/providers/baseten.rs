@@ -0,0 +1,140 @@
+use std::sync::Arc;
+use async_trait::async_trait;
+use chrono::Utc;
+use serde::Deserialize;
+use super::{QuotaProvider, QuotaData, ModelData, FetchOptions};
+use super::retry::SendWithRetry;
+use super::status::StatusEndpoint;
+use crate::db::Credentials;
+use crate::services::PricingService;
+use crate::error::{QuonitorError, Result};
+
+pub struct BasetenProvider {
+    client: reqwest::Client,
+    pricing: Arc<PricingService>,
+}
+
+#[derive(Debug, Deserialize)]
+struct BillingUsageResponse {
+    #[serde(default)]
+    models: Vec<ModelComputeUsage>,
+    #[serde(default)]
+    credits_granted: Option<f64>,
+    #[serde(default)]
+    credits_remaining: Option<f64>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ModelComputeUsage {
+    #[serde(default)]
+    model_name: Option<String>,
+    #[serde(default)]
+    instance_type: Option<String>,
+    #[serde(default)]
+    inference_minutes: f64,
+    #[serde(default)]
+    request_count: i64,
+    #[serde(default)]
+    total_cost: Option<f64>,
+}
+
+impl BasetenProvider {
+    pub fn new(client: reqwest::Client, pricing: Arc<PricingService>) -> Self {
+        Self {
+            client,
+            pricing,
+        }
+    }
+
+    fn calculate_baseten_cost(&self, instance_type: &str, minutes: f64) -> f64 {
+        self.pricing.minute_cost("baseten", instance_type, minutes)
+    }
+}
+
+#[async_trait]
+impl QuotaProvider for BasetenProvider {
+    async fn fetch_quota(&self, credentials: &Credentials, options: &FetchOptions) -> Result<QuotaData> {
+        let api_key = credentials.api_key.as_ref()
+            .ok_or_else(|| QuonitorError::Auth("Baseten requires API key".to_string()))?;
+
+        // Baseten invoices monthly, so report month-to-date spend
+        let now = Utc::now();
+        let month_start = options.month_start(now);
+
+        let url = format!(
+            "https://api.baseten.co/v1/billing/usage?start_date={}&end_date={}",
+            month_start.with_timezone(&options.timezone).format("%Y-%m-%d"),
+            now.with_timezone(&options.timezone).format("%Y-%m-%d")
+        );
+
+        let response = self.client
+            .get(&url)
+            .header("Authorization", format!("Api-Key {}", api_key))
+            .send_with_retry()
+            .await?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let error_text = response.text().await.unwrap_or_else(|_| "Unknown error".to_string());
+            return Err(QuonitorError::Provider(format!(
+                "Baseten API error ({}): {}",
+                status, error_text
+            )));
+        }
+
+        let usage: BillingUsageResponse = response.json().await?;
+
+        let mut total_cost = 0.0f64;
+        let mut total_minutes = 0.0f64;
+        let mut model_breakdown = Vec::new();
+
+        // Baseten bills per compute-minute, so token fields stay at zero and the
+        // breakdown carries cost and request counts per deployed model
+        for model in usage.models {
+            let instance_type = model.instance_type.unwrap_or_default();
+            let cost = model.total_cost
+                .unwrap_or_else(|| self.calculate_baseten_cost(&instance_type, model.inference_minutes));
+
+            total_cost += cost;
+            total_minutes += model.inference_minutes;
+
+            model_breakdown.push(ModelData {
+                model_name: model.model_name.unwrap_or_else(|| "unknown".to_string()),
+                tokens_input: 0,
+                tokens_output: 0,
+                cost_usd: cost,
+                request_count: model.request_count,
+                ..Default::default()
+            });
+        }
+
+        // Credits are dollar-denominated; expose them in cents
+        let quota_limit = usage.credits_granted.map(|c| (c * 100.0).round() as i64);
+        let quota_remaining = usage.credits_remaining.map(|c| (c * 100.0).round() as i64);
+
+        Ok(QuotaData {
+            account_id: String::new(), // Will be set by caller
+            timestamp: now.timestamp(),
+            tokens_input: None,
+            tokens_output: None,
+            cost_usd: Some(total_cost),
+            quota_limit,
+            quota_remaining,
+            model_breakdown,
+            metadata: Some(format!("{:.1} inference minutes this month", total_minutes)),
+            ..Default::default()
+        })
+    }
+
+    fn supports_oauth(&self) -> bool {
+        false
+    }
+
+    fn provider_name(&self) -> &'static str {
+        "Baseten"
+    }
+
+    fn status_endpoint(&self) -> Option<StatusEndpoint> {
+        Some(StatusEndpoint::Ping("https://api.baseten.co/".to_string()))
+    }
+}
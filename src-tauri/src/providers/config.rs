@@ -0,0 +1,248 @@
+use std::collections::HashMap;
+use std::path::Path;
+use async_trait::async_trait;
+use chrono::Utc;
+use serde::Deserialize;
+use serde_json::Value;
+use super::{QuotaProvider, QuotaData, FetchOptions, AuthMethod};
+use super::retry::SendWithRetry;
+use super::status::StatusEndpoint;
+use super::timeouts::TimeoutConfig;
+use crate::db::Credentials;
+use crate::error::{QuonitorError, Result};
+
+// User-editable file listing providers that are described rather than compiled in
+#[derive(Debug, Deserialize)]
+pub struct ProviderConfigFile {
+    #[serde(default)]
+    pub providers: Vec<ProviderDefinition>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ProviderDefinition {
+    // Registry key stored on accounts, e.g. "together"
+    pub id: String,
+    pub name: String,
+    pub url: String,
+    #[serde(default = "default_method")]
+    pub method: String,
+    // Header values may reference {api_key}, {oauth_token} and {organization}
+    #[serde(default)]
+    pub headers: HashMap<String, String>,
+    #[serde(default)]
+    pub mappings: FieldMappings,
+    // Optional Statuspage `/api/v2/status.json` URL for health checks
+    #[serde(default)]
+    pub status_url: Option<String>,
+}
+
+// JSONPath expressions locating each quota field in the response
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct FieldMappings {
+    #[serde(default)]
+    pub tokens_input: Option<String>,
+    #[serde(default)]
+    pub tokens_output: Option<String>,
+    #[serde(default)]
+    pub cost_usd: Option<String>,
+    #[serde(default)]
+    pub quota_limit: Option<String>,
+    #[serde(default)]
+    pub quota_remaining: Option<String>,
+}
+
+fn default_method() -> String {
+    "GET".to_string()
+}
+
+pub struct ConfigProvider {
+    client: reqwest::Client,
+    definition: ProviderDefinition,
+}
+
+impl ConfigProvider {
+    pub fn new(client: reqwest::Client, definition: ProviderDefinition) -> Self {
+        Self {
+            client,
+            definition,
+        }
+    }
+
+    pub fn id(&self) -> &str {
+        &self.definition.id
+    }
+
+    // A missing file just means no custom providers
+    pub fn load_all(path: &Path, timeouts: &TimeoutConfig) -> Result<Vec<Self>> {
+        if !path.exists() {
+            return Ok(Vec::new());
+        }
+
+        let json = std::fs::read_to_string(path)?;
+        let file: ProviderConfigFile = serde_json::from_str(&json)?;
+
+        Ok(file.providers
+            .into_iter()
+            .map(|definition| Self::new(timeouts.client(&definition.id), definition))
+            .collect())
+    }
+
+    fn uses_placeholder(&self, placeholder: &str) -> bool {
+        self.definition.url.contains(placeholder)
+            || self.definition.headers.values().any(|h| h.contains(placeholder))
+    }
+
+    fn render(template: &str, credentials: &Credentials) -> Result<String> {
+        let mut value = template.to_string();
+
+        for (placeholder, field) in [
+            ("{api_key}", &credentials.api_key),
+            ("{oauth_token}", &credentials.oauth_token),
+            ("{organization}", &credentials.organization),
+        ] {
+            if !value.contains(placeholder) {
+                continue;
+            }
+            let field = field.as_ref().ok_or_else(|| QuonitorError::Auth(format!(
+                "Credential for {} is required", placeholder
+            )))?;
+            value = value.replace(placeholder, field);
+        }
+
+        Ok(value)
+    }
+
+    fn number(body: &Value, path: &Option<String>) -> Result<Option<f64>> {
+        let Some(path) = path else {
+            return Ok(None);
+        };
+
+        let values = select(body, path)?;
+        if values.is_empty() {
+            return Ok(None);
+        }
+
+        // Wildcards sum over every match; numeric strings are accepted too
+        Ok(Some(values.iter()
+            .filter_map(|v| v.as_f64().or_else(|| v.as_str().and_then(|s| s.parse().ok())))
+            .sum()))
+    }
+}
+
+#[async_trait]
+impl QuotaProvider for ConfigProvider {
+    async fn fetch_quota(&self, credentials: &Credentials, _options: &FetchOptions) -> Result<QuotaData> {
+        let method = reqwest::Method::from_bytes(self.definition.method.to_uppercase().as_bytes())
+            .map_err(|_| QuonitorError::Config(format!("Invalid HTTP method: {}", self.definition.method)))?;
+
+        let mut request = self.client.request(method, Self::render(&self.definition.url, credentials)?);
+        for (name, template) in &self.definition.headers {
+            request = request.header(name, Self::render(template, credentials)?);
+        }
+
+        let response = request.send_with_retry().await?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let error_text = response.text().await.unwrap_or_else(|_| "Unknown error".to_string());
+            return Err(QuonitorError::Provider(format!(
+                "{} API error ({}): {}",
+                self.definition.name, status, error_text
+            )));
+        }
+
+        let body: Value = response.json().await?;
+        let mappings = &self.definition.mappings;
+
+        Ok(QuotaData {
+            account_id: String::new(), // Will be set by caller
+            timestamp: Utc::now().timestamp(),
+            tokens_input: Self::number(&body, &mappings.tokens_input)?.map(|v| v as i64),
+            tokens_output: Self::number(&body, &mappings.tokens_output)?.map(|v| v as i64),
+            cost_usd: Self::number(&body, &mappings.cost_usd)?,
+            quota_limit: Self::number(&body, &mappings.quota_limit)?.map(|v| v as i64),
+            quota_remaining: Self::number(&body, &mappings.quota_remaining)?.map(|v| v as i64),
+            ..Default::default()
+        })
+    }
+
+    fn supports_oauth(&self) -> bool {
+        self.uses_placeholder("{oauth_token}")
+    }
+
+    fn provider_name(&self) -> &str {
+        &self.definition.name
+    }
+
+    fn auth_methods(&self) -> Vec<AuthMethod> {
+        let mut methods = Vec::new();
+        if self.uses_placeholder("{api_key}") {
+            methods.push(AuthMethod::ApiKey);
+        }
+        if self.uses_placeholder("{oauth_token}") {
+            methods.push(AuthMethod::OAuth);
+        }
+        methods
+    }
+
+    fn reports_limits(&self) -> bool {
+        self.definition.mappings.quota_limit.is_some()
+            || self.definition.mappings.quota_remaining.is_some()
+    }
+
+    fn required_fields(&self) -> Vec<&'static str> {
+        if self.uses_placeholder("{organization}") {
+            vec!["organization"]
+        } else {
+            Vec::new()
+        }
+    }
+
+    fn status_endpoint(&self) -> Option<StatusEndpoint> {
+        let status_url = self.definition.status_url.clone()?;
+        Some(StatusEndpoint::StatusPage(status_url))
+    }
+}
+
+// Evaluates the JSONPath subset used in provider configs: `$`, `.key`,
+// `['key']`, `[index]` and the `[*]` / `.*` wildcards
+fn select<'a>(root: &'a Value, path: &str) -> Result<Vec<&'a Value>> {
+    let invalid = || QuonitorError::Config(format!("Unsupported JSONPath: {}", path));
+
+    let mut rest = path.trim().strip_prefix('$').ok_or_else(invalid)?;
+    let mut current = vec![root];
+
+    while !rest.is_empty() {
+        let segment;
+        if let Some(after) = rest.strip_prefix('[') {
+            let end = after.find(']').ok_or_else(invalid)?;
+            segment = &after[..end];
+            rest = &after[end + 1..];
+        } else if let Some(after) = rest.strip_prefix('.') {
+            let end = after.find(['.', '[']).unwrap_or(after.len());
+            segment = &after[..end];
+            rest = &after[end..];
+        } else {
+            return Err(invalid());
+        }
+
+        let key = segment.trim_matches(|c| c == '\'' || c == '"');
+        current = current
+            .into_iter()
+            .flat_map(|value| -> Vec<&Value> {
+                match (key, value) {
+                    ("*", Value::Array(items)) => items.iter().collect(),
+                    ("*", Value::Object(map)) => map.values().collect(),
+                    (_, Value::Array(items)) => key.parse::<usize>().ok()
+                        .and_then(|i| items.get(i))
+                        .into_iter()
+                        .collect(),
+                    (_, Value::Object(map)) => map.get(key).into_iter().collect(),
+                    _ => Vec::new(),
+                }
+            })
+            .collect();
+    }
+
+    Ok(current)
+}
@@ -0,0 +1,151 @@
+use std::sync::Arc;
+use async_trait::async_trait;
+use chrono::Utc;
+use serde::Deserialize;
+use super::{QuotaProvider, QuotaData, ModelData, FetchOptions};
+use super::retry::SendWithRetry;
+use super::status::StatusEndpoint;
+use crate::db::Credentials;
+use crate::services::PricingService;
+use crate::error::{QuonitorError, Result};
+
+pub struct DashScopeProvider {
+    client: reqwest::Client,
+    pricing: Arc<PricingService>,
+}
+
+#[derive(Debug, Deserialize)]
+struct UsageResponse {
+    #[serde(default)]
+    data: Vec<UsageDataPoint>,
+}
+
+#[derive(Debug, Deserialize)]
+struct UsageDataPoint {
+    #[serde(default)]
+    model: Option<String>,
+    #[serde(default)]
+    input_tokens: i64,
+    #[serde(default)]
+    output_tokens: i64,
+    #[serde(default)]
+    request_count: i64,
+    #[serde(default)]
+    free_quota_total: Option<i64>,
+    #[serde(default)]
+    free_quota_remaining: Option<i64>,
+}
+
+impl DashScopeProvider {
+    pub fn new(client: reqwest::Client, pricing: Arc<PricingService>) -> Self {
+        Self {
+            client,
+            pricing,
+        }
+    }
+
+    fn calculate_dashscope_cost(&self, model: &str, input_tokens: i64, output_tokens: i64) -> f64 {
+        self.pricing.token_cost("dashscope", model, input_tokens, output_tokens)
+    }
+}
+
+#[async_trait]
+impl QuotaProvider for DashScopeProvider {
+    async fn fetch_quota(&self, credentials: &Credentials, options: &FetchOptions) -> Result<QuotaData> {
+        let api_key = credentials.api_key.as_ref()
+            .ok_or_else(|| QuonitorError::Auth("DashScope requires API key".to_string()))?;
+
+        // Usage is reported month-to-date, matching DashScope's billing cycle
+        let now = Utc::now();
+        let month_start = options.month_start(now);
+
+        let url = format!(
+            "https://dashscope-intl.aliyuncs.com/api/v1/usage?start_time={}&end_time={}&group_by=model",
+            month_start.timestamp(),
+            now.timestamp()
+        );
+
+        let response = self.client
+            .get(&url)
+            .header("Authorization", format!("Bearer {}", api_key))
+            .header("Content-Type", "application/json")
+            .send_with_retry()
+            .await?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let error_text = response.text().await.unwrap_or_else(|_| "Unknown error".to_string());
+            return Err(QuonitorError::Provider(format!(
+                "DashScope API error ({}): {}",
+                status, error_text
+            )));
+        }
+
+        let usage_response: UsageResponse = response.json().await?;
+
+        let mut total_input = 0i64;
+        let mut total_output = 0i64;
+        let mut total_cost = 0.0f64;
+        let mut free_limit = 0i64;
+        let mut free_remaining = 0i64;
+        let mut model_breakdown = Vec::new();
+
+        for data_point in usage_response.data {
+            let model_name = data_point.model.unwrap_or_else(|| "unknown".to_string());
+            let input = data_point.input_tokens;
+            let output = data_point.output_tokens;
+
+            // The free allowance is lifetime while usage is this month's, so it can't be
+            // taken off the cost; it's only reported as the quota when the API gives it
+            let cost = self.calculate_dashscope_cost(&model_name, input, output);
+
+            total_input += input;
+            total_output += output;
+            total_cost += cost;
+            if let (Some(limit), Some(remaining)) = (data_point.free_quota_total, data_point.free_quota_remaining) {
+                free_limit += limit;
+                free_remaining += remaining;
+            }
+
+            model_breakdown.push(ModelData {
+                model_name,
+                tokens_input: input,
+                tokens_output: output,
+                cost_usd: cost,
+                request_count: data_point.request_count,
+                ..Default::default()
+            });
+        }
+
+        let (quota_limit, quota_remaining) = if free_limit > 0 {
+            (Some(free_limit), Some(free_remaining))
+        } else {
+            (None, None)
+        };
+
+        Ok(QuotaData {
+            account_id: String::new(), // Will be set by caller
+            timestamp: now.timestamp(),
+            tokens_input: Some(total_input),
+            tokens_output: Some(total_output),
+            cost_usd: Some(total_cost),
+            quota_limit,
+            quota_remaining, // Free-tier tokens left across all models
+            model_breakdown,
+            metadata: None,
+            ..Default::default()
+        })
+    }
+
+    fn supports_oauth(&self) -> bool {
+        false
+    }
+
+    fn provider_name(&self) -> &'static str {
+        "Alibaba DashScope"
+    }
+
+    fn status_endpoint(&self) -> Option<StatusEndpoint> {
+        Some(StatusEndpoint::Ping("https://dashscope-intl.aliyuncs.com/".to_string()))
+    }
+}
@@ -0,0 +1,315 @@
+use std::collections::HashMap;
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, NaiveTime, TimeZone, Utc};
+use chrono_tz::America::Los_Angeles;
+use serde::Deserialize;
+use super::{QuotaProvider, QuotaData, ModelData, FetchOptions};
+use super::retry::SendWithRetry;
+use super::status::StatusEndpoint;
+use super::google::{GoogleProvider, ServiceAccountKey};
+use crate::db::Credentials;
+use crate::error::{QuonitorError, Result};
+
+const SERVICE: &str = "generativelanguage.googleapis.com";
+
+// Free-tier daily request allowance assumed when quota metrics aren't available
+const DEFAULT_FREE_TIER_RPD: i64 = 1500;
+
+pub struct GeminiProvider {
+    client: reqwest::Client,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ConsumerQuotaMetricsResponse {
+    #[serde(default)]
+    metrics: Vec<ConsumerQuotaMetric>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ConsumerQuotaMetric {
+    #[serde(default)]
+    metric: String,
+    #[serde(default)]
+    consumer_quota_limits: Vec<ConsumerQuotaLimit>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ConsumerQuotaLimit {
+    #[serde(default)]
+    unit: String,
+    #[serde(default)]
+    quota_buckets: Vec<QuotaBucket>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct QuotaBucket {
+    #[serde(default)]
+    effective_limit: Option<String>,
+    #[serde(default)]
+    dimensions: HashMap<String, String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TimeSeriesResponse {
+    #[serde(default)]
+    time_series: Vec<TimeSeries>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TimeSeries {
+    metric: MetricLabels,
+    #[serde(default)]
+    points: Vec<Point>,
+}
+
+#[derive(Debug, Deserialize)]
+struct MetricLabels {
+    #[serde(default)]
+    labels: HashMap<String, String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Point {
+    value: PointValue,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PointValue {
+    #[serde(default)]
+    int64_value: Option<String>,
+}
+
+// Requests-per-day and requests-per-minute limits, keyed by model
+#[derive(Debug, Default)]
+struct RequestLimits {
+    per_day: HashMap<String, i64>,
+    per_minute: HashMap<String, i64>,
+}
+
+impl GeminiProvider {
+    pub fn new(client: reqwest::Client) -> Self {
+        Self {
+            client,
+        }
+    }
+
+    async fn validate_key(&self, api_key: &str) -> Result<()> {
+        let response = self.client
+            .get(format!("https://{}/v1beta/models?pageSize=1", SERVICE))
+            .header("x-goog-api-key", api_key)
+            .send_with_retry()
+            .await?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let error_text = response.text().await.unwrap_or_else(|_| "Unknown error".to_string());
+            return Err(QuonitorError::Provider(format!(
+                "Gemini API error ({}): {}",
+                status, error_text
+            )));
+        }
+
+        Ok(())
+    }
+
+    async fn get_json<T: serde::de::DeserializeOwned>(&self, url: &str, token: &str) -> Result<T> {
+        let response = self.client
+            .get(url)
+            .bearer_auth(token)
+            .send_with_retry()
+            .await?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let error_text = response.text().await.unwrap_or_else(|_| "Unknown error".to_string());
+            return Err(QuonitorError::Provider(format!(
+                "Google API error ({}): {}",
+                status, error_text
+            )));
+        }
+
+        Ok(response.json().await?)
+    }
+
+    // Effective request limits from the Service Usage API
+    async fn fetch_limits(&self, token: &str, project_id: &str) -> Result<RequestLimits> {
+        let url = format!(
+            "https://serviceusage.googleapis.com/v1beta1/projects/{}/services/{}/consumerQuotaMetrics",
+            project_id, SERVICE
+        );
+        let response: ConsumerQuotaMetricsResponse = self.get_json(&url, token).await?;
+
+        let mut limits = RequestLimits::default();
+
+        for metric in response.metrics.iter().filter(|m| m.metric.contains("requests")) {
+            for limit in &metric.consumer_quota_limits {
+                let target = if limit.unit.contains("/d/") {
+                    &mut limits.per_day
+                } else if limit.unit.contains("/min/") {
+                    &mut limits.per_minute
+                } else {
+                    continue;
+                };
+
+                for bucket in &limit.quota_buckets {
+                    let Some(value) = bucket.effective_limit.as_ref().and_then(|v| v.parse::<i64>().ok()) else {
+                        continue;
+                    };
+                    // -1 means unlimited
+                    if value < 0 {
+                        continue;
+                    }
+                    let model = bucket.dimensions.get("model").cloned().unwrap_or_else(|| "all".to_string());
+                    target.insert(model, value);
+                }
+            }
+        }
+
+        Ok(limits)
+    }
+
+    // Daily request quotas roll over at midnight Pacific time
+    fn next_daily_reset(now: DateTime<Utc>) -> Option<i64> {
+        let tomorrow = now.with_timezone(&Los_Angeles).date_naive() + Duration::days(1);
+        Los_Angeles
+            .from_local_datetime(&tomorrow.and_time(NaiveTime::MIN))
+            .earliest()
+            .map(|reset| reset.timestamp())
+    }
+
+    // Requests consumed today per model, from Cloud Monitoring quota usage metrics
+    async fn fetch_daily_usage(&self, token: &str, project_id: &str) -> Result<HashMap<String, i64>> {
+        let now = Utc::now();
+        let start = now - Duration::days(1);
+        let filter = format!(
+            "metric.type=\"serviceruntime.googleapis.com/quota/rate/net_usage\" AND resource.labels.service=\"{}\"",
+            SERVICE
+        );
+
+        let url = reqwest::Url::parse_with_params(
+            &format!("https://monitoring.googleapis.com/v3/projects/{}/timeSeries", project_id),
+            &[
+                ("filter", filter.as_str()),
+                ("interval.startTime", &start.to_rfc3339()),
+                ("interval.endTime", &now.to_rfc3339()),
+                ("aggregation.alignmentPeriod", "86400s"),
+                ("aggregation.perSeriesAligner", "ALIGN_SUM"),
+            ],
+        )
+        .map_err(|e| QuonitorError::Config(format!("Invalid monitoring URL: {}", e)))?;
+
+        let response: TimeSeriesResponse = self.get_json(url.as_str(), token).await?;
+
+        let mut usage = HashMap::new();
+        for series in response.time_series {
+            let model = series.metric.labels.get("model").cloned().unwrap_or_else(|| "all".to_string());
+            let total: i64 = series.points.iter()
+                .filter_map(|p| p.value.int64_value.as_ref())
+                .filter_map(|v| v.parse::<i64>().ok())
+                .sum();
+            *usage.entry(model).or_insert(0) += total;
+        }
+
+        Ok(usage)
+    }
+}
+
+#[async_trait]
+impl QuotaProvider for GeminiProvider {
+    async fn fetch_quota(&self, credentials: &Credentials, _options: &FetchOptions) -> Result<QuotaData> {
+        let api_key = credentials.api_key.as_ref()
+            .ok_or_else(|| QuonitorError::Auth("Gemini requires API key".to_string()))?;
+
+        self.validate_key(api_key).await?;
+
+        let now = Utc::now();
+
+        // Quota metrics aren't reachable with an API key alone, and the API sends no
+        // rate-limit headers, so without a service account for the key's project the
+        // requests left today are unknown; only the free-tier allowance is reported
+        let Some(json) = credentials.service_account_json.as_ref() else {
+            return Ok(QuotaData {
+                account_id: String::new(), // Will be set by caller
+                timestamp: now.timestamp(),
+                quota_limit: Some(DEFAULT_FREE_TIER_RPD),
+                requests_limit: Some(DEFAULT_FREE_TIER_RPD),
+                requests_reset_at: Self::next_daily_reset(now),
+                metadata: Some(
+                    "Requests left today are unavailable with an API key alone; free tier limit assumed. \
+                     Add a service account to see live quota.".to_string()
+                ),
+                ..Default::default()
+            });
+        };
+
+        let key: ServiceAccountKey = serde_json::from_str(json)?;
+        let token = GoogleProvider::service_account_token(
+            &self.client,
+            &key,
+            "https://www.googleapis.com/auth/cloud-platform.read-only",
+        ).await?;
+
+        let limits = self.fetch_limits(&token, &key.project_id).await?;
+        let usage = self.fetch_daily_usage(&token, &key.project_id).await?;
+
+        let mut model_breakdown = Vec::new();
+        let mut tightest: Option<(i64, i64)> = None; // (limit, remaining)
+
+        for (model, limit) in &limits.per_day {
+            let used = usage.get(model).copied().unwrap_or(0);
+            let remaining = (limit - used).max(0);
+
+            // Track the model closest to exhausting its daily allowance
+            let ratio = |(l, r): (i64, i64)| r as f64 / l.max(1) as f64;
+            if tightest.map_or(true, |t| ratio((*limit, remaining)) < ratio(t)) {
+                tightest = Some((*limit, remaining));
+            }
+
+            model_breakdown.push(ModelData {
+                model_name: model.clone(),
+                tokens_input: 0,
+                tokens_output: 0,
+                cost_usd: 0.0,
+                request_count: used,
+                ..Default::default()
+            });
+        }
+
+        Ok(QuotaData {
+            account_id: String::new(), // Will be set by caller
+            timestamp: now.timestamp(),
+            cost_usd: Some(0.0),
+            quota_limit: tightest.map(|(l, _)| l),
+            quota_remaining: tightest.map(|(_, r)| r),
+            model_breakdown,
+            metadata: Some("Requests per day from Service Usage quota metrics".to_string()),
+            rpm_limit: limits.per_minute.values().min().copied(),
+            requests_limit: tightest.map(|(l, _)| l),
+            requests_remaining: tightest.map(|(_, r)| r),
+            requests_reset_at: Self::next_daily_reset(now),
+            ..Default::default()
+        })
+    }
+
+    fn supports_oauth(&self) -> bool {
+        false
+    }
+
+    fn provider_name(&self) -> &'static str {
+        "Google Gemini API"
+    }
+
+    fn optional_fields(&self) -> Vec<&'static str> {
+        vec!["service_account_json"]
+    }
+
+    fn status_endpoint(&self) -> Option<StatusEndpoint> {
+        Some(StatusEndpoint::Ping("https://generativelanguage.googleapis.com/".to_string()))
+    }
+}
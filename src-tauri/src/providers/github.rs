@@ -1,45 +1,256 @@
 use async_trait::async_trait;
 use chrono::Utc;
-use super::{QuotaProvider, QuotaData};
+use serde::Deserialize;
+use super::{QuotaProvider, QuotaData, ModelData, FetchOptions};
+use super::retry::SendWithRetry;
+use super::status::StatusEndpoint;
 use crate::db::Credentials;
 use crate::error::{QuonitorError, Result};
 
+const API_BASE: &str = "https://api.github.com";
+
 pub struct GitHubProvider {
-    _client: reqwest::Client,
+    client: reqwest::Client,
+}
+
+#[derive(Debug, Deserialize)]
+struct CopilotUserResponse {
+    #[serde(default)]
+    copilot_plan: Option<String>,
+    #[serde(default)]
+    quota_reset_date: Option<String>,
+    #[serde(default)]
+    quota_snapshots: Option<QuotaSnapshots>,
+}
+
+#[derive(Debug, Deserialize)]
+struct QuotaSnapshots {
+    #[serde(default)]
+    premium_interactions: Option<QuotaSnapshot>,
+}
+
+#[derive(Debug, Deserialize)]
+struct QuotaSnapshot {
+    #[serde(default)]
+    entitlement: i64,
+    #[serde(default)]
+    remaining: i64,
+    #[serde(default)]
+    unlimited: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct BillingResponse {
+    seat_breakdown: SeatBreakdown,
+    #[serde(default)]
+    plan_type: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct SeatBreakdown {
+    #[serde(default)]
+    total: i64,
+    #[serde(default)]
+    active_this_cycle: i64,
+    #[serde(default)]
+    inactive_this_cycle: i64,
+    #[serde(default)]
+    pending_invitation: i64,
+}
+
+#[derive(Debug, Deserialize)]
+struct UsageDay {
+    #[serde(default)]
+    total_suggestions_count: i64,
+    #[serde(default)]
+    total_acceptances_count: i64,
+    #[serde(default)]
+    total_chat_turns: i64,
+    #[serde(default)]
+    breakdown: Vec<UsageBreakdown>,
+}
+
+#[derive(Debug, Deserialize)]
+struct UsageBreakdown {
+    #[serde(default)]
+    editor: Option<String>,
+    #[serde(default)]
+    suggestions_count: i64,
+    #[serde(default)]
+    acceptances_count: i64,
 }
 
 impl GitHubProvider {
-    pub fn new() -> Self {
+    pub fn new(client: reqwest::Client) -> Self {
         Self {
-            _client: reqwest::Client::new(),
+            client,
         }
     }
+
+    fn seat_price(plan_type: Option<&str>) -> f64 {
+        // Monthly price per seat in USD (as of 2026)
+        match plan_type {
+            Some("enterprise") => 39.00,
+            _ => 19.00, // Copilot Business
+        }
+    }
+
+    async fn get_json<T: serde::de::DeserializeOwned>(&self, path: &str, token: &str) -> Result<T> {
+        let response = self.client
+            .get(format!("{}{}", API_BASE, path))
+            .bearer_auth(token)
+            .header("Accept", "application/vnd.github+json")
+            .header("X-GitHub-Api-Version", "2022-11-28")
+            .header("User-Agent", "quonitor")
+            .send_with_retry()
+            .await?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let error_text = response.text().await.unwrap_or_else(|_| "Unknown error".to_string());
+            return Err(QuonitorError::Provider(format!(
+                "GitHub API error ({}): {}",
+                status, error_text
+            )));
+        }
+
+        Ok(response.json().await?)
+    }
+
+    // Premium request allowance of the token owner's individual plan
+    async fn fetch_personal_quota(&self, token: &str, quota: &mut QuotaData) -> Result<()> {
+        let user: CopilotUserResponse = self.get_json("/copilot_internal/user", token).await?;
+
+        if let Some(premium) = user.quota_snapshots.and_then(|s| s.premium_interactions) {
+            if !premium.unlimited {
+                quota.quota_limit = Some(premium.entitlement);
+                quota.quota_remaining = Some(premium.remaining);
+            }
+
+            quota.model_breakdown.push(ModelData {
+                model_name: "premium-requests".to_string(),
+                tokens_input: 0,
+                tokens_output: 0,
+                cost_usd: 0.0,
+                request_count: (premium.entitlement - premium.remaining).max(0),
+                ..Default::default()
+            });
+        }
+
+        let plan = format!(
+            "Plan: {}, resets {}",
+            user.copilot_plan.as_deref().unwrap_or("unknown"),
+            user.quota_reset_date.as_deref().unwrap_or("unknown")
+        );
+        quota.metadata = Some(match quota.metadata.take() {
+            Some(existing) => format!("{}; {}", existing, plan),
+            None => plan,
+        });
+
+        Ok(())
+    }
+
+    // Seat billing and daily suggestion/chat activity for an organization
+    async fn fetch_org_usage(&self, token: &str, org: &str, quota: &mut QuotaData) -> Result<()> {
+        let billing: BillingResponse = self.get_json(
+            &format!("/orgs/{}/copilot/billing", org),
+            token,
+        ).await?;
+
+        let monthly_cost = billing.seat_breakdown.total as f64
+            * Self::seat_price(billing.plan_type.as_deref());
+        quota.cost_usd = Some(monthly_cost);
+
+        let seats = &billing.seat_breakdown;
+        quota.metadata = Some(format!(
+            "Seats: {} total, {} active, {} inactive, {} pending",
+            seats.total, seats.active_this_cycle, seats.inactive_this_cycle, seats.pending_invitation
+        ));
+
+        let usage: Vec<UsageDay> = self.get_json(
+            &format!("/orgs/{}/copilot/usage", org),
+            token,
+        ).await?;
+
+        // Report the most recent day, matching the other providers' daily view
+        if let Some(day) = usage.last() {
+            // One entry per editor counting suggestions; they aren't tokens, so acceptances
+            // go in the metadata rather than the token fields
+            let mut editors: std::collections::HashMap<String, (i64, i64)> = std::collections::HashMap::new();
+            for entry in &day.breakdown {
+                let editor = entry.editor.clone().unwrap_or_else(|| "unknown".to_string());
+                let totals = editors.entry(editor).or_insert((0, 0));
+                totals.0 += entry.suggestions_count;
+                totals.1 += entry.acceptances_count;
+            }
+
+            if editors.is_empty() {
+                editors.insert("all-editors".to_string(), (day.total_suggestions_count, day.total_acceptances_count));
+            }
+
+            let (suggestions, acceptances) = editors.values()
+                .fold((0i64, 0i64), |(s, a), (suggestions, acceptances)| (s + suggestions, a + acceptances));
+            if let Some(metadata) = quota.metadata.as_mut() {
+                metadata.push_str(&format!(
+                    "; Latest day: {} suggestions, {} accepted",
+                    suggestions, acceptances
+                ));
+            }
+
+            for (editor, (suggestions, _)) in editors {
+                quota.model_breakdown.push(ModelData {
+                    model_name: format!("completions:{}", editor),
+                    tokens_input: 0,
+                    tokens_output: 0,
+                    cost_usd: 0.0,
+                    request_count: suggestions,
+                    ..Default::default()
+                });
+            }
+
+            quota.model_breakdown.push(ModelData {
+                model_name: "chat".to_string(),
+                tokens_input: 0,
+                tokens_output: 0,
+                cost_usd: 0.0,
+                request_count: day.total_chat_turns,
+                ..Default::default()
+            });
+        }
+
+        Ok(())
+    }
 }
 
 #[async_trait]
 impl QuotaProvider for GitHubProvider {
-    async fn fetch_quota(&self, credentials: &Credentials) -> Result<QuotaData> {
+    async fn fetch_quota(&self, credentials: &Credentials, _options: &FetchOptions) -> Result<QuotaData> {
         // GitHub Copilot requires OAuth token or PAT
-        let _token = credentials.oauth_token.as_ref()
+        let token = credentials.oauth_token.as_ref()
             .or(credentials.api_key.as_ref())
             .ok_or_else(|| QuonitorError::Auth("GitHub requires OAuth token or PAT".to_string()))?;
 
-        // TODO: Implement GitHub GraphQL API integration for Copilot metrics
-        // This requires organization access and specific GraphQL queries
-
         let now = Utc::now();
 
-        Ok(QuotaData {
-            account_id: String::new(),
+        let mut quota = QuotaData {
+            account_id: String::new(), // Will be set by caller
             timestamp: now.timestamp(),
-            tokens_input: Some(0),
-            tokens_output: Some(0),
-            cost_usd: Some(0.0),
-            quota_limit: None,
-            quota_remaining: None,
-            model_breakdown: vec![],
-            metadata: Some("GitHub provider not fully implemented yet".to_string()),
-        })
+            ..Default::default()
+        };
+
+        match credentials.organization.as_deref() {
+            Some(org) => {
+                self.fetch_org_usage(token, org, &mut quota).await?;
+
+                // Org admins usually have a personal seat too; it's fine if they don't
+                if let Err(e) = self.fetch_personal_quota(token, &mut quota).await {
+                    tracing::debug!("No personal Copilot quota for org token: {}", e);
+                }
+            }
+            None => self.fetch_personal_quota(token, &mut quota).await?,
+        }
+
+        Ok(quota)
     }
 
     fn supports_oauth(&self) -> bool {
@@ -49,4 +260,12 @@ impl QuotaProvider for GitHubProvider {
     fn provider_name(&self) -> &'static str {
         "GitHub Copilot"
     }
+
+    fn optional_fields(&self) -> Vec<&'static str> {
+        vec!["organization"]
+    }
+
+    fn status_endpoint(&self) -> Option<StatusEndpoint> {
+        Some(StatusEndpoint::StatusPage("https://www.githubstatus.com/api/v2/status.json".to_string()))
+    }
 }
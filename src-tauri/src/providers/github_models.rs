@@ -0,0 +1,148 @@
+use async_trait::async_trait;
+use chrono::Utc;
+use serde::Deserialize;
+use super::{QuotaProvider, QuotaData, RateLimits, FetchOptions};
+use super::retry::SendWithRetry;
+use super::status::StatusEndpoint;
+use crate::db::Credentials;
+use crate::error::{QuonitorError, Result};
+
+const CATALOG_URL: &str = "https://models.github.ai/catalog/models";
+const COPILOT_USER_URL: &str = "https://api.github.com/copilot_internal/user";
+
+pub struct GitHubModelsProvider {
+    client: reqwest::Client,
+}
+
+#[derive(Debug, Deserialize)]
+struct CatalogModel {
+    #[serde(default)]
+    rate_limit_tier: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CopilotUserResponse {
+    #[serde(default)]
+    copilot_plan: Option<String>,
+}
+
+impl GitHubModelsProvider {
+    pub fn new(client: reqwest::Client) -> Self {
+        Self {
+            client,
+        }
+    }
+
+    fn daily_request_limit(plan: &str, tier: &str) -> i64 {
+        // Requests per day by Copilot plan and model tier (as of 2026)
+        match (plan, tier) {
+            ("enterprise", "high") => 150,
+            ("enterprise", _) => 450,
+            ("business", "high") => 100,
+            ("business", _) => 300,
+            (_, "high") => 50,
+            _ => 150, // Free / Pro, low and embedding tiers
+        }
+    }
+
+    // The plan decides which limits apply; tokens without Copilot fall back to the free tier
+    async fn fetch_plan(&self, token: &str) -> String {
+        let response = self.client
+            .get(COPILOT_USER_URL)
+            .bearer_auth(token)
+            .header("User-Agent", "quonitor")
+            .send_with_retry()
+            .await;
+
+        let plan = match response {
+            Ok(response) if response.status().is_success() => {
+                response.json::<CopilotUserResponse>().await.ok().and_then(|u| u.copilot_plan)
+            }
+            _ => None,
+        };
+
+        plan.unwrap_or_else(|| "free".to_string())
+    }
+}
+
+#[async_trait]
+impl QuotaProvider for GitHubModelsProvider {
+    async fn fetch_quota(&self, credentials: &Credentials, _options: &FetchOptions) -> Result<QuotaData> {
+        let token = credentials.oauth_token.as_ref()
+            .or(credentials.api_key.as_ref())
+            .ok_or_else(|| QuonitorError::Auth("GitHub Models requires OAuth token or PAT".to_string()))?;
+
+        let response = self.client
+            .get(CATALOG_URL)
+            .bearer_auth(token)
+            .header("Accept", "application/vnd.github+json")
+            .header("User-Agent", "quonitor")
+            .send_with_retry()
+            .await?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let error_text = response.text().await.unwrap_or_else(|_| "Unknown error".to_string());
+            return Err(QuonitorError::Provider(format!(
+                "GitHub Models API error ({}): {}",
+                status, error_text
+            )));
+        }
+
+        let rate_limits = RateLimits::from_headers(response.headers());
+        let catalog: Vec<CatalogModel> = response.json().await?;
+        let plan = self.fetch_plan(token).await;
+
+        // GitHub does not report per-tier usage, so only the daily allowances are known
+        let mut tiers: Vec<String> = Vec::new();
+        for model in &catalog {
+            let tier = model.rate_limit_tier.clone().unwrap_or_else(|| "low".to_string());
+            if !tiers.contains(&tier) {
+                tiers.push(tier);
+            }
+        }
+
+        let allowances = tiers.iter()
+            .map(|tier| format!("{} {}/day", tier, Self::daily_request_limit(&plan, tier)))
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        let low_tier_limit = Self::daily_request_limit(&plan, "low");
+        let now = Utc::now();
+
+        Ok(QuotaData {
+            account_id: String::new(), // Will be set by caller
+            timestamp: now.timestamp(),
+            tokens_input: None,
+            tokens_output: None,
+            cost_usd: Some(0.0),
+            quota_limit: Some(low_tier_limit),
+            quota_remaining: None,
+            model_breakdown: Vec::new(),
+            metadata: Some(format!(
+                "Plan: {}, {} models; tier allowances: {}",
+                plan, catalog.len(), allowances
+            )),
+            rpm_limit: rate_limits.rpm_limit,
+            rpm_remaining: rate_limits.rpm_remaining,
+            tpm_limit: rate_limits.tpm_limit,
+            tpm_remaining: rate_limits.tpm_remaining,
+            rpm_reset_at: rate_limits.rpm_reset_at,
+            tpm_reset_at: rate_limits.tpm_reset_at,
+            requests_limit: Some(low_tier_limit),
+            ..Default::default()
+        })
+    }
+
+    fn supports_oauth(&self) -> bool {
+        true
+    }
+
+    fn provider_name(&self) -> &'static str {
+        "GitHub Models"
+    }
+
+    fn status_endpoint(&self) -> Option<StatusEndpoint> {
+        Some(StatusEndpoint::StatusPage("https://www.githubstatus.com/api/v2/status.json".to_string()))
+    }
+}
@@ -8,7 +8,9 @@ use oauth2::{
 };
 use url::Url;
 
-use super::{QuotaProvider, QuotaData};
+use super::{QuotaProvider, QuotaData, ModelData, FetchOptions, AuthMethod};
+use super::retry::SendWithRetry;
+use super::status::StatusEndpoint;
 use crate::db::Credentials;
 use crate::error::{QuonitorError, Result};
 
@@ -23,10 +25,56 @@ pub struct GoogleAuthConfig {
     pub redirect_uri: String,
 }
 
+#[derive(Debug, Deserialize)]
+pub(super) struct ServiceAccountKey {
+    client_email: String,
+    private_key: String,
+    pub project_id: String,
+    #[serde(default = "default_token_uri")]
+    token_uri: String,
+}
+
+fn default_token_uri() -> String {
+    "https://oauth2.googleapis.com/token".to_string()
+}
+
+#[derive(Debug, Serialize)]
+struct ServiceAccountClaims {
+    iss: String,
+    scope: String,
+    aud: String,
+    iat: i64,
+    exp: i64,
+}
+
+#[derive(Debug, Deserialize)]
+struct AccessTokenResponse {
+    access_token: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BigQueryResponse {
+    #[serde(default)]
+    job_complete: bool,
+    #[serde(default)]
+    rows: Vec<BigQueryRow>,
+}
+
+#[derive(Debug, Deserialize)]
+struct BigQueryRow {
+    f: Vec<BigQueryCell>,
+}
+
+#[derive(Debug, Deserialize)]
+struct BigQueryCell {
+    v: Option<String>,
+}
+
 impl GoogleProvider {
-    pub fn new() -> Self {
+    pub fn new(client: Client) -> Self {
         Self {
-            client: Client::new(),
+            client,
         }
     }
 
@@ -51,7 +99,7 @@ impl GoogleProvider {
         Ok((auth_url.to_string(), csrf_token.secret().clone()))
     }
 
-    pub async fn exchange_code(config: &GoogleAuthConfig, code: String) -> Result<String> {
+    pub async fn exchange_code(config: &GoogleAuthConfig, code: String) -> Result<Credentials> {
         let client = BasicClient::new(
             ClientId::new(config.client_id.clone()),
             Some(ClientSecret::new(config.client_secret.clone())),
@@ -69,13 +117,158 @@ impl GoogleProvider {
             .await
             .map_err(|e| QuonitorError::Auth(format!("Token exchange failed: {}", e)))?;
 
-        Ok(token_result.access_token().secret().clone())
+        // Keep what the token refresher needs to renew the token once it expires
+        Ok(Credentials {
+            oauth_token: Some(token_result.access_token().secret().clone()),
+            oauth_refresh_token: token_result.refresh_token().map(|t| t.secret().clone()),
+            oauth_expires_at: token_result.expires_in()
+                .map(|d| Utc::now().timestamp() + d.as_secs() as i64),
+            oauth_client_id: Some(config.client_id.clone()),
+            oauth_client_secret: Some(config.client_secret.clone()),
+            ..Default::default()
+        })
+    }
+
+    // Exchanges a service account key for an access token via the JWT bearer grant
+    pub(super) async fn service_account_token(client: &Client, key: &ServiceAccountKey, scope: &str) -> Result<String> {
+        let now = Utc::now().timestamp();
+        let claims = ServiceAccountClaims {
+            iss: key.client_email.clone(),
+            scope: scope.to_string(),
+            aud: key.token_uri.clone(),
+            iat: now,
+            exp: now + 3600,
+        };
+
+        let signing_key = jsonwebtoken::EncodingKey::from_rsa_pem(key.private_key.as_bytes())
+            .map_err(|e| QuonitorError::Auth(format!("Invalid service account key: {}", e)))?;
+        let assertion = jsonwebtoken::encode(
+            &jsonwebtoken::Header::new(jsonwebtoken::Algorithm::RS256),
+            &claims,
+            &signing_key,
+        )
+        .map_err(|e| QuonitorError::Auth(format!("Failed to sign service account JWT: {}", e)))?;
+
+        let response = client
+            .post(&key.token_uri)
+            .form(&[
+                ("grant_type", "urn:ietf:params:oauth:grant-type:jwt-bearer"),
+                ("assertion", assertion.as_str()),
+            ])
+            .send_with_retry()
+            .await?;
+
+        if !response.status().is_success() {
+            return Err(QuonitorError::Auth(format!(
+                "Service account token exchange failed: {}", response.status()
+            )));
+        }
+
+        let token: AccessTokenResponse = response.json().await?;
+        Ok(token.access_token)
+    }
+
+    // Sums current-month Vertex AI / Generative AI spend (net of credits) from a
+    // Cloud Billing BigQuery export, one breakdown entry per SKU
+    async fn fetch_billing_export(&self, token: &str, project_id: &str, table: &str) -> Result<Vec<ModelData>> {
+        let query = format!(
+            "SELECT sku.description AS sku,
+                    SUM(cost) + SUM(IFNULL((SELECT SUM(c.amount) FROM UNNEST(credits) c), 0)) AS net_cost,
+                    SUM(usage.amount) AS usage_amount
+             FROM `{}`
+             WHERE invoice.month = FORMAT_DATE('%Y%m', CURRENT_DATE())
+               AND service.description IN ('Vertex AI', 'Generative Language API', 'Gemini API')
+             GROUP BY sku
+             ORDER BY net_cost DESC",
+            table
+        );
+
+        let response = self.client
+            .post(format!("https://bigquery.googleapis.com/bigquery/v2/projects/{}/queries", project_id))
+            .bearer_auth(token)
+            .json(&serde_json::json!({
+                "query": query,
+                "useLegacySql": false,
+                "timeoutMs": 30000,
+            }))
+            .send_with_retry()
+            .await?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let error_text = response.text().await.unwrap_or_else(|_| "Unknown error".to_string());
+            return Err(QuonitorError::Provider(format!(
+                "BigQuery API error ({}): {}",
+                status, error_text
+            )));
+        }
+
+        let result: BigQueryResponse = response.json().await?;
+        if !result.job_complete {
+            return Err(QuonitorError::Provider("BigQuery billing query timed out".to_string()));
+        }
+
+        let cell = |row: &BigQueryRow, i: usize| row.f.get(i).and_then(|c| c.v.clone());
+
+        Ok(result.rows
+            .iter()
+            .map(|row| ModelData {
+                model_name: cell(row, 0).unwrap_or_else(|| "unknown".to_string()),
+                tokens_input: 0,
+                tokens_output: 0,
+                cost_usd: cell(row, 1).and_then(|v| v.parse().ok()).unwrap_or(0.0),
+                request_count: cell(row, 2)
+                    .and_then(|v| v.parse::<f64>().ok())
+                    .map(|v| v as i64)
+                    .unwrap_or(0),
+                ..Default::default()
+            })
+            .collect())
     }
 }
 
 #[async_trait]
 impl QuotaProvider for GoogleProvider {
-    async fn fetch_quota(&self, credentials: &Credentials) -> Result<QuotaData> {
+    async fn fetch_quota(&self, credentials: &Credentials, _options: &FetchOptions) -> Result<QuotaData> {
+        if let Some(table) = credentials.billing_export_table.as_ref() {
+            let (token, project_id) = match credentials.service_account_json.as_ref() {
+                Some(json) => {
+                    let key: ServiceAccountKey = serde_json::from_str(json)?;
+                    let token = Self::service_account_token(
+                        &self.client,
+                        &key,
+                        "https://www.googleapis.com/auth/bigquery.readonly",
+                    ).await?;
+                    (token, key.project_id)
+                }
+                None => {
+                    let token = credentials.oauth_token.clone()
+                        .ok_or_else(|| QuonitorError::Auth("Google requires OAuth token or service account".to_string()))?;
+                    // Fully qualified tables are `project.dataset.table`
+                    let project_id = table.split('.').next().unwrap_or_default().to_string();
+                    (token, project_id)
+                }
+            };
+
+            let model_breakdown = self.fetch_billing_export(&token, &project_id, table).await?;
+            let total_cost = model_breakdown.iter().map(|m| m.cost_usd).sum();
+
+            return Ok(QuotaData {
+                account_id: String::new(), // Will be set by caller
+                timestamp: Utc::now().timestamp(),
+                cost_usd: Some(total_cost),
+                model_breakdown,
+                metadata: Some("Current billing period, from BigQuery billing export".to_string()),
+                ..Default::default()
+            });
+        }
+
+        if credentials.service_account_json.is_some() && credentials.oauth_token.is_none() {
+            return Err(QuonitorError::Config(
+                "Google service accounts need a billing export table".to_string(),
+            ));
+        }
+
         // OAuth token should be in credentials.oauth_token
         let token = credentials.oauth_token.as_ref()
             .ok_or_else(|| QuonitorError::Auth("Google requires OAuth token".to_string()))?;
@@ -88,7 +281,7 @@ impl QuotaProvider for GoogleProvider {
         let response = self.client
             .get(url)
             .bearer_auth(token)
-            .send()
+            .send_with_retry()
             .await?;
 
         if !response.status().is_success() {
@@ -110,6 +303,7 @@ impl QuotaProvider for GoogleProvider {
             quota_remaining: None,
             model_breakdown: vec![],
             metadata: Some("Google Cloud tracking enabled".to_string()),
+            ..Default::default()
         })
     }
 
@@ -120,4 +314,28 @@ impl QuotaProvider for GoogleProvider {
     fn provider_name(&self) -> &'static str {
         "Google"
     }
+
+    fn auth_methods(&self) -> Vec<AuthMethod> {
+        vec![AuthMethod::OAuth, AuthMethod::ServiceAccount]
+    }
+
+    fn reports_limits(&self) -> bool {
+        false
+    }
+
+    fn optional_fields(&self) -> Vec<&'static str> {
+        vec!["billing_export_table"]
+    }
+
+    // A service account can only read the billing export; the project listing needs OAuth
+    fn method_fields(&self, method: AuthMethod) -> Vec<&'static str> {
+        match method {
+            AuthMethod::ServiceAccount => vec!["billing_export_table"],
+            _ => Vec::new(),
+        }
+    }
+
+    fn status_endpoint(&self) -> Option<StatusEndpoint> {
+        Some(StatusEndpoint::Ping("https://cloudresourcemanager.googleapis.com/".to_string()))
+    }
 }
@@ -1,14 +1,32 @@
 use async_trait::async_trait;
+use chrono::{DateTime, Datelike, Duration, NaiveTime, TimeZone, Utc};
+use chrono_tz::Tz;
 use serde::{Deserialize, Serialize};
+use std::sync::Arc;
 use crate::db::Credentials;
+use crate::services::PricingService;
 use crate::error::Result;
 
+pub mod retry;
 pub mod openai;
 pub mod anthropic;
 pub mod google;
+pub mod gemini;
 pub mod github;
+pub mod github_models;
+pub mod dashscope;
+pub mod moonshot;
+pub mod zhipu;
+pub mod baseten;
+pub mod modal;
+pub mod runpod;
+pub mod amazon_q;
+pub mod config;
+pub mod plugin;
+pub mod status;
+pub mod timeouts;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
 pub struct QuotaData {
     pub account_id: String,
     pub timestamp: i64,
@@ -19,48 +37,376 @@ pub struct QuotaData {
     pub quota_remaining: Option<i64>,
     pub model_breakdown: Vec<ModelData>,
     pub metadata: Option<String>,
+    // Short-term rate limits reported via response headers
+    #[serde(default)]
+    pub rpm_limit: Option<i64>,
+    #[serde(default)]
+    pub rpm_remaining: Option<i64>,
+    #[serde(default)]
+    pub tpm_limit: Option<i64>,
+    #[serde(default)]
+    pub tpm_remaining: Option<i64>,
+    // Unix timestamps at which the per-minute windows reset
+    #[serde(default)]
+    pub rpm_reset_at: Option<i64>,
+    #[serde(default)]
+    pub tpm_reset_at: Option<i64>,
+    // Longer request allowances (typically requests per day), separate from token/cost quotas
+    #[serde(default)]
+    pub requests_limit: Option<i64>,
+    #[serde(default)]
+    pub requests_remaining: Option<i64>,
+    #[serde(default)]
+    pub requests_reset_at: Option<i64>,
+    // Usage attributed to individual API keys, for providers that report it
+    #[serde(default)]
+    pub api_key_breakdown: Vec<ApiKeyData>,
+    // Non-text usage, for providers that report it
+    #[serde(default)]
+    pub images_generated: Option<i64>,
+    #[serde(default)]
+    pub audio_seconds: Option<f64>,
+    #[serde(default)]
+    pub video_seconds: Option<f64>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+impl QuotaData {
+    // Equal apart from when it was fetched
+    pub fn same_values(&self, other: &QuotaData) -> bool {
+        self == &QuotaData { timestamp: self.timestamp, ..other.clone() }
+    }
+
+    // Usage percentage of whichever dimension (quota, daily requests, per-minute
+    // rate limits) is closest to its limit, along with a label for it
+    pub fn usage_percentage(&self) -> Option<(f64, &'static str)> {
+        Self::closest_to_limit([
+            (self.quota_limit, self.quota_remaining, "quota"),
+            (self.requests_limit, self.requests_remaining, "request limit"),
+            (self.rpm_limit, self.rpm_remaining, "requests-per-minute limit"),
+            (self.tpm_limit, self.tpm_remaining, "tokens-per-minute limit"),
+        ])
+    }
+
+    // Like `usage_percentage`, leaving out the per-minute limits, which swing from one
+    // fetch to the next rather than building up over a period
+    pub fn period_usage_percentage(&self) -> Option<(f64, &'static str)> {
+        Self::closest_to_limit([
+            (self.quota_limit, self.quota_remaining, "quota"),
+            (self.requests_limit, self.requests_remaining, "request limit"),
+        ])
+    }
+
+    // With cost data but no explicit limits there is nothing to compare against;
+    // alerts use the account's budgets instead
+    fn closest_to_limit<const N: usize>(
+        dimensions: [(Option<i64>, Option<i64>, &'static str); N],
+    ) -> Option<(f64, &'static str)> {
+        dimensions.into_iter()
+            .filter_map(|(limit, remaining, label)| match (limit, remaining) {
+                (Some(limit), Some(remaining)) if limit > 0 => {
+                    let used = limit - remaining;
+                    Some(((used as f64 / limit as f64) * 100.0, label))
+                }
+                _ => None,
+            })
+            .max_by(|a, b| a.0.total_cmp(&b.0))
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct RateLimits {
+    pub rpm_limit: Option<i64>,
+    pub rpm_remaining: Option<i64>,
+    pub tpm_limit: Option<i64>,
+    pub tpm_remaining: Option<i64>,
+    pub rpm_reset_at: Option<i64>,
+    pub tpm_reset_at: Option<i64>,
+}
+
+impl RateLimits {
+    // Understands both the OpenAI/Groq `x-ratelimit-*` and Anthropic `anthropic-ratelimit-*` header families
+    pub fn from_headers(headers: &reqwest::header::HeaderMap) -> Self {
+        let read = |names: &[&str]| {
+            names.iter()
+                .filter_map(|name| headers.get(*name))
+                .filter_map(|value| value.to_str().ok())
+                .find_map(|value| value.trim().parse::<i64>().ok())
+        };
+        let now = Utc::now();
+        let reset = |names: &[&str]| {
+            names.iter()
+                .filter_map(|name| headers.get(*name))
+                .filter_map(|value| value.to_str().ok())
+                .find_map(|value| parse_reset(value.trim(), now))
+        };
+
+        Self {
+            rpm_limit: read(&["x-ratelimit-limit-requests", "anthropic-ratelimit-requests-limit"]),
+            rpm_remaining: read(&["x-ratelimit-remaining-requests", "anthropic-ratelimit-requests-remaining"]),
+            tpm_limit: read(&["x-ratelimit-limit-tokens", "anthropic-ratelimit-tokens-limit"]),
+            tpm_remaining: read(&["x-ratelimit-remaining-tokens", "anthropic-ratelimit-tokens-remaining"]),
+            rpm_reset_at: reset(&["x-ratelimit-reset-requests", "anthropic-ratelimit-requests-reset"]),
+            tpm_reset_at: reset(&["x-ratelimit-reset-tokens", "anthropic-ratelimit-tokens-reset"]),
+        }
+    }
+}
+
+// Reset headers are either RFC 3339 timestamps (Anthropic) or Go-style
+// durations such as "1s", "6m0s" or "20ms" (OpenAI, Groq)
+fn parse_reset(value: &str, now: DateTime<Utc>) -> Option<i64> {
+    if let Ok(at) = DateTime::parse_from_rfc3339(value) {
+        return Some(at.timestamp());
+    }
+
+    let mut millis = 0f64;
+    let mut rest = value;
+    while !rest.is_empty() {
+        let split = rest.find(|c: char| c.is_ascii_alphabetic())?;
+        let amount: f64 = rest[..split].parse().ok()?;
+        rest = &rest[split..];
+
+        let unit_len = rest.find(|c: char| !c.is_ascii_alphabetic()).unwrap_or(rest.len());
+        millis += amount * match &rest[..unit_len] {
+            "ms" => 1.0,
+            "s" => 1_000.0,
+            "m" => 60_000.0,
+            "h" => 3_600_000.0,
+            _ => return None,
+        };
+        rest = &rest[unit_len..];
+    }
+
+    // Absurdly large values would overflow the date arithmetic
+    let delay = Duration::try_milliseconds(millis.ceil() as i64)?;
+    now.checked_add_signed(delay).map(|at| at.timestamp())
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
 pub struct ModelData {
     pub model_name: String,
     pub tokens_input: i64,
     pub tokens_output: i64,
     pub cost_usd: f64,
     pub request_count: i64,
+    // Subsets of tokens_input / tokens_output that are priced differently
+    #[serde(default)]
+    pub tokens_cached_input: i64,
+    #[serde(default)]
+    pub tokens_reasoning: i64,
+    #[serde(default)]
+    pub images_generated: Option<i64>,
+    #[serde(default)]
+    pub audio_seconds: Option<f64>,
+    #[serde(default)]
+    pub video_seconds: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApiKeyData {
+    pub api_key_id: String,
+    pub tokens_input: i64,
+    pub tokens_output: i64,
+    pub cost_usd: f64,
+    pub request_count: i64,
+}
+
+// Per-account knobs that shape what a provider reports
+#[derive(Debug, Clone)]
+pub struct FetchOptions {
+    // None keeps the rolling 24h window; 0 means month-to-date
+    pub lookback_days: Option<u32>,
+    pub timezone: Tz,
+}
+
+impl Default for FetchOptions {
+    fn default() -> Self {
+        Self {
+            lookback_days: None,
+            timezone: Tz::UTC,
+        }
+    }
+}
+
+impl FetchOptions {
+    // Start of the usage window, aligned to midnight in the billing timezone
+    pub fn window_start(&self, now: DateTime<Utc>) -> DateTime<Utc> {
+        match self.lookback_days {
+            None => now - Duration::days(1),
+            Some(0) => self.month_start(now),
+            Some(days) => {
+                let local_date = now.with_timezone(&self.timezone).date_naive()
+                    - Duration::days(days as i64 - 1);
+                self.local_midnight(local_date).unwrap_or(now - Duration::days(days as i64))
+            }
+        }
+    }
+
+    pub fn month_start(&self, now: DateTime<Utc>) -> DateTime<Utc> {
+        let local = now.with_timezone(&self.timezone);
+        local.date_naive()
+            .with_day(1)
+            .and_then(|first| self.local_midnight(first))
+            .unwrap_or(now)
+    }
+
+    fn local_midnight(&self, date: chrono::NaiveDate) -> Option<DateTime<Utc>> {
+        self.timezone
+            .from_local_datetime(&date.and_time(NaiveTime::MIN))
+            .earliest()
+            .map(|dt| dt.with_timezone(&Utc))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+pub enum AuthMethod {
+    // Fills `Credentials::api_key`
+    #[serde(rename = "api_key")]
+    ApiKey,
+    // Fills `Credentials::oauth_token`
+    #[serde(rename = "oauth")]
+    OAuth,
+    // Fills `Credentials::service_account_json`
+    #[serde(rename = "service_account")]
+    ServiceAccount,
+}
+
+// What the frontend needs to render an add-account form for a provider
+#[derive(Debug, Clone, Serialize)]
+pub struct ProviderInfo {
+    pub id: String,
+    pub name: String,
+    pub auth_methods: Vec<AuthMethod>,
+    // True when the provider reports a quota/limit, false when it only reports cost
+    pub reports_limits: bool,
+    // Credential fields needed on top of the chosen auth method
+    pub required_fields: Vec<&'static str>,
+    pub optional_fields: Vec<&'static str>,
+    // Further fields needed only with a given auth method
+    pub method_fields: std::collections::HashMap<AuthMethod, Vec<&'static str>>,
 }
 
 #[async_trait]
 pub trait QuotaProvider: Send + Sync {
-    async fn fetch_quota(&self, credentials: &Credentials) -> Result<QuotaData>;
-    #[allow(dead_code)]
+    async fn fetch_quota(&self, credentials: &Credentials, options: &FetchOptions) -> Result<QuotaData>;
     fn supports_oauth(&self) -> bool;
-    #[allow(dead_code)]
-    fn provider_name(&self) -> &'static str;
+    fn provider_name(&self) -> &str;
+
+    fn auth_methods(&self) -> Vec<AuthMethod> {
+        if self.supports_oauth() {
+            vec![AuthMethod::ApiKey, AuthMethod::OAuth]
+        } else {
+            vec![AuthMethod::ApiKey]
+        }
+    }
+
+    fn reports_limits(&self) -> bool {
+        true
+    }
+
+    fn required_fields(&self) -> Vec<&'static str> {
+        Vec::new()
+    }
+
+    fn optional_fields(&self) -> Vec<&'static str> {
+        Vec::new()
+    }
+
+    fn method_fields(&self, _method: AuthMethod) -> Vec<&'static str> {
+        Vec::new()
+    }
+
+    fn status_endpoint(&self) -> Option<status::StatusEndpoint> {
+        None
+    }
 }
 
 pub struct ProviderRegistry {
     providers: std::collections::HashMap<String, Box<dyn QuotaProvider>>,
+    timeouts: timeouts::TimeoutConfig,
 }
 
 impl ProviderRegistry {
-    pub fn new() -> Self {
+    pub fn new(pricing: Arc<PricingService>, timeouts: timeouts::TimeoutConfig) -> Self {
         let mut providers: std::collections::HashMap<String, Box<dyn QuotaProvider>> = std::collections::HashMap::new();
 
-        providers.insert("openai".to_string(), Box::new(openai::OpenAIProvider::new()));
-        providers.insert("anthropic".to_string(), Box::new(anthropic::AnthropicProvider::new()));
-        providers.insert("google".to_string(), Box::new(google::GoogleProvider::new()));
-        providers.insert("github".to_string(), Box::new(github::GitHubProvider::new()));
+        providers.insert("openai".to_string(), Box::new(openai::OpenAIProvider::new(timeouts.client("openai"), pricing.clone()));
+        providers.insert("anthropic".to_string(), Box::new(anthropic::AnthropicProvider::new(timeouts.client("anthropic"), pricing.clone()));
+        providers.insert("google".to_string(), Box::new(google::GoogleProvider::new(timeouts.client("google")));
+        providers.insert("gemini".to_string(), Box::new(gemini::GeminiProvider::new(timeouts.client("gemini")));
+        providers.insert("github".to_string(), Box::new(github::GitHubProvider::new(timeouts.client("github")));
+        providers.insert("github_models".to_string(), Box::new(github_models::GitHubModelsProvider::new(timeouts.client("github_models")));
+        providers.insert("dashscope".to_string(), Box::new(dashscope::DashScopeProvider::new(timeouts.client("dashscope"), pricing.clone()));
+        providers.insert("moonshot".to_string(), Box::new(moonshot::MoonshotProvider::new(timeouts.client("moonshot"), pricing.clone()));
+        providers.insert("zhipu".to_string(), Box::new(zhipu::ZhipuProvider::new(timeouts.client("zhipu"), pricing.clone()));
+        providers.insert("baseten".to_string(), Box::new(baseten::BasetenProvider::new(timeouts.client("baseten"), pricing));
+        providers.insert("modal".to_string(), Box::new(modal::ModalProvider::new(timeouts.client("modal")));
+        providers.insert("runpod".to_string(), Box::new(runpod::RunPodProvider::new(timeouts.client("runpod")));
+        providers.insert("amazon_q".to_string(), Box::new(amazon_q::AmazonQProvider::new(timeouts.client("amazon_q")));
+
+        Self { providers, timeouts }
+    }
+
+    // Adds the user-defined providers from the JSON config; built-in ids can't be shadowed
+    pub fn register_config_providers(&mut self, path: &std::path::Path) {
+        let configured = match config::ConfigProvider::load_all(path, &self.timeouts) {
+            Ok(configured) => configured,
+            Err(e) => {
+                tracing::error!("Failed to load provider config {}: {}", path.display(), e);
+                return;
+            }
+        };
 
-        Self { providers }
+        for provider in configured {
+            if self.providers.contains_key(provider.id()) {
+                tracing::warn!("Ignoring configured provider {}: id is already registered", provider.id());
+                continue;
+            }
+            tracing::info!("Registered configured provider {}", provider.id());
+            self.providers.insert(provider.id().to_string(), Box::new(provider));
+        }
+    }
+
+    // Adds executable plugins from `dir`; like configured providers, they can't shadow existing ids
+    pub fn register_plugins(&mut self, dir: &std::path::Path) {
+        let plugins = match plugin::PluginProvider::load_all(dir, |id| self.timeouts.for_provider(id).read) {
+            Ok(plugins) => plugins,
+            Err(e) => {
+                tracing::error!("Failed to load plugins from {}: {}", dir.display(), e);
+                return;
+            }
+        };
+
+        for plugin in plugins {
+            if self.providers.contains_key(plugin.id()) {
+                tracing::warn!("Ignoring plugin {}: id is already registered", plugin.id());
+                continue;
+            }
+            tracing::info!("Registered plugin provider {}", plugin.id());
+            self.providers.insert(plugin.id().to_string(), Box::new(plugin));
+        }
     }
 
     pub fn get(&self, provider: &str) -> Option<&Box<dyn QuotaProvider>> {
         self.providers.get(provider)
     }
 
-    #[allow(dead_code)]
-    pub fn list_providers(&self) -> Vec<String> {
-        self.providers.keys().cloned().collect()
+    pub fn list_providers(&self) -> Vec<ProviderInfo> {
+        let mut infos: Vec<ProviderInfo> = self.providers.iter()
+            .map(|(id, provider)| ProviderInfo {
+                id: id.clone(),
+                name: provider.provider_name().to_string(),
+                auth_methods: provider.auth_methods(),
+                reports_limits: provider.reports_limits(),
+                required_fields: provider.required_fields(),
+                optional_fields: provider.optional_fields(),
+                method_fields: provider.auth_methods().into_iter()
+                    .map(|method| (method, provider.method_fields(method)))
+                    .filter(|(_, fields)| !fields.is_empty())
+                    .collect(),
+            })
+            .collect();
+
+        infos.sort_by(|a, b| a.name.cmp(&b.name));
+        infos
     }
 }
@@ -0,0 +1,119 @@
+use async_trait::async_trait;
+use chrono::Utc;
+use serde::Deserialize;
+use super::{QuotaProvider, QuotaData, ModelData, FetchOptions};
+use super::retry::SendWithRetry;
+use super::status::StatusEndpoint;
+use crate::db::Credentials;
+use crate::error::{QuonitorError, Result};
+
+pub struct ModalProvider {
+    client: reqwest::Client,
+}
+
+#[derive(Debug, Deserialize)]
+struct BillingResponse {
+    #[serde(default)]
+    period_spend: f64,
+    #[serde(default)]
+    free_credits_total: Option<f64>,
+    #[serde(default)]
+    free_credits_remaining: Option<f64>,
+    #[serde(default)]
+    apps: Vec<AppSpend>,
+}
+
+#[derive(Debug, Deserialize)]
+struct AppSpend {
+    #[serde(default)]
+    name: Option<String>,
+    #[serde(default)]
+    cost: f64,
+    #[serde(default)]
+    function_calls: i64,
+}
+
+impl ModalProvider {
+    pub fn new(client: reqwest::Client) -> Self {
+        Self {
+            client,
+        }
+    }
+
+    // Modal tokens come as an id/secret pair, stored as "token_id:token_secret"
+    fn split_token(api_key: &str) -> Result<(&str, &str)> {
+        api_key.split_once(':')
+            .ok_or_else(|| QuonitorError::Auth(
+                "Modal token must be in the form token_id:token_secret".to_string()
+            ))
+    }
+}
+
+#[async_trait]
+impl QuotaProvider for ModalProvider {
+    async fn fetch_quota(&self, credentials: &Credentials, _options: &FetchOptions) -> Result<QuotaData> {
+        let api_key = credentials.api_key.as_ref()
+            .ok_or_else(|| QuonitorError::Auth("Modal requires API token".to_string()))?;
+
+        let (token_id, token_secret) = Self::split_token(api_key)?;
+
+        let response = self.client
+            .get("https://api.modal.com/v1/workspace/billing/current")
+            .header("Modal-Key", token_id)
+            .header("Modal-Secret", token_secret)
+            .send_with_retry()
+            .await?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let error_text = response.text().await.unwrap_or_else(|_| "Unknown error".to_string());
+            return Err(QuonitorError::Provider(format!(
+                "Modal API error ({}): {}",
+                status, error_text
+            )));
+        }
+
+        let billing: BillingResponse = response.json().await?;
+
+        // Modal bills GPU/CPU seconds per app; each app becomes a breakdown entry
+        let model_breakdown = billing.apps
+            .into_iter()
+            .map(|app| ModelData {
+                model_name: app.name.unwrap_or_else(|| "unknown".to_string()),
+                tokens_input: 0,
+                tokens_output: 0,
+                cost_usd: app.cost,
+                request_count: app.function_calls,
+                ..Default::default()
+            })
+            .collect();
+
+        let now = Utc::now();
+
+        Ok(QuotaData {
+            account_id: String::new(), // Will be set by caller
+            timestamp: now.timestamp(),
+            tokens_input: None,
+            tokens_output: None,
+            cost_usd: Some(billing.period_spend),
+            // Free credits are dollar-denominated; expose them in cents
+            quota_limit: billing.free_credits_total.map(|c| (c * 100.0).round() as i64),
+            quota_remaining: billing.free_credits_remaining.map(|c| (c * 100.0).round() as i64),
+            model_breakdown,
+            metadata: None,
+            ..Default::default()
+        })
+    }
+
+    fn supports_oauth(&self) -> bool {
+        false
+    }
+
+    fn provider_name(&self) -> &'static str {
+        "Modal"
+    }
+
+    fn status_endpoint(&self) -> Option<StatusEndpoint> {
+        Some(StatusEndpoint::Ping("https://api.modal.com/".to_string()))
+    }
+}
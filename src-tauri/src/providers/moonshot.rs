@@ -0,0 +1,186 @@
+use std::sync::Arc;
+use async_trait::async_trait;
+use chrono::Utc;
+use serde::Deserialize;
+use super::{QuotaProvider, QuotaData, ModelData, FetchOptions};
+use super::retry::SendWithRetry;
+use super::status::StatusEndpoint;
+use crate::db::Credentials;
+use crate::services::PricingService;
+use crate::error::{QuonitorError, Result};
+
+const API_BASE: &str = "https://api.moonshot.ai/v1";
+
+pub struct MoonshotProvider {
+    client: reqwest::Client,
+    pricing: Arc<PricingService>,
+}
+
+#[derive(Debug, Deserialize)]
+struct BalanceResponse {
+    data: BalanceData,
+}
+
+#[derive(Debug, Deserialize)]
+struct BalanceData {
+    #[serde(default)]
+    available_balance: f64,
+    #[serde(default)]
+    voucher_balance: f64,
+    #[serde(default)]
+    cash_balance: f64,
+}
+
+#[derive(Debug, Deserialize)]
+struct UsageResponse {
+    #[serde(default)]
+    data: Vec<UsageDataPoint>,
+}
+
+#[derive(Debug, Deserialize)]
+struct UsageDataPoint {
+    #[serde(default)]
+    model: Option<String>,
+    #[serde(default)]
+    prompt_tokens: i64,
+    #[serde(default)]
+    completion_tokens: i64,
+    #[serde(default)]
+    cached_tokens: i64,
+    #[serde(default)]
+    request_count: i64,
+}
+
+impl MoonshotProvider {
+    pub fn new(client: reqwest::Client, pricing: Arc<PricingService>) -> Self {
+        Self {
+            client,
+            pricing,
+        }
+    }
+
+    fn calculate_moonshot_cost(&self, model: &str, input_tokens: i64, output_tokens: i64, cached_tokens: i64) -> f64 {
+        self.pricing.token_cost_detailed("moonshot", model, input_tokens, output_tokens, cached_tokens, 0)
+    }
+
+    async fn fetch_balance(&self, api_key: &str) -> Result<BalanceData> {
+        let response = self.client
+            .get(format!("{}/users/me/balance", API_BASE))
+            .header("Authorization", format!("Bearer {}", api_key))
+            .send_with_retry()
+            .await?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let error_text = response.text().await.unwrap_or_else(|_| "Unknown error".to_string());
+            return Err(QuonitorError::Provider(format!(
+                "Moonshot API error ({}): {}",
+                status, error_text
+            )));
+        }
+
+        let balance: BalanceResponse = response.json().await?;
+        Ok(balance.data)
+    }
+
+    async fn fetch_daily_usage(&self, api_key: &str, options: &FetchOptions) -> Result<Vec<UsageDataPoint>> {
+        let today = Utc::now().with_timezone(&options.timezone).format("%Y-%m-%d");
+
+        let response = self.client
+            .get(format!("{}/users/me/usage?date={}&group_by=model", API_BASE, today))
+            .header("Authorization", format!("Bearer {}", api_key))
+            .send_with_retry()
+            .await?;
+
+        if !response.status().is_success() {
+            return Err(QuonitorError::Provider(format!(
+                "Moonshot usage API error: {}", response.status()
+            )));
+        }
+
+        let usage: UsageResponse = response.json().await?;
+        Ok(usage.data)
+    }
+}
+
+#[async_trait]
+impl QuotaProvider for MoonshotProvider {
+    async fn fetch_quota(&self, credentials: &Credentials, options: &FetchOptions) -> Result<QuotaData> {
+        let api_key = credentials.api_key.as_ref()
+            .ok_or_else(|| QuonitorError::Auth("Moonshot requires API key".to_string()))?;
+
+        let balance = self.fetch_balance(api_key).await?;
+
+        // Per-model usage is best-effort; the balance alone is still worth reporting
+        let usage = match self.fetch_daily_usage(api_key, options).await {
+            Ok(usage) => usage,
+            Err(e) => {
+                tracing::warn!("Failed to fetch Moonshot daily usage: {}", e);
+                vec![]
+            }
+        };
+
+        let mut total_input = 0i64;
+        let mut total_output = 0i64;
+        let mut total_cost = 0.0f64;
+        let mut model_breakdown = Vec::new();
+
+        for data_point in usage {
+            let model_name = data_point.model.unwrap_or_else(|| "unknown".to_string());
+            let cost = self.calculate_moonshot_cost(
+                &model_name,
+                data_point.prompt_tokens,
+                data_point.completion_tokens,
+                data_point.cached_tokens,
+            );
+
+            total_input += data_point.prompt_tokens;
+            total_output += data_point.completion_tokens;
+            total_cost += cost;
+
+            model_breakdown.push(ModelData {
+                model_name,
+                tokens_input: data_point.prompt_tokens,
+                tokens_output: data_point.completion_tokens,
+                cost_usd: cost,
+                request_count: data_point.request_count,
+                tokens_cached_input: data_point.cached_tokens,
+                ..Default::default()
+            });
+        }
+
+        let now = Utc::now();
+
+        Ok(QuotaData {
+            account_id: String::new(), // Will be set by caller
+            timestamp: now.timestamp(),
+            tokens_input: Some(total_input),
+            tokens_output: Some(total_output),
+            cost_usd: Some(total_cost),
+            quota_limit: None, // Prepaid balance has no fixed ceiling
+            quota_remaining: Some((balance.available_balance * 100.0).round() as i64), // In cents
+            model_breakdown,
+            metadata: Some(format!(
+                "Balance: {:.2} (cash {:.2}, voucher {:.2})",
+                balance.available_balance, balance.cash_balance, balance.voucher_balance
+            )),
+            ..Default::default()
+        })
+    }
+
+    fn supports_oauth(&self) -> bool {
+        false
+    }
+
+    fn provider_name(&self) -> &'static str {
+        "Moonshot"
+    }
+
+    fn reports_limits(&self) -> bool {
+        false
+    }
+
+    fn status_endpoint(&self) -> Option<StatusEndpoint> {
+        Some(StatusEndpoint::Ping("https://api.moonshot.ai/".to_string()))
+    }
+}
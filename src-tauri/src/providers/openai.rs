@@ -1,106 +1,409 @@
+use std::sync::Arc;
 use async_trait::async_trait;
 use chrono::Utc;
 use serde::Deserialize;
-use super::{QuotaProvider, QuotaData, ModelData};
+use super::{QuotaProvider, QuotaData, ModelData, ApiKeyData, RateLimits, FetchOptions};
+use super::retry::SendWithRetry;
+use super::status::StatusEndpoint;
 use crate::db::Credentials;
+use crate::services::PricingService;
 use crate::error::{QuonitorError, Result};
 
+// Upper bound on pages followed per request, in case the cursor never terminates
+const MAX_PAGES: usize = 50;
+
+// Batch API usage is reported per model as "batch:<model>" in the breakdown
+const BATCH_PREFIX: &str = "batch:";
+
+// Fine-tuning jobs are reported per base model as "fine-tune:<model>"
+const FINE_TUNE_PREFIX: &str = "fine-tune:";
+
 pub struct OpenAIProvider {
     client: reqwest::Client,
+    pricing: Arc<PricingService>,
 }
 
+// Organization usage/costs endpoints share this cursor-paginated envelope
 #[derive(Debug, Deserialize)]
-struct UsageResponse {
-    data: Vec<UsageDataPoint>,
+struct Page<T> {
+    #[serde(default)]
+    data: Vec<T>,
+    #[serde(default)]
+    has_more: bool,
+    #[serde(default)]
+    next_page: Option<String>,
+}
+
+// Usage and cost data come in time buckets, each holding one result per group
+#[derive(Debug, Deserialize)]
+struct Bucket<T> {
+    #[serde(default)]
+    results: Vec<T>,
 }
 
 #[derive(Debug, Deserialize)]
 struct UsageDataPoint {
     #[serde(default)]
     _aggregation_timestamp: Option<i64>,
-    #[serde(default)]
+    #[serde(default, alias = "num_model_requests")]
     n_requests: i64,
     #[serde(default)]
     _operation: Option<String>,
     #[serde(default)]
     _snapshot_id: Option<String>,
-    #[serde(default)]
+    #[serde(default, alias = "input_tokens")]
     n_context_tokens_total: i64,
-    #[serde(default)]
+    #[serde(default, alias = "output_tokens")]
     n_generated_tokens_total: i64,
+    #[serde(default, alias = "input_cached_tokens")]
+    n_cached_context_tokens_total: i64,
+    #[serde(default)]
+    model: Option<String>,
+    #[serde(default)]
+    api_key_id: Option<String>,
+    #[serde(default)]
+    batch: Option<bool>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ImagesDataPoint {
+    #[serde(default)]
+    images: i64,
+    #[serde(default)]
+    num_model_requests: i64,
+    #[serde(default)]
+    model: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct AudioSpeechesDataPoint {
+    #[serde(default)]
+    characters: i64,
+    #[serde(default)]
+    num_model_requests: i64,
+    #[serde(default)]
+    model: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct AudioTranscriptionsDataPoint {
+    #[serde(default)]
+    seconds: i64,
+    #[serde(default)]
+    num_model_requests: i64,
+    #[serde(default)]
+    model: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct FineTuningJobList {
+    #[serde(default)]
+    data: Vec<FineTuningJob>,
+    #[serde(default)]
+    has_more: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct FineTuningJob {
+    id: String,
     #[serde(default)]
     model: Option<String>,
+    #[serde(default)]
+    created_at: i64,
+    #[serde(default)]
+    trained_tokens: Option<i64>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CostResult {
+    amount: CostAmount,
+}
+
+#[derive(Debug, Deserialize)]
+struct CostAmount {
+    #[serde(default)]
+    value: f64,
 }
 
 impl OpenAIProvider {
-    pub fn new() -> Self {
+    pub fn new(client: reqwest::Client, pricing: Arc<PricingService>) -> Self {
         Self {
-            client: reqwest::Client::new(),
+            client,
+            pricing,
         }
     }
 
-    fn calculate_openai_cost(model: &str, input_tokens: i64, output_tokens: i64) -> f64 {
-        // Pricing per million tokens (as of 2026)
-        let (input_price, output_price) = match model {
-            m if m.contains("gpt-4o") => (2.50, 10.00),
-            m if m.contains("gpt-4-turbo") => (10.00, 30.00),
-            m if m.contains("gpt-4") => (30.00, 60.00),
-            m if m.contains("gpt-3.5-turbo") => (0.50, 1.50),
-            m if m.contains("o1-preview") => (15.00, 60.00),
-            m if m.contains("o1-mini") => (3.00, 12.00),
-            _ => (1.00, 2.00), // Default fallback
-        };
-
-        let input_cost = (input_tokens as f64 / 1_000_000.0) * input_price;
-        let output_cost = (output_tokens as f64 / 1_000_000.0) * output_price;
-
-        input_cost + output_cost
+    fn calculate_openai_cost(&self, model: &str, usage: &ModelData) -> f64 {
+        let cost = self.pricing.token_cost_detailed(
+            "openai",
+            model,
+            usage.tokens_input,
+            usage.tokens_output,
+            usage.tokens_cached_input,
+            usage.tokens_reasoning,
+        );
+
+        if model.starts_with(BATCH_PREFIX) {
+            cost * self.pricing.batch_multiplier("openai")
+        } else {
+            cost
+        }
+    }
+
+    // Follows `next_page` cursors and returns every page's items, plus the rate
+    // limits reported on the last response
+    async fn get_all_pages<T: serde::de::DeserializeOwned>(
+        &self,
+        api_key: &str,
+        url: &str,
+    ) -> Result<(Vec<T>, RateLimits)> {
+        let mut items = Vec::new();
+        let mut rate_limits = RateLimits::default();
+        let mut cursor: Option<String> = None;
+
+        for _ in 0..MAX_PAGES {
+            let page_url = match &cursor {
+                Some(page) => format!("{}&page={}", url, page),
+                None => url.to_string(),
+            };
+
+            let response = self.client
+                .get(&page_url)
+                .header("Authorization", format!("Bearer {}", api_key))
+                .header("Content-Type", "application/json")
+                .send_with_retry()
+                .await?;
+
+            if !response.status().is_success() {
+                let status = response.status();
+                let error_text = response.text().await.unwrap_or_else(|_| "Unknown error".to_string());
+                return Err(QuonitorError::Provider(format!(
+                    "OpenAI API error ({}): {}",
+                    status, error_text
+                )));
+            }
+
+            rate_limits = RateLimits::from_headers(response.headers());
+            let page: Page<T> = response.json().await?;
+            items.extend(page.data);
+
+            match page.next_page {
+                Some(next) if page.has_more => cursor = Some(next),
+                _ => return Ok((items, rate_limits)),
+            }
+        }
+
+        tracing::warn!("OpenAI pagination stopped after {} pages: {}", MAX_PAGES, url);
+        Ok((items, rate_limits))
+    }
+
+    // Like `get_all_pages`, flattened across the time buckets
+    async fn get_all_results<T: serde::de::DeserializeOwned>(
+        &self,
+        api_key: &str,
+        url: &str,
+    ) -> Result<(Vec<T>, RateLimits)> {
+        let (buckets, rate_limits) = self.get_all_pages::<Bucket<T>>(api_key, url).await?;
+        Ok((buckets.into_iter().flat_map(|bucket| bucket.results).collect(), rate_limits))
+    }
+
+    fn model_entry(
+        map: &mut std::collections::HashMap<String, ModelData>,
+        model: Option<String>,
+    ) -> &mut ModelData {
+        let model_name = model.unwrap_or_else(|| "unknown".to_string());
+        map.entry(model_name.clone()).or_insert_with(|| ModelData {
+            model_name,
+            ..Default::default()
+        })
+    }
+
+    fn usage_url(endpoint: &str, start_time: i64, end_time: i64) -> String {
+        format!(
+            "https://api.openai.com/v1/organization/usage/{}?start_time={}&end_time={}&bucket_width=1d&group_by=model",
+            endpoint, start_time, end_time
+        )
+    }
+
+    // Image generation, text-to-speech and transcription usage, one entry per model
+    async fn fetch_media_usage(&self, api_key: &str, start_time: i64, end_time: i64) -> Result<Vec<ModelData>> {
+        let (images, _) = self.get_all_results::<ImagesDataPoint>(
+            api_key,
+            &Self::usage_url("images", start_time, end_time),
+        ).await?;
+        let (speeches, _) = self.get_all_results::<AudioSpeechesDataPoint>(
+            api_key,
+            &Self::usage_url("audio_speeches", start_time, end_time),
+        ).await?;
+        let (transcriptions, _) = self.get_all_results::<AudioTranscriptionsDataPoint>(
+            api_key,
+            &Self::usage_url("audio_transcriptions", start_time, end_time),
+        ).await?;
+
+        let mut media: std::collections::HashMap<String, ModelData> = std::collections::HashMap::new();
+
+        for point in images {
+            let usage = Self::model_entry(&mut media, point.model);
+            usage.cost_usd += self.pricing.image_cost("openai", &usage.model_name, point.images);
+            *usage.images_generated.get_or_insert(0) += point.images;
+            usage.request_count += point.num_model_requests;
+        }
+
+        // Text-to-speech is billed per character, priced as input units
+        for point in speeches {
+            let usage = Self::model_entry(&mut media, point.model);
+            usage.cost_usd += self.pricing.token_cost("openai", &usage.model_name, point.characters, 0);
+            usage.request_count += point.num_model_requests;
+        }
+
+        for point in transcriptions {
+            let usage = Self::model_entry(&mut media, point.model);
+            usage.cost_usd += self.pricing.minute_cost("openai", &usage.model_name, point.seconds as f64 / 60.0);
+            *usage.audio_seconds.get_or_insert(0.0) += point.seconds as f64;
+            usage.request_count += point.num_model_requests;
+        }
+
+        Ok(media.into_values().collect())
+    }
+
+    // Training cost of fine-tuning jobs created in the window, one entry per base model.
+    // Jobs are listed newest first, so paging stops once it passes the window start.
+    async fn fetch_fine_tuning_usage(&self, api_key: &str, start_time: i64) -> Result<Vec<ModelData>> {
+        let mut jobs_by_model: std::collections::HashMap<String, ModelData> = std::collections::HashMap::new();
+        let mut after: Option<String> = None;
+
+        for _ in 0..MAX_PAGES {
+            let mut url = "https://api.openai.com/v1/fine_tuning/jobs?limit=100".to_string();
+            if let Some(after) = &after {
+                url = format!("{}&after={}", url, after);
+            }
+
+            let response = self.client
+                .get(&url)
+                .header("Authorization", format!("Bearer {}", api_key))
+                .send_with_retry()
+                .await?;
+
+            if !response.status().is_success() {
+                let status = response.status();
+                let error_text = response.text().await.unwrap_or_else(|_| "Unknown error".to_string());
+                return Err(QuonitorError::Provider(format!(
+                    "OpenAI API error ({}): {}",
+                    status, error_text
+                )));
+            }
+
+            let page: FineTuningJobList = response.json().await?;
+            let reached_start = page.data.iter().any(|job| job.created_at < start_time);
+
+            for job in page.data.iter().filter(|job| job.created_at >= start_time) {
+                let base_model = job.model.clone().unwrap_or_else(|| "unknown".to_string());
+                let trained_tokens = job.trained_tokens.unwrap_or(0);
+
+                let usage = Self::model_entry(
+                    &mut jobs_by_model,
+                    Some(format!("{}{}", FINE_TUNE_PREFIX, base_model)),
+                );
+                usage.tokens_input += trained_tokens;
+                usage.cost_usd += self.pricing.training_cost("openai", &base_model, trained_tokens);
+                usage.request_count += 1;
+            }
+
+            match page.data.last() {
+                Some(last) if page.has_more && !reached_start => after = Some(last.id.clone()),
+                _ => return Ok(jobs_by_model.into_values().collect()),
+            }
+        }
+
+        tracing::warn!("OpenAI fine-tuning job listing stopped after {} pages", MAX_PAGES);
+        Ok(jobs_by_model.into_values().collect())
+    }
+
+    // Actual invoiced spend; only available to organization admin keys
+    async fn fetch_actual_cost(&self, admin_key: &str, start_time: i64) -> Result<f64> {
+        let url = format!(
+            "https://api.openai.com/v1/organization/costs?start_time={}&bucket_width=1d",
+            start_time
+        );
+
+        let (results, _) = self.get_all_results::<CostResult>(admin_key, &url).await?;
+
+        Ok(results.iter().map(|result| result.amount.value).sum())
     }
 }
 
 #[async_trait]
 impl QuotaProvider for OpenAIProvider {
-    async fn fetch_quota(&self, credentials: &Credentials) -> Result<QuotaData> {
-        let api_key = credentials.api_key.as_ref()
+    async fn fetch_quota(&self, credentials: &Credentials, options: &FetchOptions) -> Result<QuotaData> {
+        let api_key = credentials.admin_key.as_ref()
+            .or(credentials.api_key.as_ref())
             .ok_or_else(|| QuonitorError::Auth("OpenAI requires API key".to_string()))?;
 
-        // Fetch usage data for the last day with per-model breakdown
+        // Fetch usage data for the account's lookback window with per-model and per-key breakdown
         let now = Utc::now();
-        let start_time = now - chrono::Duration::days(1);
+        let start_time = options.window_start(now);
 
+        // Grouping by batch splits Batch API requests out so they can be discounted
         let url = format!(
-            "https://api.openai.com/v1/organization/usage/completions?start_time={}&end_time={}&bucket_width=1d&group_by=model",
-            start_time.timestamp(),
-            now.timestamp()
+            "{}&group_by=api_key_id&group_by=batch",
+            Self::usage_url("completions", start_time.timestamp(), now.timestamp())
         );
 
-        let response = self.client
-            .get(&url)
-            .header("Authorization", format!("Bearer {}", api_key))
-            .header("Content-Type", "application/json")
-            .send()
-            .await?;
-
-        if !response.status().is_success() {
-            let status = response.status();
-            let error_text = response.text().await.unwrap_or_else(|_| "Unknown error".to_string());
-            return Err(QuonitorError::Provider(format!(
-                "OpenAI API error ({}): {}",
-                status, error_text
-            )));
+        let (mut data_points, rate_limits) = self.get_all_results::<UsageDataPoint>(api_key, &url).await?;
+
+        // Embeddings are reported by their own endpoint but aggregate the same way
+        // (input tokens only), so they fold into the same per-model and per-key totals
+        let embeddings_url = format!(
+            "{}&group_by=api_key_id",
+            Self::usage_url("embeddings", start_time.timestamp(), now.timestamp())
+        );
+        match self.get_all_results::<UsageDataPoint>(api_key, &embeddings_url).await {
+            Ok((embeddings, _)) => data_points.extend(embeddings),
+            Err(e) => {
+                tracing::warn!("Failed to fetch OpenAI embeddings usage: {}", e);
+            }
         }
 
-        let usage_response: UsageResponse = response.json().await?;
+        // Aggregate by model and by API key across all pages
+        let mut model_map: std::collections::HashMap<String, ModelData> = std::collections::HashMap::new();
+        let mut key_map: std::collections::HashMap<String, ApiKeyData> = std::collections::HashMap::new();
+
+        for data_point in data_points {
+            let mut model_name = data_point.model.unwrap_or_else(|| "unknown".to_string());
+            if data_point.batch == Some(true) {
+                model_name = format!("{}{}", BATCH_PREFIX, model_name);
+            }
+            let usage = ModelData {
+                model_name: model_name.clone(),
+                tokens_input: data_point.n_context_tokens_total,
+                tokens_output: data_point.n_generated_tokens_total,
+                request_count: data_point.n_requests,
+                // Reasoning tokens aren't broken out by the usage API; they're
+                // already included in the generated token count
+                tokens_cached_input: data_point.n_cached_context_tokens_total,
+                ..Default::default()
+            };
 
-        // Aggregate by model
-        let mut model_map: std::collections::HashMap<String, (i64, i64, i64)> = std::collections::HashMap::new();
+            // Cost depends on the model, so price each (model, key) row before folding it into the key
+            let key_id = data_point.api_key_id.unwrap_or_else(|| "unknown".to_string());
+            let key = key_map.entry(key_id.clone()).or_insert_with(|| ApiKeyData {
+                api_key_id: key_id,
+                tokens_input: 0,
+                tokens_output: 0,
+                cost_usd: 0.0,
+                request_count: 0,
+            });
+            key.tokens_input += usage.tokens_input;
+            key.tokens_output += usage.tokens_output;
+            key.cost_usd += self.calculate_openai_cost(&model_name, &usage);
+            key.request_count += usage.request_count;
 
-        for data_point in usage_response.data {
-            let model_name = data_point.model.unwrap_or_else(|| "unknown".to_string());
-            let entry = model_map.entry(model_name).or_insert((0, 0, 0));
-            entry.0 += data_point.n_context_tokens_total;
-            entry.1 += data_point.n_generated_tokens_total;
-            entry.2 += data_point.n_requests;
+            let entry = Self::model_entry(&mut model_map, Some(model_name));
+            entry.tokens_input += usage.tokens_input;
+            entry.tokens_output += usage.tokens_output;
+            entry.request_count += usage.request_count;
+            entry.tokens_cached_input += usage.tokens_cached_input;
+            entry.tokens_reasoning += usage.tokens_reasoning;
         }
 
         // Calculate totals and per-model data
@@ -109,20 +412,62 @@ impl QuotaProvider for OpenAIProvider {
         let mut total_cost = 0.0f64;
         let mut model_breakdown = Vec::new();
 
-        for (model_name, (input, output, requests)) in model_map {
-            total_input += input;
-            total_output += output;
+        for (model_name, mut usage) in model_map {
+            total_input += usage.tokens_input;
+            total_output += usage.tokens_output;
 
-            let cost = Self::calculate_openai_cost(&model_name, input, output);
-            total_cost += cost;
+            usage.cost_usd = self.calculate_openai_cost(&model_name, &usage);
+            total_cost += usage.cost_usd;
 
-            model_breakdown.push(ModelData {
-                model_name,
-                tokens_input: input,
-                tokens_output: output,
-                cost_usd: cost,
-                request_count: requests,
-            });
+            model_breakdown.push(usage);
+        }
+
+        // Media endpoints are separate from completions; missing them shouldn't hide text usage
+        let mut images_generated = None;
+        let mut audio_seconds = None;
+        match self.fetch_media_usage(api_key, start_time.timestamp(), now.timestamp()).await {
+            Ok(media) => {
+                for usage in media {
+                    total_cost += usage.cost_usd;
+                    if let Some(images) = usage.images_generated {
+                        *images_generated.get_or_insert(0) += images;
+                    }
+                    if let Some(seconds) = usage.audio_seconds {
+                        *audio_seconds.get_or_insert(0.0) += seconds;
+                    }
+                    model_breakdown.push(usage);
+                }
+            }
+            Err(e) => {
+                tracing::warn!("Failed to fetch OpenAI image/audio usage: {}", e);
+            }
+        }
+
+        // Training runs never show up in inference usage, so they get their own entries
+        match self.fetch_fine_tuning_usage(api_key, start_time.timestamp()).await {
+            Ok(fine_tunes) => {
+                for usage in fine_tunes {
+                    total_cost += usage.cost_usd;
+                    model_breakdown.push(usage);
+                }
+            }
+            Err(e) => {
+                tracing::warn!("Failed to fetch OpenAI fine-tuning jobs: {}", e);
+            }
+        }
+
+        // Prefer invoiced cost over the pricing-table estimate when we can get it
+        let mut cost_source = "estimate";
+        if let Some(admin_key) = credentials.admin_key.as_ref() {
+            match self.fetch_actual_cost(admin_key, start_time.timestamp()).await {
+                Ok(actual) => {
+                    total_cost = actual;
+                    cost_source = "actual";
+                }
+                Err(e) => {
+                    tracing::warn!("Falling back to estimated OpenAI cost: {}", e);
+                }
+            }
         }
 
         Ok(QuotaData {
@@ -134,7 +479,17 @@ impl QuotaProvider for OpenAIProvider {
             quota_limit: None, // OpenAI doesn't expose hard limits via API
             quota_remaining: None,
             model_breakdown,
-            metadata: None,
+            metadata: Some(format!("cost_source={}", cost_source)),
+            rpm_limit: rate_limits.rpm_limit,
+            rpm_remaining: rate_limits.rpm_remaining,
+            tpm_limit: rate_limits.tpm_limit,
+            tpm_remaining: rate_limits.tpm_remaining,
+            rpm_reset_at: rate_limits.rpm_reset_at,
+            tpm_reset_at: rate_limits.tpm_reset_at,
+            api_key_breakdown: key_map.into_values().collect(),
+            images_generated,
+            audio_seconds,
+            ..Default::default()
         })
     }
 
@@ -145,4 +500,16 @@ impl QuotaProvider for OpenAIProvider {
     fn provider_name(&self) -> &'static str {
         "OpenAI"
     }
+
+    fn reports_limits(&self) -> bool {
+        false
+    }
+
+    fn optional_fields(&self) -> Vec<&'static str> {
+        vec!["admin_key"]
+    }
+
+    fn status_endpoint(&self) -> Option<StatusEndpoint> {
+        Some(StatusEndpoint::StatusPage("https://status.openai.com/api/v2/status.json".to_string()))
+    }
 }
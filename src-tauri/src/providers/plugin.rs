@@ -0,0 +1,233 @@
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::time::Duration;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+use super::{QuotaProvider, QuotaData, FetchOptions, AuthMethod};
+use super::status::StatusEndpoint;
+use crate::db::Credentials;
+use crate::error::{QuonitorError, Result};
+
+// Bumped whenever the request/response shape changes incompatibly
+const PROTOCOL_VERSION: u32 = 1;
+
+// Credential fields a plugin may ask the add-account form for
+const CREDENTIAL_FIELDS: &[&str] = &[
+    "admin_key",
+    "organization",
+    "service_account_json",
+    "billing_export_table",
+];
+
+// `plugins/<name>/plugin.json`, describing an executable that speaks the stdio protocol.
+// Plugins are executables only; there is no WASM runtime to load modules into.
+#[derive(Debug, Clone, Deserialize)]
+pub struct PluginManifest {
+    pub id: String,
+    pub name: String,
+    // Resolved relative to the plugin's directory
+    pub command: String,
+    #[serde(default)]
+    pub args: Vec<String>,
+    #[serde(default = "default_auth_methods")]
+    pub auth_methods: Vec<String>,
+    #[serde(default)]
+    pub required_fields: Vec<String>,
+    #[serde(default)]
+    pub optional_fields: Vec<String>,
+    #[serde(default = "default_reports_limits")]
+    pub reports_limits: bool,
+    // Optional Statuspage `/api/v2/status.json` URL for health checks
+    #[serde(default)]
+    pub status_url: Option<String>,
+}
+
+fn default_auth_methods() -> Vec<String> {
+    vec!["api_key".to_string()]
+}
+
+fn default_reports_limits() -> bool {
+    true
+}
+
+// Written to the plugin's stdin as a single JSON document
+#[derive(Debug, Serialize)]
+struct PluginRequest<'a> {
+    version: u32,
+    method: &'static str,
+    credentials: &'a Credentials,
+    lookback_days: Option<u32>,
+    timezone: String,
+    window_start: i64,
+}
+
+// Read from the plugin's stdout: either the quota or an error message
+#[derive(Debug, Deserialize)]
+struct PluginResponse {
+    #[serde(default)]
+    quota: Option<QuotaData>,
+    #[serde(default)]
+    error: Option<String>,
+}
+
+pub struct PluginProvider {
+    manifest: PluginManifest,
+    command: PathBuf,
+    timeout: Duration,
+}
+
+impl PluginProvider {
+    pub fn new(manifest: PluginManifest, dir: &Path, timeout: Duration) -> Self {
+        let command = dir.join(&manifest.command);
+        Self {
+            manifest,
+            command,
+            timeout,
+        }
+    }
+
+    pub fn id(&self) -> &str {
+        &self.manifest.id
+    }
+
+    // Every subdirectory with a readable plugin.json is a plugin; broken ones are skipped
+    pub fn load_all(dir: &Path, timeout_for: impl Fn(&str) -> Duration) -> Result<Vec<Self>> {
+        if !dir.exists() {
+            return Ok(Vec::new());
+        }
+
+        let mut plugins = Vec::new();
+        for entry in std::fs::read_dir(dir)? {
+            let plugin_dir = entry?.path();
+            let manifest_path = plugin_dir.join("plugin.json");
+            if !manifest_path.is_file() {
+                continue;
+            }
+
+            let manifest: PluginManifest = match std::fs::read_to_string(&manifest_path)
+                .map_err(QuonitorError::from)
+                .and_then(|json| serde_json::from_str(&json).map_err(QuonitorError::from))
+            {
+                Ok(manifest) => manifest,
+                Err(e) => {
+                    tracing::warn!("Skipping plugin {}: {}", manifest_path.display(), e);
+                    continue;
+                }
+            };
+
+            let timeout = timeout_for(&manifest.id);
+            plugins.push(Self::new(manifest, &plugin_dir, timeout));
+        }
+
+        Ok(plugins)
+    }
+
+    fn known_fields(fields: &[String]) -> Vec<&'static str> {
+        CREDENTIAL_FIELDS.iter()
+            .copied()
+            .filter(|known| fields.iter().any(|f| f == known))
+            .collect()
+    }
+
+    async fn run(&self, request: &PluginRequest<'_>) -> Result<QuotaData> {
+        let mut child = Command::new(&self.command)
+            .args(&self.manifest.args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+            .map_err(|e| QuonitorError::Provider(format!(
+                "Failed to start plugin {}: {}", self.command.display(), e
+            )))?;
+
+        let input = serde_json::to_vec(request)?;
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin.write_all(&input).await?;
+            // Closing stdin tells the plugin the request is complete
+        }
+
+        let output = tokio::time::timeout(self.timeout, child.wait_with_output())
+            .await
+            .map_err(|_| QuonitorError::Timeout(format!(
+                "Plugin {} did not respond within {:?}", self.manifest.id, self.timeout
+            )))??;
+
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        if !stderr.trim().is_empty() {
+            tracing::debug!("Plugin {} stderr: {}", self.manifest.id, stderr.trim());
+        }
+
+        if !output.status.success() {
+            return Err(QuonitorError::Provider(format!(
+                "{} plugin exited with {}: {}",
+                self.manifest.name, output.status, stderr.trim()
+            )));
+        }
+
+        let response: PluginResponse = serde_json::from_slice(&output.stdout)?;
+        match (response.quota, response.error) {
+            (_, Some(error)) => Err(QuonitorError::Provider(format!("{} plugin error: {}", self.manifest.name, error))),
+            (Some(quota), None) => Ok(quota),
+            (None, None) => Err(QuonitorError::Provider(format!(
+                "{} plugin returned neither quota nor error", self.manifest.name
+            ))),
+        }
+    }
+}
+
+#[async_trait]
+impl QuotaProvider for PluginProvider {
+    async fn fetch_quota(&self, credentials: &Credentials, options: &FetchOptions) -> Result<QuotaData> {
+        let request = PluginRequest {
+            version: PROTOCOL_VERSION,
+            method: "fetch_quota",
+            credentials,
+            lookback_days: options.lookback_days,
+            timezone: options.timezone.name().to_string(),
+            window_start: options.window_start(chrono::Utc::now()).timestamp(),
+        };
+
+        let mut quota = self.run(&request).await?;
+        quota.account_id = String::new(); // Will be set by caller
+        Ok(quota)
+    }
+
+    fn supports_oauth(&self) -> bool {
+        self.manifest.auth_methods.iter().any(|m| m == "oauth")
+    }
+
+    fn provider_name(&self) -> &str {
+        &self.manifest.name
+    }
+
+    fn auth_methods(&self) -> Vec<AuthMethod> {
+        self.manifest.auth_methods.iter()
+            .filter_map(|method| match method.as_str() {
+                "api_key" => Some(AuthMethod::ApiKey),
+                "oauth" => Some(AuthMethod::OAuth),
+                "service_account" => Some(AuthMethod::ServiceAccount),
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn reports_limits(&self) -> bool {
+        self.manifest.reports_limits
+    }
+
+    fn required_fields(&self) -> Vec<&'static str> {
+        Self::known_fields(&self.manifest.required_fields)
+    }
+
+    fn optional_fields(&self) -> Vec<&'static str> {
+        Self::known_fields(&self.manifest.optional_fields)
+    }
+
+    fn status_endpoint(&self) -> Option<StatusEndpoint> {
+        let status_url = self.manifest.status_url.clone()?;
+        Some(StatusEndpoint::StatusPage(status_url))
+    }
+}
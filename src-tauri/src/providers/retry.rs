@@ -0,0 +1,77 @@
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::time::Duration;
+use async_trait::async_trait;
+use rand::Rng;
+use reqwest::{RequestBuilder, Response, StatusCode};
+use tracing::debug;
+
+pub const DEFAULT_MAX_ATTEMPTS: u32 = 3;
+
+const BASE_DELAY_MS: u64 = 500;
+const MAX_DELAY_MS: u64 = 60_000;
+
+// Shared by every provider; updated from the `retry_max_attempts` setting
+static MAX_ATTEMPTS: AtomicU32 = AtomicU32::new(DEFAULT_MAX_ATTEMPTS);
+
+pub fn set_max_attempts(attempts: u32) {
+    MAX_ATTEMPTS.store(attempts.max(1), Ordering::Relaxed);
+}
+
+#[async_trait]
+pub trait SendWithRetry {
+    // Sends the request, retrying 429/5xx responses and transient network errors
+    // with exponential backoff and jitter, honoring `Retry-After` when present
+    async fn send_with_retry(self) -> reqwest::Result<Response>;
+}
+
+#[async_trait]
+impl SendWithRetry for RequestBuilder {
+    async fn send_with_retry(self) -> reqwest::Result<Response> {
+        let max_attempts = MAX_ATTEMPTS.load(Ordering::Relaxed);
+        let mut attempt = 1;
+
+        loop {
+            // Streaming bodies can't be replayed, so those get a single shot
+            let Some(request) = self.try_clone() else {
+                return self.send().await;
+            };
+
+            let delay = match request.send().await {
+                Ok(response) if attempt < max_attempts && is_retryable(response.status()) => {
+                    retry_after(&response).unwrap_or_else(|| backoff(attempt))
+                }
+                Err(e) if attempt < max_attempts && (e.is_timeout() || e.is_connect()) => backoff(attempt),
+                result => return result,
+            };
+
+            debug!("Retrying request (attempt {} of {}) in {:?}", attempt + 1, max_attempts, delay);
+            tokio::time::sleep(delay).await;
+            attempt += 1;
+        }
+    }
+}
+
+fn is_retryable(status: StatusCode) -> bool {
+    status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error()
+}
+
+fn backoff(attempt: u32) -> Duration {
+    let exponential = BASE_DELAY_MS.saturating_mul(1 << (attempt - 1).min(16));
+    let jitter = rand::thread_rng().gen_range(0..=exponential / 2);
+    Duration::from_millis((exponential + jitter).min(MAX_DELAY_MS))
+}
+
+// `Retry-After` is either a number of seconds or an HTTP date
+fn retry_after(response: &Response) -> Option<Duration> {
+    let value = response.headers().get(reqwest::header::RETRY_AFTER)?.to_str().ok()?;
+
+    let seconds = match value.trim().parse::<u64>() {
+        Ok(seconds) => seconds,
+        Err(_) => {
+            let date = chrono::DateTime::parse_from_rfc2822(value).ok()?;
+            (date.timestamp() - chrono::Utc::now().timestamp()).max(0) as u64
+        }
+    };
+
+    Some(Duration::from_millis(seconds.saturating_mul(1000).min(MAX_DELAY_MS)))
+}
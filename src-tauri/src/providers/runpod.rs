@@ -0,0 +1,165 @@
+use async_trait::async_trait;
+use chrono::Utc;
+use serde::Deserialize;
+use super::{QuotaProvider, QuotaData, ModelData, FetchOptions};
+use super::retry::SendWithRetry;
+use super::status::StatusEndpoint;
+use crate::db::Credentials;
+use crate::error::{QuonitorError, Result};
+
+const GRAPHQL_URL: &str = "https://api.runpod.io/graphql";
+
+const ACCOUNT_QUERY: &str = r#"query {
+  myself {
+    clientBalance
+    currentSpendPerHr
+    spendLimit
+    endpoints {
+      name
+      spendToday
+      requestsToday
+    }
+  }
+}"#;
+
+pub struct RunPodProvider {
+    client: reqwest::Client,
+}
+
+#[derive(Debug, Deserialize)]
+struct GraphQLResponse {
+    data: Option<GraphQLData>,
+    #[serde(default)]
+    errors: Vec<GraphQLError>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GraphQLError {
+    message: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct GraphQLData {
+    myself: Myself,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Myself {
+    #[serde(default)]
+    client_balance: f64,
+    #[serde(default)]
+    current_spend_per_hr: f64,
+    #[serde(default)]
+    spend_limit: Option<f64>,
+    #[serde(default)]
+    endpoints: Vec<Endpoint>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Endpoint {
+    #[serde(default)]
+    name: Option<String>,
+    #[serde(default)]
+    spend_today: f64,
+    #[serde(default)]
+    requests_today: i64,
+}
+
+impl RunPodProvider {
+    pub fn new(client: reqwest::Client) -> Self {
+        Self {
+            client,
+        }
+    }
+}
+
+#[async_trait]
+impl QuotaProvider for RunPodProvider {
+    async fn fetch_quota(&self, credentials: &Credentials, _options: &FetchOptions) -> Result<QuotaData> {
+        let api_key = credentials.api_key.as_ref()
+            .ok_or_else(|| QuonitorError::Auth("RunPod requires API key".to_string()))?;
+
+        let response = self.client
+            .post(GRAPHQL_URL)
+            .bearer_auth(api_key)
+            .json(&serde_json::json!({ "query": ACCOUNT_QUERY }))
+            .send_with_retry()
+            .await?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let error_text = response.text().await.unwrap_or_else(|_| "Unknown error".to_string());
+            return Err(QuonitorError::Provider(format!(
+                "RunPod API error ({}): {}",
+                status, error_text
+            )));
+        }
+
+        let body: GraphQLResponse = response.json().await?;
+
+        // GraphQL reports failures with a 200 status and an errors array
+        let myself = match body.data {
+            Some(data) => data.myself,
+            None => {
+                let message = body.errors.into_iter()
+                    .map(|e| e.message)
+                    .collect::<Vec<_>>()
+                    .join("; ");
+                return Err(QuonitorError::Provider(format!("RunPod API error: {}", message)));
+            }
+        };
+
+        let mut total_cost = 0.0f64;
+        let mut model_breakdown = Vec::new();
+
+        for endpoint in myself.endpoints {
+            total_cost += endpoint.spend_today;
+
+            model_breakdown.push(ModelData {
+                model_name: endpoint.name.unwrap_or_else(|| "unknown".to_string()),
+                tokens_input: 0,
+                tokens_output: 0,
+                cost_usd: endpoint.spend_today,
+                request_count: endpoint.requests_today,
+                ..Default::default()
+            });
+        }
+
+        let now = Utc::now();
+
+        Ok(QuotaData {
+            account_id: String::new(), // Will be set by caller
+            timestamp: now.timestamp(),
+            tokens_input: None,
+            tokens_output: None,
+            cost_usd: Some(total_cost),
+            // The balance in cents. The spend limit is an hourly cap, not what the
+            // balance is measured against, so it's only shown in the metadata
+            quota_limit: None,
+            quota_remaining: Some((myself.client_balance * 100.0).round() as i64),
+            model_breakdown,
+            metadata: Some(match myself.spend_limit {
+                Some(limit) => format!(
+                    "Current spend: ${:.2}/hr, limit ${:.2}/hr",
+                    myself.current_spend_per_hr, limit
+                ),
+                None => format!("Current spend: ${:.2}/hr", myself.current_spend_per_hr),
+            }),
+            ..Default::default()
+        })
+    }
+
+    fn supports_oauth(&self) -> bool {
+        false
+    }
+
+    fn provider_name(&self) -> &'static str {
+        "RunPod"
+    }
+
+    fn status_endpoint(&self) -> Option<StatusEndpoint> {
+        Some(StatusEndpoint::Ping("https://api.runpod.io/".to_string()))
+    }
+}
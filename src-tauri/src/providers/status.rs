@@ -0,0 +1,92 @@
+use std::time::Duration;
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+
+// Status probes should answer quickly; a hanging probe is itself a signal
+const PROBE_TIMEOUT: Duration = Duration::from_secs(10);
+
+// Where to look to tell a provider outage apart from a bad key
+#[derive(Debug, Clone)]
+pub enum StatusEndpoint {
+    // Atlassian Statuspage `/api/v2/status.json` summary
+    StatusPage(String),
+    // Any HTTP response counts as reachable, even 401/404
+    Ping(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ServiceState {
+    Operational,
+    Degraded,
+    Outage,
+    Unreachable,
+    Unknown,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ProviderStatus {
+    pub provider: String,
+    pub state: ServiceState,
+    pub description: Option<String>,
+    pub checked_at: i64,
+}
+
+#[derive(Debug, Deserialize)]
+struct StatusPageResponse {
+    status: StatusPageIndicator,
+}
+
+#[derive(Debug, Deserialize)]
+struct StatusPageIndicator {
+    #[serde(default)]
+    indicator: String,
+    #[serde(default)]
+    description: Option<String>,
+}
+
+pub async fn probe(client: &reqwest::Client, provider: &str, endpoint: Option<StatusEndpoint>) -> ProviderStatus {
+    let (state, description) = match endpoint {
+        Some(StatusEndpoint::StatusPage(url)) => probe_status_page(client, &url).await,
+        Some(StatusEndpoint::Ping(url)) => probe_ping(client, &url).await,
+        None => (ServiceState::Unknown, Some("No status endpoint for this provider".to_string())),
+    };
+
+    ProviderStatus {
+        provider: provider.to_string(),
+        state,
+        description,
+        checked_at: Utc::now().timestamp(),
+    }
+}
+
+async fn probe_status_page(client: &reqwest::Client, url: &str) -> (ServiceState, Option<String>) {
+    let response = match client.get(url).timeout(PROBE_TIMEOUT).send().await {
+        Ok(response) if response.status().is_success() => response,
+        Ok(response) => return (ServiceState::Unknown, Some(format!("Status page returned {}", response.status()))),
+        Err(e) => return (ServiceState::Unreachable, Some(e.to_string())),
+    };
+
+    match response.json::<StatusPageResponse>().await {
+        Ok(page) => {
+            let state = match page.status.indicator.as_str() {
+                "none" => ServiceState::Operational,
+                "minor" | "maintenance" => ServiceState::Degraded,
+                "major" | "critical" => ServiceState::Outage,
+                _ => ServiceState::Unknown,
+            };
+            (state, page.status.description)
+        }
+        Err(e) => (ServiceState::Unknown, Some(format!("Unreadable status page: {}", e))),
+    }
+}
+
+async fn probe_ping(client: &reqwest::Client, url: &str) -> (ServiceState, Option<String>) {
+    match client.get(url).timeout(PROBE_TIMEOUT).send().await {
+        Ok(response) if response.status().is_server_error() => {
+            (ServiceState::Degraded, Some(format!("API returned {}", response.status())))
+        }
+        Ok(_) => (ServiceState::Operational, None),
+        Err(e) => (ServiceState::Unreachable, Some(e.to_string())),
+    }
+}
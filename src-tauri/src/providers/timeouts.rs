@@ -0,0 +1,76 @@
+use std::collections::HashMap;
+use std::time::Duration;
+use crate::db::Repository;
+use crate::services::Settings;
+use crate::services::settings::ProviderOverrides;
+
+pub const DEFAULT_CONNECT_TIMEOUT_SECS: u64 = 10;
+pub const DEFAULT_READ_TIMEOUT_SECS: u64 = 60;
+
+#[derive(Debug, Clone, Copy)]
+pub struct Timeouts {
+    pub connect: Duration,
+    pub read: Duration,
+}
+
+impl Default for Timeouts {
+    fn default() -> Self {
+        Self {
+            connect: Duration::from_secs(DEFAULT_CONNECT_TIMEOUT_SECS),
+            read: Duration::from_secs(DEFAULT_READ_TIMEOUT_SECS),
+        }
+    }
+}
+
+// Global timeouts from `Settings::connect_timeout_seconds` / `read_timeout_seconds`,
+// overridable per provider in `Settings::provider_overrides`
+// Provider clients are built once at startup, so changes apply on the next launch
+#[derive(Debug, Clone, Default)]
+pub struct TimeoutConfig {
+    global: Timeouts,
+    overrides: HashMap<String, ProviderOverrides>,
+}
+
+impl TimeoutConfig {
+    pub async fn load(repo: &Repository) -> Self {
+        let settings = match Settings::load(repo).await {
+            Ok(settings) => settings,
+            Err(e) => {
+                tracing::warn!("Failed to load timeout settings, using defaults: {}", e);
+                return Self::default();
+            }
+        };
+
+        Self {
+            global: Timeouts {
+                connect: Duration::from_secs(settings.connect_timeout_seconds.max(1)),
+                read: Duration::from_secs(settings.read_timeout_seconds.max(1)),
+            },
+            overrides: settings.provider_overrides,
+        }
+    }
+
+    pub fn for_provider(&self, provider: &str) -> Timeouts {
+        let overrides = self.overrides.get(provider);
+        let seconds = |value: Option<u64>| value.map(|s| Duration::from_secs(s.max(1)));
+
+        Timeouts {
+            connect: seconds(overrides.and_then(|o| o.connect_timeout_seconds)).unwrap_or(self.global.connect),
+            read: seconds(overrides.and_then(|o| o.read_timeout_seconds)).unwrap_or(self.global.read),
+        }
+    }
+
+    // A client whose requests give up instead of stalling the whole fetch cycle
+    pub fn client(&self, provider: &str) -> reqwest::Client {
+        let timeouts = self.for_provider(provider);
+
+        reqwest::Client::builder()
+            .connect_timeout(timeouts.connect)
+            .read_timeout(timeouts.read)
+            .build()
+            .unwrap_or_else(|e| {
+                tracing::error!("Failed to build HTTP client for {}: {}", provider, e);
+                reqwest::Client::new()
+            })
+    }
+}
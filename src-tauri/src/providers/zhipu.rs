@@ -0,0 +1,176 @@
+use std::sync::Arc;
+use async_trait::async_trait;
+use chrono::Utc;
+use serde::Deserialize;
+use super::{QuotaProvider, QuotaData, ModelData, FetchOptions};
+use super::retry::SendWithRetry;
+use super::status::StatusEndpoint;
+use crate::db::Credentials;
+use crate::services::PricingService;
+use crate::error::{QuonitorError, Result};
+
+const API_BASE: &str = "https://open.bigmodel.cn/api";
+
+pub struct ZhipuProvider {
+    client: reqwest::Client,
+    pricing: Arc<PricingService>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PackageResponse {
+    #[serde(default)]
+    data: Vec<TokenPackage>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TokenPackage {
+    #[serde(default)]
+    total_tokens: i64,
+    #[serde(default)]
+    remaining_tokens: i64,
+    #[serde(default)]
+    status: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct UsageResponse {
+    #[serde(default)]
+    data: Vec<UsageDataPoint>,
+}
+
+#[derive(Debug, Deserialize)]
+struct UsageDataPoint {
+    #[serde(default)]
+    model: Option<String>,
+    #[serde(default)]
+    prompt_tokens: i64,
+    #[serde(default)]
+    completion_tokens: i64,
+    #[serde(default)]
+    request_count: i64,
+}
+
+impl ZhipuProvider {
+    pub fn new(client: reqwest::Client, pricing: Arc<PricingService>) -> Self {
+        Self {
+            client,
+            pricing,
+        }
+    }
+
+    fn calculate_zhipu_cost(&self, model: &str, input_tokens: i64, output_tokens: i64) -> f64 {
+        self.pricing.token_cost("zhipu", model, input_tokens, output_tokens)
+    }
+
+    async fn get_json<T: serde::de::DeserializeOwned>(&self, url: &str, api_key: &str) -> Result<T> {
+        let response = self.client
+            .get(url)
+            .header("Authorization", format!("Bearer {}", api_key))
+            .send_with_retry()
+            .await?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let error_text = response.text().await.unwrap_or_else(|_| "Unknown error".to_string());
+            return Err(QuonitorError::Provider(format!(
+                "Zhipu API error ({}): {}",
+                status, error_text
+            )));
+        }
+
+        Ok(response.json().await?)
+    }
+}
+
+#[async_trait]
+impl QuotaProvider for ZhipuProvider {
+    async fn fetch_quota(&self, credentials: &Credentials, options: &FetchOptions) -> Result<QuotaData> {
+        let api_key = credentials.api_key.as_ref()
+            .ok_or_else(|| QuonitorError::Auth("Zhipu requires API key".to_string()))?;
+
+        let now = Utc::now();
+        let start_time = options.window_start(now);
+
+        let usage: UsageResponse = self.get_json(
+            &format!(
+                "{}/paas/v4/usage?start_time={}&end_time={}&group_by=model",
+                API_BASE,
+                start_time.timestamp(),
+                now.timestamp()
+            ),
+            api_key,
+        ).await?;
+
+        let packages: PackageResponse = self.get_json(
+            &format!("{}/biz/tokenAccounts/list/my", API_BASE),
+            api_key,
+        ).await?;
+
+        let mut total_input = 0i64;
+        let mut total_output = 0i64;
+        let mut total_cost = 0.0f64;
+        let mut model_breakdown = Vec::new();
+
+        for data_point in usage.data {
+            let model_name = data_point.model.unwrap_or_else(|| "unknown".to_string());
+            let cost = self.calculate_zhipu_cost(
+                &model_name,
+                data_point.prompt_tokens,
+                data_point.completion_tokens,
+            );
+
+            total_input += data_point.prompt_tokens;
+            total_output += data_point.completion_tokens;
+            total_cost += cost;
+
+            model_breakdown.push(ModelData {
+                model_name,
+                tokens_input: data_point.prompt_tokens,
+                tokens_output: data_point.completion_tokens,
+                cost_usd: cost,
+                request_count: data_point.request_count,
+                ..Default::default()
+            });
+        }
+
+        // Only packages that are still usable count towards the remaining quota
+        let active: Vec<&TokenPackage> = packages.data.iter()
+            .filter(|p| p.status.as_deref().map_or(true, |s| s.eq_ignore_ascii_case("effective")))
+            .collect();
+
+        let (quota_limit, quota_remaining) = if active.is_empty() {
+            (None, None)
+        } else {
+            (
+                Some(active.iter().map(|p| p.total_tokens).sum()),
+                Some(active.iter().map(|p| p.remaining_tokens).sum()),
+            )
+        };
+
+        Ok(QuotaData {
+            account_id: String::new(), // Will be set by caller
+            timestamp: now.timestamp(),
+            tokens_input: Some(total_input),
+            tokens_output: Some(total_output),
+            cost_usd: Some(total_cost),
+            quota_limit,
+            quota_remaining,
+            model_breakdown,
+            metadata: None,
+            ..Default::default()
+        })
+    }
+
+    fn supports_oauth(&self) -> bool {
+        false
+    }
+
+    fn provider_name(&self) -> &'static str {
+        "Zhipu AI"
+    }
+
+    fn status_endpoint(&self) -> Option<StatusEndpoint> {
+        Some(StatusEndpoint::Ping("https://open.bigmodel.cn/".to_string()))
+    }
+}
@@ -0,0 +1,71 @@
+use std::io::{self, Write};
+use std::sync::OnceLock;
+use regex::Regex;
+use tracing_subscriber::fmt::MakeWriter;
+
+const MASK: &str = "[REDACTED]";
+
+// Secrets that provider responses and URLs can echo back. Group 1 is kept so the
+// masked text still shows what kind of secret it was.
+const PATTERNS: &[&str] = &[
+    // OpenAI and Anthropic API keys
+    r"\b(sk-(?:ant-|proj-)?)[A-Za-z0-9_\-]{16,}",
+    // Google OAuth access and refresh tokens, API keys
+    r"\b(ya29\.)[A-Za-z0-9_\-]+",
+    r"(1//)[A-Za-z0-9_\-]{20,}",
+    r"\b(AIza)[0-9A-Za-z_\-]{35}",
+    // GitHub tokens
+    r"\b(gh[pousr]_)[A-Za-z0-9]{20,}",
+    r"\b(github_pat_)[A-Za-z0-9_]{20,}",
+    // JWTs (service account assertions, ID tokens)
+    r"\b(eyJ)[A-Za-z0-9_\-]+\.[A-Za-z0-9_\-]+\.[A-Za-z0-9_\-]+",
+    // Authorization headers and credential fields in JSON, form bodies and query strings
+    r"(?i)(bearer\s+)[A-Za-z0-9._\-~+/]+=*",
+    r#"(?i)("?(?:api_key|apikey|x-api-key|access_token|refresh_token|id_token|client_secret|password)"?\s*[:=]\s*"?)[^"&\s,}]+"#,
+    r"([?&]key=)[^&\s]+",
+];
+
+fn patterns() -> &'static [Regex] {
+    static COMPILED: OnceLock<Vec<Regex>> = OnceLock::new();
+    COMPILED.get_or_init(|| {
+        PATTERNS.iter()
+            .map(|p| Regex::new(p).expect("invalid redaction pattern"))
+            .collect()
+    })
+}
+
+// Masks anything that looks like a credential
+pub fn redact(text: &str) -> String {
+    let mut text = text.to_string();
+    for pattern in patterns() {
+        if pattern.is_match(&text) {
+            text = pattern.replace_all(&text, format!("${{1}}{}", MASK)).into_owned();
+        }
+    }
+    text
+}
+
+// Log output goes through `redact` on its way to stdout. The fmt layer writes each
+// event in one call, so a secret is never split across writes.
+pub struct RedactingStdout;
+
+impl<'a> MakeWriter<'a> for RedactingStdout {
+    type Writer = RedactingWriter;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        RedactingWriter(io::stdout())
+    }
+}
+
+pub struct RedactingWriter(io::Stdout);
+
+impl Write for RedactingWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.write_all(redact(&String::from_utf8_lossy(buf)).as_bytes())?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.0.flush()
+    }
+}
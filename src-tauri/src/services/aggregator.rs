@@ -1,27 +1,51 @@
+use std::collections::HashMap;
 use std::sync::Arc;
 use chrono::Utc;
-use crate::db::{Repository, Credentials, QuotaSnapshot, ModelUsage};
-use crate::providers::{ProviderRegistry, QuotaData};
+use crate::db::{Repository, Account, Credentials, QuotaSnapshot, ModelUsage, ApiKeyUsage, FetchError, FetchBackoff};
+use crate::providers::{ProviderRegistry, QuotaProvider, QuotaData, ModelData, ApiKeyData, FetchOptions};
+use crate::providers::status::{self, ProviderStatus};
 use crate::crypto::CryptoService;
-use crate::error::Result;
-use tracing::{info, error};
+use crate::error::{QuonitorError, Result};
+use crate::services::{Cache, Settings};
+use crate::services::rate_limit::RateLimiter;
+use tracing::{info, warn, error};
+
+// Delay before the next scheduled fetch after one failure, doubling with each
+// further consecutive failure up to the circuit breaker's cool-down
+const BACKOFF_BASE_SECONDS: i64 = 60;
 
 pub struct Aggregator {
     repo: Arc<Repository>,
     providers: Arc<ProviderRegistry>,
     crypto: Arc<CryptoService>,
+    // Holds each account's fetch status
+    cache: Arc<Cache>,
+    client: reqwest::Client,
+    limiter: RateLimiter,
 }
 
 impl Aggregator {
-    pub fn new(repo: Arc<Repository>, providers: Arc<ProviderRegistry>, crypto: Arc<CryptoService>) -> Self {
+    pub fn new(repo: Arc<Repository>, providers: Arc<ProviderRegistry>, crypto: Arc<CryptoService>, cache: Arc<Cache>) -> Self {
         Self {
             repo,
             providers,
             crypto,
+            cache,
+            client: reqwest::Client::new(),
+            limiter: RateLimiter::new(),
         }
     }
 
-    pub async fn fetch_all_quotas(&self) -> Vec<QuotaData> {
+    // Fetches the accounts `due` selects, e.g. those a schedule says are due; it's asked
+    // just before each account. `force` (a manual refresh) also fetches accounts that
+    // are backing off or whose circuit breaker is open.
+    pub async fn fetch_quotas(&self, due: impl Fn(&str) -> bool, force: bool) -> Vec<QuotaData> {
+        // Nothing can be fetched without credentials; not an error per account
+        if self.crypto.is_locked() {
+            info!("Credentials are locked, skipping fetch");
+            return vec![];
+        }
+
         let accounts = match self.repo.get_all_accounts().await {
             Ok(accounts) => accounts,
             Err(e) => {
@@ -30,9 +54,32 @@ impl Aggregator {
             }
         };
 
+        let backoffs: HashMap<String, FetchBackoff> = if force {
+            HashMap::new()
+        } else {
+            match self.repo.get_fetch_backoffs().await {
+                Ok(backoffs) => backoffs.into_iter().map(|b| (b.account_id.clone(), b)).collect(),
+                Err(e) => {
+                    warn!("Failed to load backoff state: {}", e);
+                    HashMap::new()
+                }
+            }
+        };
+        let now = Utc::now().timestamp();
+        self.limiter.reload(&self.repo).await;
+
         let mut quotas = Vec::new();
 
-        for account in accounts {
+        // Accounts with revoked/expired OAuth grants would only fail again
+        for account in accounts.into_iter().filter(|a| !a.needs_reauth && due(&a.id)) {
+            if let Some(backoff) = backoffs.get(&account.id).filter(|b| b.next_attempt_at > now) {
+                info!(
+                    "Skipping account {} after {} consecutive failures, next attempt at {}",
+                    account.id, backoff.consecutive_failures, backoff.next_attempt_at
+                );
+                continue;
+            }
+
             match self.fetch_account_quota(&account.id).await {
                 Ok(quota) => quotas.push(quota),
                 Err(e) => {
@@ -44,27 +91,168 @@ impl Aggregator {
         quotas
     }
 
+    // The latest stored snapshot of every account, with the model and key breakdowns
+    // recorded alongside it, to show before anything has been fetched this run
+    pub async fn load_stored_quotas(&self) -> Result<Vec<QuotaData>> {
+        let mut quotas = Vec::new();
+        for account in self.repo.get_all_accounts().await? {
+            let Some(snapshot) = self.repo.get_latest_snapshot(&account.id).await? else {
+                continue;
+            };
+
+            let model_breakdown = self.repo.get_model_usage_since(&account.id, snapshot.timestamp, None, 0).await?
+                .into_iter()
+                .filter(|usage| usage.timestamp == snapshot.timestamp)
+                .map(|usage| ModelData {
+                    model_name: usage.model_name,
+                    tokens_input: usage.tokens_input,
+                    tokens_output: usage.tokens_output,
+                    cost_usd: usage.cost_usd,
+                    request_count: usage.request_count,
+                    tokens_cached_input: usage.tokens_cached_input,
+                    tokens_reasoning: usage.tokens_reasoning,
+                    images_generated: usage.images_generated,
+                    audio_seconds: usage.audio_seconds,
+                    video_seconds: usage.video_seconds,
+                })
+                .collect();
+            let api_key_breakdown = self.repo.get_api_key_usage_since(&account.id, snapshot.timestamp, None, 0).await?
+                .into_iter()
+                .filter(|usage| usage.timestamp == snapshot.timestamp)
+                .map(|usage| ApiKeyData {
+                    api_key_id: usage.api_key_id,
+                    tokens_input: usage.tokens_input,
+                    tokens_output: usage.tokens_output,
+                    cost_usd: usage.cost_usd,
+                    request_count: usage.request_count,
+                })
+                .collect();
+
+            quotas.push(QuotaData {
+                account_id: snapshot.account_id,
+                timestamp: snapshot.timestamp,
+                tokens_input: snapshot.tokens_input,
+                tokens_output: snapshot.tokens_output,
+                cost_usd: snapshot.cost_usd,
+                quota_limit: snapshot.quota_limit,
+                quota_remaining: snapshot.quota_remaining,
+                model_breakdown,
+                metadata: snapshot.metadata,
+                rpm_limit: snapshot.rpm_limit,
+                rpm_remaining: snapshot.rpm_remaining,
+                tpm_limit: snapshot.tpm_limit,
+                tpm_remaining: snapshot.tpm_remaining,
+                rpm_reset_at: snapshot.rpm_reset_at,
+                tpm_reset_at: snapshot.tpm_reset_at,
+                requests_limit: snapshot.requests_limit,
+                requests_remaining: snapshot.requests_remaining,
+                requests_reset_at: snapshot.requests_reset_at,
+                api_key_breakdown,
+                images_generated: snapshot.images_generated,
+                audio_seconds: snapshot.audio_seconds,
+                video_seconds: snapshot.video_seconds,
+            });
+        }
+        Ok(quotas)
+    }
+
     pub async fn validate_credentials(&self, provider_id: &str, credentials: &Credentials) -> Result<QuotaData> {
         let provider = self.providers.get(provider_id)
             .ok_or_else(|| crate::error::QuonitorError::Config(format!("Provider {} not found", provider_id)))?;
 
-        let quota = provider.fetch_quota(credentials).await?;
+        self.limiter.acquire(provider_id).await;
+        let quota = provider.fetch_quota(credentials, &FetchOptions::default()).await?;
         Ok(quota)
     }
 
+    // Checks the provider's status page (or pings its API) so a failing fetch can
+    // be attributed to either the account's credentials or the provider itself
+    pub async fn check_provider_status(&self, provider_id: &str) -> Result<ProviderStatus> {
+        let provider = self.providers.get(provider_id)
+            .ok_or_else(|| crate::error::QuonitorError::Config(format!("Provider {} not found", provider_id)))?;
+
+        Ok(status::probe(&self.client, provider_id, provider.status_endpoint()).await)
+    }
+
     pub async fn fetch_account_quota(&self, account_id: &str) -> Result<QuotaData> {
+        if self.crypto.is_locked() {
+            return Err(QuonitorError::Locked);
+        }
+
         let account = self.repo.get_account(account_id).await?
             .ok_or_else(|| crate::error::QuonitorError::Config(format!("Account {} not found", account_id)))?;
 
         let provider = self.providers.get(&account.provider)
             .ok_or_else(|| crate::error::QuonitorError::Config(format!("Provider {} not found", account.provider)))?;
 
+        match self.fetch_and_store(&account, provider.as_ref()).await {
+            Ok(quota) => {
+                self.cache.record_success(account_id).await;
+                if let Err(e) = self.repo.clear_fetch_backoff(account_id).await {
+                    warn!("Failed to clear backoff state for account {}: {}", account_id, e);
+                }
+                Ok(quota)
+            }
+            Err(e) => {
+                self.cache.record_failure(account_id, e.kind(), &e.to_string()).await;
+                self.record_error(&account, &e).await;
+                if let Err(err) = self.back_off(&account).await {
+                    warn!("Failed to update backoff state for account {}: {}", account_id, err);
+                }
+                Err(e)
+            }
+        }
+    }
+
+    // Pushes the next scheduled attempt out exponentially, and trips the circuit
+    // breaker after enough consecutive failures. Once the cool-down passes the next
+    // scheduled fetch tries again, and a further failure re-trips it straight away.
+    async fn back_off(&self, account: &Account) -> Result<()> {
+        let settings = Settings::load(&self.repo).await?;
+        let previous = self.repo.get_fetch_backoff(&account.id).await?;
+        let now = Utc::now().timestamp();
+
+        let failures = previous.as_ref().map(|b| b.consecutive_failures).unwrap_or(0) + 1;
+        let cooldown = settings.breaker_cooldown_minutes as i64 * 60;
+        let tripped = failures >= settings.breaker_failure_threshold as i64;
+        let delay = if tripped {
+            cooldown
+        } else {
+            (BACKOFF_BASE_SECONDS << (failures - 1).min(20)).min(cooldown)
+        };
+
+        let tripped_at = match previous.and_then(|b| b.tripped_at) {
+            Some(since) if tripped => Some(since),
+            _ if tripped => {
+                warn!(
+                    "Pausing scheduled fetches for account {} for {} minutes after {} consecutive failures",
+                    account.id, settings.breaker_cooldown_minutes, failures
+                );
+                Some(now)
+            }
+            _ => None,
+        };
+
+        self.repo.set_fetch_backoff(&FetchBackoff {
+            account_id: account.id.clone(),
+            consecutive_failures: failures,
+            next_attempt_at: now + delay,
+            tripped_at,
+        }).await?;
+        Ok(())
+    }
+
+    async fn fetch_and_store(&self, account: &Account, provider: &dyn QuotaProvider) -> Result<QuotaData> {
+        let account_id = account.id.as_str();
+
         // Decrypt credentials
-        let creds_json = self.crypto.decrypt(&account.credentials_encrypted)?;
+        let creds_json = self.crypto.decrypt(account_id, &account.provider, &account.credentials_encrypted)?;
         let credentials: Credentials = serde_json::from_str(&creds_json)?;
 
         // Fetch quota from provider
-        let mut quota = provider.fetch_quota(&credentials).await?;
+        let options = self.fetch_options(account_id).await?;
+        self.limiter.acquire(&account.provider).await;
+        let mut quota = provider.fetch_quota(&credentials, &options).await?;
         quota.account_id = account_id.to_string();
 
         // Store in database
@@ -78,6 +266,37 @@ impl Aggregator {
         Ok(quota)
     }
 
+    // Logging a failure must never mask the failure itself
+    async fn record_error(&self, account: &Account, e: &QuonitorError) {
+        let record = FetchError {
+            id: None,
+            account_id: account.id.clone(),
+            provider: account.provider.clone(),
+            error_kind: e.kind().to_string(),
+            http_status: e.http_status().map(i64::from),
+            message: e.to_string(),
+            timestamp: Utc::now().timestamp(),
+        };
+
+        if let Err(err) = self.repo.insert_fetch_error(&record).await {
+            warn!("Failed to record fetch error for account {}: {}", account.id, err);
+        }
+    }
+
+    async fn fetch_options(&self, account_id: &str) -> Result<FetchOptions> {
+        let settings = self.repo.get_account_settings(account_id).await?;
+        let mut options = FetchOptions::default();
+
+        if let Some(days) = settings.get("lookback_days").and_then(|d| d.parse::<u32>().ok()) {
+            options.lookback_days = Some(days);
+        }
+        if let Some(tz) = settings.get("billing_timezone").and_then(|tz| tz.parse().ok()) {
+            options.timezone = tz;
+        }
+
+        Ok(options)
+    }
+
     async fn store_quota(&self, quota: &QuotaData) -> Result<()> {
         // Store account-level snapshot
         let snapshot = QuotaSnapshot {
@@ -90,6 +309,18 @@ impl Aggregator {
             quota_limit: quota.quota_limit,
             quota_remaining: quota.quota_remaining,
             metadata: quota.metadata.clone(),
+            rpm_limit: quota.rpm_limit,
+            rpm_remaining: quota.rpm_remaining,
+            tpm_limit: quota.tpm_limit,
+            tpm_remaining: quota.tpm_remaining,
+            requests_limit: quota.requests_limit,
+            requests_remaining: quota.requests_remaining,
+            requests_reset_at: quota.requests_reset_at,
+            rpm_reset_at: quota.rpm_reset_at,
+            tpm_reset_at: quota.tpm_reset_at,
+            images_generated: quota.images_generated,
+            audio_seconds: quota.audio_seconds,
+            video_seconds: quota.video_seconds,
         };
 
         self.repo.insert_quota_snapshot(&snapshot).await?;
@@ -105,11 +336,32 @@ impl Aggregator {
                 tokens_output: model.tokens_output,
                 cost_usd: model.cost_usd,
                 request_count: model.request_count,
+                tokens_cached_input: model.tokens_cached_input,
+                tokens_reasoning: model.tokens_reasoning,
+                images_generated: model.images_generated,
+                audio_seconds: model.audio_seconds,
+                video_seconds: model.video_seconds,
             };
 
             self.repo.insert_model_usage(&usage).await?;
         }
 
+        // Store per-API-key usage
+        for key in &quota.api_key_breakdown {
+            let usage = ApiKeyUsage {
+                id: None,
+                account_id: quota.account_id.clone(),
+                api_key_id: key.api_key_id.clone(),
+                timestamp: quota.timestamp,
+                tokens_input: key.tokens_input,
+                tokens_output: key.tokens_output,
+                cost_usd: key.cost_usd,
+                request_count: key.request_count,
+            };
+
+            self.repo.insert_api_key_usage(&usage).await?;
+        }
+
         Ok(())
     }
 }
@@ -0,0 +1,58 @@
+use crate::db::{Repository, UsageDelta};
+use crate::error::Result;
+
+// Spend over the last hour is compared with the same hour on each of the previous
+// seven days, so a busy working hour isn't mistaken for a spike
+const WINDOW: i64 = 3600;
+const BASELINE_DAYS: i64 = 7;
+// At most one unusual-spend alert per account in this long
+pub const COOLDOWN: i64 = 6 * 3600;
+
+#[derive(Debug, Clone, Copy)]
+pub struct Spike {
+    pub current_usd: f64,
+    // Average over the baseline days
+    pub baseline_usd: f64,
+}
+
+// A spike when the last hour's spend is at least `min_usd` and above `multiple` times
+// the baseline. None until the account has a full baseline of history.
+pub async fn detect(repo: &Repository, account_id: &str, now: i64, multiple: f64, min_usd: f64) -> Result<Option<Spike>> {
+    let baseline_start = now - BASELINE_DAYS * 86400;
+    let deltas = repo.get_usage_deltas(account_id, baseline_start - WINDOW).await?;
+    if deltas.first().map_or(true, |delta| delta.timestamp > baseline_start) {
+        return Ok(None);
+    }
+
+    let current_usd = spend(&deltas, now);
+    let baseline_usd = (1..=BASELINE_DAYS)
+        .map(|day| spend(&deltas, now - day * 86400))
+        .sum::<f64>() / BASELINE_DAYS as f64;
+
+    if current_usd >= min_usd && current_usd > baseline_usd * multiple {
+        Ok(Some(Spike { current_usd, baseline_usd }))
+    } else {
+        Ok(None)
+    }
+}
+
+// Spend over the last hour, for burn rate alerts
+pub async fn last_hour_spend(repo: &Repository, account_id: &str, now: i64) -> Result<f64> {
+    let deltas = repo.get_usage_deltas(account_id, now - WINDOW).await?;
+    Ok(spend(&deltas, now))
+}
+
+// Spend in the hour up to `until`. A delta is spread evenly over its interval, so usage
+// first seen after the app was closed for a day doesn't all land in one hour.
+fn spend(deltas: &[UsageDelta], until: i64) -> f64 {
+    let since = until - WINDOW;
+    deltas
+        .iter()
+        .filter_map(|delta| {
+            let cost = delta.cost_usd?;
+            let start = delta.timestamp - delta.interval_seconds.max(1);
+            let overlap = delta.timestamp.min(until) - start.max(since);
+            (overlap > 0).then(|| cost * overlap as f64 / delta.interval_seconds.max(1) as f64)
+        })
+        .sum()
+}
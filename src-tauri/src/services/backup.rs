@@ -0,0 +1,136 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use base64::{engine::general_purpose, Engine as _};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use crate::crypto::CryptoService;
+use crate::db::Repository;
+use crate::error::{QuonitorError, Result};
+
+const ARCHIVE_FORMAT: &str = "quonitor-backup";
+const ARCHIVE_VERSION: u32 = 1;
+pub const MIN_PASSPHRASE_LEN: usize = 8;
+
+// A single JSON file: the database with credentials scrubbed, plus the credentials
+// re-encrypted under the user's passphrase so they survive a move to another machine
+#[derive(Debug, Serialize, Deserialize)]
+struct BackupArchive {
+    format: String,
+    version: u32,
+    created_at: i64,
+    // Base64 SQLite database file
+    database: String,
+    // Base64 passphrase-sealed JSON map of account id to credentials JSON
+    credentials: String,
+}
+
+// Returns the number of accounts whose credentials were included
+pub async fn create_backup(
+    repo: &Repository,
+    crypto: &CryptoService,
+    passphrase: &str,
+    path: &Path,
+) -> Result<usize> {
+    if passphrase.chars().count() < MIN_PASSPHRASE_LEN {
+        return Err(QuonitorError::Config(format!(
+            "Backup passphrase must be at least {} characters", MIN_PASSPHRASE_LEN
+        )));
+    }
+
+    if crypto.is_locked() {
+        return Err(QuonitorError::Locked);
+    }
+
+    let mut credentials = HashMap::new();
+    for account in repo.get_all_accounts().await? {
+        match crypto.decrypt(&account.id, &account.provider, &account.credentials_encrypted) {
+            Ok(json) => {
+                credentials.insert(account.id, json);
+            }
+            Err(e) => tracing::warn!("Leaving credentials for {} out of backup: {}", account.id, e),
+        }
+    }
+    let sealed = CryptoService::seal_with_passphrase(&serde_json::to_vec(&credentials)?, passphrase)?;
+
+    let staging = staging_path();
+    let database = async {
+        repo.backup_to(&staging).await?;
+        Ok::<_, QuonitorError>(tokio::fs::read(&staging).await?)
+    }
+    .await;
+    remove_staging(&staging).await;
+
+    let archive = BackupArchive {
+        format: ARCHIVE_FORMAT.to_string(),
+        version: ARCHIVE_VERSION,
+        created_at: Utc::now().timestamp(),
+        database: general_purpose::STANDARD.encode(database?),
+        credentials: general_purpose::STANDARD.encode(sealed),
+    };
+    tokio::fs::write(path, serde_json::to_vec(&archive)?).await?;
+
+    Ok(credentials.len())
+}
+
+// Replaces all local data with the archive's. Accounts whose credentials can't be
+// restored are flagged for re-authentication. Returns the number of accounts restored.
+pub async fn restore_backup(
+    repo: &Repository,
+    crypto: &CryptoService,
+    passphrase: &str,
+    path: &Path,
+) -> Result<usize> {
+    let archive: BackupArchive = serde_json::from_slice(&tokio::fs::read(path).await?)?;
+    if archive.format != ARCHIVE_FORMAT || archive.version > ARCHIVE_VERSION {
+        return Err(QuonitorError::Config("Not a supported Quonitor backup".to_string()));
+    }
+
+    let decode = |field: &str| {
+        general_purpose::STANDARD.decode(field)
+            .map_err(|e| QuonitorError::Config(format!("Corrupted backup: {}", e)))
+    };
+
+    // Check the passphrase before touching any local data
+    let sealed = decode(&archive.credentials)?;
+    let credentials: HashMap<String, String> =
+        serde_json::from_slice(&CryptoService::open_with_passphrase(&sealed, passphrase)?)?;
+
+    let staging = staging_path();
+    let restored = async {
+        tokio::fs::write(&staging, decode(&archive.database)?).await?;
+
+        // Bring older backups up to the current schema before copying them in
+        let staged = Repository::new(&format!("sqlite://{}", staging.display()), None).await?;
+        staged.close().await;
+
+        repo.restore_from(&staging).await?;
+        Ok::<_, QuonitorError>(())
+    }
+    .await;
+    remove_staging(&staging).await;
+    restored?;
+
+    let accounts = repo.get_all_accounts().await?;
+    for account in &accounts {
+        match credentials.get(&account.id) {
+            Some(json) => {
+                repo.update_account_credentials(&account.id, &crypto.encrypt(&account.id, &account.provider, json)?).await?;
+                repo.set_account_needs_reauth(&account.id, false).await?;
+            }
+            None => repo.set_account_needs_reauth(&account.id, true).await?,
+        }
+    }
+
+    Ok(accounts.len())
+}
+
+fn staging_path() -> PathBuf {
+    std::env::temp_dir().join(format!("quonitor-backup-{}.db", Uuid::new_v4()))
+}
+
+async fn remove_staging(path: &Path) {
+    for suffix in ["", "-wal", "-shm"] {
+        let _ = tokio::fs::remove_file(format!("{}{}", path.display(), suffix)).await;
+    }
+}
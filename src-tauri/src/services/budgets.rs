@@ -0,0 +1,122 @@
+use chrono::{DateTime, Datelike, Duration, Months, TimeZone, Utc};
+use serde::Serialize;
+use crate::db::{Budget, Repository};
+use crate::error::{QuonitorError, Result};
+
+pub const SCOPES: &[&str] = &["global", "account", "provider", "model"];
+pub const PERIODS: &[&str] = &["daily", "weekly", "monthly"];
+// Projections wait until this share of the period has passed, so one early burst of
+// usage doesn't project a huge overrun
+const FORECAST_MIN_ELAPSED: f64 = 0.2;
+
+// A budget together with its spend in the current period
+#[derive(Debug, Clone, Serialize)]
+pub struct BudgetStatus {
+    #[serde(flatten)]
+    pub budget: Budget,
+    pub period_start: i64,
+    pub period_end: i64,
+    pub spent_usd: f64,
+    pub percent_used: f64,
+    // Spend at the end of the period at the run rate so far; None early in the period
+    pub projected_usd: Option<f64>,
+}
+
+impl BudgetStatus {
+    // When spend at the run rate so far reaches the budget, if that's within the period
+    pub fn projected_overrun_at(&self) -> Option<i64> {
+        let projected = self.projected_usd?;
+        if projected < self.budget.amount_usd || self.spent_usd <= 0.0 {
+            return None;
+        }
+        let rate = projected / (self.period_end - self.period_start) as f64;
+        Some(self.period_start + (self.budget.amount_usd / rate) as i64)
+    }
+}
+
+pub fn validate(budget: &Budget) -> Result<()> {
+    if !SCOPES.contains(&budget.scope.as_str()) {
+        return Err(QuonitorError::Config(format!("Invalid budget scope: {}", budget.scope)));
+    }
+    if !PERIODS.contains(&budget.period.as_str()) {
+        return Err(QuonitorError::Config(format!("Invalid budget period: {}", budget.period)));
+    }
+    if budget.scope != "global" && budget.target.as_deref().unwrap_or("").trim().is_empty() {
+        return Err(QuonitorError::Config(format!("A {} budget needs a target", budget.scope)));
+    }
+    if budget.amount_usd.is_nan() || budget.amount_usd <= 0.0 {
+        return Err(QuonitorError::Config("Budget amount must be positive".to_string()));
+    }
+    Ok(())
+}
+
+// The period containing `now`, counted in whole periods from the reset anchor.
+// Monthly periods keep the anchor's day of month, clamped in shorter months.
+pub fn period_bounds(budget: &Budget, now: DateTime<Utc>) -> (i64, i64) {
+    let anchor = Utc.timestamp_opt(budget.reset_anchor, 0).single().unwrap_or(now);
+
+    let step = match budget.period.as_str() {
+        "daily" => Duration::days(1),
+        "weekly" => Duration::weeks(1),
+        _ => {
+            let mut months = ((now.year() - anchor.year()) * 12 + now.month() as i32 - anchor.month() as i32).max(0) as u32;
+            let add = |months: u32| anchor.checked_add_months(Months::new(months)).unwrap_or(anchor);
+            if months > 0 && add(months) > now {
+                months -= 1;
+            }
+            return (add(months).timestamp(), add(months + 1).timestamp());
+        }
+    };
+
+    let elapsed = (now - anchor).num_seconds().max(0);
+    let periods = elapsed / step.num_seconds();
+    let start = anchor + step * periods as i32;
+    (start.timestamp(), (start + step).timestamp())
+}
+
+pub async fn status(repo: &Repository, budget: Budget, now: DateTime<Utc>) -> Result<BudgetStatus> {
+    let (period_start, period_end) = period_bounds(&budget, now);
+    let spent_usd = repo.get_spend_since(&budget.scope, budget.target.as_deref(), period_start).await?;
+    let percent_used = spent_usd / budget.amount_usd * 100.0;
+    let projected_usd = projection(spent_usd, period_start, period_end, now.timestamp());
+
+    Ok(BudgetStatus {
+        budget,
+        period_start,
+        period_end,
+        spent_usd,
+        percent_used,
+        projected_usd,
+    })
+}
+
+// Linear: the spend so far, scaled from the elapsed part of the period to all of it
+fn projection(spent_usd: f64, period_start: i64, period_end: i64, now: i64) -> Option<f64> {
+    let length = (period_end - period_start) as f64;
+    let elapsed = (now - period_start) as f64;
+    if length <= 0.0 || elapsed < length * FORECAST_MIN_ELAPSED {
+        return None;
+    }
+    Some(spent_usd / elapsed * length)
+}
+
+pub async fn all_statuses(repo: &Repository) -> Result<Vec<BudgetStatus>> {
+    let now = Utc::now();
+    let mut statuses = Vec::new();
+    for budget in repo.get_budgets().await? {
+        statuses.push(status(repo, budget, now).await?);
+    }
+    Ok(statuses)
+}
+
+// The budgets set on one account, which stand in for a quota limit it doesn't report
+pub async fn account_statuses(repo: &Repository, account_id: &str) -> Result<Vec<BudgetStatus>> {
+    let now = Utc::now();
+    let mut statuses = Vec::new();
+    for budget in repo.get_budgets().await? {
+        if budget.scope == "account" && budget.target.as_deref() == Some(account_id) {
+            statuses.push(status(repo, budget, now).await?);
+        }
+    }
+    Ok(statuses)
+}
@@ -1,43 +1,289 @@
-use std::collections::HashMap;
-use std::sync::Arc;
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, OnceLock};
+use chrono::Utc;
+use serde::Serialize;
+use tauri::{AppHandle, Emitter};
 use tokio::sync::RwLock;
+use tracing::warn;
+use crate::db::UsageDelta;
 use crate::providers::QuotaData;
 
+// Emitted with the new `CacheEntry` whenever an account's data changes
+pub const UPDATED_EVENT: &str = "quota-updated";
+// Usage deltas kept per account for the account list's trend charts
+pub const SPARKLINE_POINTS: usize = 48;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Source {
+    // Fetched this run
+    Live,
+    // The last stored snapshot, loaded at launch; nothing fetched for the account since
+    Restored,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CacheEntry {
+    #[serde(flatten)]
+    pub quota: QuotaData,
+    // When it was fetched; for restored data, when the snapshot was taken
+    pub fetched_at: i64,
+    pub source: Source,
+    // Set when read: restored, or older than the reader's `stale_after`
+    pub is_stale: bool,
+}
+
+// How an account's fetches have been going, kept apart from its data so a failed fetch
+// doesn't go unnoticed behind the last good numbers
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct AccountStatus {
+    pub account_id: String,
+    pub last_error: Option<String>,
+    // `QuonitorError::kind` of the last error
+    pub error_kind: Option<String>,
+    pub last_error_at: Option<i64>,
+    // Consecutive failures; 0 once a fetch succeeds
+    pub failure_count: u32,
+    pub last_success_at: Option<i64>,
+}
+
+// Totals over every cached account, for the tray tooltip and dashboard header
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct QuotaSummary {
+    pub account_count: usize,
+    // As reported by each provider for its current window, a rolling day by default
+    pub total_cost_usd: f64,
+    pub total_tokens_input: i64,
+    pub total_tokens_output: i64,
+    // The account closest to one of its limits; None when no account reports limits
+    pub worst_percentage: Option<f64>,
+    pub worst_account_id: Option<String>,
+    // Accounts past their own warning threshold but not critical, and past critical
+    pub warning_count: usize,
+    pub critical_count: usize,
+}
+
 #[derive(Clone)]
 pub struct Cache {
-    data: Arc<RwLock<HashMap<String, QuotaData>>>,
+    data: Arc<RwLock<HashMap<String, CacheEntry>>>,
+    statuses: Arc<RwLock<HashMap<String, AccountStatus>>>,
+    // Newest last
+    sparklines: Arc<RwLock<HashMap<String, VecDeque<UsageDelta>>>>,
+    // Set once the app is running; updates before that aren't emitted
+    app: Arc<OnceLock<AppHandle>>,
 }
 
 impl Cache {
     pub fn new() -> Self {
         Self {
             data: Arc::new(RwLock::new(HashMap::new())),
+            statuses: Arc::new(RwLock::new(HashMap::new())),
+            sparklines: Arc::new(RwLock::new(HashMap::new())),
+            app: Arc::new(OnceLock::new()),
         }
     }
 
-    pub async fn set(&self, account_id: String, quota: QuotaData) {
-        let mut data = self.data.write().await;
-        data.insert(account_id, quota);
+    pub fn attach(&self, app: AppHandle) {
+        let _ = self.app.set(app);
+    }
+
+    // Emits `UPDATED_EVENT` and returns true unless a fetch already cached the same
+    // values. Either way the entry's fetch time moves on, so it doesn't turn stale.
+    pub async fn set(&self, account_id: String, quota: QuotaData) -> bool {
+        let entry = CacheEntry {
+            quota,
+            fetched_at: Utc::now().timestamp(),
+            source: Source::Live,
+            is_stale: false,
+        };
+
+        let changed = {
+            let mut data = self.data.write().await;
+            let previous = data.get(&account_id);
+            let changed = previous
+                .map_or(true, |old| old.source != Source::Live || !old.quota.same_values(&entry.quota));
+            if let Some(delta) = previous.and_then(|old| usage_delta(&old.quota, &entry.quota)) {
+                self.push_sparkline(delta).await;
+            }
+            data.insert(account_id, entry.clone());
+            changed
+        };
+
+        if changed {
+            if let Some(app) = self.app.get() {
+                if let Err(e) = app.emit(UPDATED_EVENT, &entry) {
+                    warn!("Failed to emit quota update: {}", e);
+                }
+            }
+        }
+        changed
     }
 
-    pub async fn get(&self, account_id: &str) -> Option<QuotaData> {
+    // Stored data never replaces a fetch that finished first. Restored entries are
+    // emitted too, so the dashboard shows them before the first fetch completes.
+    pub async fn restore(&self, quota: QuotaData) {
+        let entry = {
+            let mut data = self.data.write().await;
+            if data.contains_key(&quota.account_id) {
+                return;
+            }
+            let entry = CacheEntry {
+                fetched_at: quota.timestamp,
+                quota,
+                source: Source::Restored,
+                is_stale: true,
+            };
+            data.insert(entry.quota.account_id.clone(), entry.clone());
+            entry
+        };
+
+        if let Some(app) = self.app.get() {
+            if let Err(e) = app.emit(UPDATED_EVENT, &entry) {
+                warn!("Failed to emit restored quota: {}", e);
+            }
+        }
+    }
+
+    // Entries fetched more than `stale_after` seconds ago are marked stale
+    pub async fn get(&self, account_id: &str, stale_after: i64) -> Option<CacheEntry> {
         let data = self.data.read().await;
-        data.get(account_id).cloned()
+        data.get(account_id).map(|entry| with_staleness(entry, Utc::now().timestamp() - stale_after))
     }
 
-    pub async fn get_all(&self) -> Vec<QuotaData> {
+    pub async fn get_all(&self, stale_after: i64) -> Vec<CacheEntry> {
         let data = self.data.read().await;
-        data.values().cloned().collect()
+        let cutoff = Utc::now().timestamp() - stale_after;
+        data.values().map(|entry| with_staleness(entry, cutoff)).collect()
+    }
+
+    // `thresholds` gives each account's warning and critical percentages
+    pub async fn get_summary(&self, thresholds: impl Fn(&str) -> (f64, f64)) -> QuotaSummary {
+        let data = self.data.read().await;
+        let mut summary = QuotaSummary {
+            account_count: data.len(),
+            ..Default::default()
+        };
+
+        for entry in data.values() {
+            let quota = &entry.quota;
+            summary.total_cost_usd += quota.cost_usd.unwrap_or(0.0);
+            summary.total_tokens_input += quota.tokens_input.unwrap_or(0);
+            summary.total_tokens_output += quota.tokens_output.unwrap_or(0);
+
+            let Some((percentage, _)) = quota.usage_percentage() else {
+                continue;
+            };
+            if summary.worst_percentage.map_or(true, |worst| percentage > worst) {
+                summary.worst_percentage = Some(percentage);
+                summary.worst_account_id = Some(quota.account_id.clone());
+            }
+            let (warning, critical) = thresholds(&quota.account_id);
+            if percentage >= critical {
+                summary.critical_count += 1;
+            } else if percentage >= warning {
+                summary.warning_count += 1;
+            }
+        }
+
+        summary
+    }
+
+    // Fills an account's trend chart from stored deltas (oldest first), at launch
+    pub async fn seed_sparkline(&self, account_id: &str, deltas: Vec<UsageDelta>) {
+        let skip = deltas.len().saturating_sub(SPARKLINE_POINTS);
+        let mut sparklines = self.sparklines.write().await;
+        sparklines.insert(account_id.to_string(), deltas.into_iter().skip(skip).collect());
+    }
+
+    // Oldest first
+    pub async fn get_sparkline(&self, account_id: &str) -> Vec<UsageDelta> {
+        let sparklines = self.sparklines.read().await;
+        sparklines.get(account_id).map(|points| points.iter().cloned().collect()).unwrap_or_default()
+    }
+
+    async fn push_sparkline(&self, delta: UsageDelta) {
+        let mut sparklines = self.sparklines.write().await;
+        let points = sparklines.entry(delta.account_id.clone()).or_default();
+        if points.len() == SPARKLINE_POINTS {
+            points.pop_front();
+        }
+        points.push_back(delta);
     }
 
     pub async fn remove(&self, account_id: &str) {
         let mut data = self.data.write().await;
         data.remove(account_id);
+        self.statuses.write().await.remove(account_id);
+        self.sparklines.write().await.remove(account_id);
     }
 
-    #[allow(dead_code)]
     pub async fn clear(&self) {
         let mut data = self.data.write().await;
         data.clear();
+        self.statuses.write().await.clear();
+        self.sparklines.write().await.clear();
+    }
+
+    pub async fn record_success(&self, account_id: &str) {
+        let mut statuses = self.statuses.write().await;
+        let status = statuses.entry(account_id.to_string()).or_insert_with(|| new_status(account_id));
+        status.failure_count = 0;
+        status.last_success_at = Some(Utc::now().timestamp());
+    }
+
+    // The previous error stays until the next one, for showing after a recovery
+    pub async fn record_failure(&self, account_id: &str, kind: &str, message: &str) {
+        let mut statuses = self.statuses.write().await;
+        let status = statuses.entry(account_id.to_string()).or_insert_with(|| new_status(account_id));
+        status.last_error = Some(message.to_string());
+        status.error_kind = Some(kind.to_string());
+        status.last_error_at = Some(Utc::now().timestamp());
+        status.failure_count += 1;
+    }
+
+    // Accounts not fetched this run have a status with nothing recorded
+    pub async fn get_status(&self, account_id: &str) -> AccountStatus {
+        let statuses = self.statuses.read().await;
+        statuses.get(account_id).cloned().unwrap_or_else(|| new_status(account_id))
     }
 }
+
+fn with_staleness(entry: &CacheEntry, cutoff: i64) -> CacheEntry {
+    CacheEntry {
+        is_stale: entry.source == Source::Restored || entry.fetched_at < cutoff,
+        ..entry.clone()
+    }
+}
+
+fn new_status(account_id: &str) -> AccountStatus {
+    AccountStatus {
+        account_id: account_id.to_string(),
+        ..Default::default()
+    }
+}
+
+// Usage between two fetches, worked out as `Repository::insert_quota_snapshot` does: a
+// drop in a running total means the provider's period reset. None unless `current` is
+// newer.
+fn usage_delta(previous: &QuotaData, current: &QuotaData) -> Option<UsageDelta> {
+    if current.timestamp <= previous.timestamp {
+        return None;
+    }
+
+    fn since<T: PartialOrd + std::ops::Sub<Output = T> + Copy>(previous: Option<T>, current: Option<T>) -> Option<T> {
+        match (previous, current) {
+            (Some(previous), Some(current)) if current >= previous => Some(current - previous),
+            (Some(_), Some(current)) => Some(current),
+            _ => None,
+        }
+    }
+
+    Some(UsageDelta {
+        account_id: current.account_id.clone(),
+        timestamp: current.timestamp,
+        interval_seconds: current.timestamp - previous.timestamp,
+        tokens_input: since(previous.tokens_input, current.tokens_input),
+        tokens_output: since(previous.tokens_output, current.tokens_output),
+        cost_usd: since(previous.cost_usd, current.cost_usd),
+    })
+}
@@ -0,0 +1,104 @@
+use std::sync::{OnceLock, RwLock};
+use std::time::Duration;
+use chrono::Utc;
+use serde::Serialize;
+use tauri::{AppHandle, Emitter};
+use tokio::time;
+use tracing::{info, warn};
+
+// Endpoints that answer 204 with an empty body. Captive portals intercept or redirect
+// them (failing TLS on the way), so any other outcome counts as offline.
+const PROBE_URLS: &[&str] = &[
+    "https://www.gstatic.com/generate_204",
+    "https://cp.cloudflare.com/generate_204",
+];
+const PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+// While offline only the probe runs, this often
+const OFFLINE_RETRY_INTERVAL: Duration = Duration::from_secs(60);
+// Emitted with a `ConnectivityStatus` whenever the status changes
+pub const STATUS_EVENT: &str = "connectivity-changed";
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ConnectivityStatus {
+    pub online: bool,
+    // When the status last changed
+    pub since: i64,
+}
+
+pub struct Connectivity {
+    client: reqwest::Client,
+    status: RwLock<ConnectivityStatus>,
+    // Set once the app is running; status changes before that are only logged
+    app: OnceLock<AppHandle>,
+}
+
+impl Connectivity {
+    pub fn new() -> Self {
+        let client = reqwest::Client::builder()
+            .timeout(PROBE_TIMEOUT)
+            .redirect(reqwest::redirect::Policy::none())
+            .build()
+            .unwrap_or_default();
+
+        Self {
+            client,
+            status: RwLock::new(ConnectivityStatus { online: true, since: Utc::now().timestamp() }),
+            app: OnceLock::new(),
+        }
+    }
+
+    pub fn attach(&self, app: AppHandle) {
+        let _ = self.app.set(app);
+    }
+
+    pub fn status(&self) -> ConnectivityStatus {
+        self.status.read().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    // Probes the network and records the result. Only a change is logged and emitted,
+    // so an outage shows up once rather than as a failure for every account.
+    pub async fn check(&self) -> bool {
+        let online = self.probe().await;
+
+        let changed = {
+            let mut status = self.status.write().unwrap_or_else(|e| e.into_inner());
+            let changed = status.online != online;
+            if changed {
+                *status = ConnectivityStatus { online, since: Utc::now().timestamp() };
+            }
+            changed.then(|| status.clone())
+        };
+
+        if let Some(status) = changed {
+            if online {
+                info!("Network is reachable again, resuming fetches");
+            } else {
+                warn!("Network is unreachable, pausing fetches until it's back");
+            }
+            if let Some(app) = self.app.get() {
+                if let Err(e) = app.emit(STATUS_EVENT, status) {
+                    warn!("Failed to emit connectivity status: {}", e);
+                }
+            }
+        }
+
+        online
+    }
+
+    // Re-probes every minute until the network is back
+    pub async fn wait_until_online(&self) {
+        while !self.check().await {
+            time::sleep(OFFLINE_RETRY_INTERVAL).await;
+        }
+    }
+
+    async fn probe(&self) -> bool {
+        for url in PROBE_URLS {
+            match self.client.get(*url).send().await {
+                Ok(response) if response.status() == reqwest::StatusCode::NO_CONTENT => return true,
+                _ => {}
+            }
+        }
+        false
+    }
+}
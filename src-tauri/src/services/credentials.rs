@@ -0,0 +1,292 @@
+use std::collections::HashSet;
+use std::path::Path;
+use base64::{engine::general_purpose, Engine as _};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use crate::crypto::CryptoService;
+use crate::db::{Account, Credentials, Repository};
+use crate::error::{QuonitorError, Result};
+use crate::services::backup::MIN_PASSPHRASE_LEN;
+use crate::services::{email, webhook};
+use crate::services::Aggregator;
+use tracing::{info, warn};
+
+const BUNDLE_FORMAT: &str = "quonitor-credentials";
+const BUNDLE_VERSION: u32 = 1;
+
+// Accounts and their credentials sealed under a passphrase, for moving accounts to
+// another machine without their history. Nothing but the format is readable without it.
+#[derive(Debug, Serialize, Deserialize)]
+struct CredentialBundle {
+    format: String,
+    version: u32,
+    created_at: i64,
+    // Base64 passphrase-sealed JSON list of `BundledAccount`
+    accounts: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct BundledAccount {
+    provider: String,
+    name: String,
+    credentials: serde_json::Value,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct CredentialImport {
+    pub imported: usize,
+    // Already present here: same provider and name
+    pub skipped: usize,
+}
+
+// One entry of a plain JSON account list, as generated from a team's secret store:
+// [{"provider": "openai", "name": "Team", "credentials": {"api_key": "..."}}]
+#[derive(Debug, Deserialize)]
+struct ImportedAccount {
+    provider: String,
+    name: String,
+    credentials: Credentials,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AccountImportResult {
+    // Position in the list, from 0
+    pub index: usize,
+    pub provider: String,
+    pub name: String,
+    // None when the entry checked out
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct AccountImport {
+    // 0 unless every entry checked out
+    pub imported: usize,
+    pub results: Vec<AccountImportResult>,
+}
+
+// Sets, changes or (with `new_passphrase` None) removes the master password,
+// re-encrypting every account's credentials, and the SMTP password, under the new key.
+// `current` is required while a master password is set. Nothing changes unless every
+// credential re-encrypts.
+pub async fn set_master_password(
+    repo: &Repository,
+    crypto: &CryptoService,
+    current: Option<&str>,
+    new_passphrase: Option<&str>,
+) -> Result<()> {
+    let rekey = crypto.begin_rekey(current, new_passphrase)?;
+    // Verifying `current` doesn't unlock; decrypting below needs the key in memory
+    if let (true, Some(current)) = (crypto.is_locked(), current) {
+        crypto.unlock(current)?;
+        bind_to_accounts(repo, crypto).await?;
+    }
+
+    let mut credentials = Vec::new();
+    for account in repo.get_all_accounts().await? {
+        let json = crypto.decrypt(&account.id, &account.provider, &account.credentials_encrypted)?;
+        credentials.push((account.id, rekey.encrypt(&account.id, &account.provider, &json)?));
+    }
+
+    let smtp_password = email::rekey_password(repo, crypto, &rekey).await?;
+    let webhook_secret = webhook::rekey_secret(repo, crypto, &rekey).await?;
+
+    // Until `finish_rekey` the old key stays in use, so a failed store changes nothing
+    repo.replace_account_credentials(&credentials).await?;
+    email::store_rekeyed_password(repo, smtp_password).await?;
+    webhook::store_rekeyed_secret(repo, webhook_secret).await?;
+    crypto.finish_rekey(rekey)?;
+
+    info!(
+        "{} master password, re-encrypted {} accounts",
+        if new_passphrase.is_some() { "Set" } else { "Removed" },
+        credentials.len()
+    );
+    Ok(())
+}
+
+// Re-encrypts credentials from before they were bound to their account. Runs at
+// startup, and after unlocking or restoring, since locked credentials can't be read;
+// until then those accounts fail to decrypt. Returns the number of accounts updated.
+pub async fn bind_to_accounts(repo: &Repository, crypto: &CryptoService) -> Result<usize> {
+    if crypto.is_locked() {
+        return Ok(0);
+    }
+
+    let mut credentials = Vec::new();
+    for account in repo.get_all_accounts().await? {
+        if CryptoService::is_bound(&account.credentials_encrypted) {
+            continue;
+        }
+        // A blob that can't be read is the account's problem, not the others'
+        let bound = crypto.decrypt_unbound(&account.credentials_encrypted)
+            .and_then(|json| crypto.encrypt(&account.id, &account.provider, &json));
+        match bound {
+            Ok(encrypted) => credentials.push((account.id.clone(), encrypted)),
+            Err(QuonitorError::Locked) => return Err(QuonitorError::Locked),
+            Err(e) => {
+                warn!("Failed to bind credentials of account {}: {}", account.id, e);
+                repo.set_account_needs_reauth(&account.id, true).await?;
+            }
+        }
+    }
+
+    if !credentials.is_empty() {
+        repo.replace_account_credentials(&credentials).await?;
+        info!("Bound credentials of {} accounts to their account", credentials.len());
+    }
+    Ok(credentials.len())
+}
+
+// Writes the given accounts (all of them when empty) to `path`. Returns how many were exported.
+pub async fn export_credentials(
+    repo: &Repository,
+    crypto: &CryptoService,
+    passphrase: &str,
+    account_ids: &[String],
+    path: &Path,
+) -> Result<usize> {
+    if passphrase.chars().count() < MIN_PASSPHRASE_LEN {
+        return Err(QuonitorError::Config(format!(
+            "Export passphrase must be at least {} characters", MIN_PASSPHRASE_LEN
+        )));
+    }
+
+    let mut accounts = Vec::new();
+    for account in repo.get_all_accounts().await? {
+        if !account_ids.is_empty() && !account_ids.contains(&account.id) {
+            continue;
+        }
+        let json = crypto.decrypt(&account.id, &account.provider, &account.credentials_encrypted)?;
+        accounts.push(BundledAccount {
+            provider: account.provider,
+            name: account.name,
+            credentials: serde_json::from_str(&json)?,
+        });
+    }
+
+    let sealed = CryptoService::seal_with_passphrase(&serde_json::to_vec(&accounts)?, passphrase)?;
+    let bundle = CredentialBundle {
+        format: BUNDLE_FORMAT.to_string(),
+        version: BUNDLE_VERSION,
+        created_at: Utc::now().timestamp(),
+        accounts: general_purpose::STANDARD.encode(sealed),
+    };
+    tokio::fs::write(path, serde_json::to_vec(&bundle)?).await?;
+
+    Ok(accounts.len())
+}
+
+// Adds the bundle's accounts as new accounts, re-encrypted under this machine's key.
+// Accounts matching an existing one by provider and name are left out.
+pub async fn import_credentials(
+    repo: &Repository,
+    crypto: &CryptoService,
+    passphrase: &str,
+    path: &Path,
+) -> Result<CredentialImport> {
+    let bundle: CredentialBundle = serde_json::from_slice(&tokio::fs::read(path).await?)
+        .map_err(|_| QuonitorError::Config("Not a Quonitor credentials file".to_string()))?;
+    if bundle.format != BUNDLE_FORMAT || bundle.version > BUNDLE_VERSION {
+        return Err(QuonitorError::Config("Not a supported Quonitor credentials file".to_string()));
+    }
+
+    let sealed = general_purpose::STANDARD.decode(&bundle.accounts)
+        .map_err(|e| QuonitorError::Config(format!("Corrupted credentials file: {}", e)))?;
+    let accounts: Vec<BundledAccount> =
+        serde_json::from_slice(&CryptoService::open_with_passphrase(&sealed, passphrase)?)?;
+
+    let mut existing: HashSet<(String, String)> = repo.get_all_accounts().await?
+        .into_iter()
+        .map(|a| (a.provider, a.name))
+        .collect();
+
+    let mut summary = CredentialImport::default();
+    for bundled in accounts {
+        if !existing.insert((bundled.provider.clone(), bundled.name.clone())) {
+            warn!("Skipping imported account {} ({}), it already exists", bundled.name, bundled.provider);
+            summary.skipped += 1;
+            continue;
+        }
+
+        let id = Uuid::new_v4().to_string();
+        let credentials_encrypted = crypto.encrypt(&id, &bundled.provider, &bundled.credentials.to_string())?;
+        repo.insert_account(&Account {
+            id,
+            provider: bundled.provider,
+            name: bundled.name,
+            credentials_encrypted,
+            created_at: Utc::now().timestamp(),
+            last_synced: None,
+            needs_reauth: false,
+            sort_order: 0,
+            favorite: false,
+        }).await?;
+        summary.imported += 1;
+    }
+
+    Ok(summary)
+}
+
+// Adds every account in a JSON list, or none: each entry's credentials are checked with
+// its provider first, and a single failure (or a clash with an existing account's
+// provider and name) leaves everything as it was. Results are reported per entry.
+pub async fn import_accounts(
+    repo: &Repository,
+    crypto: &CryptoService,
+    aggregator: &Aggregator,
+    json: &str,
+) -> Result<AccountImport> {
+    let entries: Vec<ImportedAccount> = serde_json::from_str(json)
+        .map_err(|e| QuonitorError::Config(format!("Not a list of accounts: {}", e)))?;
+
+    let mut existing: HashSet<(String, String)> = repo.get_all_accounts().await?
+        .into_iter()
+        .map(|a| (a.provider, a.name))
+        .collect();
+
+    let mut report = AccountImport::default();
+    let mut accounts = Vec::new();
+    for (index, entry) in entries.into_iter().enumerate() {
+        let name = entry.name.trim().to_string();
+        let error = if name.is_empty() {
+            Some("Account name is required".to_string())
+        } else if !existing.insert((entry.provider.clone(), name.clone())) {
+            Some("An account with this provider and name already exists".to_string())
+        } else {
+            match aggregator.validate_credentials(&entry.provider, &entry.credentials).await {
+                Ok(_) => None,
+                Err(e) => Some(e.to_string()),
+            }
+        };
+
+        if error.is_none() {
+            let id = Uuid::new_v4().to_string();
+            let credentials_encrypted = crypto.encrypt(&id, &entry.provider, &serde_json::to_string(&entry.credentials)?)?;
+            accounts.push(Account {
+                id,
+                provider: entry.provider.clone(),
+                name: name.clone(),
+                credentials_encrypted,
+                created_at: Utc::now().timestamp(),
+                last_synced: None,
+                needs_reauth: false,
+                sort_order: 0,
+                favorite: false,
+            });
+        }
+        report.results.push(AccountImportResult { index, provider: entry.provider, name, error });
+    }
+
+    let failed = report.results.iter().filter(|result| result.error.is_some()).count();
+    if failed > 0 {
+        warn!("Imported no accounts, {} of {} entries failed", failed, report.results.len());
+        return Ok(report);
+    }
+
+    repo.insert_accounts(&accounts).await?;
+    report.imported = accounts.len();
+    info!("Imported {} accounts", report.imported);
+    Ok(report)
+}
@@ -0,0 +1,159 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use chrono::{Datelike, Local, Utc, Weekday};
+use crate::db::Repository;
+use crate::error::Result;
+use crate::services::{Notifier, Settings};
+use tracing::{error, info};
+
+// Local date each digest was last sent, so one goes out per day (or week) however
+// often the check runs
+const SENT_KEY: &str = "digest_last_sent";
+// Accounts and models listed, and accounts listed as movers
+const TOP: usize = 5;
+const MOVERS: usize = 3;
+
+#[derive(Clone, Copy)]
+enum Period {
+    Daily,
+    Weekly,
+}
+
+impl Period {
+    fn key(self) -> &'static str {
+        match self {
+            Period::Daily => "daily",
+            Period::Weekly => "weekly",
+        }
+    }
+
+    fn days(self) -> i64 {
+        match self {
+            Period::Daily => 1,
+            Period::Weekly => 7,
+        }
+    }
+}
+
+// Sends the daily digest (yesterday) and the weekly one (the past seven days, on
+// Mondays) at `Settings::digest_time`, through the notifier's channels and routing
+pub struct DigestService {
+    repo: Arc<Repository>,
+    notifier: Arc<Notifier>,
+}
+
+impl DigestService {
+    pub fn new(repo: Arc<Repository>, notifier: Arc<Notifier>) -> Self {
+        Self { repo, notifier }
+    }
+
+    pub async fn run_if_due(&self) {
+        let settings = match Settings::load(&self.repo).await {
+            Ok(settings) => settings,
+            Err(e) => {
+                error!("Failed to load settings for the digest: {}", e);
+                return;
+            }
+        };
+        let Some(time) = settings.digest_time() else {
+            return;
+        };
+
+        let now = Local::now();
+        if now.time() < time {
+            return;
+        }
+
+        let mut due = Vec::new();
+        if settings.digest_daily {
+            due.push(Period::Daily);
+        }
+        if settings.digest_weekly && now.weekday() == Weekday::Mon {
+            due.push(Period::Weekly);
+        }
+
+        for period in due {
+            if let Err(e) = self.send_if_unsent(period).await {
+                error!("Failed to send the {} digest: {}", period.key(), e);
+            }
+        }
+    }
+
+    async fn send_if_unsent(&self, period: Period) -> Result<()> {
+        let key = format!("{}.{}", SENT_KEY, period.key());
+        let today = Local::now().date_naive().to_string();
+        if self.repo.get_settings_matching(&key).await?.get(&key) == Some(&today) {
+            return Ok(());
+        }
+
+        let (summary, body) = self.compose(period).await?;
+        // Held back during quiet hours, and sent at the next check after them
+        if self.notifier.send_digest(&summary, &body).await? {
+            self.repo.replace_settings(&[(key.as_str(), Some(today))]).await?;
+            info!("Sent the {} digest", period.key());
+        }
+        Ok(())
+    }
+
+    // Usage is counted in whole UTC days, like the dashboard summary
+    async fn compose(&self, period: Period) -> Result<(String, String)> {
+        let now = Utc::now().timestamp();
+        let until = now - now.rem_euclid(86400);
+        let since = until - period.days() * 86400;
+        let previous_since = since - period.days() * 86400;
+
+        let names: HashMap<String, String> = self.repo.get_all_accounts().await?
+            .into_iter()
+            .map(|account| (account.id, account.name))
+            .collect();
+        let name = |account_id: &str| names.get(account_id).cloned().unwrap_or_else(|| account_id.to_string());
+
+        let spend = self.repo.get_spend_by_account(since, until).await?;
+        let previous: HashMap<String, f64> = self.repo.get_spend_by_account(previous_since, since).await?
+            .into_iter()
+            .collect();
+        let models = self.repo.get_top_models(&[], since, until, TOP as i64).await?;
+        let total: f64 = spend.iter().map(|(_, cost)| cost).sum();
+        let previous_total: f64 = previous.values().sum();
+
+        let (summary, label, comparison) = match period {
+            Period::Daily => ("Daily Usage Digest", "yesterday", "the day before"),
+            Period::Weekly => ("Weekly Usage Digest", "the past week", "the week before"),
+        };
+
+        let mut body = format!("${:.2} spent {} (${:.2} {})", total, label, previous_total, comparison);
+
+        if !spend.is_empty() {
+            body.push_str("\n\nBy account:");
+            for (account_id, cost) in spend.iter().take(TOP) {
+                body.push_str(&format!("\n  {}: ${:.2}", name(account_id), cost));
+            }
+        }
+
+        if !models.is_empty() {
+            body.push_str("\n\nTop models:");
+            for (model, cost) in &models {
+                body.push_str(&format!("\n  {}: ${:.2}", model, cost));
+            }
+        }
+
+        let mut movers: Vec<(String, f64)> = spend
+            .iter()
+            .map(|(account_id, cost)| (account_id.clone(), cost - previous.get(account_id).copied().unwrap_or(0.0)))
+            .chain(previous.iter()
+                .filter(|(account_id, _)| !spend.iter().any(|(id, _)| id == *account_id))
+                .map(|(account_id, cost)| (account_id.clone(), -cost)))
+            .filter(|(_, change)| change.abs() >= 0.01)
+            .collect();
+        movers.sort_by(|a, b| b.1.abs().total_cmp(&a.1.abs()));
+        if !movers.is_empty() {
+            body.push_str("\n\nBiggest movers:");
+            for (account_id, change) in movers.iter().take(MOVERS) {
+                let sign = if *change >= 0.0 { "+" } else { "-" };
+                body.push_str(&format!("\n  {}: {}${:.2}", name(account_id), sign, change.abs()));
+            }
+        }
+
+        Ok((summary.to_string(), body))
+    }
+}
@@ -0,0 +1,181 @@
+use base64::{engine::general_purpose, Engine as _};
+use chrono::{Local, TimeZone};
+use lettre::message::{Mailbox, MultiPart};
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+use crate::crypto::{CryptoService, Rekey};
+use crate::db::Repository;
+use crate::error::{QuonitorError, Result};
+use crate::services::notifier::Alert;
+use crate::services::Settings;
+use tracing::{info, warn};
+
+// The SMTP password lives in its own settings row, encrypted like account credentials
+// and bound to this pseudo-account, so it never reaches the frontend with `Settings`
+const PASSWORD_KEY: &str = "smtp_password";
+const PASSWORD_BINDING: &str = "smtp";
+
+pub async fn has_password(repo: &Repository) -> Result<bool> {
+    Ok(repo.get_settings_matching(PASSWORD_KEY).await?.contains_key(PASSWORD_KEY))
+}
+
+// Stores the password, or with None removes it
+pub async fn set_password(repo: &Repository, crypto: &CryptoService, password: Option<&str>) -> Result<()> {
+    let value = match password.filter(|password| !password.is_empty()) {
+        Some(password) => Some(general_purpose::STANDARD.encode(crypto.encrypt(PASSWORD_BINDING, PASSWORD_BINDING, password)?)),
+        None => None,
+    };
+    repo.replace_settings(&[(PASSWORD_KEY, value)]).await?;
+    Ok(())
+}
+
+async fn password(repo: &Repository, crypto: &CryptoService) -> Result<Option<String>> {
+    let Some(stored) = repo.get_settings_matching(PASSWORD_KEY).await?.remove(PASSWORD_KEY) else {
+        return Ok(None);
+    };
+    let sealed = general_purpose::STANDARD
+        .decode(stored.trim())
+        .map_err(|e| QuonitorError::Encryption(format!("Stored SMTP password is corrupt: {}", e)))?;
+    crypto.decrypt(PASSWORD_BINDING, PASSWORD_BINDING, &sealed).map(Some)
+}
+
+// The stored password encrypted under a new credentials key, for changing the master
+// password; None when there is none
+pub async fn rekey_password(repo: &Repository, crypto: &CryptoService, rekey: &Rekey) -> Result<Option<String>> {
+    match password(repo, crypto).await? {
+        Some(password) => Ok(Some(general_purpose::STANDARD.encode(rekey.encrypt(PASSWORD_BINDING, PASSWORD_BINDING, &password)?))),
+        None => Ok(None),
+    }
+}
+
+pub async fn store_rekeyed_password(repo: &Repository, sealed: Option<String>) -> Result<()> {
+    if sealed.is_some() {
+        repo.replace_settings(&[(PASSWORD_KEY, sealed)]).await?;
+    }
+    Ok(())
+}
+
+// Mails alerts at or above `Settings::email_min_threshold` to `Settings::email_to`, as
+// plain text with an HTML alternative
+pub async fn send(repo: &Repository, crypto: &CryptoService, settings: &Settings, alert: &Alert) {
+    if !settings.email_enabled() {
+        return;
+    }
+    if alert.threshold.unwrap_or(0.0) < settings.email_min_threshold as f64 {
+        return;
+    }
+
+    if let Err(e) = deliver(repo, crypto, settings, alert).await {
+        warn!("Failed to email alert: {}", e);
+    }
+}
+
+// Sends regardless of the threshold, for checking the configuration
+pub async fn deliver(repo: &Repository, crypto: &CryptoService, settings: &Settings, alert: &Alert) -> Result<()> {
+    let (Some(host), Some(from)) = (&settings.smtp_host, &settings.smtp_from) else {
+        return Err(QuonitorError::Config("Set an SMTP server and sender first".to_string()));
+    };
+    let recipients = settings.email_recipients();
+    if recipients.is_empty() {
+        return Err(QuonitorError::Config("Set at least one email recipient first".to_string()));
+    }
+
+    let mut message = Message::builder()
+        .from(mailbox(from)?)
+        .subject(format!("[Quonitor] {}", alert.summary));
+    for to in recipients {
+        message = message.to(mailbox(to)?);
+    }
+    let message = message
+        .multipart(MultiPart::alternative_plain_html(plain_text(alert), html(alert)))
+        .map_err(|e| QuonitorError::Config(format!("Failed to build email: {}", e)))?;
+
+    let builder = if settings.smtp_implicit_tls {
+        AsyncSmtpTransport::<Tokio1Executor>::relay(host)
+    } else {
+        AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(host)
+    }
+    .map_err(|e| QuonitorError::Config(format!("Invalid SMTP server {}: {}", host, e)))?
+    .port(settings.smtp_port);
+
+    let builder = match &settings.smtp_username {
+        Some(username) => {
+            // Fails while the credentials are locked; other channels still get the alert
+            let password = password(repo, crypto).await?.unwrap_or_default();
+            builder.credentials(Credentials::new(username.clone(), password))
+        }
+        None => builder,
+    };
+
+    builder
+        .build()
+        .send(message)
+        .await
+        .map_err(|e| QuonitorError::Config(format!("SMTP delivery failed: {}", e)))?;
+
+    info!("Emailed alert \"{}\"", alert.summary);
+    Ok(())
+}
+
+fn mailbox(address: &str) -> Result<Mailbox> {
+    address
+        .parse()
+        .map_err(|e| QuonitorError::Config(format!("Invalid email address \"{}\": {}", address, e)))
+}
+
+// Each detail of the alert that's known, as (label, value)
+fn details(alert: &Alert) -> Vec<(&'static str, String)> {
+    let mut details = Vec::new();
+    if let Some(account_id) = &alert.account_id {
+        details.push(("Account", account_id.clone()));
+    }
+    if let Some(provider) = &alert.provider {
+        details.push(("Provider", provider.clone()));
+    }
+    if let Some(percentage) = alert.percentage {
+        details.push(("Usage", format!("{:.1}%", percentage)));
+    }
+    if let Some(threshold) = alert.threshold {
+        details.push(("Threshold", format!("{:.0}%", threshold)));
+    }
+    if let Some(cost_usd) = alert.cost_usd {
+        details.push(("Cost", format!("${:.2}", cost_usd)));
+    }
+    let sent_at = Local
+        .timestamp_opt(alert.timestamp, 0)
+        .single()
+        .map(|at| at.format("%Y-%m-%d %H:%M %Z").to_string())
+        .unwrap_or_default();
+    details.push(("Time", sent_at));
+    details
+}
+
+fn plain_text(alert: &Alert) -> String {
+    let mut text = format!("{}\n\n{}\n\n", alert.summary, alert.body);
+    for (label, value) in details(alert) {
+        text.push_str(&format!("{}: {}\n", label, value));
+    }
+    text
+}
+
+fn html(alert: &Alert) -> String {
+    let rows: String = details(alert)
+        .into_iter()
+        .map(|(label, value)| format!(
+            "<tr><td style=\"padding:2px 12px 2px 0;color:#6b7280\">{}</td><td>{}</td></tr>",
+            label, escape(&value)
+        ))
+        .collect();
+
+    format!(
+        "<div style=\"font-family:sans-serif\"><h2 style=\"margin:0 0 8px\">{}</h2><p style=\"white-space:pre-line\">{}</p><table>{}</table></div>",
+        escape(&alert.summary), escape(&alert.body), rows
+    )
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
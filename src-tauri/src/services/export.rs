@@ -0,0 +1,64 @@
+use chrono::{TimeZone, Utc};
+use crate::db::UsageExportRow;
+
+const HEADER: &[&str] = &[
+    "record_type",
+    "timestamp",
+    "account_id",
+    "account_name",
+    "provider",
+    "model",
+    "tokens_input",
+    "tokens_output",
+    "cost_usd",
+    "request_count",
+    "quota_limit",
+    "quota_remaining",
+];
+
+// RFC 4180 CSV with UTC RFC 3339 timestamps, so spreadsheets parse it without hints
+pub fn usage_csv(rows: &[UsageExportRow]) -> String {
+    let mut csv = HEADER.join(",");
+    csv.push_str("\r\n");
+
+    for row in rows {
+        let timestamp = Utc.timestamp_opt(row.timestamp, 0)
+            .single()
+            .map(|t| t.to_rfc3339())
+            .unwrap_or_else(|| row.timestamp.to_string());
+
+        let fields = [
+            row.record_type.clone(),
+            timestamp,
+            row.account_id.clone(),
+            row.account_name.clone(),
+            row.provider.clone(),
+            row.model_name.clone().unwrap_or_default(),
+            optional(row.tokens_input),
+            optional(row.tokens_output),
+            row.cost_usd.map(|c| format!("{:.6}", c)).unwrap_or_default(),
+            optional(row.request_count),
+            optional(row.quota_limit),
+            optional(row.quota_remaining),
+        ];
+
+        let line: Vec<String> = fields.iter().map(|f| escape(f)).collect();
+        csv.push_str(&line.join(","));
+        csv.push_str("\r\n");
+    }
+
+    csv
+}
+
+fn optional(value: Option<i64>) -> String {
+    value.map(|v| v.to_string()).unwrap_or_default()
+}
+
+// Quotes a CSV field when it needs it
+pub fn escape(field: &str) -> String {
+    if field.contains([',', '"', '\r', '\n']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
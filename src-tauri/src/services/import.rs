@@ -0,0 +1,253 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use chrono::{DateTime, NaiveDate, NaiveDateTime};
+use serde::{Deserialize, Serialize};
+use crate::db::{ModelUsage, QuotaSnapshot, Repository};
+use crate::error::{QuonitorError, Result};
+use crate::services::PricingService;
+
+// Header names tried, case-insensitively, for each field when no explicit mapping is
+// given. Covers the usage exports of the OpenAI dashboard and Anthropic console as
+// well as Quonitor's own CSV export.
+const TIMESTAMP_ALIASES: &[&str] = &["timestamp", "date", "day", "usage_date", "usage_date_utc", "start_time", "bucket_start"];
+const MODEL_ALIASES: &[&str] = &["model", "model_name", "model_version", "snapshot_id"];
+const INPUT_ALIASES: &[&str] = &["tokens_input", "input_tokens", "prompt_tokens", "n_context_tokens_total", "usage_input_tokens_no_cache"];
+const OUTPUT_ALIASES: &[&str] = &["tokens_output", "output_tokens", "completion_tokens", "n_generated_tokens_total", "usage_output_tokens"];
+const COST_ALIASES: &[&str] = &["cost_usd", "cost", "amount", "amount_usd", "cost (usd)"];
+const REQUESTS_ALIASES: &[&str] = &["request_count", "requests", "num_model_requests", "n_requests"];
+
+// Source column for each field; None falls back to the aliases above
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ColumnMapping {
+    pub timestamp: Option<String>,
+    pub model: Option<String>,
+    pub tokens_input: Option<String>,
+    pub tokens_output: Option<String>,
+    pub cost_usd: Option<String>,
+    pub request_count: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ImportFormat {
+    Csv,
+    // An array of flat objects
+    Json,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ImportSummary {
+    pub rows_read: usize,
+    pub rows_skipped: usize,
+    pub days_imported: usize,
+    // Days the account already has snapshots for, left untouched to avoid double counting
+    pub days_skipped: usize,
+}
+
+#[derive(Debug, Default)]
+struct ModelTotals {
+    tokens_input: i64,
+    tokens_output: i64,
+    cost_usd: f64,
+    request_count: i64,
+}
+
+// Imports per-day, per-model usage into `account_id`'s history as one closing snapshot
+// per day plus its model breakdown, matching what a day of live fetches leaves behind.
+// Missing costs are priced from the pricing table.
+pub async fn import_usage(
+    repo: &Repository,
+    pricing: &PricingService,
+    account_id: &str,
+    content: &str,
+    format: ImportFormat,
+    mapping: &ColumnMapping,
+) -> Result<ImportSummary> {
+    let account = repo.get_account(account_id).await?
+        .ok_or_else(|| QuonitorError::Config(format!("Account {} not found", account_id)))?;
+
+    let records = match format {
+        ImportFormat::Csv => parse_csv(content)?,
+        ImportFormat::Json => parse_json(content)?,
+    };
+    let headers: Vec<String> = records.iter()
+        .flat_map(|r| r.keys().cloned())
+        .collect::<HashSet<_>>()
+        .into_iter()
+        .collect();
+    let column = |explicit: &Option<String>, aliases: &[&str]| resolve(&headers, explicit, aliases);
+
+    let timestamp_col = column(&mapping.timestamp, TIMESTAMP_ALIASES)
+        .ok_or_else(|| QuonitorError::Config("No date or timestamp column found".to_string()))?;
+    let model_col = column(&mapping.model, MODEL_ALIASES);
+    let input_col = column(&mapping.tokens_input, INPUT_ALIASES);
+    let output_col = column(&mapping.tokens_output, OUTPUT_ALIASES);
+    let cost_col = column(&mapping.cost_usd, COST_ALIASES);
+    let requests_col = column(&mapping.request_count, REQUESTS_ALIASES);
+    if input_col.is_none() && output_col.is_none() && cost_col.is_none() {
+        return Err(QuonitorError::Config("No token or cost columns found".to_string()));
+    }
+
+    let mut summary = ImportSummary { rows_read: records.len(), ..Default::default() };
+    let mut days: BTreeMap<NaiveDate, BTreeMap<String, ModelTotals>> = BTreeMap::new();
+
+    for record in &records {
+        let field = |col: &Option<String>| col.as_ref().and_then(|c| record.get(c)).map(|v| v.trim());
+        let Some(day) = record.get(&timestamp_col).and_then(|v| parse_day(v.trim())) else {
+            summary.rows_skipped += 1;
+            continue;
+        };
+
+        let model = field(&model_col).filter(|m| !m.is_empty()).unwrap_or("unknown").to_string();
+        let tokens_input = field(&input_col).and_then(parse_int).unwrap_or(0);
+        let tokens_output = field(&output_col).and_then(parse_int).unwrap_or(0);
+        let cost = field(&cost_col).and_then(parse_amount).unwrap_or_else(|| {
+            pricing.token_cost(&account.provider, &model, tokens_input, tokens_output)
+        });
+
+        let totals = days.entry(day).or_default().entry(model).or_default();
+        totals.tokens_input += tokens_input;
+        totals.tokens_output += tokens_output;
+        totals.cost_usd += cost;
+        totals.request_count += field(&requests_col).and_then(parse_int).unwrap_or(0);
+    }
+
+    let (Some(first), Some(last)) = (days.keys().next(), days.keys().next_back()) else {
+        return Ok(summary);
+    };
+    let existing: HashSet<String> = repo
+        .get_snapshot_days(account_id, day_start(*first), day_start(*last) + 86400)
+        .await?
+        .into_iter()
+        .collect();
+
+    for (day, models) in days {
+        if existing.contains(&day.format("%Y-%m-%d").to_string()) {
+            summary.days_skipped += 1;
+            continue;
+        }
+
+        // Last second of the day, so it's that day's closing snapshot
+        let timestamp = day_start(day) + 86399;
+        repo.insert_quota_snapshot(&QuotaSnapshot {
+            id: None,
+            account_id: account_id.to_string(),
+            timestamp,
+            tokens_input: Some(models.values().map(|m| m.tokens_input).sum()),
+            tokens_output: Some(models.values().map(|m| m.tokens_output).sum()),
+            cost_usd: Some(models.values().map(|m| m.cost_usd).sum()),
+            quota_limit: None,
+            quota_remaining: None,
+            metadata: Some(r#"{"source":"import"}"#.to_string()),
+            rpm_limit: None,
+            rpm_remaining: None,
+            tpm_limit: None,
+            tpm_remaining: None,
+            requests_limit: None,
+            requests_remaining: None,
+            requests_reset_at: None,
+            rpm_reset_at: None,
+            tpm_reset_at: None,
+            images_generated: None,
+            audio_seconds: None,
+            video_seconds: None,
+        }).await?;
+
+        for (model_name, totals) in models {
+            repo.insert_model_usage(&ModelUsage {
+                id: None,
+                account_id: account_id.to_string(),
+                model_name,
+                timestamp,
+                tokens_input: totals.tokens_input,
+                tokens_output: totals.tokens_output,
+                cost_usd: totals.cost_usd,
+                request_count: totals.request_count,
+                tokens_cached_input: 0,
+                tokens_reasoning: 0,
+                images_generated: None,
+                audio_seconds: None,
+                video_seconds: None,
+            }).await?;
+        }
+
+        summary.days_imported += 1;
+    }
+
+    Ok(summary)
+}
+
+// Records keyed by lowercased header
+fn parse_csv(content: &str) -> Result<Vec<HashMap<String, String>>> {
+    let mut reader = csv::ReaderBuilder::new()
+        .flexible(true)
+        .trim(csv::Trim::All)
+        .from_reader(content.trim_start_matches('\u{feff}').as_bytes());
+
+    let headers: Vec<String> = reader.headers()
+        .map_err(|e| QuonitorError::Config(format!("Invalid CSV: {}", e)))?
+        .iter()
+        .map(|h| h.to_lowercase())
+        .collect();
+
+    reader.records()
+        .map(|record| {
+            let record = record.map_err(|e| QuonitorError::Config(format!("Invalid CSV: {}", e)))?;
+            Ok(headers.iter().cloned().zip(record.iter().map(str::to_string)).collect())
+        })
+        .collect()
+}
+
+fn parse_json(content: &str) -> Result<Vec<HashMap<String, String>>> {
+    let rows: Vec<serde_json::Map<String, serde_json::Value>> = serde_json::from_str(content)?;
+
+    Ok(rows.into_iter()
+        .map(|row| {
+            row.into_iter()
+                .map(|(key, value)| {
+                    let value = match value {
+                        serde_json::Value::String(s) => s,
+                        serde_json::Value::Null => String::new(),
+                        other => other.to_string(),
+                    };
+                    (key.to_lowercase(), value)
+                })
+                .collect()
+        })
+        .collect())
+}
+
+fn resolve(headers: &[String], explicit: &Option<String>, aliases: &[&str]) -> Option<String> {
+    match explicit {
+        Some(column) => Some(column.to_lowercase()),
+        None => aliases.iter()
+            .find(|alias| headers.iter().any(|h| h == *alias))
+            .map(|alias| alias.to_string()),
+    }
+}
+
+// Dates, RFC 3339 / "YYYY-MM-DD HH:MM:SS" timestamps or unix seconds, taken as UTC
+fn parse_day(value: &str) -> Option<NaiveDate> {
+    if let Ok(seconds) = value.parse::<i64>() {
+        return DateTime::from_timestamp(seconds, 0).map(|t| t.date_naive());
+    }
+    if let Ok(time) = DateTime::parse_from_rfc3339(value) {
+        return Some(time.naive_utc().date());
+    }
+    if let Ok(time) = NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M:%S") {
+        return Some(time.date());
+    }
+    NaiveDate::parse_from_str(value.get(..10)?, "%Y-%m-%d").ok()
+}
+
+fn parse_int(value: &str) -> Option<i64> {
+    let value = value.replace(',', "");
+    value.parse::<i64>().ok().or_else(|| value.parse::<f64>().ok().map(|v| v.round() as i64))
+}
+
+fn parse_amount(value: &str) -> Option<f64> {
+    value.trim_start_matches('$').replace(',', "").parse().ok()
+}
+
+fn day_start(day: NaiveDate) -> i64 {
+    day.and_hms_opt(0, 0, 0).map(|t| t.and_utc().timestamp()).unwrap_or_default()
+}
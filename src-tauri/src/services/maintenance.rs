@@ -0,0 +1,98 @@
+use std::sync::Arc;
+use std::time::Instant;
+use chrono::Utc;
+use serde::Serialize;
+use crate::db::Repository;
+use crate::error::Result;
+use crate::services::Settings;
+use tracing::{error, info, warn};
+
+const WEEK_SECONDS: i64 = 7 * 86400;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MaintenanceReport {
+    pub ran_at: i64,
+    pub integrity_ok: bool,
+    // Problems reported by `PRAGMA integrity_check`; empty when it passed
+    pub integrity_errors: Vec<String>,
+    pub size_before: i64,
+    pub size_after: i64,
+    pub duration_ms: u64,
+}
+
+pub struct MaintenanceService {
+    repo: Arc<Repository>,
+}
+
+impl MaintenanceService {
+    pub fn new(repo: Arc<Repository>) -> Self {
+        Self { repo }
+    }
+
+    // Checks integrity, then compacts and re-analyzes the database. A failed check
+    // skips VACUUM, which would rewrite a damaged file and could lose more rows.
+    pub async fn run(&self) -> Result<MaintenanceReport> {
+        let started = Instant::now();
+        let size_before = self.repo.database_size().await?;
+
+        let integrity_errors = self.repo.integrity_check().await?;
+        let integrity_ok = integrity_errors.is_empty();
+        if integrity_ok {
+            self.repo.vacuum().await?;
+        } else {
+            warn!("Integrity check found {} problems, skipping VACUUM", integrity_errors.len());
+        }
+        self.repo.analyze().await?;
+
+        let report = MaintenanceReport {
+            ran_at: Utc::now().timestamp(),
+            integrity_ok,
+            integrity_errors,
+            size_before,
+            size_after: self.repo.database_size().await?,
+            duration_ms: started.elapsed().as_millis() as u64,
+        };
+        self.repo.log_maintenance(report.ran_at, report.integrity_ok, report.size_before, report.size_after).await?;
+
+        info!(
+            "Database maintenance finished: {} -> {} bytes in {}ms",
+            report.size_before, report.size_after, report.duration_ms
+        );
+        Ok(report)
+    }
+
+    // Deletes history past each data type's retention period; returns the rows removed
+    pub async fn cleanup(&self) -> Result<u64> {
+        let settings = Settings::load(&self.repo).await?;
+        let deleted = self.repo.cleanup_old_data(&settings.retention_cutoffs(Utc::now().timestamp())).await?;
+        if deleted > 0 {
+            info!("Retention cleanup removed {} rows", deleted);
+        }
+        Ok(deleted)
+    }
+
+    // Runs if weekly maintenance is enabled and the last run is a week old
+    pub async fn run_if_due(&self) {
+        let due = async {
+            if !Settings::load(&self.repo).await?.weekly_maintenance {
+                return Ok(false);
+            }
+            let last = self.repo.get_last_maintenance().await?;
+            Ok::<_, crate::error::QuonitorError>(match last {
+                Some(ran_at) => Utc::now().timestamp() - ran_at >= WEEK_SECONDS,
+                None => true,
+            })
+        }
+        .await;
+
+        match due {
+            Ok(true) => {
+                if let Err(e) = self.run().await {
+                    error!("Scheduled database maintenance failed: {}", e);
+                }
+            }
+            Ok(false) => {}
+            Err(e) => error!("Failed to check maintenance schedule: {}", e),
+        }
+    }
+}
@@ -1,9 +1,39 @@
 pub mod scheduler;
+pub mod schedule;
+pub mod quiet_hours;
 pub mod notifier;
+pub mod webhook;
+pub mod email;
+pub mod push;
+pub mod routing;
 pub mod aggregator;
 pub mod cache;
+pub mod connectivity;
+pub mod token_refresher;
+pub mod pricing;
+pub mod export;
+pub mod report;
+pub mod import;
+pub mod backup;
+pub mod credentials;
+pub mod rollup;
+pub mod rate_limit;
+pub mod budgets;
+pub mod anomaly;
+pub mod settings;
+pub mod snapshots;
+pub mod maintenance;
+pub mod digest;
 
 pub use scheduler::Scheduler;
 pub use notifier::Notifier;
 pub use aggregator::Aggregator;
 pub use cache::Cache;
+pub use connectivity::Connectivity;
+pub use token_refresher::TokenRefresher;
+pub use pricing::PricingService;
+pub use rollup::RollupService;
+pub use settings::Settings;
+pub use snapshots::SnapshotService;
+pub use maintenance::MaintenanceService;
+pub use digest::DigestService;
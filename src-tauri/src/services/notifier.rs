@@ -1,41 +1,126 @@
 use std::sync::Arc;
-use chrono::{Utc, Timelike};
-use notify_rust::Notification;
-use crate::db::{Repository, NotificationState};
+use chrono::{Local, TimeZone, Utc};
+use notify_rust::{Notification, Timeout, Urgency};
+use serde::Serialize;
+use crate::crypto::CryptoService;
+use crate::db::{AccountNotificationPrefs, Repository, NotificationState};
+use crate::services::{anomaly, budgets, email, routing, Settings};
+use crate::services::quiet_hours::QuietHours;
+use crate::services::push::Push;
+use crate::services::webhook::Webhooks;
 use crate::providers::QuotaData;
 use crate::error::Result;
 use tracing::{info, warn};
 
+// Seconds alerts stay on the desktop unless their threshold's settings say otherwise
+const DEFAULT_TIMEOUT_SECONDS: u32 = 6;
+// Percentage points usage must fall below an alerted threshold before "Back to Normal"
+const RECOVERY_MARGIN: f64 = 5.0;
+
+// When each account's last unusual-spend alert went out, as `anomaly_alerted.<account id>`
+const ANOMALY_KEY: &str = "anomaly_alerted";
+// When each account's last burn rate alert went out, as `burn_rate_alerted.<account id>`;
+// a runaway job is alerted on again each hour it keeps going
+const BURN_RATE_KEY: &str = "burn_rate_alerted";
+const BURN_RATE_COOLDOWN: i64 = 3600;
+// The last sync time each account's stale-data alert was sent for, as
+// `stale_alerted.<account id>`, so each outage is alerted once
+const STALE_KEY: &str = "stale_alerted";
+
+// An alert as recorded in the history and sent to the desktop and the other channels
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Alert {
+    pub summary: String,
+    pub body: String,
+    pub account_id: Option<String>,
+    // Filled in from the account when sent
+    pub provider: Option<String>,
+    pub percentage: Option<f64>,
+    pub cost_usd: Option<f64>,
+    pub threshold: Option<f64>,
+    pub timestamp: i64,
+}
+
+// How the desktop shows an alert
+struct Presentation {
+    urgency: Urgency,
+    // 0 keeps it until dismissed
+    timeout_seconds: u32,
+    sound: Option<String>,
+}
+
+impl From<Urgency> for Presentation {
+    fn from(urgency: Urgency) -> Self {
+        Self { urgency, timeout_seconds: DEFAULT_TIMEOUT_SECONDS, sound: None }
+    }
+}
+
 pub struct Notifier {
     repo: Arc<Repository>,
+    // Decrypts the SMTP password
+    crypto: Arc<CryptoService>,
+    webhooks: Webhooks,
+    push: Push,
 }
 
 impl Notifier {
-    pub fn new(repo: Arc<Repository>) -> Self {
-        Self { repo }
+    pub fn new(repo: Arc<Repository>, crypto: Arc<CryptoService>) -> Self {
+        Self { repo, crypto, webhooks: Webhooks::new(), push: Push::new() }
     }
 
-    pub async fn check_and_notify(&self, quota: &QuotaData) -> Result<()> {
-        // Check if notifications are enabled
-        let enabled = self.repo.get_setting("notifications_enabled").await?
-            .unwrap_or_else(|| "true".to_string());
+    // The current settings, or None when notifications are off or it's quiet hours
+    async fn allowed_settings(&self) -> Result<Option<Settings>> {
+        let settings = Settings::load(&self.repo).await?;
+        if !settings.notifications_enabled || self.is_quiet_hours(&settings) {
+            return Ok(None);
+        }
+
+        Ok(Some(settings))
+    }
 
-        if enabled != "true" {
+    pub async fn check_and_notify(&self, quota: &QuotaData) -> Result<()> {
+        let settings = Settings::load(&self.repo).await?;
+        if !settings.notifications_enabled {
             return Ok(());
         }
-
-        // Check if we're in quiet hours
-        if self.is_quiet_hours().await? {
+        // Only the critical alert may come during quiet hours
+        let quiet = self.is_quiet_hours(&settings);
+        if quiet && !settings.critical_bypasses_quiet_hours {
             return Ok(());
         }
 
-        // Calculate usage percentage
-        let percentage = self.calculate_usage_percentage(quota);
-        if percentage.is_none() {
-            return Ok(()); // Can't determine percentage
+        let prefs = self.repo.get_account_notification_prefs(&quota.account_id).await?
+            .unwrap_or_else(|| AccountNotificationPrefs::defaults(&quota.account_id));
+        // Left unrecorded, so the alert comes once the snooze ends
+        if prefs.is_silenced(Utc::now().timestamp()) {
+            return Ok(());
         }
+        // The three alert levels keep the 75/90/95 names in the state and settings
+        let (warning, caution, critical) = prefs.thresholds();
 
-        let percentage = percentage.unwrap();
+        // Calculate usage percentage of the most constrained dimension. Most providers
+        // report spend but no limit, so fall back to the account's own budgets.
+        let (percentage, dimension, budget) = match quota.usage_percentage() {
+            Some((percentage, dimension)) => (percentage, dimension, None),
+            None => {
+                let Some(status) = budgets::account_statuses(&self.repo, &quota.account_id).await?
+                    .into_iter()
+                    .max_by(|a, b| a.percent_used.total_cmp(&b.percent_used))
+                else {
+                    return Ok(()); // Can't determine percentage
+                };
+                (status.percent_used, budget_label(&status.budget.period), Some(status))
+            }
+        };
+
+        // Thresholds `check_budgets` has already alerted on this period
+        let budget_alerted = match &budget {
+            Some(status) => match self.repo.get_budget_alert(&status.budget.id).await? {
+                Some((period_start, percent)) if period_start == status.period_start => percent,
+                _ => 0,
+            },
+            None => 0,
+        };
 
         // Get or create notification state
         let mut state = self.repo.get_notification_state(&quota.account_id).await?
@@ -46,103 +131,479 @@ impl Notifier {
                 last_95_percent_notified: None,
             });
 
+        // Back well below thresholds alerted before (a new billing period, a raised limit):
+        // say so, and clear them so the next breach alerts at once rather than after the
+        // cooldown. Per-minute limits don't count, or every lull between bursts would.
+        let settled = match &budget {
+            Some(status) => Some((status.percent_used, dimension)),
+            None => quota.period_usage_percentage(),
+        };
+        let mut recovered = None;
+        if let Some((settled, settled_dimension)) = settled.filter(|_| !quiet) {
+            for (threshold, notified) in [
+                (critical, &mut state.last_95_percent_notified),
+                (caution, &mut state.last_90_percent_notified),
+                (warning, &mut state.last_75_percent_notified),
+            ] {
+                if settled < threshold - RECOVERY_MARGIN && notified.take().is_some() {
+                    recovered.get_or_insert((threshold, settled, settled_dimension));
+                }
+            }
+        }
+        if let Some((threshold, settled, settled_dimension)) = recovered {
+            self.notify(
+                &settings,
+                Alert {
+                    summary: "Back to Normal".to_string(),
+                    body: format!("Your {} account is back below {:.0}% of its {}, at {:.1}%",
+                        quota.account_id, threshold, settled_dimension, settled),
+                    account_id: Some(quota.account_id.clone()),
+                    percentage: Some(settled),
+                    cost_usd: budget.as_ref().map(|status| status.spent_usd).or(quota.cost_usd),
+                    // So it goes where the breach's alert went
+                    threshold: Some(threshold),
+                    ..Default::default()
+                },
+                Urgency::Low,
+            ).await?;
+            info!("Sent recovery notification for account {}", quota.account_id);
+        }
+
         let now = Utc::now().timestamp();
         let one_day_ago = now - 86400;
+        let mut sent = None;
 
-        // Check 95% threshold
-        if percentage >= 95.0 && self.should_notify_threshold(&state.last_95_percent_notified, one_day_ago) {
-            self.send_notification(
-                "URGENT: Quota Critical",
-                &format!("Your {} account is at {:.1}% - approaching limit!",
-                    quota.account_id, percentage),
-                notify_rust::Urgency::Critical,
-            )?;
+        // Check the critical threshold (95% by default)
+        if percentage >= critical && (budget_alerted as f64) < critical && settings.threshold_95_enabled && self.should_notify_threshold(&state.last_95_percent_notified, one_day_ago) {
+            self.notify_as(
+                &settings,
+                Alert {
+                    summary: "URGENT: Quota Critical".to_string(),
+                    body: format!("Your {} account is at {:.1}% of its {} - approaching limit!",
+                        quota.account_id, percentage, dimension),
+                    account_id: Some(quota.account_id.clone()),
+                    percentage: Some(percentage),
+                    cost_usd: budget.as_ref().map(|status| status.spent_usd).or(quota.cost_usd),
+                    threshold: Some(critical),
+                    ..Default::default()
+                },
+                threshold_presentation(&settings, 95),
+            ).await?;
             state.last_95_percent_notified = Some(now);
-            info!("Sent 95% notification for account {}", quota.account_id);
-        }
-        // Check 90% threshold
-        else if percentage >= 90.0 && self.should_notify_threshold(&state.last_90_percent_notified, one_day_ago) {
-            self.send_notification(
-                "Quota Caution",
-                &format!("Your {} account is at {:.1}% usage",
-                    quota.account_id, percentage),
-                notify_rust::Urgency::Normal,
-            )?;
+            sent = Some(critical as i64);
+            info!("Sent {}% notification for account {}", critical, quota.account_id);
+        }
+        // Check the caution threshold (90% by default)
+        else if !quiet && percentage >= caution && (budget_alerted as f64) < caution && settings.threshold_90_enabled && self.should_notify_threshold(&state.last_90_percent_notified, one_day_ago) {
+            self.notify_as(
+                &settings,
+                Alert {
+                    summary: "Quota Caution".to_string(),
+                    body: format!("Your {} account is at {:.1}% of its {}",
+                        quota.account_id, percentage, dimension),
+                    account_id: Some(quota.account_id.clone()),
+                    percentage: Some(percentage),
+                    cost_usd: budget.as_ref().map(|status| status.spent_usd).or(quota.cost_usd),
+                    threshold: Some(caution),
+                    ..Default::default()
+                },
+                threshold_presentation(&settings, 90),
+            ).await?;
             state.last_90_percent_notified = Some(now);
-            info!("Sent 90% notification for account {}", quota.account_id);
-        }
-        // Check 75% threshold
-        else if percentage >= 75.0 && self.should_notify_threshold(&state.last_75_percent_notified, one_day_ago) {
-            self.send_notification(
-                "Quota Warning",
-                &format!("Your {} account is at {:.1}% usage",
-                    quota.account_id, percentage),
-                notify_rust::Urgency::Low,
-            )?;
+            sent = Some(caution as i64);
+            info!("Sent {}% notification for account {}", caution, quota.account_id);
+        }
+        // Check the warning threshold (75% by default)
+        else if !quiet && percentage >= warning && (budget_alerted as f64) < warning && settings.threshold_75_enabled && self.should_notify_threshold(&state.last_75_percent_notified, one_day_ago) {
+            self.notify_as(
+                &settings,
+                Alert {
+                    summary: "Quota Warning".to_string(),
+                    body: format!("Your {} account is at {:.1}% of its {}",
+                        quota.account_id, percentage, dimension),
+                    account_id: Some(quota.account_id.clone()),
+                    percentage: Some(percentage),
+                    cost_usd: budget.as_ref().map(|status| status.spent_usd).or(quota.cost_usd),
+                    threshold: Some(warning),
+                    ..Default::default()
+                },
+                threshold_presentation(&settings, 75),
+            ).await?;
             state.last_75_percent_notified = Some(now);
-            info!("Sent 75% notification for account {}", quota.account_id);
+            sent = Some(warning as i64);
+            info!("Sent {}% notification for account {}", warning, quota.account_id);
         }
 
         // Update state
         self.repo.update_notification_state(&state).await?;
 
+        // So `check_budgets` doesn't repeat the alert for the same budget
+        if let (Some(status), Some(threshold)) = (&budget, sent) {
+            self.repo.set_budget_alert(&status.budget.id, status.period_start, threshold).await?;
+        }
+
         Ok(())
     }
 
-    fn should_notify_threshold(&self, last_notified: &Option<i64>, threshold: i64) -> bool {
-        match last_notified {
-            Some(time) => *time < threshold,
-            None => true,
+    // Alerts once per threshold per budget period, escalating from 75% to 90% to 100%
+    pub async fn check_budgets(&self) -> Result<()> {
+        let Some(settings) = self.allowed_settings().await? else {
+            return Ok(());
+        };
+
+        for status in budgets::all_statuses(&self.repo).await? {
+            if settings.forecast_alerts {
+                self.check_forecast(&settings, &status).await?;
+            }
+
+            let Some(threshold) = [100, 90, 75].into_iter().find(|t| status.percent_used >= *t as f64) else {
+                continue;
+            };
+
+            let already_sent = match self.repo.get_budget_alert(&status.budget.id).await? {
+                Some((period_start, percent)) => period_start == status.period_start && percent >= threshold,
+                None => false,
+            };
+            if already_sent {
+                continue;
+            }
+
+            let (summary, urgency) = match threshold {
+                100 => ("Budget Exceeded", Urgency::Critical),
+                90 => ("Budget Caution", Urgency::Normal),
+                _ => ("Budget Warning", Urgency::Low),
+            };
+            self.notify(
+                &settings,
+                Alert {
+                    summary: summary.to_string(),
+                    body: format!("{} is at {:.1}% (${:.2} of ${:.2} {})",
+                        status.budget.name, status.percent_used, status.spent_usd,
+                        status.budget.amount_usd, status.budget.period),
+                    account_id: status.budget.target.clone().filter(|_| status.budget.scope == "account"),
+                    percentage: Some(status.percent_used),
+                    cost_usd: Some(status.spent_usd),
+                    threshold: Some(threshold as f64),
+                    ..Default::default()
+                },
+                urgency,
+            ).await?;
+            self.repo.set_budget_alert(&status.budget.id, status.period_start, threshold).await?;
+            info!("Sent {}% budget notification for {}", threshold, status.budget.name);
+        }
+
+        Ok(())
+    }
+
+    // Spend well above what the account usually spends at this hour is the earliest sign
+    // of a leaked key, so it's alerted on apart from the thresholds; see `anomaly`
+    pub async fn check_spend_anomaly(&self, account_id: &str) -> Result<()> {
+        let Some(settings) = self.allowed_settings().await? else {
+            return Ok(());
+        };
+        if !settings.anomaly_detection {
+            return Ok(());
+        }
+
+        let now = Utc::now().timestamp();
+        let key = format!("{}.{}", ANOMALY_KEY, account_id);
+        let last_alerted = self.repo.get_settings_matching(&key).await?
+            .get(&key)
+            .and_then(|at| at.parse().ok());
+        if !self.should_notify_threshold(&last_alerted, now - anomaly::COOLDOWN) {
+            return Ok(());
         }
+
+        let Some(spike) = anomaly::detect(&self.repo, account_id, now, settings.anomaly_multiple, settings.anomaly_min_usd).await? else {
+            return Ok(());
+        };
+
+        let usual = if spike.baseline_usd >= 0.01 {
+            format!("{:.1}x its usual ${:.2} at this hour", spike.current_usd / spike.baseline_usd, spike.baseline_usd)
+        } else {
+            "where it usually spends nothing at this hour".to_string()
+        };
+        self.notify(
+            &settings,
+            Alert {
+                summary: "Unusual Spend".to_string(),
+                body: format!("Your {} account spent ${:.2} in the last hour, {}. Check for a leaked key.",
+                    account_id, spike.current_usd, usual),
+                account_id: Some(account_id.to_string()),
+                cost_usd: Some(spike.current_usd),
+                ..Default::default()
+            },
+            Urgency::Critical,
+        ).await?;
+        self.repo.replace_settings(&[(key.as_str(), Some(now.to_string()))]).await?;
+        info!("Sent unusual spend notification for account {}", account_id);
+
+        Ok(())
+    }
+
+    // Alerts once per period while a budget is still under its amount but on track to
+    // pass it before the period ends
+    async fn check_forecast(&self, settings: &Settings, status: &budgets::BudgetStatus) -> Result<()> {
+        let (Some(projected_usd), Some(overrun_at)) = (status.projected_usd, status.projected_overrun_at()) else {
+            return Ok(());
+        };
+        if status.percent_used >= 100.0 {
+            return Ok(());
+        }
+        if self.repo.get_budget_forecast_alert(&status.budget.id).await? == Some(status.period_start) {
+            return Ok(());
+        }
+
+        let format = if status.budget.period == "daily" { "%H:%M" } else { "%b %-d" };
+        let by = Local
+            .timestamp_opt(overrun_at, 0)
+            .single()
+            .map(|at| at.format(format).to_string())
+            .unwrap_or_default();
+        self.notify(
+            settings,
+            Alert {
+                summary: "Budget Forecast".to_string(),
+                body: format!("{} is on track to hit {:.0}% of its ${:.2} {} budget, passing it by {}",
+                    status.budget.name, projected_usd / status.budget.amount_usd * 100.0,
+                    status.budget.amount_usd, status.budget.period, by),
+                account_id: status.budget.target.clone().filter(|_| status.budget.scope == "account"),
+                percentage: Some(status.percent_used),
+                cost_usd: Some(status.spent_usd),
+                ..Default::default()
+            },
+            Urgency::Normal,
+        ).await?;
+        self.repo.set_budget_forecast_alert(&status.budget.id, status.period_start).await?;
+        info!("Sent forecast notification for {}", status.budget.name);
+
+        Ok(())
     }
 
-    fn calculate_usage_percentage(&self, quota: &QuotaData) -> Option<f64> {
-        if let (Some(limit), Some(remaining)) = (quota.quota_limit, quota.quota_remaining) {
-            if limit > 0 {
-                let used = limit - remaining;
-                return Some((used as f64 / limit as f64) * 100.0);
+    // Absolute spend over the last hour, which catches a runaway batch job even while the
+    // period's totals still look fine
+    pub async fn check_burn_rate(&self, account_id: &str) -> Result<()> {
+        let Some(settings) = self.allowed_settings().await? else {
+            return Ok(());
+        };
+        let limit = self.repo.get_account_notification_prefs(account_id).await?
+            .and_then(|prefs| prefs.burn_rate_usd_per_hour)
+            .unwrap_or(settings.burn_rate_usd_per_hour);
+        if limit <= 0.0 {
+            return Ok(());
+        }
+
+        let now = Utc::now().timestamp();
+        let key = format!("{}.{}", BURN_RATE_KEY, account_id);
+        let last_alerted = self.repo.get_settings_matching(&key).await?
+            .get(&key)
+            .and_then(|at| at.parse().ok());
+        if !self.should_notify_threshold(&last_alerted, now - BURN_RATE_COOLDOWN) {
+            return Ok(());
+        }
+
+        let spent = anomaly::last_hour_spend(&self.repo, account_id, now).await?;
+        if spent <= limit {
+            return Ok(());
+        }
+
+        self.notify(
+            &settings,
+            Alert {
+                summary: "High Burn Rate".to_string(),
+                body: format!("Your {} account spent ${:.2} in the last hour, over the ${:.2} an hour limit",
+                    account_id, spent, limit),
+                account_id: Some(account_id.to_string()),
+                cost_usd: Some(spent),
+                ..Default::default()
+            },
+            Urgency::Critical,
+        ).await?;
+        self.repo.replace_settings(&[(key.as_str(), Some(now.to_string()))]).await?;
+        info!("Sent burn rate notification for account {}", account_id);
+
+        Ok(())
+    }
+
+    // Returns false when notifications are off or it's quiet hours
+    pub async fn send_digest(&self, summary: &str, body: &str) -> Result<bool> {
+        let Some(settings) = self.allowed_settings().await? else {
+            return Ok(false);
+        };
+
+        self.notify(
+            &settings,
+            Alert {
+                summary: summary.to_string(),
+                body: body.to_string(),
+                ..Default::default()
+            },
+            Urgency::Low,
+        ).await?;
+        Ok(true)
+    }
+
+    // A fetch that keeps failing leaves the account unwatched, which is worse than a breach
+    // that is at least known about, so accounts that have failed since their last
+    // successful sync and haven't synced for `Settings::stale_alert_hours` are alerted on
+    pub async fn check_stale(&self) -> Result<()> {
+        let Some(settings) = self.allowed_settings().await? else {
+            return Ok(());
+        };
+        if settings.stale_alert_hours == 0 {
+            return Ok(());
+        }
+
+        let now = Utc::now().timestamp();
+        let alerted = self.repo.get_settings_matching(&format!("{}.%", STALE_KEY)).await?;
+        for account in self.repo.get_all_accounts().await? {
+            let synced = account.last_synced.unwrap_or(account.created_at);
+            if now - synced < settings.stale_alert_hours as i64 * 3600 {
+                continue;
+            }
+            let key = format!("{}.{}", STALE_KEY, account.id);
+            if alerted.get(&key) == Some(&synced.to_string()) {
+                continue;
+            }
+            let Some(error) = self.repo.get_fetch_errors(&account.id, 1).await?.into_iter().next() else {
+                continue;
+            };
+            if error.timestamp < synced {
+                continue;
             }
+
+            let since = match account.last_synced {
+                Some(_) => format!("hasn't synced for {} hours", (now - synced) / 3600),
+                None => "has never synced".to_string(),
+            };
+            self.notify(
+                &settings,
+                Alert {
+                    summary: "Monitoring Blind Spot".to_string(),
+                    body: format!("{} {}; the last fetch failed: {}", account.name, since, error.message),
+                    account_id: Some(account.id.clone()),
+                    ..Default::default()
+                },
+                Urgency::Normal,
+            ).await?;
+            self.repo.replace_settings(&[(key.as_str(), Some(synced.to_string()))]).await?;
+            info!("Sent stale data notification for account {}", account.id);
         }
 
-        // Fallback: if we have cost data but no explicit limits
-        // This is a placeholder - in practice, users would set their own budget limits
-        None
+        Ok(())
     }
 
-    async fn is_quiet_hours(&self) -> Result<bool> {
-        let start = self.repo.get_setting("quiet_hours_start").await?;
-        let end = self.repo.get_setting("quiet_hours_end").await?;
+    // Group thresholds apply to the members' combined latest snapshots; at most one alert a day
+    pub async fn check_groups(&self) -> Result<()> {
+        let Some(settings) = self.allowed_settings().await? else {
+            return Ok(());
+        };
 
-        if let (Some(start), Some(end)) = (start, end) {
-            if !start.is_empty() && !end.is_empty() {
-                let now = chrono::Local::now();
-                let current_hour = now.hour();
+        let now = Utc::now().timestamp();
+        for group in self.repo.get_account_groups().await? {
+            if !self.should_notify_threshold(&group.last_notified_at, now - 86400) {
+                continue;
+            }
 
-                // Parse hours (format: "HH:MM")
-                if let (Some(start_hour), Some(end_hour)) = (
-                    start.split(':').next().and_then(|h| h.parse::<u32>().ok()),
-                    end.split(':').next().and_then(|h| h.parse::<u32>().ok()),
-                ) {
-                    if start_hour < end_hour {
-                        return Ok(current_hour >= start_hour && current_hour < end_hour);
-                    } else {
-                        return Ok(current_hour >= start_hour || current_hour < end_hour);
-                    }
+            let summary = self.repo.get_group_summary(&group.id).await?;
+            let quota_breach = match (group.quota_threshold_percent, summary.percent_used) {
+                (Some(threshold), Some(percent)) if percent >= threshold => {
+                    Some(format!("{:.1}% of combined quota used", percent))
+                }
+                _ => None,
+            };
+            let cost_breach = match group.cost_threshold_usd {
+                Some(threshold) if summary.cost_usd >= threshold => {
+                    Some(format!("${:.2} combined cost (threshold ${:.2})", summary.cost_usd, threshold))
                 }
+                _ => None,
+            };
+
+            let reasons: Vec<String> = quota_breach.into_iter().chain(cost_breach).collect();
+            if reasons.is_empty() {
+                continue;
+            }
+
+            self.notify(
+                &settings,
+                Alert {
+                    summary: "Group Threshold Reached".to_string(),
+                    body: format!("Group {}: {}", group.name, reasons.join(", ")),
+                    percentage: summary.percent_used,
+                    cost_usd: Some(summary.cost_usd),
+                    threshold: group.quota_threshold_percent,
+                    ..Default::default()
+                },
+                Urgency::Normal,
+            ).await?;
+            self.repo.set_group_notified(&group.id, now).await?;
+            info!("Sent threshold notification for group {}", group.name);
+        }
+
+        Ok(())
+    }
+
+    fn should_notify_threshold(&self, last_notified: &Option<i64>, threshold: i64) -> bool {
+        match last_notified {
+            Some(time) => *time < threshold,
+            None => true,
+        }
+    }
+
+    fn is_quiet_hours(&self, settings: &Settings) -> bool {
+        QuietHours::load(settings).is_some_and(|quiet_hours| quiet_hours.is_quiet(Utc::now()))
+    }
+
+    // Records the notification in the history, then sends it to the channels its
+    // routing rules pick: the desktop, webhooks, email and push targets
+    async fn notify(&self, settings: &Settings, alert: Alert, urgency: Urgency) -> Result<()> {
+        self.notify_as(settings, alert, urgency.into()).await
+    }
+
+    async fn notify_as(&self, settings: &Settings, mut alert: Alert, presentation: Presentation) -> Result<()> {
+        alert.timestamp = Utc::now().timestamp();
+        if let Some(account_id) = &alert.account_id {
+            // Muted and snoozed accounts get no budget alerts either
+            let prefs = self.repo.get_account_notification_prefs(account_id).await?;
+            if prefs.is_some_and(|prefs| prefs.is_silenced(alert.timestamp)) {
+                return Ok(());
             }
+            alert.provider = self.repo.get_account(account_id).await?.map(|account| account.provider);
         }
 
-        Ok(false)
+        self.repo.insert_notification(alert.account_id.as_deref(), alert.threshold, &alert.summary, &alert.body, alert.timestamp).await?;
+
+        let channels = routing::channels(&self.repo, &alert).await?;
+        if channels.contains("desktop") {
+            self.send_notification(&alert.summary, &alert.body, &presentation)?;
+        }
+        if channels.contains("webhook") {
+            self.webhooks.send(&self.repo, &self.crypto, settings, &alert).await;
+        }
+        if channels.contains("email") {
+            email::send(&self.repo, &self.crypto, settings, &alert).await;
+        }
+        if channels.contains("push") {
+            self.push.send(&self.repo, settings, &alert, presentation.urgency).await;
+        }
+        Ok(())
     }
 
-    fn send_notification(&self, summary: &str, body: &str, urgency: notify_rust::Urgency) -> Result<()> {
-        match Notification::new()
+    fn send_notification(&self, summary: &str, body: &str, presentation: &Presentation) -> Result<()> {
+        let mut notification = Notification::new();
+        notification
             .summary(summary)
             .body(body)
-            .urgency(urgency)
-            .timeout(notify_rust::Timeout::Milliseconds(6000))
-            .show()
-        {
+            .urgency(presentation.urgency)
+            .timeout(match presentation.timeout_seconds {
+                0 => Timeout::Never,
+                seconds => Timeout::Milliseconds(seconds * 1000),
+            });
+        if let Some(sound) = &presentation.sound {
+            notification.sound_name(sound);
+        }
+
+        match notification.show() {
             Ok(_) => Ok(()),
             Err(e) => {
                 warn!("Failed to send notification: {}", e);
@@ -151,3 +612,27 @@ impl Notifier {
         }
     }
 }
+
+// The desktop presentation set for the warning (75), caution (90) or critical (95) alert
+fn threshold_presentation(settings: &Settings, level: u32) -> Presentation {
+    let (urgency, timeout_seconds, sound) = match level {
+        95 => (&settings.threshold_95_urgency, settings.threshold_95_timeout_seconds, &settings.threshold_95_sound),
+        90 => (&settings.threshold_90_urgency, settings.threshold_90_timeout_seconds, &settings.threshold_90_sound),
+        _ => (&settings.threshold_75_urgency, settings.threshold_75_timeout_seconds, &settings.threshold_75_sound),
+    };
+    let urgency = match urgency.as_str() {
+        "low" => Urgency::Low,
+        "critical" => Urgency::Critical,
+        _ => Urgency::Normal,
+    };
+
+    Presentation { urgency, timeout_seconds, sound: sound.clone() }
+}
+
+fn budget_label(period: &str) -> &'static str {
+    match period {
+        "daily" => "daily budget",
+        "weekly" => "weekly budget",
+        _ => "monthly budget",
+    }
+}
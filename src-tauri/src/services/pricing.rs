@@ -0,0 +1,236 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::{Arc, PoisonError, RwLock};
+use serde::{Deserialize, Serialize};
+use crate::db::{Repository, PricingOverride};
+use crate::services::Settings;
+use crate::error::{QuonitorError, Result};
+use tracing::{info, warn};
+
+// Shipped with the app so costs work offline and before the first refresh
+const BUNDLED_PRICING: &str = include_str!("pricing.json");
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub struct Price {
+    // Per million tokens in USD
+    #[serde(default)]
+    pub input: f64,
+    #[serde(default)]
+    pub output: f64,
+    // Cache-hit input and reasoning output rates; fall back to input/output when unset
+    #[serde(default)]
+    pub cached_input: Option<f64>,
+    #[serde(default)]
+    pub reasoning: Option<f64>,
+    // Per minute in USD, for GPU compute or transcribed audio
+    #[serde(default)]
+    pub per_minute: f64,
+    // Per generated image in USD
+    #[serde(default)]
+    pub per_image: f64,
+    // Per million fine-tuning training tokens in USD
+    #[serde(default)]
+    pub training: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct PriceRule {
+    #[serde(rename = "match")]
+    pattern: String,
+    #[serde(flatten)]
+    price: Price,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct ProviderPricing {
+    // Checked in order; the first rule whose pattern the model name contains wins
+    #[serde(default)]
+    models: Vec<PriceRule>,
+    #[serde(default)]
+    default: Option<Price>,
+    // Fraction taken off for asynchronous batch requests, e.g. 0.5
+    #[serde(default)]
+    batch_discount: Option<f64>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct PricingTable {
+    #[serde(default)]
+    updated: Option<String>,
+    providers: HashMap<String, ProviderPricing>,
+}
+
+impl PricingTable {
+    fn bundled() -> Self {
+        serde_json::from_str(BUNDLED_PRICING).expect("Bundled pricing table is invalid")
+    }
+
+    // Lays `remote` over this table. A remote rule replaces the rule with the same
+    // pattern; new patterns go first so they win over broader bundled ones.
+    fn merged_with(mut self, remote: PricingTable) -> Self {
+        for (name, remote) in remote.providers {
+            let pricing = self.providers.entry(name).or_default();
+            let mut added = Vec::new();
+            for rule in remote.models {
+                match pricing.models.iter_mut().find(|r| r.pattern == rule.pattern) {
+                    Some(existing) => *existing = rule,
+                    None => added.push(rule),
+                }
+            }
+            added.append(&mut pricing.models);
+            pricing.models = added;
+            if remote.default.is_some() {
+                pricing.default = remote.default;
+            }
+            if remote.batch_discount.is_some() {
+                pricing.batch_discount = remote.batch_discount;
+            }
+        }
+        if remote.updated.is_some() {
+            self.updated = remote.updated;
+        }
+        self
+    }
+}
+
+pub struct PricingService {
+    repo: Arc<Repository>,
+    client: reqwest::Client,
+    cache_path: PathBuf,
+    table: RwLock<PricingTable>,
+    overrides: RwLock<Vec<PricingOverride>>,
+}
+
+impl PricingService {
+    pub fn new(repo: Arc<Repository>, cache_path: PathBuf) -> Self {
+        // Layer the last table fetched from the remote URL on top, if it's still readable
+        let cached = std::fs::read_to_string(&cache_path)
+            .ok()
+            .and_then(|json| serde_json::from_str::<PricingTable>(&json).ok());
+        let table = match cached {
+            Some(cached) => PricingTable::bundled().merged_with(cached),
+            None => PricingTable::bundled(),
+        };
+
+        Self {
+            repo,
+            client: reqwest::Client::new(),
+            cache_path,
+            table: RwLock::new(table),
+            overrides: RwLock::new(Vec::new()),
+        }
+    }
+
+    pub fn token_cost(&self, provider: &str, model: &str, input_tokens: i64, output_tokens: i64) -> f64 {
+        self.token_cost_detailed(provider, model, input_tokens, output_tokens, 0, 0)
+    }
+
+    // Cached input tokens are a subset of input_tokens, and reasoning tokens a
+    // subset of output_tokens; each subset is billed at its own rate
+    pub fn token_cost_detailed(
+        &self,
+        provider: &str,
+        model: &str,
+        input_tokens: i64,
+        output_tokens: i64,
+        cached_input_tokens: i64,
+        reasoning_tokens: i64,
+    ) -> f64 {
+        let price = self.price(provider, model);
+        let per_token = |tokens: i64, rate: f64| (tokens.max(0) as f64 / 1_000_000.0) * rate;
+
+        let input_cost = per_token(input_tokens - cached_input_tokens, price.input)
+            + per_token(cached_input_tokens, price.cached_input.unwrap_or(price.input));
+        let output_cost = per_token(output_tokens - reasoning_tokens, price.output)
+            + per_token(reasoning_tokens, price.reasoning.unwrap_or(price.output));
+
+        input_cost + output_cost
+    }
+
+    pub fn minute_cost(&self, provider: &str, instance_type: &str, minutes: f64) -> f64 {
+        minutes * self.price(provider, instance_type).per_minute
+    }
+
+    pub fn batch_multiplier(&self, provider: &str) -> f64 {
+        let table = self.table.read().unwrap_or_else(PoisonError::into_inner);
+        let discount = table.providers.get(provider).and_then(|p| p.batch_discount).unwrap_or(0.0);
+        1.0 - discount.clamp(0.0, 1.0)
+    }
+
+    pub fn training_cost(&self, provider: &str, model: &str, trained_tokens: i64) -> f64 {
+        (trained_tokens as f64 / 1_000_000.0) * self.price(provider, model).training
+    }
+
+    pub fn image_cost(&self, provider: &str, model: &str, images: i64) -> f64 {
+        images as f64 * self.price(provider, model).per_image
+    }
+
+    // User overrides take precedence over the bundled/remote table
+    pub fn price(&self, provider: &str, model: &str) -> Price {
+        let overrides = self.overrides.read().unwrap_or_else(PoisonError::into_inner);
+        if let Some(o) = overrides.iter().find(|o| o.provider == provider && model.contains(&o.model)) {
+            return Price {
+                input: o.input_price,
+                output: o.output_price,
+                per_minute: o.per_minute_price,
+                ..Default::default()
+            };
+        }
+        drop(overrides);
+
+        let table = self.table.read().unwrap_or_else(PoisonError::into_inner);
+        let Some(pricing) = table.providers.get(provider) else {
+            return Price::default();
+        };
+
+        pricing.models.iter()
+            .find(|rule| model.contains(&rule.pattern))
+            .map(|rule| rule.price)
+            .unwrap_or(pricing.default.unwrap_or_default())
+    }
+
+    pub async fn reload_overrides(&self) -> Result<()> {
+        let mut overrides = self.repo.get_pricing_overrides().await?;
+        // Most specific pattern first, so "gpt-4o-mini" beats "gpt-4o"
+        overrides.sort_by(|a, b| b.model.len().cmp(&a.model.len()));
+
+        *self.overrides.write().unwrap_or_else(PoisonError::into_inner) = overrides;
+        Ok(())
+    }
+
+    // Fetches the table from the `pricing_url` setting and caches it on disk.
+    // Returns false when no URL is configured.
+    pub async fn refresh(&self) -> Result<bool> {
+        let Some(url) = Settings::load(&self.repo).await?.pricing_url else {
+            return Ok(false);
+        };
+
+        let response = self.client.get(&url).send().await?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            return Err(QuonitorError::Provider(format!(
+                "Pricing update failed ({})",
+                status
+            )));
+        }
+
+        let json = response.text().await?;
+        let table: PricingTable = serde_json::from_str(&json)?;
+        if table.providers.is_empty() {
+            return Err(QuonitorError::Config("Remote pricing table has no providers".to_string()));
+        }
+
+        if let Err(e) = std::fs::write(&self.cache_path, &json) {
+            warn!("Failed to cache pricing table: {}", e);
+        }
+
+        info!(
+            "Updated pricing table ({})",
+            table.updated.as_deref().unwrap_or("undated")
+        );
+        *self.table.write().unwrap_or_else(PoisonError::into_inner) = PricingTable::bundled().merged_with(table);
+
+        Ok(true)
+    }
+}
@@ -0,0 +1,168 @@
+use std::time::Duration;
+use notify_rust::Urgency;
+use crate::db::Repository;
+use crate::error::{QuonitorError, Result};
+use crate::services::notifier::Alert;
+use crate::services::Settings;
+use tracing::{info, warn};
+
+// An account's own push targets, replacing `Settings::push_targets` for its alerts
+pub const ACCOUNT_SETTING: &str = "push_targets";
+const TIMEOUT: Duration = Duration::from_secs(10);
+const PUSHOVER_URL: &str = "https://api.pushover.net/1/messages.json";
+
+// Push targets are one per line (or comma separated), as the service followed by its
+// parameters:
+//   ntfy <topic URL> [access token]
+//   gotify <server URL> <app token>
+//   pushover <user key> <app token>
+#[derive(Debug, Clone, PartialEq)]
+pub enum PushTarget {
+    Ntfy { url: String, token: Option<String> },
+    Gotify { url: String, token: String },
+    Pushover { user: String, token: String },
+}
+
+pub fn parse(targets: &str) -> Result<Vec<PushTarget>> {
+    targets
+        .split(['\n', ','])
+        .map(str::trim)
+        .filter(|target| !target.is_empty())
+        .map(parse_target)
+        .collect()
+}
+
+fn parse_target(target: &str) -> Result<PushTarget> {
+    let invalid = |reason: &str| QuonitorError::Config(format!("Invalid push target \"{}\": {}", target, reason));
+    let parts: Vec<&str> = target.split_whitespace().collect();
+    let url = |url: &str| {
+        if url.starts_with("https://") || url.starts_with("http://") {
+            Ok(url.trim_end_matches('/').to_string())
+        } else {
+            Err(invalid("the URL must be http(s)"))
+        }
+    };
+
+    match parts.as_slice() {
+        ["ntfy", topic] => Ok(PushTarget::Ntfy { url: url(topic)?, token: None }),
+        ["ntfy", topic, token] => Ok(PushTarget::Ntfy { url: url(topic)?, token: Some(token.to_string()) }),
+        ["gotify", server, token] => Ok(PushTarget::Gotify { url: url(server)?, token: token.to_string() }),
+        ["pushover", user, token] => Ok(PushTarget::Pushover { user: user.to_string(), token: token.to_string() }),
+        [service, ..] if !["ntfy", "gotify", "pushover"].contains(service) => Err(invalid("expected ntfy, gotify or pushover")),
+        _ => Err(invalid("wrong number of parameters")),
+    }
+}
+
+// Sends alerts to ntfy, Gotify and Pushover, mapping the desktop urgency to each
+// service's priority. Failures are logged and don't hold up other channels.
+pub struct Push {
+    client: reqwest::Client,
+}
+
+impl Push {
+    pub fn new() -> Self {
+        let client = reqwest::Client::builder()
+            .timeout(TIMEOUT)
+            .build()
+            .unwrap_or_default();
+
+        Self { client }
+    }
+
+    pub async fn send(&self, repo: &Repository, settings: &Settings, alert: &Alert, urgency: Urgency) {
+        for target in targets(repo, settings, alert.account_id.as_deref()).await {
+            if let Err(e) = self.send_to(&target, alert, urgency).await {
+                warn!("Failed to push alert: {}", e);
+            }
+        }
+    }
+
+    async fn send_to(&self, target: &PushTarget, alert: &Alert, urgency: Urgency) -> Result<()> {
+        let request = match target {
+            PushTarget::Ntfy { url, token } => {
+                let priority = match urgency {
+                    Urgency::Low => "2",
+                    Urgency::Normal => "3",
+                    Urgency::Critical => "5",
+                };
+                let request = self.client
+                    .post(url)
+                    .header("Title", &alert.summary)
+                    .header("Priority", priority)
+                    .header("Tags", "chart_with_upwards_trend")
+                    .body(alert.body.clone());
+                match token {
+                    Some(token) => request.bearer_auth(token),
+                    None => request,
+                }
+            }
+            PushTarget::Gotify { url, token } => {
+                let priority = match urgency {
+                    Urgency::Low => 2,
+                    Urgency::Normal => 5,
+                    Urgency::Critical => 8,
+                };
+                self.client
+                    .post(format!("{}/message", url))
+                    .header("X-Gotify-Key", token)
+                    .json(&serde_json::json!({
+                        "title": alert.summary,
+                        "message": alert.body,
+                        "priority": priority,
+                    }))
+            }
+            PushTarget::Pushover { user, token } => {
+                let priority = match urgency {
+                    Urgency::Low => "-1",
+                    Urgency::Normal => "0",
+                    Urgency::Critical => "1",
+                };
+                self.client.post(PUSHOVER_URL).form(&[
+                    ("token", token.as_str()),
+                    ("user", user.as_str()),
+                    ("title", alert.summary.as_str()),
+                    ("message", alert.body.as_str()),
+                    ("priority", priority),
+                ])
+            }
+        };
+
+        let response = request.send().await?;
+        if !response.status().is_success() {
+            return Err(QuonitorError::Provider(format!("{} answered {}", service(target), response.status())));
+        }
+        info!("Pushed alert to {}", service(target));
+        Ok(())
+    }
+}
+
+fn service(target: &PushTarget) -> &'static str {
+    match target {
+        PushTarget::Ntfy { .. } => "ntfy",
+        PushTarget::Gotify { .. } => "Gotify",
+        PushTarget::Pushover { .. } => "Pushover",
+    }
+}
+
+// The account's own targets when it has any, otherwise the global ones. Invalid targets
+// (stored before validation, or edited by hand) are skipped with a warning.
+async fn targets(repo: &Repository, settings: &Settings, account_id: Option<&str>) -> Vec<PushTarget> {
+    let account_targets = match account_id {
+        Some(account_id) => match repo.get_account_settings(account_id).await {
+            Ok(mut values) => values.remove(ACCOUNT_SETTING).filter(|targets| !targets.trim().is_empty()),
+            Err(e) => {
+                warn!("Failed to load push targets of account {}: {}", account_id, e);
+                None
+            }
+        },
+        None => None,
+    };
+
+    let Some(targets) = account_targets.or_else(|| settings.push_targets.clone()) else {
+        return Vec::new();
+    };
+    parse(&targets).unwrap_or_else(|e| {
+        warn!("Ignoring push targets: {}", e);
+        Vec::new()
+    })
+}
@@ -0,0 +1,216 @@
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use chrono::{DateTime, Datelike, Local, NaiveTime, TimeZone, Utc, Weekday};
+use chrono_tz::Tz;
+use crate::error::{QuonitorError, Result};
+use crate::services::Settings;
+use crate::services::settings::parse_time;
+use tracing::warn;
+
+const DAYS: [Weekday; 7] = [
+    Weekday::Mon, Weekday::Tue, Weekday::Wed, Weekday::Thu, Weekday::Fri, Weekday::Sat, Weekday::Sun,
+];
+
+// (start, end) "HH:MM" times. A range ending before its start runs past midnight
+// into the next day and belongs to the day it starts on; one starting and ending at the
+// same time covers the whole day.
+type Range = (NaiveTime, NaiveTime);
+
+// When alerts are held back: `Settings::quiet_hours_start`-`end` every day, except on
+// days `Settings::quiet_hours_weekdays` gives ranges of their own, in
+// `Settings::quiet_hours_timezone` or else local time
+pub struct QuietHours {
+    default: Option<Range>,
+    days: HashMap<Weekday, Vec<Range>>,
+    timezone: Option<Tz>,
+}
+
+impl QuietHours {
+    pub fn parse(settings: &Settings) -> Result<Self> {
+        let default = match (&settings.quiet_hours_start, &settings.quiet_hours_end) {
+            (Some(start), Some(end)) => Some((parse_time("Quiet hours start", start)?, parse_time("Quiet hours end", end)?)),
+            (None, None) => None,
+            _ => {
+                return Err(QuonitorError::Config(
+                    "Quiet hours need both a start and an end".to_string(),
+                ));
+            }
+        };
+        let days = match &settings.quiet_hours_weekdays {
+            Some(weekdays) => parse_weekdays(weekdays)?,
+            None => HashMap::new(),
+        };
+        let timezone = match &settings.quiet_hours_timezone {
+            Some(timezone) => Some(timezone.parse::<Tz>()
+                .map_err(|_| QuonitorError::Config(format!("Unknown timezone: {}", timezone)))?),
+            None => None,
+        };
+
+        Ok(Self { default, days, timezone })
+    }
+
+    // Quiet hours that don't parse (settings written before validation, or edited by
+    // hand) are ignored with a warning rather than silencing every alert
+    pub fn load(settings: &Settings) -> Option<Self> {
+        match Self::parse(settings) {
+            Ok(quiet_hours) => Some(quiet_hours),
+            Err(e) => {
+                warn!("Ignoring quiet hours: {}", e);
+                None
+            }
+        }
+    }
+
+    pub fn is_quiet(&self, now: DateTime<Utc>) -> bool {
+        match self.timezone {
+            Some(timezone) => self.is_quiet_at(timezone.from_utc_datetime(&now.naive_utc()).naive_local()),
+            None => self.is_quiet_at(Local.from_utc_datetime(&now.naive_utc()).naive_local()),
+        }
+    }
+
+    fn is_quiet_at(&self, at: chrono::NaiveDateTime) -> bool {
+        let time = at.time();
+        let today = at.weekday();
+
+        // Today's ranges from their start, and yesterday's that run past midnight
+        self.ranges(today).iter().any(|&(start, end)| match start.cmp(&end) {
+            Ordering::Equal => true,
+            Ordering::Less => start <= time && time < end,
+            Ordering::Greater => time >= start,
+        }) || self.ranges(today.pred()).iter().any(|&(start, end)| start > end && time < end)
+    }
+
+    fn ranges(&self, day: Weekday) -> &[Range] {
+        match self.days.get(&day) {
+            Some(ranges) => ranges,
+            None => self.default.as_slice(),
+        }
+    }
+}
+
+// Entries separated by semicolons or newlines, each a day or day range and the ranges
+// for it: "mon-fri 22:00-07:00; sat 00:00-00:00, 12:00-14:00". "off" gives a day no
+// quiet hours.
+fn parse_weekdays(value: &str) -> Result<HashMap<Weekday, Vec<Range>>> {
+    let mut days: HashMap<Weekday, Vec<Range>> = HashMap::new();
+
+    for entry in value.split([';', '\n']).map(str::trim).filter(|entry| !entry.is_empty()) {
+        let invalid = || QuonitorError::Config(format!("Quiet hours \"{}\" must look like mon-fri 22:00-07:00", entry));
+        let (day_spec, ranges) = entry.split_once(char::is_whitespace).ok_or_else(invalid)?;
+
+        let ranges: Vec<Range> = if ranges.trim().eq_ignore_ascii_case("off") {
+            Vec::new()
+        } else {
+            ranges
+                .split(',')
+                .map(str::trim)
+                .filter(|range| !range.is_empty())
+                .map(|range| {
+                    let (start, end) = range.split_once('-').ok_or_else(invalid)?;
+                    Ok((parse_time("Quiet hours start", start)?, parse_time("Quiet hours end", end)?))
+                })
+                .collect::<Result<_>>()?
+        };
+
+        for day in parse_days(day_spec).ok_or_else(invalid)? {
+            days.entry(day).or_default().extend(ranges.iter().copied());
+        }
+    }
+
+    Ok(days)
+}
+
+// "mon", or a range such as "mon-fri" or "fri-mon" that wraps past Sunday
+fn parse_days(spec: &str) -> Option<Vec<Weekday>> {
+    let day = |name: &str| name.trim().parse::<Weekday>().ok();
+    let (first, last) = match spec.split_once('-') {
+        Some((first, last)) => (day(first)?, day(last)?),
+        None => (day(spec)?, day(spec)?),
+    };
+
+    let start = first.num_days_from_monday() as usize;
+    let count = (last.num_days_from_monday() as usize + 7 - start) % 7 + 1;
+    Some((0..count).map(|offset| DAYS[(start + offset) % 7]).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{NaiveDate, NaiveDateTime};
+
+    fn time(value: &str) -> NaiveTime {
+        NaiveTime::parse_from_str(value, "%H:%M").unwrap()
+    }
+
+    fn at(day: Weekday, value: &str) -> NaiveDateTime {
+        NaiveDate::from_isoywd_opt(2026, 10, day).unwrap().and_time(time(value))
+    }
+
+    fn quiet_hours(default: Option<(&str, &str)>, weekdays: &str) -> QuietHours {
+        QuietHours {
+            default: default.map(|(start, end)| (time(start), time(end))),
+            days: parse_weekdays(weekdays).unwrap(),
+            timezone: None,
+        }
+    }
+
+    #[test]
+    fn range_crossing_midnight() {
+        let quiet = quiet_hours(Some(("22:00", "07:00")), "");
+
+        assert!(!quiet.is_quiet_at(at(Weekday::Wed, "21:59")));
+        assert!(quiet.is_quiet_at(at(Weekday::Wed, "22:00")));
+        assert!(quiet.is_quiet_at(at(Weekday::Wed, "23:30")));
+        assert!(quiet.is_quiet_at(at(Weekday::Thu, "00:00")));
+        assert!(quiet.is_quiet_at(at(Weekday::Thu, "06:59")));
+        assert!(!quiet.is_quiet_at(at(Weekday::Thu, "07:00")));
+        assert!(!quiet.is_quiet_at(at(Weekday::Thu, "12:00")));
+    }
+
+    #[test]
+    fn previous_day_spills_over() {
+        // Only Friday has quiet hours; they carry into Saturday morning
+        let quiet = quiet_hours(None, "fri 23:00-02:00");
+
+        assert!(quiet.is_quiet_at(at(Weekday::Sat, "01:30")));
+        assert!(!quiet.is_quiet_at(at(Weekday::Sat, "02:00")));
+        assert!(!quiet.is_quiet_at(at(Weekday::Fri, "01:30")));
+        assert!(!quiet.is_quiet_at(at(Weekday::Sat, "23:30")));
+    }
+
+    #[test]
+    fn off_overrides_default() {
+        let quiet = quiet_hours(Some(("22:00", "07:00")), "sun off");
+
+        assert!(!quiet.is_quiet_at(at(Weekday::Sun, "23:00")));
+        // Saturday night still runs into Sunday morning
+        assert!(quiet.is_quiet_at(at(Weekday::Sun, "03:00")));
+        assert!(!quiet.is_quiet_at(at(Weekday::Mon, "03:00")));
+        assert!(quiet.is_quiet_at(at(Weekday::Mon, "23:00")));
+    }
+
+    #[test]
+    fn equal_start_and_end_is_all_day() {
+        let quiet = quiet_hours(None, "sat 00:00-00:00; sun 09:00-09:00");
+
+        assert!(quiet.is_quiet_at(at(Weekday::Sat, "00:00")));
+        assert!(quiet.is_quiet_at(at(Weekday::Sat, "13:00")));
+        assert!(quiet.is_quiet_at(at(Weekday::Sun, "08:59")));
+        assert!(!quiet.is_quiet_at(at(Weekday::Mon, "13:00")));
+    }
+
+    #[test]
+    fn day_ranges() {
+        assert_eq!(parse_days("wed"), Some(vec![Weekday::Wed]));
+        assert_eq!(
+            parse_days("mon-fri"),
+            Some(vec![Weekday::Mon, Weekday::Tue, Weekday::Wed, Weekday::Thu, Weekday::Fri]),
+        );
+        assert_eq!(
+            parse_days("fri-mon"),
+            Some(vec![Weekday::Fri, Weekday::Sat, Weekday::Sun, Weekday::Mon]),
+        );
+        assert_eq!(parse_days("sun-sun"), Some(vec![Weekday::Sun]));
+        assert_eq!(parse_days("fri-someday"), None);
+    }
+}
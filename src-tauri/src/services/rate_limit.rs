@@ -0,0 +1,88 @@
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+use tokio::time;
+use crate::db::Repository;
+use crate::services::Settings;
+
+// Fetches that may go out back to back before the rate applies
+const BURST: f64 = 2.0;
+
+struct Bucket {
+    tokens: f64,
+    refilled_at: Instant,
+}
+
+// Token bucket per provider, so several accounts at one provider (e.g. under the same
+// organization) don't fire their usage requests together and trip its rate limits.
+// Counts fetches; a provider making several requests per fetch is limited as one.
+pub struct RateLimiter {
+    buckets: Mutex<HashMap<String, Bucket>>,
+    limits: Mutex<Limits>,
+}
+
+#[derive(Default)]
+struct Limits {
+    global: u32,
+    overrides: HashMap<String, u32>,
+}
+
+impl RateLimiter {
+    pub fn new() -> Self {
+        Self {
+            buckets: Mutex::new(HashMap::new()),
+            limits: Mutex::new(Limits { global: Settings::default().rate_limit_per_minute, overrides: HashMap::new() }),
+        }
+    }
+
+    // Picks up `Settings::rate_limit_per_minute` and its per-provider overrides
+    pub async fn reload(&self, repo: &Repository) {
+        let settings = match Settings::load(repo).await {
+            Ok(settings) => settings,
+            Err(e) => {
+                tracing::warn!("Failed to load rate limits: {}", e);
+                return;
+            }
+        };
+
+        let overrides = settings.provider_overrides.into_iter()
+            .filter_map(|(provider, o)| Some((provider, o.rate_limit_per_minute?)))
+            .collect();
+        *self.limits.lock().await = Limits { global: settings.rate_limit_per_minute, overrides };
+    }
+
+    // Waits for the provider's bucket to have a token. A limit of 0 disables limiting.
+    pub async fn acquire(&self, provider: &str) {
+        let per_minute = {
+            let limits = self.limits.lock().await;
+            limits.overrides.get(provider).copied().unwrap_or(limits.global)
+        };
+        if per_minute == 0 {
+            return;
+        }
+        let per_second = per_minute as f64 / 60.0;
+
+        loop {
+            let wait = {
+                let mut buckets = self.buckets.lock().await;
+                let now = Instant::now();
+                let bucket = buckets
+                    .entry(provider.to_string())
+                    .or_insert(Bucket { tokens: BURST, refilled_at: now });
+
+                let elapsed = now.duration_since(bucket.refilled_at).as_secs_f64();
+                bucket.tokens = (bucket.tokens + elapsed * per_second).min(BURST);
+                bucket.refilled_at = now;
+
+                if bucket.tokens >= 1.0 {
+                    bucket.tokens -= 1.0;
+                    return;
+                }
+                Duration::from_secs_f64((1.0 - bucket.tokens) / per_second)
+            };
+
+            tracing::debug!("Rate limiting {} fetches, waiting {:?}", provider, wait);
+            time::sleep(wait).await;
+        }
+    }
+}
@@ -0,0 +1,195 @@
+use std::collections::HashMap;
+use chrono::{Months, NaiveDate, Utc};
+use serde::Serialize;
+use crate::db::{AccountSpend, DailyUsage, ModelSpendDetail, Repository};
+use crate::error::{QuonitorError, Result};
+use crate::services::export::escape;
+
+#[derive(Debug, Clone, Copy)]
+pub enum Format {
+    Json,
+    Csv,
+    Html,
+}
+
+impl Format {
+    pub fn parse(value: &str) -> Result<Self> {
+        match value.to_ascii_lowercase().as_str() {
+            "json" => Ok(Format::Json),
+            "csv" => Ok(Format::Csv),
+            "html" => Ok(Format::Html),
+            _ => Err(QuonitorError::Config(format!("Unknown report format: {}", value))),
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            Format::Json => "json",
+            Format::Csv => "csv",
+            Format::Html => "html",
+        }
+    }
+}
+
+// Usage for one UTC calendar month, broken down by account, model and day, for expense
+// reports. Like the dashboard, it sums each account's closing snapshot per day.
+#[derive(Debug, Clone, Serialize)]
+pub struct Statement {
+    // YYYY-MM
+    pub period: String,
+    pub since: i64,
+    pub until: i64,
+    pub generated_at: i64,
+    pub total_tokens_input: i64,
+    pub total_tokens_output: i64,
+    pub total_cost_usd: f64,
+    pub accounts: Vec<AccountSpend>,
+    pub models: Vec<ModelSpendDetail>,
+    pub daily: Vec<DailyUsage>,
+}
+
+// `period` is a month as YYYY-MM; no accounts means every account
+pub async fn build(repo: &Repository, period: &str, account_ids: &[String]) -> Result<Statement> {
+    let (since, until) = month_range(period)?;
+
+    let summary = repo.get_usage_summary(account_ids, since, until).await?;
+    let accounts = repo.get_account_spend(account_ids, since, until).await?;
+    let models = repo.get_model_spend(account_ids, since, until).await?;
+
+    Ok(Statement {
+        period: period.trim().to_string(),
+        since,
+        until,
+        generated_at: Utc::now().timestamp(),
+        total_tokens_input: summary.total_tokens_input,
+        total_tokens_output: summary.total_tokens_output,
+        total_cost_usd: summary.total_cost_usd,
+        accounts,
+        models,
+        daily: summary.daily,
+    })
+}
+
+pub fn render(statement: &Statement, format: Format) -> Result<String> {
+    Ok(match format {
+        Format::Json => serde_json::to_string_pretty(statement)?,
+        Format::Csv => csv(statement),
+        Format::Html => html(statement),
+    })
+}
+
+// [start, end) of a YYYY-MM month in UTC
+fn month_range(period: &str) -> Result<(i64, i64)> {
+    let invalid = || QuonitorError::Config(format!("Report period \"{}\" must be a month like 2024-03", period));
+    let start = NaiveDate::parse_from_str(&format!("{}-01", period.trim()), "%Y-%m-%d").map_err(|_| invalid())?;
+    let end = start.checked_add_months(Months::new(1)).ok_or_else(invalid)?;
+
+    let timestamp = |date: NaiveDate| date.and_hms_opt(0, 0, 0).map(|t| t.and_utc().timestamp()).unwrap_or_default();
+    Ok((timestamp(start), timestamp(end)))
+}
+
+// One table, with the section each row belongs to in the first column
+fn csv(statement: &Statement) -> String {
+    let names = account_names(statement);
+    let mut csv = String::from("section,date,account_id,account_name,provider,model,tokens_input,tokens_output,request_count,cost_usd\r\n");
+    let mut line = |fields: [&str; 10]| {
+        let fields: Vec<String> = fields.iter().map(|f| escape(f)).collect();
+        csv.push_str(&fields.join(","));
+        csv.push_str("\r\n");
+    };
+
+    for account in &statement.accounts {
+        line([
+            "account", "", &account.account_id, &account.account_name, &account.provider, "",
+            &account.tokens_input.to_string(), &account.tokens_output.to_string(), "", &format!("{:.6}", account.cost_usd),
+        ]);
+    }
+    for model in &statement.models {
+        let (name, provider) = names.get(model.account_id.as_str()).copied().unwrap_or(("", ""));
+        line([
+            "model", "", &model.account_id, name, provider, &model.model_name,
+            &model.tokens_input.to_string(), &model.tokens_output.to_string(),
+            &model.request_count.to_string(), &format!("{:.6}", model.cost_usd),
+        ]);
+    }
+    for day in &statement.daily {
+        line([
+            "day", &day.day, "", "", "", "",
+            &day.tokens_input.to_string(), &day.tokens_output.to_string(), "", &format!("{:.6}", day.cost_usd),
+        ]);
+    }
+    line([
+        "total", &statement.period, "", "", "", "",
+        &statement.total_tokens_input.to_string(), &statement.total_tokens_output.to_string(), "",
+        &format!("{:.6}", statement.total_cost_usd),
+    ]);
+
+    csv
+}
+
+// A standalone page that prints cleanly, for attaching to expense reports
+fn html(statement: &Statement) -> String {
+    let names = account_names(statement);
+    let mut html = format!(
+        "<!DOCTYPE html>\n<html><head><meta charset=\"utf-8\"><title>Quonitor statement {period}</title>\n\
+         <style>body{{font-family:sans-serif;margin:2em;color:#111}}table{{border-collapse:collapse;margin-bottom:2em;width:100%}}\
+         th,td{{border-bottom:1px solid #ddd;padding:4px 8px;text-align:left}}td.n,th.n{{text-align:right}}</style>\n\
+         </head><body>\n<h1>Usage statement {period}</h1>\n<p>Total <strong>${total:.2}</strong> &middot; \
+         {input} input / {output} output tokens &middot; generated {generated}</p>\n",
+        period = html_escape(&statement.period),
+        total = statement.total_cost_usd,
+        input = statement.total_tokens_input,
+        output = statement.total_tokens_output,
+        generated = chrono::DateTime::from_timestamp(statement.generated_at, 0)
+            .map(|t| t.format("%Y-%m-%d %H:%M UTC").to_string())
+            .unwrap_or_default(),
+    );
+
+    html.push_str("<h2>By account</h2>\n<table><tr><th>Account</th><th>Provider</th><th class=\"n\">Input tokens</th><th class=\"n\">Output tokens</th><th class=\"n\">Cost</th></tr>\n");
+    for account in &statement.accounts {
+        html.push_str(&format!(
+            "<tr><td>{}</td><td>{}</td><td class=\"n\">{}</td><td class=\"n\">{}</td><td class=\"n\">${:.2}</td></tr>\n",
+            html_escape(&account.account_name), html_escape(&account.provider),
+            account.tokens_input, account.tokens_output, account.cost_usd,
+        ));
+    }
+    html.push_str("</table>\n");
+
+    html.push_str("<h2>By model</h2>\n<table><tr><th>Account</th><th>Model</th><th class=\"n\">Requests</th><th class=\"n\">Input tokens</th><th class=\"n\">Output tokens</th><th class=\"n\">Cost</th></tr>\n");
+    for model in &statement.models {
+        let (name, _) = names.get(model.account_id.as_str()).copied().unwrap_or((model.account_id.as_str(), ""));
+        html.push_str(&format!(
+            "<tr><td>{}</td><td>{}</td><td class=\"n\">{}</td><td class=\"n\">{}</td><td class=\"n\">{}</td><td class=\"n\">${:.2}</td></tr>\n",
+            html_escape(name), html_escape(&model.model_name),
+            model.request_count, model.tokens_input, model.tokens_output, model.cost_usd,
+        ));
+    }
+    html.push_str("</table>\n");
+
+    html.push_str("<h2>By day</h2>\n<table><tr><th>Date (UTC)</th><th class=\"n\">Input tokens</th><th class=\"n\">Output tokens</th><th class=\"n\">Cost</th></tr>\n");
+    for day in &statement.daily {
+        html.push_str(&format!(
+            "<tr><td>{}</td><td class=\"n\">{}</td><td class=\"n\">{}</td><td class=\"n\">${:.2}</td></tr>\n",
+            html_escape(&day.day), day.tokens_input, day.tokens_output, day.cost_usd,
+        ));
+    }
+    html.push_str("</table>\n</body></html>\n");
+
+    html
+}
+
+// account_id -> (name, provider)
+fn account_names(statement: &Statement) -> HashMap<&str, (&str, &str)> {
+    statement.accounts
+        .iter()
+        .map(|account| (account.account_id.as_str(), (account.account_name.as_str(), account.provider.as_str())))
+        .collect()
+}
+
+fn html_escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
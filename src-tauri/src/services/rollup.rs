@@ -0,0 +1,147 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use chrono::Utc;
+use serde::Deserialize;
+use crate::db::{QuotaSnapshot, Repository};
+use crate::error::Result;
+use tracing::{debug, error};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Granularity {
+    Hour,
+    Day,
+}
+
+impl Granularity {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Granularity::Hour => "hour",
+            Granularity::Day => "day",
+        }
+    }
+
+    pub fn seconds(self) -> i64 {
+        match self {
+            Granularity::Hour => 3600,
+            Granularity::Day => 86400,
+        }
+    }
+
+    // Raw snapshots for the last week, hourly up to a month, daily beyond that
+    pub fn for_range(days: u32) -> Option<Self> {
+        match days {
+            0..=7 => None,
+            8..=31 => Some(Granularity::Hour),
+            _ => Some(Granularity::Day),
+        }
+    }
+}
+
+// Paging and downsampling for the history commands. Pages are `limit` rows from
+// `offset`, oldest first; keep requesting until a page comes back short. With
+// `max_points` the range is thinned to about that many rows before paging.
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+pub struct HistoryPage {
+    pub limit: Option<u32>,
+    #[serde(default)]
+    pub offset: u32,
+    pub max_points: Option<u32>,
+}
+
+impl HistoryPage {
+    // LIMIT and OFFSET for the query. Downsampling needs the whole range, so it pages afterwards.
+    pub fn query_bounds(&self) -> (Option<u32>, u32) {
+        match self.max_points {
+            Some(_) => (None, 0),
+            None => (self.limit, self.offset),
+        }
+    }
+
+    // Downsamples and pages rows fetched with `query_bounds`. Each series (a model, an
+    // API key) keeps its last row per time bucket, like a rollup's closing snapshot,
+    // with the buckets sized so all series together come to about `max_points`.
+    pub fn finish<T>(&self, rows: Vec<T>, series: impl Fn(&T) -> &str, timestamp: impl Fn(&T) -> i64) -> Vec<T> {
+        let Some(max_points) = self.max_points else {
+            return rows;
+        };
+
+        let rows = if rows.len() > max_points as usize {
+            let start = rows.iter().map(&timestamp).min().unwrap_or_default();
+            let end = rows.iter().map(&timestamp).max().unwrap_or_default();
+            let series_count = rows.iter().map(&series).collect::<HashSet<_>>().len();
+            let buckets = (max_points as usize / series_count.max(1)).max(1) as i64;
+            let width = ((end - start) / buckets + 1).max(1);
+
+            let mut last: HashMap<(&str, i64), usize> = HashMap::new();
+            for (i, row) in rows.iter().enumerate() {
+                last.insert((series(row), (timestamp(row) - start) / width), i);
+            }
+            let keep: HashSet<usize> = last.into_values().collect();
+
+            rows.into_iter()
+                .enumerate()
+                .filter(|(i, _)| keep.contains(i))
+                .map(|(_, row)| row)
+                .collect()
+        } else {
+            rows
+        };
+
+        rows.into_iter()
+            .skip(self.offset as usize)
+            .take(self.limit.map_or(usize::MAX, |l| l as usize))
+            .collect()
+    }
+}
+
+pub struct RollupService {
+    repo: Arc<Repository>,
+}
+
+impl RollupService {
+    pub fn new(repo: Arc<Repository>) -> Self {
+        Self { repo }
+    }
+
+    // Re-aggregates from the most recent bucket onwards; the first run covers all history
+    pub async fn run(&self) {
+        for granularity in [Granularity::Hour, Granularity::Day] {
+            match self.rollup(granularity).await {
+                Ok(rows) => debug!("Rolled up {} {} buckets", rows, granularity.as_str()),
+                Err(e) => error!("Failed to roll up {} buckets: {}", granularity.as_str(), e),
+            }
+        }
+    }
+
+    async fn rollup(&self, granularity: Granularity) -> Result<u64> {
+        let since = self.repo.get_latest_rollup_start(granularity.as_str()).await?.unwrap_or(0);
+        Ok(self.repo.rollup_snapshots(granularity.as_str(), granularity.seconds(), since).await?)
+    }
+
+    // History for charts, from rollups when the range is long enough to warrant it
+    pub async fn history(&self, account_id: &str, days: u32, page: HistoryPage) -> Result<Vec<QuotaSnapshot>> {
+        let since = Utc::now().timestamp() - (days as i64 * 86400);
+        let (limit, offset) = page.query_bounds();
+
+        let snapshots = match Granularity::for_range(days) {
+            None => self.repo.get_snapshots_since(account_id, since, limit, offset).await?,
+            Some(granularity) => {
+                let mut snapshots = self.repo.get_rollups_since(account_id, granularity.as_str(), since, limit, offset).await?;
+
+                // The current bucket isn't rolled up until it closes; show where it stands.
+                // It comes after every rollup, so only on the page that isn't full.
+                let current_bucket = Utc::now().timestamp() / granularity.seconds() * granularity.seconds();
+                if !matches!(limit, Some(l) if snapshots.len() >= l as usize) {
+                    if let Some(latest) = self.repo.get_latest_snapshot(account_id).await? {
+                        if latest.timestamp >= current_bucket {
+                            snapshots.push(latest);
+                        }
+                    }
+                }
+                snapshots
+            }
+        };
+
+        Ok(page.finish(snapshots, |s| s.account_id.as_str(), |s| s.timestamp))
+    }
+}
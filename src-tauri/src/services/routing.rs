@@ -0,0 +1,75 @@
+use std::collections::HashSet;
+use crate::db::{NotificationRoute, Repository};
+use crate::error::{QuonitorError, Result};
+use crate::services::notifier::Alert;
+
+pub const CHANNELS: &[&str] = &["desktop", "webhook", "email", "push"];
+
+pub fn validate_channels(channels: &[String]) -> Result<()> {
+    match channels.iter().find(|c| !CHANNELS.contains(&c.as_str())) {
+        Some(channel) => Err(QuonitorError::Config(format!("Unknown notification channel: {}", channel))),
+        None => Ok(()),
+    }
+}
+
+pub fn validate(route: &NotificationRoute) -> Result<()> {
+    if route.name.trim().is_empty() {
+        return Err(QuonitorError::Config("Routing rules need a name".to_string()));
+    }
+    if route.channels.is_empty() {
+        return Err(QuonitorError::Config("Routing rules need at least one channel".to_string()));
+    }
+    validate_channels(&route.channels)?;
+    if let (Some(min), Some(max)) = (route.min_threshold, route.max_threshold) {
+        if min > max {
+            return Err(QuonitorError::Config("The minimum threshold is above the maximum".to_string()));
+        }
+    }
+    Ok(())
+}
+
+// The channels an alert goes to: its account's own selection when it has one, else
+// those of every rule it matches, or all of them when there are no rules or it matches
+// none, so rules only redirect the alerts they name
+pub async fn channels(repo: &Repository, alert: &Alert) -> Result<HashSet<String>> {
+    let all = || CHANNELS.iter().map(|c| c.to_string()).collect();
+
+    if let Some(account_id) = &alert.account_id {
+        let prefs = repo.get_account_notification_prefs(account_id).await?;
+        if let Some(channels) = prefs.and_then(|prefs| prefs.channels) {
+            return Ok(channels.into_iter().collect());
+        }
+    }
+
+    let routes = repo.get_notification_routes().await?;
+    if routes.is_empty() {
+        return Ok(all());
+    }
+
+    let tags = match &alert.account_id {
+        Some(account_id) => repo.get_all_account_tags().await?.remove(account_id).unwrap_or_default(),
+        None => Vec::new(),
+    };
+
+    let matched: HashSet<String> = routes
+        .into_iter()
+        .filter(|route| matches(route, alert, &tags))
+        .flat_map(|route| route.channels)
+        .collect();
+
+    Ok(if matched.is_empty() { all() } else { matched })
+}
+
+// Conditions that are set must all hold; an alert without a threshold or account
+// (e.g. a global budget's) can't match a rule on one
+fn matches(route: &NotificationRoute, alert: &Alert, tags: &[String]) -> bool {
+    let threshold = |bound: Option<f64>, holds: fn(f64, f64) -> bool| match bound {
+        Some(bound) => alert.threshold.is_some_and(|threshold| holds(threshold, bound)),
+        None => true,
+    };
+
+    threshold(route.min_threshold, |threshold, min| threshold >= min)
+        && threshold(route.max_threshold, |threshold, max| threshold <= max)
+        && route.account_id.as_ref().map_or(true, |id| alert.account_id.as_ref() == Some(id))
+        && route.account_tag.as_ref().map_or(true, |tag| tags.contains(tag))
+}
@@ -0,0 +1,251 @@
+use std::collections::{HashMap, HashSet};
+use chrono::{DateTime, Duration, Local, NaiveTime, Utc};
+use croner::Cron;
+use crate::db::{QuotaSnapshot, Repository};
+use crate::error::{QuonitorError, Result};
+use crate::services::Settings;
+use tracing::warn;
+
+// Refresh schedules are standard five-field cron expressions ("*/10 8-20 * * MON-FRI"),
+// evaluated in local time. The global one (`Settings::refresh_schedule`) replaces the
+// fixed interval; an account's own (this account setting) replaces the global one.
+pub const ACCOUNT_SETTING: &str = "refresh_schedule";
+
+// Adaptive polling fetches an account using this much of its quota per hour at the
+// configured interval, proportionally more or less often the faster or slower it goes
+const ADAPTIVE_PERCENT_PER_HOUR: f64 = 1.0;
+// Usage over this window sets an account's adaptive interval
+const ADAPTIVE_WINDOW_SECONDS: i64 = 6 * 3600;
+
+pub fn parse(expression: &str) -> Result<Cron> {
+    Cron::new(expression.trim())
+        .parse()
+        .map_err(|e| QuonitorError::Config(format!("Invalid cron expression \"{}\": {}", expression, e)))
+}
+
+// The schedules in effect, reloaded before every scheduled fetch
+pub struct Plan {
+    global: Option<Cron>,
+    accounts: HashMap<String, Cron>,
+    // In adaptive mode, the interval of each account without a schedule of its own
+    adaptive: HashMap<String, Duration>,
+    // Local (start, end) times with no scheduled fetches; may wrap past midnight
+    blackouts: Vec<(NaiveTime, NaiveTime)>,
+}
+
+// One scheduled fetch: when, and which accounts it covers
+pub struct Run {
+    pub at: DateTime<Local>,
+    pub global: bool,
+    accounts: HashSet<String>,
+    scheduled: HashSet<String>,
+}
+
+impl Plan {
+    // Invalid expressions (settings written before validation, or edited by hand) are
+    // ignored with a warning so they fall back to the interval or global schedule
+    pub async fn load(repo: &Repository, settings: &Settings, interval: Duration) -> Self {
+        let global = settings.refresh_schedule.as_deref().and_then(|expression| match parse(expression) {
+            Ok(cron) => Some(cron),
+            Err(e) => {
+                warn!("Ignoring refresh schedule: {}", e);
+                None
+            }
+        });
+
+        let accounts: HashMap<String, Cron> = match repo.get_account_setting_values(ACCOUNT_SETTING).await {
+            Ok(values) => values
+                .into_iter()
+                .filter(|(_, expression)| !expression.trim().is_empty())
+                .filter_map(|(account_id, expression)| match parse(&expression) {
+                    Ok(cron) => Some((account_id, cron)),
+                    Err(e) => {
+                        warn!("Ignoring refresh schedule of account {}: {}", account_id, e);
+                        None
+                    }
+                })
+                .collect(),
+            Err(e) => {
+                warn!("Failed to load account refresh schedules: {}", e);
+                HashMap::new()
+            }
+        };
+
+        // Adaptive mode stretches the interval, so it has nothing to do under a global schedule
+        let adaptive = if settings.adaptive_polling && global.is_none() {
+            adaptive_intervals(repo, settings, interval, &accounts).await
+        } else {
+            HashMap::new()
+        };
+
+        Self { global, accounts, adaptive, blackouts: settings.blackout_windows() }
+    }
+
+    // The next fetch after `now`. Without a global schedule, accounts that follow it
+    // are due `interval` after the last time they were fetched; in adaptive mode each
+    // is due its own interval after `last_fetched` (or `last_global` before its first).
+    pub fn next_run(
+        &self,
+        now: DateTime<Local>,
+        last_global: DateTime<Local>,
+        interval: Duration,
+        last_fetched: &HashMap<String, DateTime<Local>>,
+    ) -> Run {
+        let global_at = match &self.global {
+            Some(cron) => next_after(cron, now),
+            // Every account has its own time
+            None if !self.adaptive.is_empty() => None,
+            None => Some(last_global + interval),
+        };
+        let account_times: Vec<(&String, DateTime<Local>)> = self.accounts
+            .iter()
+            .filter_map(|(account_id, cron)| Some((account_id, next_after(cron, now)?)))
+            .chain(self.adaptive.iter().map(|(account_id, interval)| {
+                let last = last_fetched.get(account_id).copied().unwrap_or(last_global);
+                (account_id, last + *interval)
+            }))
+            .collect();
+
+        // Expressions that never match again (e.g. "0 0 30 2 *") leave nothing to
+        // wait for; check back after the interval in case the schedule changes
+        let at = global_at
+            .into_iter()
+            .chain(account_times.iter().map(|(_, at)| *at))
+            .min()
+            .unwrap_or(now + interval);
+
+        let scheduled: HashSet<String> = self.accounts.keys().chain(self.adaptive.keys()).cloned().collect();
+
+        // Whatever falls in a blackout waits for its end, where everything is fetched
+        // to catch up on what was skipped
+        if let Some(end) = self.blackout_end(at) {
+            return Run { at: end, global: true, accounts: scheduled.clone(), scheduled };
+        }
+
+        Run {
+            at,
+            global: global_at.is_some_and(|global_at| global_at <= at),
+            accounts: account_times
+                .into_iter()
+                .filter(|(_, account_at)| *account_at <= at)
+                .map(|(account_id, _)| account_id.clone())
+                .collect(),
+            scheduled,
+        }
+    }
+
+    // End of the blackout `at` falls in, following on through back-to-back windows
+    fn blackout_end(&self, at: DateTime<Local>) -> Option<DateTime<Local>> {
+        let mut end = None;
+        let mut time = at;
+        // Bounded, as windows covering the whole day would never end
+        for _ in 0..self.blackouts.len() {
+            let Some(next) = self.blackouts.iter().find_map(|window| window_end(*window, time)) else {
+                break;
+            };
+            end = Some(next);
+            time = next;
+        }
+        end
+    }
+}
+
+fn window_end((start, end): (NaiveTime, NaiveTime), at: DateTime<Local>) -> Option<DateTime<Local>> {
+    let time = at.time();
+    let inside = if start < end {
+        start <= time && time < end
+    } else {
+        time >= start || time < end
+    };
+    if !inside {
+        return None;
+    }
+
+    // A window that wraps past midnight ends tomorrow when entered before midnight
+    let date = if time >= end { at.date_naive().succ_opt()? } else { at.date_naive() };
+    date.and_time(end).and_local_timezone(Local).earliest()
+}
+
+impl Run {
+    pub fn includes(&self, account_id: &str) -> bool {
+        if self.scheduled.contains(account_id) {
+            self.accounts.contains(account_id)
+        } else {
+            self.global
+        }
+    }
+
+    // Accounts on their own timing that this run covers
+    pub fn due_accounts(&self) -> impl Iterator<Item = &String> {
+        self.accounts.iter()
+    }
+}
+
+fn next_after(cron: &Cron, after: DateTime<Local>) -> Option<DateTime<Local>> {
+    cron.find_next_occurrence(&after, false).ok()
+}
+
+async fn adaptive_intervals(
+    repo: &Repository,
+    settings: &Settings,
+    interval: Duration,
+    scheduled: &HashMap<String, Cron>,
+) -> HashMap<String, Duration> {
+    let accounts = match repo.get_all_accounts().await {
+        Ok(accounts) => accounts,
+        Err(e) => {
+            warn!("Failed to load accounts for adaptive polling: {}", e);
+            return HashMap::new();
+        }
+    };
+
+    let min = Duration::seconds(settings.adaptive_min_interval_seconds as i64);
+    let max = Duration::seconds(settings.adaptive_max_interval_seconds as i64);
+    let since = Utc::now().timestamp() - ADAPTIVE_WINDOW_SECONDS;
+
+    let mut intervals = HashMap::new();
+    for account in accounts.into_iter().filter(|a| !scheduled.contains_key(&a.id)) {
+        let snapshots = match repo.get_snapshots_since(&account.id, since, None, 0).await {
+            Ok(snapshots) => snapshots,
+            Err(e) => {
+                warn!("Failed to load recent usage of account {}: {}", account.id, e);
+                Vec::new()
+            }
+        };
+        intervals.insert(account.id, adaptive_interval(&snapshots, interval).clamp(min, max));
+    }
+    intervals
+}
+
+// Scales `interval` by how fast the account's quota is being used. Idle accounts get
+// the longest interval (clamped by the caller); accounts without a quota, or without
+// enough history to tell, keep `interval`.
+fn adaptive_interval(snapshots: &[QuotaSnapshot], interval: Duration) -> Duration {
+    let (Some(first), Some(last)) = (snapshots.first(), snapshots.last()) else {
+        return interval;
+    };
+    let hours = (last.timestamp - first.timestamp) as f64 / 3600.0;
+    if hours <= 0.0 {
+        return interval;
+    }
+
+    let idle = first.tokens_input == last.tokens_input
+        && first.tokens_output == last.tokens_output
+        && first.cost_usd == last.cost_usd
+        && first.quota_remaining == last.quota_remaining;
+    if idle {
+        return Duration::MAX;
+    }
+
+    let used_percent = match (first.quota_remaining, last.quota_remaining, last.quota_limit) {
+        (Some(before), Some(after), Some(limit)) if limit > 0 => (before - after) as f64 / limit as f64 * 100.0,
+        _ => return interval,
+    };
+    // Remaining going up is a reset, not usage
+    if used_percent <= 0.0 {
+        return interval;
+    }
+
+    let seconds = interval.num_seconds() as f64 * ADAPTIVE_PERCENT_PER_HOUR / (used_percent / hours);
+    Duration::try_seconds(seconds as i64).unwrap_or(Duration::MAX)
+}
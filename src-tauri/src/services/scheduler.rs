@@ -1,30 +1,204 @@
+use std::collections::HashMap;
 use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::time::Duration;
+use chrono::Local;
 use tokio::time;
-use tokio::sync::RwLock;
-use crate::services::{Aggregator, Notifier, Cache};
-use tracing::{info, error};
+use tokio::sync::{watch, Mutex, Notify, RwLock};
+use crate::db::Repository;
+use crate::power;
+use crate::services::{Aggregator, Notifier, Cache, Connectivity, TokenRefresher, RollupService, SnapshotService, MaintenanceService, DigestService, Settings};
+use crate::services::cache::SPARKLINE_POINTS;
+use crate::services::schedule::Plan;
+use tracing::{debug, info, error, warn};
 
-pub struct Scheduler {
+const ROLLUP_INTERVAL: Duration = Duration::from_secs(3600);
+// How often to check whether an automatic backup is due
+const BACKUP_CHECK_INTERVAL: Duration = Duration::from_secs(3600);
+// How often to check whether a usage digest is due
+const DIGEST_CHECK_INTERVAL: Duration = Duration::from_secs(300);
+
+// Factor to stretch the refresh interval by, per the settings and the current power
+// and connection status
+async fn slow_down(settings: &Settings) -> u64 {
+    if !settings.slow_on_battery && !settings.slow_on_metered {
+        return 1;
+    }
+
+    let status = power::status().await;
+    if (settings.slow_on_battery && status.on_battery) || (settings.slow_on_metered && status.metered) {
+        settings.slow_polling_factor as u64
+    } else {
+        1
+    }
+}
+
+// Fetch cycles, one at a time so overlapping cycles can't store the same snapshot
+// twice. A manual refresh joins a manual cycle that's under way, and cancels a
+// scheduled one (between accounts) to start over with every account; a scheduled
+// cycle joins whatever is running.
+#[derive(Clone)]
+struct Cycles {
     aggregator: Arc<Aggregator>,
     notifier: Arc<Notifier>,
     cache: Arc<Cache>,
+    token_refresher: Arc<TokenRefresher>,
+    lock: Arc<Mutex<()>>,
+    in_flight: Arc<std::sync::Mutex<Option<InFlight>>>,
+    next_id: Arc<AtomicU64>,
+}
+
+struct InFlight {
+    id: u64,
+    manual: bool,
+    cancelled: Arc<AtomicBool>,
+    done: watch::Receiver<bool>,
+}
+
+enum Start {
+    Join(watch::Receiver<bool>),
+    Run(u64, Arc<AtomicBool>, watch::Sender<bool>),
+}
+
+impl Cycles {
+    // Returns true when the cycle was coalesced into the one already running
+    async fn run(&self, manual: bool, due: impl Fn(&str) -> bool) -> bool {
+        let start = {
+            let mut in_flight = self.in_flight.lock().unwrap_or_else(|e| e.into_inner());
+            match in_flight.as_ref() {
+                Some(current) if !manual || current.manual => Start::Join(current.done.clone()),
+                current => {
+                    if let Some(current) = current {
+                        info!("Cancelling the scheduled fetch cycle for a manual refresh");
+                        current.cancelled.store(true, Ordering::Relaxed);
+                    }
+                    // Registered before waiting for the lock, so refreshes arriving
+                    // meanwhile join this cycle rather than the cancelled one
+                    let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+                    let cancelled = Arc::new(AtomicBool::new(false));
+                    let (done_tx, done_rx) = watch::channel(false);
+                    *in_flight = Some(InFlight { id, manual, cancelled: cancelled.clone(), done: done_rx });
+                    Start::Run(id, cancelled, done_tx)
+                }
+            }
+        };
+
+        let (id, cancelled, done) = match start {
+            Start::Join(mut done) => {
+                let _ = done.wait_for(|done| *done).await;
+                return true;
+            }
+            Start::Run(id, cancelled, done) => (id, cancelled, done),
+        };
+
+        {
+            let _cycle = self.lock.lock().await;
+            // Checked before each account, so a cancelled cycle stops at the next one
+            self.fetch(manual, |account_id| !cancelled.load(Ordering::Relaxed) && due(account_id)).await;
+        }
+
+        {
+            let mut in_flight = self.in_flight.lock().unwrap_or_else(|e| e.into_inner());
+            if in_flight.as_ref().is_some_and(|current| current.id == id) {
+                *in_flight = None;
+            }
+        }
+        let _ = done.send(true);
+        false
+    }
+
+    async fn fetch(&self, manual: bool, due: impl Fn(&str) -> bool) {
+        // Renew expiring OAuth tokens before they're used
+        self.token_refresher.refresh_expired().await;
+
+        // Manual, so accounts that are backing off are retried too
+        let quotas = self.aggregator.fetch_quotas(due, manual).await;
+
+        for quota in quotas {
+            // Unchanged values aren't sent to the UI, but are still checked: an alert held
+            // back by a snooze or quiet hours is due once they end, and budget
+            // percentages move without the quota changing
+            if !self.cache.set(quota.account_id.clone(), quota.clone()).await {
+                debug!("No change for account {}", quota.account_id);
+            }
+
+            // Check notifications
+            if let Err(e) = self.notifier.check_and_notify(&quota).await {
+                error!("Notification check failed: {}", e);
+            }
+            if let Err(e) = self.notifier.check_spend_anomaly(&quota.account_id).await {
+                error!("Unusual spend check failed: {}", e);
+            }
+            if let Err(e) = self.notifier.check_burn_rate(&quota.account_id).await {
+                error!("Burn rate check failed: {}", e);
+            }
+        }
+
+        if let Err(e) = self.notifier.check_budgets().await {
+            error!("Budget check failed: {}", e);
+        }
+
+        if let Err(e) = self.notifier.check_groups().await {
+            error!("Group threshold check failed: {}", e);
+        }
+
+        if let Err(e) = self.notifier.check_stale().await {
+            error!("Stale data check failed: {}", e);
+        }
+
+        info!("Completed {} fetch cycle", if manual { "manual" } else { "scheduled" });
+    }
+}
+
+pub struct Scheduler {
+    repo: Arc<Repository>,
+    aggregator: Arc<Aggregator>,
+    cycles: Cycles,
+    cache: Arc<Cache>,
+    connectivity: Arc<Connectivity>,
+    rollups: Arc<RollupService>,
+    snapshots: Arc<SnapshotService>,
+    maintenance: Arc<MaintenanceService>,
+    digests: Arc<DigestService>,
     interval_seconds: Arc<RwLock<u64>>,
+    // Wakes the fetch loop to recompute its next run after a schedule change
+    reschedule: Arc<Notify>,
     running: Arc<RwLock<bool>>,
 }
 
 impl Scheduler {
     pub fn new(
+        repo: Arc<Repository>,
         aggregator: Arc<Aggregator>,
         notifier: Arc<Notifier>,
         cache: Arc<Cache>,
+        connectivity: Arc<Connectivity>,
+        token_refresher: Arc<TokenRefresher>,
+        rollups: Arc<RollupService>,
+        snapshots: Arc<SnapshotService>,
+        maintenance: Arc<MaintenanceService>,
         interval_seconds: u64,
     ) -> Self {
         Self {
-            aggregator,
-            notifier,
+            digests: Arc::new(DigestService::new(repo.clone(), notifier.clone())),
+            repo,
+            aggregator: aggregator.clone(),
+            cycles: Cycles {
+                aggregator,
+                notifier,
+                cache: cache.clone(),
+                token_refresher,
+                lock: Arc::new(Mutex::new(())),
+                in_flight: Arc::new(std::sync::Mutex::new(None)),
+                next_id: Arc::new(AtomicU64::new(0)),
+            },
             cache,
+            connectivity,
+            rollups,
+            snapshots,
+            maintenance,
             interval_seconds: Arc::new(RwLock::new(interval_seconds)),
+            reschedule: Arc::new(Notify::new()),
             running: Arc::new(RwLock::new(false)),
         }
     }
@@ -40,44 +214,136 @@ impl Scheduler {
 
         info!("Starting scheduler");
 
-        // Run immediately on start
-        self.run_fetch_cycle().await;
+        // Show what was last stored straight away, marked as restored until fetched data
+        // replaces it
+        match self.aggregator.load_stored_quotas().await {
+            Ok(quotas) => {
+                for quota in quotas {
+                    match self.repo.get_recent_usage_deltas(&quota.account_id, SPARKLINE_POINTS as i64).await {
+                        Ok(deltas) => self.cache.seed_sparkline(&quota.account_id, deltas).await,
+                        Err(e) => warn!("Failed to load usage deltas for account {}: {}", quota.account_id, e),
+                    }
+                    self.cache.restore(quota).await;
+                }
+            }
+            Err(e) => warn!("Failed to load stored quotas: {}", e),
+        }
+
+        let settings = Settings::load(&self.repo).await.unwrap_or_else(|e| {
+            warn!("Failed to load settings for startup: {}", e);
+            Settings::default()
+        });
+        if settings.lazy_startup {
+            info!("Skipping the startup fetch, accounts are fetched on schedule");
+        } else {
+            // Eases the load at login, when everything else is starting too. The
+            // background jobs below wait along with it.
+            if settings.startup_delay_seconds > 0 {
+                info!("Delaying the startup fetch by {} seconds", settings.startup_delay_seconds);
+                time::sleep(Duration::from_secs(settings.startup_delay_seconds)).await;
+            }
+            self.run_fetch_cycle().await;
+        }
+
+        // Aggregate history into rollups hourly, independent of the fetch interval
+        let rollups = self.rollups.clone();
+        let rollups_running = self.running.clone();
+        tokio::spawn(async move {
+            loop {
+                rollups.run().await;
+                time::sleep(ROLLUP_INTERVAL).await;
+
+                if !*rollups_running.read().await {
+                    break;
+                }
+            }
+        });
+
+        // Apply retention, back up on the configured cadence and run weekly maintenance,
+        // checked hourly. Sequential, so VACUUM never rewrites the file mid-backup.
+        let snapshots = self.snapshots.clone();
+        let maintenance = self.maintenance.clone();
+        let snapshots_running = self.running.clone();
+        tokio::spawn(async move {
+            loop {
+                if let Err(e) = maintenance.cleanup().await {
+                    error!("Retention cleanup failed: {}", e);
+                }
+                snapshots.run().await;
+                maintenance.run_if_due().await;
+                time::sleep(BACKUP_CHECK_INTERVAL).await;
+
+                if !*snapshots_running.read().await {
+                    break;
+                }
+            }
+        });
+
+        let digests = self.digests.clone();
+        let digests_running = self.running.clone();
+        tokio::spawn(async move {
+            loop {
+                digests.run_if_due().await;
+                time::sleep(DIGEST_CHECK_INTERVAL).await;
 
-        // Then run on interval
-        let aggregator = self.aggregator.clone();
-        let notifier = self.notifier.clone();
-        let cache = self.cache.clone();
+                if !*digests_running.read().await {
+                    break;
+                }
+            }
+        });
+
+        // Then run on the interval or cron schedules
+        let repo = self.repo.clone();
+        let cycles = self.cycles.clone();
+        let connectivity = self.connectivity.clone();
         let interval = self.interval_seconds.clone();
+        let reschedule = self.reschedule.clone();
         let running = self.running.clone();
 
         tokio::spawn(async move {
+            let mut last_global = Local::now();
+            let mut last_fetched = HashMap::new();
             loop {
-                let interval_secs = *interval.read().await;
-                time::sleep(Duration::from_secs(interval_secs)).await;
+                let settings = Settings::load(&repo).await.unwrap_or_else(|e| {
+                    warn!("Failed to load settings for scheduling: {}", e);
+                    Settings::default()
+                });
+                let interval_secs = *interval.read().await * slow_down(&settings).await;
+                let interval = chrono::Duration::seconds(interval_secs as i64);
+                let run = Plan::load(&repo, &settings, interval)
+                    .await
+                    .next_run(Local::now(), last_global, interval, &last_fetched);
+
+                let wait = (run.at - Local::now()).to_std().unwrap_or_default();
+                tokio::select! {
+                    _ = time::sleep(wait) => {}
+                    _ = reschedule.notified() => continue,
+                }
 
                 if !*running.read().await {
                     break;
                 }
+                if run.global {
+                    last_global = run.at;
+                }
+                for account_id in run.due_accounts() {
+                    last_fetched.insert(account_id.clone(), run.at);
+                }
 
-                // Fetch quotas
-                let quotas = aggregator.fetch_all_quotas().await;
-
-                // Update cache
-                for quota in quotas {
-                    // Check notifications
-                    if let Err(e) = notifier.check_and_notify(&quota).await {
-                        error!("Notification check failed: {}", e);
+                // Rather than every account failing each cycle, wait out the outage
+                // and then run the fetch that was due
+                if settings.pause_when_offline && !connectivity.check().await {
+                    connectivity.wait_until_online().await;
+                    if !*running.read().await {
+                        break;
                     }
-
-                    cache.set(quota.account_id.clone(), quota).await;
                 }
 
-                info!("Completed scheduled fetch cycle");
+                cycles.run(false, |account_id| run.includes(account_id)).await;
             }
         });
     }
 
-    #[allow(dead_code)]
     pub async fn stop(&self) {
         let mut running = self.running.write().await;
         *running = false;
@@ -88,21 +354,27 @@ impl Scheduler {
         let mut interval = self.interval_seconds.write().await;
         *interval = seconds;
         info!("Updated scheduler interval to {} seconds", seconds);
+        self.reschedule();
     }
 
-    pub async fn run_fetch_cycle(&self) {
-        info!("Running manual fetch cycle");
-
-        let quotas = self.aggregator.fetch_all_quotas().await;
+    // Call after changing the global or an account's refresh schedule
+    pub fn reschedule(&self) {
+        self.reschedule.notify_one();
+    }
 
-        for quota in quotas {
-            if let Err(e) = self.notifier.check_and_notify(&quota).await {
-                error!("Notification check failed: {}", e);
-            }
+    // Returns true when the refresh was coalesced into a manual refresh already running
+    pub async fn run_fetch_cycle(&self) -> bool {
+        info!("Running manual fetch cycle");
 
-            self.cache.set(quota.account_id.clone(), quota).await;
+        let pause_when_offline = match Settings::load(&self.repo).await {
+            Ok(settings) => settings.pause_when_offline,
+            Err(_) => Settings::default().pause_when_offline,
+        };
+        if pause_when_offline && !self.connectivity.check().await {
+            info!("Offline, skipping manual fetch cycle");
+            return false;
         }
 
-        info!("Completed manual fetch cycle");
+        self.cycles.run(true, |_| true).await
     }
 }
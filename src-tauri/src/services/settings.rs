@@ -0,0 +1,596 @@
+use std::collections::HashMap;
+use std::str::FromStr;
+use chrono::NaiveTime;
+use serde::{Deserialize, Serialize};
+use crate::db::{Repository, RetentionCutoffs};
+use crate::error::{QuonitorError, Result};
+use crate::services::{push, schedule};
+use crate::services::quiet_hours::QuietHours;
+use tracing::warn;
+
+pub const URGENCIES: &[&str] = &["low", "normal", "critical"];
+
+// Settings that can be overridden per provider
+const OVERRIDE_FIELDS: &[&str] = &["connect_timeout_seconds", "read_timeout_seconds", "rate_limit_per_minute"];
+
+// One provider's values for the settings of the same names; unset ones use the global
+// setting. Provider clients are built at startup, so timeouts apply on the next launch.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ProviderOverrides {
+    pub connect_timeout_seconds: Option<u64>,
+    pub read_timeout_seconds: Option<u64>,
+    pub rate_limit_per_minute: Option<u32>,
+}
+
+// Application settings. Each field is stored as its own row in `settings`, keyed by
+// the field name; rows that are missing or fail to parse fall back to the default.
+// Per-provider overrides are stored as `<field>.<provider>`, e.g. `read_timeout_seconds.openai`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub refresh_interval_seconds: u64,
+    // Cron expression used instead of the interval when set; see `schedule`
+    pub refresh_schedule: Option<String>,
+    // The first fetch after launch waits this long; with `lazy_startup` it's skipped and
+    // stored data is shown until the first scheduled fetch
+    pub startup_delay_seconds: u64,
+    pub lazy_startup: bool,
+    // "HH:MM-HH:MM" local-time windows, comma separated, in which nothing is fetched
+    // on schedule; everything is fetched when one ends
+    pub blackout_windows: Option<String>,
+    // Probe the network before each fetch cycle and skip it while offline
+    pub pause_when_offline: bool,
+    // Multiply the refresh interval by `slow_polling_factor` on battery or a metered
+    // connection. Cron schedules aren't stretched.
+    pub slow_on_battery: bool,
+    pub slow_on_metered: bool,
+    pub slow_polling_factor: u32,
+    // Fetch accounts whose quota is going fast more often and idle ones less often,
+    // within these bounds; see `schedule`
+    pub adaptive_polling: bool,
+    pub adaptive_min_interval_seconds: u64,
+    pub adaptive_max_interval_seconds: u64,
+    pub notifications_enabled: bool,
+    pub threshold_75_enabled: bool,
+    pub threshold_90_enabled: bool,
+    pub threshold_95_enabled: bool,
+    // How the desktop shows each threshold's alert: its urgency ("low", "normal" or
+    // "critical"), seconds on screen (0 until dismissed) and the platform's name for a
+    // sound to play, e.g. "message-new-instant" or "Glass"; silent when unset
+    pub threshold_75_urgency: String,
+    pub threshold_90_urgency: String,
+    pub threshold_95_urgency: String,
+    pub threshold_75_timeout_seconds: u32,
+    pub threshold_90_timeout_seconds: u32,
+    pub threshold_95_timeout_seconds: u32,
+    pub threshold_75_sound: Option<String>,
+    pub threshold_90_sound: Option<String>,
+    pub threshold_95_sound: Option<String>,
+    // Critical threshold alerts still come during quiet hours
+    pub critical_bypasses_quiet_hours: bool,
+    // Alert when an account spends more than `anomaly_multiple` times its usual amount
+    // for the hour, and at least `anomaly_min_usd`; see `anomaly`
+    pub anomaly_detection: bool,
+    pub anomaly_multiple: f64,
+    pub anomaly_min_usd: f64,
+    // Alert once a period when a budget's spend is on track to pass it before the
+    // period ends; see `budgets`
+    pub forecast_alerts: bool,
+    // Alert when an account's fetches have been failing and it hasn't synced for this
+    // many hours; 0 disables it
+    pub stale_alert_hours: u32,
+    // Alert when an account spends more than this in an hour; 0 disables it. Accounts
+    // can set their own in their notification preferences.
+    pub burn_rate_usd_per_hour: f64,
+    // "HH:MM" every day; both or neither are set. Days in `quiet_hours_weekdays`
+    // ("mon-fri 22:00-07:00; sat off") have their own instead. In the timezone when set,
+    // else local time; see `quiet_hours`.
+    pub quiet_hours_start: Option<String>,
+    pub quiet_hours_end: Option<String>,
+    pub quiet_hours_weekdays: Option<String>,
+    pub quiet_hours_timezone: Option<String>,
+    // Alerts are also POSTed as JSON to these http(s) URLs, comma or newline separated,
+    // and signed when a secret is set; the secret is stored encrypted apart from these,
+    // see `webhook`
+    pub webhook_urls: Option<String>,
+    // Alerts are emailed to `email_to` (comma separated) through this server when set,
+    // those with a threshold below `email_min_threshold` excepted. STARTTLS unless
+    // `smtp_implicit_tls`; the password is stored encrypted apart from these, see `email`.
+    pub smtp_host: Option<String>,
+    pub smtp_port: u16,
+    pub smtp_implicit_tls: bool,
+    pub smtp_username: Option<String>,
+    pub smtp_from: Option<String>,
+    pub email_to: Option<String>,
+    pub email_min_threshold: u32,
+    // ntfy, Gotify and Pushover targets for alerts, see `push`; accounts with targets
+    // of their own use those instead
+    pub push_targets: Option<String>,
+    // Spend summaries sent at `digest_time` ("HH:MM", local): yesterday's every day,
+    // the past week's on Mondays; see `digest`
+    pub digest_daily: bool,
+    pub digest_weekly: bool,
+    pub digest_time: String,
+    // Days of history to keep per data type. Raw snapshots back the dashboard summary
+    // and monthly budgets, so they're kept for at least a month.
+    pub retention_snapshot_days: u32,
+    pub retention_model_usage_days: u32,
+    pub retention_rollup_days: u32,
+    pub retention_notification_days: u32,
+    pub retry_max_attempts: u32,
+    // Fetches per minute to any one provider, across its accounts; 0 disables the
+    // limit. Overridable per provider in `provider_overrides`.
+    pub rate_limit_per_minute: u32,
+    // Scheduled fetches of an account stop after this many consecutive failures,
+    // until it's refreshed manually or the cool-down passes
+    pub breaker_failure_threshold: u32,
+    pub breaker_cooldown_minutes: u32,
+    pub connect_timeout_seconds: u64,
+    pub read_timeout_seconds: u64,
+    // Keyed by provider id; see `ProviderOverrides`
+    pub provider_overrides: HashMap<String, ProviderOverrides>,
+    pub pricing_url: Option<String>,
+    // Automatic local backups; 0 disables them
+    pub backup_interval_hours: u32,
+    pub backups_to_keep: u32,
+    // Run integrity check, VACUUM and ANALYZE once a week
+    pub weekly_maintenance: bool,
+    // Confirm with the OS (Windows Hello, Touch ID, polkit) before exporting credentials
+    pub require_os_auth: bool,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            refresh_interval_seconds: 300,
+            refresh_schedule: None,
+            startup_delay_seconds: 0,
+            lazy_startup: false,
+            blackout_windows: None,
+            pause_when_offline: true,
+            slow_on_battery: false,
+            slow_on_metered: false,
+            slow_polling_factor: 4,
+            adaptive_polling: false,
+            adaptive_min_interval_seconds: 60,
+            adaptive_max_interval_seconds: 3600,
+            notifications_enabled: true,
+            threshold_75_enabled: true,
+            threshold_90_enabled: true,
+            threshold_95_enabled: true,
+            threshold_75_urgency: "low".to_string(),
+            threshold_90_urgency: "normal".to_string(),
+            threshold_95_urgency: "critical".to_string(),
+            threshold_75_timeout_seconds: 6,
+            threshold_90_timeout_seconds: 6,
+            threshold_95_timeout_seconds: 6,
+            threshold_75_sound: None,
+            threshold_90_sound: None,
+            threshold_95_sound: None,
+            critical_bypasses_quiet_hours: false,
+            anomaly_detection: true,
+            anomaly_multiple: 3.0,
+            anomaly_min_usd: 1.0,
+            forecast_alerts: true,
+            stale_alert_hours: 6,
+            burn_rate_usd_per_hour: 0.0,
+            quiet_hours_start: None,
+            quiet_hours_end: None,
+            quiet_hours_weekdays: None,
+            quiet_hours_timezone: None,
+            webhook_urls: None,
+            smtp_host: None,
+            smtp_port: 587,
+            smtp_implicit_tls: false,
+            smtp_username: None,
+            smtp_from: None,
+            email_to: None,
+            email_min_threshold: 0,
+            push_targets: None,
+            digest_daily: false,
+            digest_weekly: false,
+            digest_time: "08:00".to_string(),
+            retention_snapshot_days: 90,
+            retention_model_usage_days: 90,
+            retention_rollup_days: 730,
+            retention_notification_days: 180,
+            retry_max_attempts: 3,
+            rate_limit_per_minute: 30,
+            breaker_failure_threshold: 5,
+            breaker_cooldown_minutes: 60,
+            connect_timeout_seconds: 10,
+            read_timeout_seconds: 60,
+            provider_overrides: HashMap::new(),
+            pricing_url: None,
+            backup_interval_hours: 24,
+            backups_to_keep: 7,
+            weekly_maintenance: false,
+            require_os_auth: false,
+        }
+    }
+}
+
+impl Settings {
+    pub async fn load(repo: &Repository) -> Result<Self> {
+        let rows = repo.get_all_settings().await?;
+        let defaults = Self::default();
+
+        Ok(Self {
+            refresh_interval_seconds: parse_or(&rows, "refresh_interval_seconds", defaults.refresh_interval_seconds),
+            refresh_schedule: optional(&rows, "refresh_schedule"),
+            startup_delay_seconds: parse_or(&rows, "startup_delay_seconds", defaults.startup_delay_seconds),
+            lazy_startup: parse_or(&rows, "lazy_startup", defaults.lazy_startup),
+            blackout_windows: optional(&rows, "blackout_windows"),
+            pause_when_offline: parse_or(&rows, "pause_when_offline", defaults.pause_when_offline),
+            slow_on_battery: parse_or(&rows, "slow_on_battery", defaults.slow_on_battery),
+            slow_on_metered: parse_or(&rows, "slow_on_metered", defaults.slow_on_metered),
+            slow_polling_factor: parse_or(&rows, "slow_polling_factor", defaults.slow_polling_factor),
+            adaptive_polling: parse_or(&rows, "adaptive_polling", defaults.adaptive_polling),
+            adaptive_min_interval_seconds: parse_or(&rows, "adaptive_min_interval_seconds", defaults.adaptive_min_interval_seconds),
+            adaptive_max_interval_seconds: parse_or(&rows, "adaptive_max_interval_seconds", defaults.adaptive_max_interval_seconds),
+            notifications_enabled: parse_or(&rows, "notifications_enabled", defaults.notifications_enabled),
+            threshold_75_enabled: parse_or(&rows, "threshold_75_enabled", defaults.threshold_75_enabled),
+            threshold_90_enabled: parse_or(&rows, "threshold_90_enabled", defaults.threshold_90_enabled),
+            threshold_95_enabled: parse_or(&rows, "threshold_95_enabled", defaults.threshold_95_enabled),
+            threshold_75_urgency: optional(&rows, "threshold_75_urgency").unwrap_or(defaults.threshold_75_urgency),
+            threshold_90_urgency: optional(&rows, "threshold_90_urgency").unwrap_or(defaults.threshold_90_urgency),
+            threshold_95_urgency: optional(&rows, "threshold_95_urgency").unwrap_or(defaults.threshold_95_urgency),
+            threshold_75_timeout_seconds: parse_or(&rows, "threshold_75_timeout_seconds", defaults.threshold_75_timeout_seconds),
+            threshold_90_timeout_seconds: parse_or(&rows, "threshold_90_timeout_seconds", defaults.threshold_90_timeout_seconds),
+            threshold_95_timeout_seconds: parse_or(&rows, "threshold_95_timeout_seconds", defaults.threshold_95_timeout_seconds),
+            threshold_75_sound: optional(&rows, "threshold_75_sound"),
+            threshold_90_sound: optional(&rows, "threshold_90_sound"),
+            threshold_95_sound: optional(&rows, "threshold_95_sound"),
+            critical_bypasses_quiet_hours: parse_or(&rows, "critical_bypasses_quiet_hours", defaults.critical_bypasses_quiet_hours),
+            anomaly_detection: parse_or(&rows, "anomaly_detection", defaults.anomaly_detection),
+            anomaly_multiple: parse_or(&rows, "anomaly_multiple", defaults.anomaly_multiple),
+            anomaly_min_usd: parse_or(&rows, "anomaly_min_usd", defaults.anomaly_min_usd),
+            forecast_alerts: parse_or(&rows, "forecast_alerts", defaults.forecast_alerts),
+            stale_alert_hours: parse_or(&rows, "stale_alert_hours", defaults.stale_alert_hours),
+            burn_rate_usd_per_hour: parse_or(&rows, "burn_rate_usd_per_hour", defaults.burn_rate_usd_per_hour),
+            quiet_hours_start: optional(&rows, "quiet_hours_start"),
+            quiet_hours_end: optional(&rows, "quiet_hours_end"),
+            quiet_hours_weekdays: optional(&rows, "quiet_hours_weekdays"),
+            quiet_hours_timezone: optional(&rows, "quiet_hours_timezone"),
+            webhook_urls: optional(&rows, "webhook_urls"),
+            smtp_host: optional(&rows, "smtp_host"),
+            smtp_port: parse_or(&rows, "smtp_port", defaults.smtp_port),
+            smtp_implicit_tls: parse_or(&rows, "smtp_implicit_tls", defaults.smtp_implicit_tls),
+            smtp_username: optional(&rows, "smtp_username"),
+            smtp_from: optional(&rows, "smtp_from"),
+            email_to: optional(&rows, "email_to"),
+            email_min_threshold: parse_or(&rows, "email_min_threshold", defaults.email_min_threshold),
+            push_targets: optional(&rows, "push_targets"),
+            digest_daily: parse_or(&rows, "digest_daily", defaults.digest_daily),
+            digest_weekly: parse_or(&rows, "digest_weekly", defaults.digest_weekly),
+            digest_time: optional(&rows, "digest_time").unwrap_or(defaults.digest_time),
+            retention_snapshot_days: parse_or(&rows, "retention_snapshot_days", defaults.retention_snapshot_days),
+            retention_model_usage_days: parse_or(&rows, "retention_model_usage_days", defaults.retention_model_usage_days),
+            retention_rollup_days: parse_or(&rows, "retention_rollup_days", defaults.retention_rollup_days),
+            retention_notification_days: parse_or(&rows, "retention_notification_days", defaults.retention_notification_days),
+            retry_max_attempts: parse_or(&rows, "retry_max_attempts", defaults.retry_max_attempts),
+            rate_limit_per_minute: parse_or(&rows, "rate_limit_per_minute", defaults.rate_limit_per_minute),
+            breaker_failure_threshold: parse_or(&rows, "breaker_failure_threshold", defaults.breaker_failure_threshold),
+            breaker_cooldown_minutes: parse_or(&rows, "breaker_cooldown_minutes", defaults.breaker_cooldown_minutes),
+            connect_timeout_seconds: parse_or(&rows, "connect_timeout_seconds", defaults.connect_timeout_seconds),
+            read_timeout_seconds: parse_or(&rows, "read_timeout_seconds", defaults.read_timeout_seconds),
+            provider_overrides: provider_overrides(&rows),
+            pricing_url: optional(&rows, "pricing_url"),
+            backup_interval_hours: parse_or(&rows, "backup_interval_hours", defaults.backup_interval_hours),
+            backups_to_keep: parse_or(&rows, "backups_to_keep", defaults.backups_to_keep),
+            weekly_maintenance: parse_or(&rows, "weekly_maintenance", defaults.weekly_maintenance),
+            require_os_auth: parse_or(&rows, "require_os_auth", defaults.require_os_auth),
+        })
+    }
+
+    // Validates and writes every field in one transaction; None fields remove their row
+    pub async fn save(&self, repo: &Repository) -> Result<()> {
+        self.validate()?;
+
+        let values = [
+            ("refresh_interval_seconds", Some(self.refresh_interval_seconds.to_string())),
+            ("refresh_schedule", self.refresh_schedule.clone()),
+            ("startup_delay_seconds", Some(self.startup_delay_seconds.to_string())),
+            ("lazy_startup", Some(self.lazy_startup.to_string())),
+            ("blackout_windows", self.blackout_windows.clone()),
+            ("pause_when_offline", Some(self.pause_when_offline.to_string())),
+            ("slow_on_battery", Some(self.slow_on_battery.to_string())),
+            ("slow_on_metered", Some(self.slow_on_metered.to_string())),
+            ("slow_polling_factor", Some(self.slow_polling_factor.to_string())),
+            ("adaptive_polling", Some(self.adaptive_polling.to_string())),
+            ("adaptive_min_interval_seconds", Some(self.adaptive_min_interval_seconds.to_string())),
+            ("adaptive_max_interval_seconds", Some(self.adaptive_max_interval_seconds.to_string())),
+            ("notifications_enabled", Some(self.notifications_enabled.to_string())),
+            ("threshold_75_enabled", Some(self.threshold_75_enabled.to_string())),
+            ("threshold_90_enabled", Some(self.threshold_90_enabled.to_string())),
+            ("threshold_95_enabled", Some(self.threshold_95_enabled.to_string())),
+            ("threshold_75_urgency", Some(self.threshold_75_urgency.clone())),
+            ("threshold_90_urgency", Some(self.threshold_90_urgency.clone())),
+            ("threshold_95_urgency", Some(self.threshold_95_urgency.clone())),
+            ("threshold_75_timeout_seconds", Some(self.threshold_75_timeout_seconds.to_string())),
+            ("threshold_90_timeout_seconds", Some(self.threshold_90_timeout_seconds.to_string())),
+            ("threshold_95_timeout_seconds", Some(self.threshold_95_timeout_seconds.to_string())),
+            ("threshold_75_sound", self.threshold_75_sound.clone()),
+            ("threshold_90_sound", self.threshold_90_sound.clone()),
+            ("threshold_95_sound", self.threshold_95_sound.clone()),
+            ("critical_bypasses_quiet_hours", Some(self.critical_bypasses_quiet_hours.to_string())),
+            ("anomaly_detection", Some(self.anomaly_detection.to_string())),
+            ("anomaly_multiple", Some(self.anomaly_multiple.to_string())),
+            ("anomaly_min_usd", Some(self.anomaly_min_usd.to_string())),
+            ("forecast_alerts", Some(self.forecast_alerts.to_string())),
+            ("stale_alert_hours", Some(self.stale_alert_hours.to_string())),
+            ("burn_rate_usd_per_hour", Some(self.burn_rate_usd_per_hour.to_string())),
+            ("quiet_hours_start", self.quiet_hours_start.clone()),
+            ("quiet_hours_end", self.quiet_hours_end.clone()),
+            ("quiet_hours_weekdays", self.quiet_hours_weekdays.clone()),
+            ("quiet_hours_timezone", self.quiet_hours_timezone.clone()),
+            ("webhook_urls", self.webhook_urls.clone()),
+            ("smtp_host", self.smtp_host.clone()),
+            ("smtp_port", Some(self.smtp_port.to_string())),
+            ("smtp_implicit_tls", Some(self.smtp_implicit_tls.to_string())),
+            ("smtp_username", self.smtp_username.clone()),
+            ("smtp_from", self.smtp_from.clone()),
+            ("email_to", self.email_to.clone()),
+            ("email_min_threshold", Some(self.email_min_threshold.to_string())),
+            ("push_targets", self.push_targets.clone()),
+            ("digest_daily", Some(self.digest_daily.to_string())),
+            ("digest_weekly", Some(self.digest_weekly.to_string())),
+            ("digest_time", Some(self.digest_time.clone())),
+            ("retention_snapshot_days", Some(self.retention_snapshot_days.to_string())),
+            ("retention_model_usage_days", Some(self.retention_model_usage_days.to_string())),
+            ("retention_rollup_days", Some(self.retention_rollup_days.to_string())),
+            ("retention_notification_days", Some(self.retention_notification_days.to_string())),
+            ("retry_max_attempts", Some(self.retry_max_attempts.to_string())),
+            ("rate_limit_per_minute", Some(self.rate_limit_per_minute.to_string())),
+            ("breaker_failure_threshold", Some(self.breaker_failure_threshold.to_string())),
+            ("breaker_cooldown_minutes", Some(self.breaker_cooldown_minutes.to_string())),
+            ("connect_timeout_seconds", Some(self.connect_timeout_seconds.to_string())),
+            ("read_timeout_seconds", Some(self.read_timeout_seconds.to_string())),
+            ("pricing_url", self.pricing_url.clone()),
+            ("backup_interval_hours", Some(self.backup_interval_hours.to_string())),
+            ("backups_to_keep", Some(self.backups_to_keep.to_string())),
+            ("weekly_maintenance", Some(self.weekly_maintenance.to_string())),
+            ("require_os_auth", Some(self.require_os_auth.to_string())),
+        ];
+
+        // Every override row is written, so ones cleared or for a removed provider are deleted
+        let mut providers: Vec<String> = repo.get_all_settings().await?
+            .into_keys()
+            .filter_map(|key| {
+                let (field, provider) = key.split_once('.')?;
+                OVERRIDE_FIELDS.contains(&field).then(|| provider.to_string())
+            })
+            .collect();
+        providers.extend(self.provider_overrides.keys().cloned());
+        providers.sort();
+        providers.dedup();
+
+        let overrides: Vec<(String, Option<String>)> = providers.iter()
+            .flat_map(|provider| {
+                let o = self.provider_overrides.get(provider).cloned().unwrap_or_default();
+                [
+                    ("connect_timeout_seconds", o.connect_timeout_seconds.map(|v| v.to_string())),
+                    ("read_timeout_seconds", o.read_timeout_seconds.map(|v| v.to_string())),
+                    ("rate_limit_per_minute", o.rate_limit_per_minute.map(|v| v.to_string())),
+                ]
+                .map(|(field, value)| (format!("{}.{}", field, provider), value))
+            })
+            .collect();
+
+        let values: Vec<(&str, Option<String>)> = values.into_iter()
+            .chain(overrides.iter().map(|(key, value)| (key.as_str(), value.clone())))
+            .collect();
+
+        repo.replace_settings(&values).await?;
+        Ok(())
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        check_range("Refresh interval", self.refresh_interval_seconds, 30, 86_400)?;
+        check_range("Snapshot retention", self.retention_snapshot_days as u64, 32, 3650)?;
+        check_range("Model usage retention", self.retention_model_usage_days as u64, 1, 3650)?;
+        check_range("Rollup retention", self.retention_rollup_days as u64, 1, 3650)?;
+        check_range("Notification retention", self.retention_notification_days as u64, 1, 3650)?;
+        check_range("Retry attempts", self.retry_max_attempts as u64, 1, 10)?;
+        check_range("Rate limit", self.rate_limit_per_minute as u64, 0, 600)?;
+        check_range("Startup delay", self.startup_delay_seconds, 0, 3600)?;
+        check_range("Adaptive minimum interval", self.adaptive_min_interval_seconds, 30, 86_400)?;
+        check_range("Adaptive maximum interval", self.adaptive_max_interval_seconds, self.adaptive_min_interval_seconds, 86_400)?;
+        check_range("Slow polling factor", self.slow_polling_factor as u64, 2, 20)?;
+        check_range("Circuit breaker failures", self.breaker_failure_threshold as u64, 1, 50)?;
+        check_range("Circuit breaker cool-down", self.breaker_cooldown_minutes as u64, 1, 10_080)?;
+        check_range("Connect timeout", self.connect_timeout_seconds, 1, 600)?;
+        check_range("Read timeout", self.read_timeout_seconds, 1, 600)?;
+        for (provider, overrides) in &self.provider_overrides {
+            if provider.is_empty() || !provider.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-') {
+                return Err(QuonitorError::Config(format!("\"{}\" is not a provider id", provider)));
+            }
+            if let Some(seconds) = overrides.connect_timeout_seconds {
+                check_range(&format!("Connect timeout for {}", provider), seconds, 1, 600)?;
+            }
+            if let Some(seconds) = overrides.read_timeout_seconds {
+                check_range(&format!("Read timeout for {}", provider), seconds, 1, 600)?;
+            }
+            if let Some(per_minute) = overrides.rate_limit_per_minute {
+                check_range(&format!("Rate limit for {}", provider), per_minute as u64, 0, 600)?;
+            }
+        }
+        check_range("Backup interval", self.backup_interval_hours as u64, 0, 720)?;
+        check_range("Backups to keep", self.backups_to_keep as u64, 1, 100)?;
+        check_range("SMTP port", self.smtp_port as u64, 1, 65_535)?;
+        check_range("Email threshold", self.email_min_threshold as u64, 0, 100)?;
+        check_range("Stale data alert", self.stale_alert_hours as u64, 0, 720)?;
+        for (urgency, timeout) in [
+            (&self.threshold_75_urgency, self.threshold_75_timeout_seconds),
+            (&self.threshold_90_urgency, self.threshold_90_timeout_seconds),
+            (&self.threshold_95_urgency, self.threshold_95_timeout_seconds),
+        ] {
+            if !URGENCIES.contains(&urgency.as_str()) {
+                return Err(QuonitorError::Config(format!("Unknown urgency \"{}\"; use low, normal or critical", urgency)));
+            }
+            check_range("Notification timeout", timeout as u64, 0, 3600)?;
+        }
+
+        if !(1.5..=100.0).contains(&self.anomaly_multiple) {
+            return Err(QuonitorError::Config("Unusual spend multiple must be between 1.5 and 100".to_string()));
+        }
+        if !(0.0..=10_000.0).contains(&self.anomaly_min_usd) {
+            return Err(QuonitorError::Config("Unusual spend minimum must be between $0 and $10,000".to_string()));
+        }
+        if !(0.0..=100_000.0).contains(&self.burn_rate_usd_per_hour) {
+            return Err(QuonitorError::Config("Burn rate alert must be between $0 and $100,000 an hour".to_string()));
+        }
+
+        QuietHours::parse(self)?;
+
+        parse_time("Digest time", &self.digest_time)?;
+
+        if let Some(windows) = &self.blackout_windows {
+            parse_windows(windows)?;
+        }
+
+        if let Some(expression) = &self.refresh_schedule {
+            schedule::parse(expression)?;
+        }
+
+        for url in self.webhook_urls() {
+            if !url.starts_with("https://") && !url.starts_with("http://") {
+                return Err(QuonitorError::Config(format!("Webhook URL \"{}\" must be an http(s) URL", url)));
+            }
+        }
+
+        if self.smtp_host.is_some() && self.smtp_from.is_none() {
+            return Err(QuonitorError::Config("Email alerts need a sender address".to_string()));
+        }
+
+        if let Some(targets) = &self.push_targets {
+            push::parse(targets)?;
+        }
+
+        if let Some(url) = &self.pricing_url {
+            if !url.starts_with("https://") && !url.starts_with("http://") {
+                return Err(QuonitorError::Config("Pricing URL must be an http(s) URL".to_string()));
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn retention_cutoffs(&self, now: i64) -> RetentionCutoffs {
+        let before = |days: u32| now - days as i64 * 86400;
+        RetentionCutoffs {
+            snapshots: before(self.retention_snapshot_days),
+            model_usage: before(self.retention_model_usage_days),
+            rollups: before(self.retention_rollup_days),
+            history: before(self.retention_notification_days),
+        }
+    }
+
+    // None when unparseable
+    pub fn digest_time(&self) -> Option<NaiveTime> {
+        parse_time("", &self.digest_time).ok()
+    }
+
+    pub fn webhook_urls(&self) -> Vec<&str> {
+        self.webhook_urls
+            .as_deref()
+            .map(|urls| urls.split([',', '\n']).map(str::trim).filter(|url| !url.is_empty()).collect())
+            .unwrap_or_default()
+    }
+
+    pub fn email_recipients(&self) -> Vec<&str> {
+        self.email_to
+            .as_deref()
+            .map(|to| to.split(',').map(str::trim).filter(|to| !to.is_empty()).collect())
+            .unwrap_or_default()
+    }
+
+    pub fn email_enabled(&self) -> bool {
+        self.smtp_host.is_some() && !self.email_recipients().is_empty()
+    }
+
+    // Blackout windows as (start, end) pairs; empty when unset or unparseable
+    pub fn blackout_windows(&self) -> Vec<(NaiveTime, NaiveTime)> {
+        self.blackout_windows
+            .as_deref()
+            .and_then(|windows| parse_windows(windows).ok())
+            .unwrap_or_default()
+    }
+}
+
+fn parse_windows(value: &str) -> Result<Vec<(NaiveTime, NaiveTime)>> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|window| !window.is_empty())
+        .map(|window| {
+            let (start, end) = window.split_once('-').ok_or_else(|| {
+                QuonitorError::Config(format!("Blackout window \"{}\" must look like 22:00-06:00", window))
+            })?;
+            let start = parse_time("Blackout window start", start)?;
+            let end = parse_time("Blackout window end", end)?;
+            if start == end {
+                return Err(QuonitorError::Config(format!("Blackout window \"{}\" is empty", window)));
+            }
+            Ok((start, end))
+        })
+        .collect()
+}
+
+// Rows named `<field>.<provider>` for the fields in OVERRIDE_FIELDS
+fn provider_overrides(rows: &HashMap<String, String>) -> HashMap<String, ProviderOverrides> {
+    let mut overrides: HashMap<String, ProviderOverrides> = HashMap::new();
+
+    for (key, value) in rows {
+        let Some((field, provider)) = key.split_once('.') else {
+            continue;
+        };
+        if !OVERRIDE_FIELDS.contains(&field) {
+            continue;
+        }
+        let Ok(value) = value.trim().parse::<u64>() else {
+            warn!("Ignoring invalid setting {}={}", key, value);
+            continue;
+        };
+
+        let entry = overrides.entry(provider.to_string()).or_default();
+        match field {
+            "connect_timeout_seconds" => entry.connect_timeout_seconds = Some(value),
+            "read_timeout_seconds" => entry.read_timeout_seconds = Some(value),
+            _ => entry.rate_limit_per_minute = Some(u32::try_from(value).unwrap_or(u32::MAX)),
+        }
+    }
+
+    overrides
+}
+
+fn parse_or<T: FromStr>(rows: &HashMap<String, String>, key: &str, default: T) -> T {
+    let Some(value) = rows.get(key) else {
+        return default;
+    };
+
+    value.trim().parse().unwrap_or_else(|_| {
+        warn!("Ignoring invalid setting {}={}, using the default", key, value);
+        default
+    })
+}
+
+fn optional(rows: &HashMap<String, String>, key: &str) -> Option<String> {
+    rows.get(key)
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty())
+}
+
+fn check_range(label: &str, value: u64, min: u64, max: u64) -> Result<()> {
+    if (min..=max).contains(&value) {
+        Ok(())
+    } else {
+        Err(QuonitorError::Config(format!("{} must be between {} and {}", label, min, max)))
+    }
+}
+
+pub(crate) fn parse_time(label: &str, value: &str) -> Result<NaiveTime> {
+    NaiveTime::parse_from_str(value.trim(), "%H:%M")
+        .map_err(|_| QuonitorError::Config(format!("{} must be a time like 22:00", label)))
+}
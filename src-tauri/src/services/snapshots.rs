@@ -0,0 +1,134 @@
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use chrono::{DateTime, NaiveDateTime, Utc};
+use serde::Serialize;
+use crate::db::Repository;
+use crate::error::{QuonitorError, Result};
+use crate::services::Settings;
+use tracing::{error, info, warn};
+
+const PREFIX: &str = "quonitor-";
+const EXTENSION: &str = ".db";
+const TIMESTAMP_FORMAT: &str = "%Y%m%d-%H%M%S";
+
+// One automatic backup file, named after the time it was taken
+#[derive(Debug, Clone, Serialize)]
+pub struct SnapshotInfo {
+    pub name: String,
+    pub created_at: i64,
+    pub size_bytes: u64,
+}
+
+// Rotating local backups in `<profile dir>/backups`. Unlike the passphrase archives in
+// `services::backup` these keep credentials under this machine's key, so they only
+// restore here.
+pub struct SnapshotService {
+    repo: Arc<Repository>,
+    dir: PathBuf,
+}
+
+impl SnapshotService {
+    pub fn new(repo: Arc<Repository>, profile_dir: &Path) -> Self {
+        Self {
+            repo,
+            dir: profile_dir.join("backups"),
+        }
+    }
+
+    // Takes a snapshot if the newest one is older than the configured cadence
+    pub async fn run(&self) {
+        let settings = match Settings::load(&self.repo).await {
+            Ok(settings) => settings,
+            Err(e) => {
+                error!("Failed to load backup settings: {}", e);
+                return;
+            }
+        };
+        if settings.backup_interval_hours == 0 {
+            return;
+        }
+
+        let newest = match self.list().await {
+            Ok(snapshots) => snapshots.first().map(|s| s.created_at),
+            Err(e) => {
+                error!("Failed to list backups: {}", e);
+                return;
+            }
+        };
+        let due = match newest {
+            Some(created_at) => Utc::now().timestamp() - created_at >= settings.backup_interval_hours as i64 * 3600,
+            None => true,
+        };
+
+        if due {
+            if let Err(e) = self.create(settings.backups_to_keep as usize).await {
+                error!("Scheduled backup failed: {}", e);
+            }
+        }
+    }
+
+    // Writes a new snapshot, then deletes all but the `keep` newest
+    pub async fn create(&self, keep: usize) -> Result<SnapshotInfo> {
+        tokio::fs::create_dir_all(&self.dir).await?;
+
+        let now = Utc::now();
+        let name = format!("{}{}{}", PREFIX, now.format(TIMESTAMP_FORMAT), EXTENSION);
+        let path = self.dir.join(&name);
+        self.repo.snapshot_to(&path).await?;
+
+        for old in self.list().await?.iter().skip(keep.max(1)) {
+            if let Err(e) = tokio::fs::remove_file(self.dir.join(&old.name)).await {
+                warn!("Failed to remove old backup {}: {}", old.name, e);
+            }
+        }
+
+        info!("Created backup {}", name);
+        Ok(SnapshotInfo {
+            name,
+            created_at: now.timestamp(),
+            size_bytes: tokio::fs::metadata(&path).await?.len(),
+        })
+    }
+
+    // Newest first
+    pub async fn list(&self) -> Result<Vec<SnapshotInfo>> {
+        let mut snapshots = Vec::new();
+        let mut entries = match tokio::fs::read_dir(&self.dir).await {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(snapshots),
+            Err(e) => return Err(e.into()),
+        };
+
+        while let Some(entry) = entries.next_entry().await? {
+            let name = entry.file_name().to_string_lossy().into_owned();
+            let Some(created_at) = parse_name(&name) else {
+                continue;
+            };
+            snapshots.push(SnapshotInfo {
+                name,
+                created_at,
+                size_bytes: entry.metadata().await?.len(),
+            });
+        }
+
+        snapshots.sort_by(|a, b| b.created_at.cmp(&a.created_at));
+        Ok(snapshots)
+    }
+
+    pub async fn restore(&self, name: &str) -> Result<()> {
+        // Only names we listed, so a crafted one can't point outside the backup dir
+        if !self.list().await?.iter().any(|s| s.name == name) {
+            return Err(QuonitorError::Config(format!("Backup {} not found", name)));
+        }
+
+        self.repo.restore_snapshot(&self.dir.join(name)).await?;
+        info!("Restored backup {}", name);
+        Ok(())
+    }
+}
+
+fn parse_name(name: &str) -> Option<i64> {
+    let stamp = name.strip_prefix(PREFIX)?.strip_suffix(EXTENSION)?;
+    let time = NaiveDateTime::parse_from_str(stamp, TIMESTAMP_FORMAT).ok()?;
+    Some(DateTime::<Utc>::from_naive_utc_and_offset(time, Utc).timestamp())
+}
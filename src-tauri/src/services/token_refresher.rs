@@ -0,0 +1,150 @@
+use std::sync::Arc;
+use chrono::Utc;
+use serde::Deserialize;
+use crate::db::{Repository, Account, Credentials};
+use crate::crypto::CryptoService;
+use crate::error::{QuonitorError, Result};
+use tracing::{info, warn, error};
+
+// Refresh tokens this many seconds before they actually expire
+const EXPIRY_MARGIN_SECONDS: i64 = 120;
+
+#[derive(Debug, Deserialize)]
+struct RefreshResponse {
+    access_token: String,
+    #[serde(default)]
+    refresh_token: Option<String>,
+    #[serde(default)]
+    expires_in: Option<i64>,
+}
+
+pub struct TokenRefresher {
+    repo: Arc<Repository>,
+    crypto: Arc<CryptoService>,
+    client: reqwest::Client,
+}
+
+impl TokenRefresher {
+    pub fn new(repo: Arc<Repository>, crypto: Arc<CryptoService>) -> Self {
+        Self {
+            repo,
+            crypto,
+            client: reqwest::Client::new(),
+        }
+    }
+
+    pub async fn refresh_expired(&self) {
+        // Failing here would flag every OAuth account for re-auth
+        if self.crypto.is_locked() {
+            return;
+        }
+
+        let accounts = match self.repo.get_all_accounts().await {
+            Ok(accounts) => accounts,
+            Err(e) => {
+                error!("Failed to get accounts: {}", e);
+                return;
+            }
+        };
+
+        for account in accounts.iter().filter(|a| !a.needs_reauth) {
+            if Self::token_url(&account.provider).is_none() {
+                continue;
+            }
+
+            match self.refresh_account(account).await {
+                Ok(()) => {}
+                // Only a rejected refresh token needs the user; anything else is retried next cycle
+                Err(e @ QuonitorError::Auth(_)) => {
+                    warn!("Token refresh rejected for account {}: {}", account.id, e);
+                    if let Err(e) = self.repo.set_account_needs_reauth(&account.id, true).await {
+                        error!("Failed to flag account {} for re-auth: {}", account.id, e);
+                    }
+                }
+                Err(e) => warn!("Token refresh failed for account {}, will retry: {}", account.id, e),
+            }
+        }
+    }
+
+    fn token_url(provider: &str) -> Option<&'static str> {
+        match provider {
+            "google" => Some("https://oauth2.googleapis.com/token"),
+            "github" => Some("https://github.com/login/oauth/access_token"),
+            _ => None,
+        }
+    }
+
+    async fn refresh_account(&self, account: &Account) -> Result<()> {
+        let creds_json = self.crypto.decrypt(&account.id, &account.provider, &account.credentials_encrypted)?;
+        let mut credentials: Credentials = serde_json::from_str(&creds_json)?;
+
+        // Nothing to do for PATs, unknown expiry, or tokens that are still valid
+        let Some(expires_at) = credentials.oauth_expires_at else {
+            return Ok(());
+        };
+        if expires_at > Utc::now().timestamp() + EXPIRY_MARGIN_SECONDS {
+            return Ok(());
+        }
+
+        let (Some(refresh_token), Some(client_id)) = (
+            credentials.oauth_refresh_token.clone(),
+            credentials.oauth_client_id.clone(),
+        ) else {
+            return Err(QuonitorError::Auth("Token expired and no refresh token is stored".to_string()));
+        };
+
+        let token_url = Self::token_url(&account.provider)
+            .ok_or_else(|| QuonitorError::Config(format!("Provider {} does not support token refresh", account.provider)))?;
+
+        let mut form = vec![
+            ("grant_type", "refresh_token".to_string()),
+            ("refresh_token", refresh_token),
+            ("client_id", client_id),
+        ];
+        if let Some(secret) = credentials.oauth_client_secret.clone() {
+            form.push(("client_secret", secret));
+        }
+
+        let response = self.client
+            .post(token_url)
+            .header("Accept", "application/json")
+            .form(&form)
+            .send()
+            .await?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let error_text = response.text().await.unwrap_or_else(|_| "Unknown error".to_string());
+            let message = format!("Token refresh failed ({}): {}", status, error_text);
+            return Err(match status.as_u16() {
+                400 | 401 => QuonitorError::Auth(message),
+                _ => QuonitorError::Provider(message),
+            });
+        }
+
+        // GitHub reports a rejected grant as a 200 with an error field
+        let body: serde_json::Value = response.json().await?;
+        if let Some(error) = body.get("error").and_then(|e| e.as_str()) {
+            let message = format!("Token refresh failed: {}", error);
+            return Err(match error {
+                "invalid_grant" | "bad_refresh_token" | "unauthorized_client" => QuonitorError::Auth(message),
+                _ => QuonitorError::Provider(message),
+            });
+        }
+        let refreshed: RefreshResponse = serde_json::from_value(body)?;
+
+        credentials.oauth_token = Some(refreshed.access_token);
+        credentials.oauth_expires_at = refreshed.expires_in.map(|s| Utc::now().timestamp() + s);
+        // Some providers rotate refresh tokens on every use
+        if let Some(refresh_token) = refreshed.refresh_token {
+            credentials.oauth_refresh_token = Some(refresh_token);
+        }
+
+        let encrypted = self.crypto.encrypt(&account.id, &account.provider, &serde_json::to_string(&credentials)?)?;
+        self.repo.update_account_credentials(&account.id, &encrypted).await?;
+
+        info!("Refreshed OAuth token for account {}", account.id);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,134 @@
+use std::time::Duration;
+use base64::{engine::general_purpose, Engine as _};
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use crate::crypto::{CryptoService, Rekey};
+use crate::db::Repository;
+use crate::error::{QuonitorError, Result};
+use crate::services::notifier::Alert;
+use crate::services::Settings;
+use tracing::{info, warn};
+
+const TIMEOUT: Duration = Duration::from_secs(10);
+// "sha256=<hex>" HMAC of the request body under the signing secret
+pub const SIGNATURE_HEADER: &str = "X-Quonitor-Signature";
+
+// The signing secret is kept like the SMTP password: encrypted in its own settings
+// row, bound to this pseudo-account, and never sent to the frontend
+const SECRET_KEY: &str = "webhook_signing_secret";
+const SECRET_BINDING: &str = "webhook";
+// Where `Settings` kept the secret in plain text; moved over on first use
+const LEGACY_SECRET_KEY: &str = "webhook_secret";
+
+pub async fn has_secret(repo: &Repository) -> Result<bool> {
+    let rows = repo.get_settings_matching("webhook_%secret").await?;
+    Ok(rows.contains_key(SECRET_KEY) || rows.get(LEGACY_SECRET_KEY).is_some_and(|s| !s.trim().is_empty()))
+}
+
+// Stores the secret, or with None removes it
+pub async fn set_secret(repo: &Repository, crypto: &CryptoService, secret: Option<&str>) -> Result<()> {
+    let value = match secret.filter(|secret| !secret.is_empty()) {
+        Some(secret) => Some(general_purpose::STANDARD.encode(crypto.encrypt(SECRET_BINDING, SECRET_BINDING, secret)?)),
+        None => None,
+    };
+    repo.replace_settings(&[(SECRET_KEY, value), (LEGACY_SECRET_KEY, None)]).await?;
+    Ok(())
+}
+
+async fn secret(repo: &Repository, crypto: &CryptoService) -> Result<Option<String>> {
+    let mut rows = repo.get_settings_matching("webhook_%secret").await?;
+    let Some(stored) = rows.remove(SECRET_KEY) else {
+        let legacy = rows.remove(LEGACY_SECRET_KEY).map(|s| s.trim().to_string()).filter(|s| !s.is_empty());
+        if let Some(legacy) = &legacy {
+            if let Err(e) = set_secret(repo, crypto, Some(legacy)).await {
+                warn!("Failed to encrypt the webhook secret: {}", e);
+            }
+        }
+        return Ok(legacy);
+    };
+    let sealed = general_purpose::STANDARD
+        .decode(stored.trim())
+        .map_err(|e| QuonitorError::Encryption(format!("Stored webhook secret is corrupt: {}", e)))?;
+    crypto.decrypt(SECRET_BINDING, SECRET_BINDING, &sealed).map(Some)
+}
+
+// The stored secret encrypted under a new credentials key, for changing the master
+// password; None when there is none
+pub async fn rekey_secret(repo: &Repository, crypto: &CryptoService, rekey: &Rekey) -> Result<Option<String>> {
+    match secret(repo, crypto).await? {
+        Some(secret) => Ok(Some(general_purpose::STANDARD.encode(rekey.encrypt(SECRET_BINDING, SECRET_BINDING, &secret)?))),
+        None => Ok(None),
+    }
+}
+
+pub async fn store_rekeyed_secret(repo: &Repository, sealed: Option<String>) -> Result<()> {
+    if sealed.is_some() {
+        repo.replace_settings(&[(SECRET_KEY, sealed)]).await?;
+    }
+    Ok(())
+}
+
+// Posts each alert as JSON to the URLs in `Settings::webhook_urls`, for PagerDuty,
+// n8n, Zapier and the like. Failures are logged and don't hold up other channels.
+pub struct Webhooks {
+    client: reqwest::Client,
+}
+
+impl Webhooks {
+    pub fn new() -> Self {
+        let client = reqwest::Client::builder()
+            .timeout(TIMEOUT)
+            .build()
+            .unwrap_or_default();
+
+        Self { client }
+    }
+
+    pub async fn send(&self, repo: &Repository, crypto: &CryptoService, settings: &Settings, alert: &Alert) {
+        let urls = settings.webhook_urls();
+        if urls.is_empty() {
+            return;
+        }
+
+        // An unsigned request would only be turned away by a receiver expecting a signature
+        let secret = match secret(repo, crypto).await {
+            Ok(secret) => secret,
+            Err(e) => {
+                warn!("Not sending webhooks, the signing secret can't be read: {}", e);
+                return;
+            }
+        };
+
+        let body = match serde_json::to_vec(alert) {
+            Ok(body) => body,
+            Err(e) => {
+                warn!("Failed to serialize webhook payload: {}", e);
+                return;
+            }
+        };
+        let signature = secret.as_deref().map(|secret| sign(secret, &body));
+
+        for url in urls {
+            let mut request = self.client
+                .post(url)
+                .header(reqwest::header::CONTENT_TYPE, "application/json")
+                .body(body.clone());
+            if let Some(signature) = &signature {
+                request = request.header(SIGNATURE_HEADER, signature);
+            }
+
+            match request.send().await {
+                Ok(response) if response.status().is_success() => info!("Sent alert to webhook {}", url),
+                Ok(response) => warn!("Webhook {} answered {}", url, response.status()),
+                Err(e) => warn!("Failed to send alert to webhook {}: {}", url, e),
+            }
+        }
+    }
+}
+
+fn sign(secret: &str, body: &[u8]) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes())
+        .expect("HMAC accepts keys of any length");
+    mac.update(body);
+    format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+}
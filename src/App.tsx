@@ -1,18 +1,33 @@
 import { useState, useEffect } from "react";
 import { listen } from "@tauri-apps/api/event";
 import { invoke } from "@tauri-apps/api/core";
-import { RefreshCw, Settings, Plus } from "lucide-react";
+import { useQueryClient } from "@tanstack/react-query";
+import { RefreshCw, Settings, Plus, Bell, Lock } from "lucide-react";
 import QuotaCard from "./components/QuotaCard";
 import AccountManager from "./components/AccountManager";
 import SettingsPanel from "./components/SettingsPanel";
-import { useQuotaData } from "./hooks/useQuotaData";
+import NotificationInbox from "./components/NotificationInbox";
+import UnlockPanel from "./components/UnlockPanel";
+import type { AccountResponse, CachedQuota, ConnectivityStatus } from "./types";
+import { useQuotaData, useDashboardSummary, useNotificationHistory, useLockState, useKeyStorage } from "./hooks/useQuotaData";
 
 function App() {
   const [showAccountManager, setShowAccountManager] = useState(false);
+  // Account whose credentials are being replaced
+  const [credentialsAccount, setCredentialsAccount] = useState<AccountResponse | null>(null);
+  // Card being dragged to a new position
+  const [draggedId, setDraggedId] = useState<string | null>(null);
   const [showSettings, setShowSettings] = useState(false);
+  const [showInbox, setShowInbox] = useState(false);
   const [isRefreshing, setIsRefreshing] = useState(false);
+  const [connectivity, setConnectivity] = useState<ConnectivityStatus | null>(null);
 
-  const { quotas, accounts, refetch, isLoading } = useQuotaData();
+  const { quotas, summary: totals, accounts, refetch, isLoading } = useQuotaData();
+  const { data: summary } = useDashboardSummary();
+  const { data: unread = [] } = useNotificationHistory(true);
+  const { data: lockState } = useLockState();
+  const { data: keyStorage } = useKeyStorage();
+  const queryClient = useQueryClient();
 
   useEffect(() => {
     const unlisten = listen("refresh-requested", () => {
@@ -24,10 +39,45 @@ function App() {
     };
   }, []);
 
+  useEffect(() => {
+    invoke<ConnectivityStatus>("get_connectivity").then(setConnectivity).catch(console.error);
+    const unlisten = listen<ConnectivityStatus>("connectivity-changed", (event) => {
+      setConnectivity(event.payload);
+      // Back online: the scheduler fetches straight away
+      if (event.payload.online) {
+        setTimeout(() => refetch(), 5000);
+      }
+    });
+
+    return () => {
+      unlisten.then((fn) => fn());
+    };
+  }, []);
+
+  // Scheduled fetches push their results, so the cards update without polling
+  useEffect(() => {
+    const unlisten = listen<CachedQuota>("quota-updated", (event) => {
+      const updated = event.payload;
+      queryClient.setQueryData<CachedQuota[]>(["quotas"], (quotas = []) => {
+        const index = quotas.findIndex((q) => q.account_id === updated.account_id);
+        return index === -1
+          ? [...quotas, updated]
+          : quotas.map((q, i) => (i === index ? updated : q));
+      });
+      queryClient.invalidateQueries({ queryKey: ["quota-summary"] });
+      queryClient.invalidateQueries({ queryKey: ["account-status", updated.account_id] });
+      queryClient.invalidateQueries({ queryKey: ["sparkline", updated.account_id] });
+    });
+
+    return () => {
+      unlisten.then((fn) => fn());
+    };
+  }, []);
+
   const handleRefresh = async () => {
     setIsRefreshing(true);
     try {
-      await invoke("refresh_now");
+      await invoke<boolean>("refresh_now");
       // Wait a moment for the refresh to complete
       setTimeout(() => {
         refetch();
@@ -39,27 +89,76 @@ function App() {
     }
   };
 
+  const handleLock = async () => {
+    try {
+      await invoke("lock");
+      queryClient.invalidateQueries({ queryKey: ["lock-state"] });
+    } catch (error) {
+      console.error("Failed to lock:", error);
+    }
+  };
+
+  const handleUnlocked = () => {
+    queryClient.invalidateQueries({ queryKey: ["lock-state"] });
+    // The unlock kicks off a fetch; pick up its results
+    setTimeout(() => refetch(), 1000);
+  };
+
+  const handleChooseKeyStorage = async (keyringOnly: boolean) => {
+    try {
+      await invoke("set_keyring_only", { enabled: keyringOnly });
+    } catch (error) {
+      alert(`Failed to update key storage: ${error}`);
+    } finally {
+      queryClient.invalidateQueries({ queryKey: ["key-storage"] });
+    }
+  };
+
   const handleAccountAdded = () => {
     setShowAccountManager(false);
     refetch();
   };
 
+  const handleCredentialsUpdated = () => {
+    setCredentialsAccount(null);
+    refetch();
+  };
+
+  // Cards follow the account list order, which the backend keeps
+  const position = (accountId: string) => {
+    const index = accounts.findIndex((a) => a.id === accountId);
+    return index === -1 ? accounts.length : index;
+  };
+  const orderedQuotas = [...quotas].sort((a, b) => position(a.account_id) - position(b.account_id));
+
+  const handleDrop = async (targetId: string) => {
+    if (!draggedId || draggedId === targetId) return;
+
+    const ids = orderedQuotas.map((q) => q.account_id).filter((id) => id !== draggedId);
+    ids.splice(ids.indexOf(targetId), 0, draggedId);
+    setDraggedId(null);
+    try {
+      const reordered = await invoke<AccountResponse[]>("reorder_accounts", { accountIds: ids });
+      queryClient.setQueryData(["accounts"], reordered);
+    } catch (error) {
+      console.error("Failed to reorder accounts:", error);
+    }
+  };
+
   const handleAccountDeleted = () => {
     refetch();
   };
 
   const getOverallStatus = () => {
-    if (quotas.length === 0) return "No accounts configured";
-
-    const totalCost = quotas.reduce((sum, q) => sum + (q.cost_usd || 0), 0);
-    const totalInput = quotas.reduce((sum, q) => sum + (q.tokens_input || 0), 0);
-    const totalOutput = quotas.reduce((sum, q) => sum + (q.tokens_output || 0), 0);
+    if (!totals || totals.account_count === 0) return "No accounts configured";
 
     return {
-      accountCount: quotas.length,
-      totalCost: totalCost.toFixed(2),
-      totalInput: (totalInput / 1000000).toFixed(2),
-      totalOutput: (totalOutput / 1000000).toFixed(2),
+      accountCount: totals.account_count,
+      totalCost: totals.total_cost_usd.toFixed(2),
+      totalInput: (totals.total_tokens_input / 1000000).toFixed(2),
+      totalOutput: (totals.total_tokens_output / 1000000).toFixed(2),
+      warningCount: totals.warning_count,
+      criticalCount: totals.critical_count,
     };
   };
 
@@ -79,9 +178,24 @@ function App() {
                 <>
                   {status.accountCount} {status.accountCount === 1 ? "account" : "accounts"} •
                   ${status.totalCost} total • {status.totalInput}M input / {status.totalOutput}M output tokens
+                  {status.criticalCount > 0 && (
+                    <span className="text-red-400"> • {status.criticalCount} critical</span>
+                  )}
+                  {status.warningCount > 0 && (
+                    <span className="text-yellow-400"> • {status.warningCount} warning</span>
+                  )}
                 </>
               )}
             </p>
+            {summary && summary.days > 0 && (
+              <p className="text-xs text-gray-500 mt-0.5">
+                Last 30 days: ${summary.total_cost_usd.toFixed(2)} • avg ${summary.avg_daily_cost_usd.toFixed(2)}/day
+                {" "}• ${summary.month_to_date_cost_usd.toFixed(2)} this month
+                {summary.month_over_month_percent !== null &&
+                  ` (${summary.month_over_month_percent >= 0 ? "+" : ""}${summary.month_over_month_percent.toFixed(0)}% vs last month)`}
+                {summary.top_models.length > 0 && ` • top model ${summary.top_models[0].model_name}`}
+              </p>
+            )}
           </div>
           <div className="flex items-center gap-3">
             <button
@@ -92,6 +206,26 @@ function App() {
               <RefreshCw className={`w-4 h-4 ${isRefreshing ? "animate-spin" : ""}`} />
               Refresh
             </button>
+            <button
+              onClick={() => setShowInbox(!showInbox)}
+              className="relative p-2 hover:bg-gray-700 rounded-lg transition-colors"
+            >
+              <Bell className="w-5 h-5" />
+              {unread.length > 0 && (
+                <span className="absolute -top-1 -right-1 min-w-[1.25rem] px-1 text-xs bg-red-600 rounded-full text-center">
+                  {unread.length}
+                </span>
+              )}
+            </button>
+            {lockState?.enabled && !lockState.locked && (
+              <button
+                onClick={handleLock}
+                title="Lock"
+                className="p-2 hover:bg-gray-700 rounded-lg transition-colors"
+              >
+                <Lock className="w-5 h-5" />
+              </button>
+            )}
             <button
               onClick={() => setShowSettings(!showSettings)}
               className="p-2 hover:bg-gray-700 rounded-lg transition-colors"
@@ -111,19 +245,69 @@ function App() {
 
       {/* Main Content */}
       <main className="p-6">
+        {connectivity && !connectivity.online && (
+          <div className="mb-6 bg-yellow-900/40 border border-yellow-800 rounded-lg p-3 text-sm text-yellow-300">
+            Offline since {new Date(connectivity.since * 1000).toLocaleTimeString()}. Fetching resumes when the
+            connection is back.
+          </div>
+        )}
+
+        {keyStorage && !keyStorage.chosen && (
+          <div className="mb-6 bg-gray-800 rounded-lg border border-gray-700 p-4">
+            <p className="text-sm text-gray-300 mb-3">
+              Where should Quonitor keep the key that encrypts your credentials? Keeping it only in the system
+              keyring means a copy of the data directory can't be decrypted on its own, but Quonitor won't start
+              without the keyring.
+            </p>
+            <div className="flex gap-2">
+              <button
+                onClick={() => handleChooseKeyStorage(true)}
+                className="px-4 py-2 bg-blue-600 hover:bg-blue-700 rounded transition-colors"
+              >
+                System keyring only
+              </button>
+              <button
+                onClick={() => handleChooseKeyStorage(false)}
+                className="px-4 py-2 bg-gray-700 hover:bg-gray-600 rounded transition-colors"
+              >
+                Keyring and key file
+              </button>
+            </div>
+          </div>
+        )}
+
         {showAccountManager && (
           <div className="mb-6">
             <AccountManager onAccountAdded={handleAccountAdded} onClose={() => setShowAccountManager(false)} />
           </div>
         )}
 
+        {credentialsAccount && (
+          <div className="mb-6">
+            <AccountManager
+              key={credentialsAccount.id}
+              account={credentialsAccount}
+              onAccountAdded={handleCredentialsUpdated}
+              onClose={() => setCredentialsAccount(null)}
+            />
+          </div>
+        )}
+
+        {showInbox && (
+          <div className="mb-6">
+            <NotificationInbox onClose={() => setShowInbox(false)} />
+          </div>
+        )}
+
         {showSettings && (
           <div className="mb-6">
             <SettingsPanel onClose={() => setShowSettings(false)} />
           </div>
         )}
 
-        {isLoading ? (
+        {lockState?.locked ? (
+          <UnlockPanel onUnlocked={handleUnlocked} />
+        ) : isLoading ? (
           <div className="flex items-center justify-center h-64">
             <div className="text-gray-400">Loading...</div>
           </div>
@@ -140,15 +324,25 @@ function App() {
           </div>
         ) : (
           <div className="grid grid-cols-1 lg:grid-cols-2 xl:grid-cols-3 gap-6">
-            {quotas.map((quota) => {
+            {orderedQuotas.map((quota) => {
               const account = accounts.find((a) => a.id === quota.account_id);
               return (
-                <QuotaCard
+                <div
                   key={quota.account_id}
-                  quota={quota}
-                  account={account}
-                  onDelete={handleAccountDeleted}
-                />
+                  draggable
+                  onDragStart={() => setDraggedId(quota.account_id)}
+                  onDragEnd={() => setDraggedId(null)}
+                  onDragOver={(e) => e.preventDefault()}
+                  onDrop={() => handleDrop(quota.account_id)}
+                  className={draggedId === quota.account_id ? "opacity-50" : ""}
+                >
+                  <QuotaCard
+                    quota={quota}
+                    account={account}
+                    onDelete={handleAccountDeleted}
+                    onUpdateCredentials={account ? () => setCredentialsAccount(account) : undefined}
+                  />
+                </div>
               );
             })}
           </div>
@@ -1,20 +1,41 @@
 import { useState } from "react";
 import { invoke } from "@tauri-apps/api/core";
 import { open } from "@tauri-apps/plugin-shell";
-import { X, ExternalLink } from "lucide-react";
-import type { Credentials } from "../types";
+import { X, ExternalLink, CheckCircle, XCircle } from "lucide-react";
+import { useProviders } from "../hooks/useQuotaData";
+import type { AccountResponse, AuthMethod, CredentialTest, Credentials } from "../types";
+
+// Credential field each auth method fills in
+const AUTH_FIELDS: Record<AuthMethod, keyof Credentials> = {
+  api_key: "api_key",
+  oauth: "oauth_token",
+  service_account: "service_account_json",
+};
+
+const FIELD_LABELS: Partial<Record<keyof Credentials, string>> = {
+  api_key: "API Key",
+  oauth_token: "OAuth Token",
+  service_account_json: "Service Account JSON",
+  admin_key: "Admin Key",
+  organization: "Organization",
+  billing_export_table: "Billing Export Table",
+};
 
 interface AccountManagerProps {
+  // Replace this account's credentials instead of adding an account
+  account?: AccountResponse;
   onAccountAdded: () => void;
   onClose: () => void;
 }
 
-export default function AccountManager({ onAccountAdded, onClose }: AccountManagerProps) {
-  const [provider, setProvider] = useState("openai");
-  const [name, setName] = useState("");
+export default function AccountManager({ account, onAccountAdded, onClose }: AccountManagerProps) {
+  const { data: providers = [] } = useProviders();
+  const [provider, setProvider] = useState(account?.provider ?? "openai");
+  const [name, setName] = useState(account?.name ?? "");
   
-  // Standard API Key
-  const [apiKey, setApiKey] = useState("");
+  // Credential fields for everything except the Google OAuth flow
+  const [authMethod, setAuthMethod] = useState<AuthMethod>("api_key");
+  const [fields, setFields] = useState<Partial<Record<keyof Credentials, string>>>({});
   
   // OAuth (Google)
   const [clientId, setClientId] = useState("");
@@ -24,6 +45,16 @@ export default function AccountManager({ onAccountAdded, onClose }: AccountManag
   
   const [isSubmitting, setIsSubmitting] = useState(false);
   const [error, setError] = useState("");
+  const [isTesting, setIsTesting] = useState(false);
+  const [testResult, setTestResult] = useState<CredentialTest | null>(null);
+
+  const info = providers.find((p) => p.id === provider);
+  const methods = info?.auth_methods.length ? info.auth_methods : (["api_key"] as AuthMethod[]);
+  const activeMethod = methods.includes(authMethod) ? authMethod : methods[0];
+  const primaryField = AUTH_FIELDS[activeMethod];
+  const methodFields = info?.method_fields[activeMethod] ?? [];
+  const requiredFields = [...(info?.required_fields ?? []), ...methodFields];
+  const optionalFields = (info?.optional_fields ?? []).filter((field) => !methodFields.includes(field));
 
   const handleGenerateAuthUrl = async () => {
     if (!clientId.trim() || !clientSecret.trim()) {
@@ -53,6 +84,37 @@ export default function AccountManager({ onAccountAdded, onClose }: AccountManag
     }
   };
 
+  // The entered fields, trimmed; throws naming the first required field left empty
+  const buildCredentials = (): Credentials => {
+    const missing = [primaryField, ...requiredFields]
+      .find((field) => !fields[field]?.trim());
+    if (missing) {
+      throw new Error(`${FIELD_LABELS[missing] ?? missing} is required`);
+    }
+
+    const credentials: Credentials = {};
+    for (const [field, value] of Object.entries(fields)) {
+      if (value?.trim()) {
+        (credentials as Record<string, string>)[field] = value.trim();
+      }
+    }
+    return credentials;
+  };
+
+  const handleTest = async () => {
+    setError("");
+    setTestResult(null);
+    setIsTesting(true);
+    try {
+      const credentials = buildCredentials();
+      setTestResult(await invoke<CredentialTest>("test_credentials", { provider, credentials }));
+    } catch (err) {
+      setError(err instanceof Error ? err.message : String(err));
+    } finally {
+      setIsTesting(false);
+    }
+  };
+
   const handleSubmit = async (e: React.FormEvent) => {
     e.preventDefault();
     setError("");
@@ -80,37 +142,128 @@ export default function AccountManager({ onAccountAdded, onClose }: AccountManag
           code: authCode.trim(),
         });
       } else {
-        // Standard API Key
-        if (!apiKey.trim()) {
-          throw new Error("API Key is required");
-        }
-        credentials = { api_key: apiKey };
+        credentials = buildCredentials();
       }
 
-      await invoke("add_account", {
-        request: {
-          provider,
-          name: name.trim(),
-          credentials,
-        },
-      });
+      if (account) {
+        await invoke("update_account_credentials", { accountId: account.id, credentials });
+      } else {
+        await invoke("add_account", {
+          request: {
+            provider,
+            name: name.trim(),
+            credentials,
+          },
+        });
+      }
 
       onAccountAdded();
       // Reset form
       setName("");
-      setApiKey("");
+      setFields({});
       setClientId("");
       setClientSecret("");
       setAuthCode("");
       setAuthUrl("");
     } catch (err) {
-      console.error("Failed to add account:", err);
-      setError(err instanceof Error ? err.message : "Failed to add account");
+      console.error("Failed to save account:", err);
+      const fallback = account ? "Failed to update credentials" : "Failed to add account";
+      setError(err instanceof Error ? err.message : typeof err === "string" ? err : fallback);
     } finally {
       setIsSubmitting(false);
     }
   };
 
+  const renderField = (field: keyof Credentials, required: boolean) => (
+    <div key={field}>
+      <label className="block text-sm font-medium text-gray-300 mb-2">
+        {FIELD_LABELS[field] ?? field}
+        {!required && <span className="text-gray-500"> (optional)</span>}
+      </label>
+      {field === "service_account_json" ? (
+        <textarea
+          value={fields[field] ?? ""}
+          onChange={(e) => {
+            setFields({ ...fields, [field]: e.target.value });
+            setTestResult(null);
+          }}
+          rows={4}
+          className="w-full bg-gray-700 border border-gray-600 rounded px-3 py-2 text-white placeholder-gray-400 font-mono text-xs"
+        />
+      ) : (
+        <input
+          type={field === "organization" || field === "billing_export_table" ? "text" : "password"}
+          value={fields[field] ?? ""}
+          onChange={(e) => {
+            setFields({ ...fields, [field]: e.target.value });
+            setTestResult(null);
+          }}
+          className="w-full bg-gray-700 border border-gray-600 rounded px-3 py-2 text-white placeholder-gray-400 font-mono"
+        />
+      )}
+    </div>
+  );
+
+  const renderCredentialFields = () => (
+    <div className="space-y-4">
+      {methods.length > 1 && (
+        <div>
+          <label className="block text-sm font-medium text-gray-300 mb-2">
+            Authentication
+          </label>
+          <select
+            value={activeMethod}
+            onChange={(e) => setAuthMethod(e.target.value as AuthMethod)}
+            className="w-full bg-gray-700 border border-gray-600 rounded px-3 py-2 text-white"
+          >
+            {methods.map((method) => (
+              <option key={method} value={method}>
+                {FIELD_LABELS[AUTH_FIELDS[method]]}
+              </option>
+            ))}
+          </select>
+        </div>
+      )}
+      {renderField(primaryField, true)}
+      {requiredFields.map((field) => renderField(field, true))}
+      {optionalFields.map((field) => renderField(field, false))}
+      <p className="text-xs text-gray-400 mt-1">
+        Your credentials are encrypted and stored securely in your system keychain
+      </p>
+      {info && !info.reports_limits && (
+        <p className="text-xs text-gray-400">
+          {info.name} only reports spend, not quota limits
+        </p>
+      )}
+      <button
+        type="button"
+        onClick={handleTest}
+        disabled={isTesting || isSubmitting || !fields[primaryField]?.trim()}
+        className="px-3 py-1.5 bg-gray-700 hover:bg-gray-600 disabled:opacity-50 rounded transition-colors text-sm"
+      >
+        {isTesting ? "Testing..." : "Test connection"}
+      </button>
+      {testResult && (testResult.success ? (
+        <div className="p-3 bg-green-900/30 border border-green-700 rounded text-green-300 text-sm flex items-center gap-2">
+          <CheckCircle className="w-4 h-4 shrink-0" />
+          Connected
+          {testResult.quota?.cost_usd != null && ` • $${testResult.quota.cost_usd.toFixed(2)} spent`}
+          {testResult.quota && testResult.quota.model_breakdown.length > 0 &&
+            ` • ${testResult.quota.model_breakdown.length} models`}
+        </div>
+      ) : (
+        <div className="p-3 bg-red-900/30 border border-red-700 rounded text-red-300 text-sm">
+          <p className="font-medium flex items-center gap-2">
+            <XCircle className="w-4 h-4 shrink-0" />
+            Connection failed ({testResult.error_kind}
+            {testResult.http_status !== null && ` ${testResult.http_status}`})
+          </p>
+          <p className="text-red-200/80 mt-1 break-words">{testResult.message}</p>
+        </div>
+      ))}
+    </div>
+  );
+
   const renderGoogleAuth = () => (
     <div className="space-y-4">
       <div>
@@ -171,7 +324,9 @@ export default function AccountManager({ onAccountAdded, onClose }: AccountManag
   return (
     <div className="bg-gray-800 rounded-lg border border-gray-700 p-6">
       <div className="flex items-center justify-between mb-4">
-        <h2 className="text-xl font-semibold">Add New Account</h2>
+        <h2 className="text-xl font-semibold">
+          {account ? `Update Credentials for ${account.name}` : "Add New Account"}
+        </h2>
         <button
           onClick={onClose}
           className="p-1 hover:bg-gray-700 rounded transition-colors"
@@ -193,62 +348,50 @@ export default function AccountManager({ onAccountAdded, onClose }: AccountManag
           </label>
           <select
             value={provider}
+            disabled={!!account}
             onChange={(e) => {
               setProvider(e.target.value);
               setError("");
               setAuthUrl("");
+              setFields({});
+              setTestResult(null);
             }}
-            className="w-full bg-gray-700 border border-gray-600 rounded px-3 py-2 text-white"
+            className="w-full bg-gray-700 border border-gray-600 rounded px-3 py-2 text-white disabled:opacity-60"
           >
-            <option value="openai">OpenAI</option>
-            {/* Anthropic hidden until usage API is supported
-            <option value="anthropic">Anthropic / Claude</option>
-            */}
-            <option value="google">Google / Antigravity</option>
-            <option value="github">GitHub Copilot (Coming Soon)</option>
+            {providers.map((p) => (
+              <option key={p.id} value={p.id}>
+                {p.name}
+              </option>
+            ))}
           </select>
         </div>
 
-        <div>
-          <label className="block text-sm font-medium text-gray-300 mb-2">
-            Account Name
-          </label>
-          <input
-            type="text"
-            value={name}
-            onChange={(e) => setName(e.target.value)}
-            placeholder="e.g., Work Account, Personal"
-            className="w-full bg-gray-700 border border-gray-600 rounded px-3 py-2 text-white placeholder-gray-400"
-          />
-        </div>
-
-        {provider === "google" ? (
-          renderGoogleAuth()
-        ) : (
+        {!account && (
           <div>
             <label className="block text-sm font-medium text-gray-300 mb-2">
-              API Key
+              Account Name
             </label>
             <input
-              type="password"
-              value={apiKey}
-              onChange={(e) => setApiKey(e.target.value)}
-              placeholder="sk-..."
-              className="w-full bg-gray-700 border border-gray-600 rounded px-3 py-2 text-white placeholder-gray-400 font-mono"
+              type="text"
+              value={name}
+              onChange={(e) => setName(e.target.value)}
+              placeholder="e.g., Work Account, Personal"
+              className="w-full bg-gray-700 border border-gray-600 rounded px-3 py-2 text-white placeholder-gray-400"
             />
-            <p className="text-xs text-gray-400 mt-1">
-              Your API key is encrypted and stored securely in your system keychain
-            </p>
           </div>
         )}
 
+        {provider === "google" ? renderGoogleAuth() : renderCredentialFields()}
+
         <div className="flex gap-3 pt-2">
           <button
             type="submit"
-            disabled={isSubmitting || (provider === "google" && !authCode) || (provider !== "google" && !apiKey)}
+            disabled={isSubmitting || (provider === "google" ? !authCode : !fields[primaryField]?.trim())}
             className="flex-1 px-4 py-2 bg-green-600 hover:bg-green-700 disabled:bg-gray-600 rounded transition-colors"
           >
-            {isSubmitting ? "Adding..." : "Add Account"}
+            {account
+              ? isSubmitting ? "Checking..." : "Update Credentials"
+              : isSubmitting ? "Adding..." : "Add Account"}
           </button>
           <button
             type="button"
@@ -0,0 +1,75 @@
+import { invoke } from "@tauri-apps/api/core";
+import { useQueryClient } from "@tanstack/react-query";
+import { X, Check } from "lucide-react";
+import { useQuotaData, useNotificationHistory } from "../hooks/useQuotaData";
+
+interface NotificationInboxProps {
+  onClose: () => void;
+}
+
+export default function NotificationInbox({ onClose }: NotificationInboxProps) {
+  const queryClient = useQueryClient();
+  const { accounts } = useQuotaData();
+  const { data: notifications = [], isLoading } = useNotificationHistory();
+
+  const handleAcknowledge = async (notificationId: number) => {
+    try {
+      await invoke("acknowledge_notification", { notificationId });
+      queryClient.invalidateQueries({ queryKey: ["notifications"] });
+    } catch (error) {
+      console.error("Failed to acknowledge notification:", error);
+    }
+  };
+
+  const accountName = (accountId: string | null) =>
+    accountId ? accounts.find((a) => a.id === accountId)?.name ?? accountId : null;
+
+  return (
+    <div className="bg-gray-800 rounded-lg border border-gray-700 p-6">
+      <div className="flex items-center justify-between mb-4">
+        <h2 className="text-xl font-semibold">Notifications</h2>
+        <button
+          onClick={onClose}
+          className="p-1 hover:bg-gray-700 rounded transition-colors"
+        >
+          <X className="w-5 h-5" />
+        </button>
+      </div>
+
+      {isLoading ? (
+        <p className="text-sm text-gray-400">Loading...</p>
+      ) : notifications.length === 0 ? (
+        <p className="text-sm text-gray-400">No notifications yet</p>
+      ) : (
+        <ul className="space-y-2 max-h-96 overflow-y-auto">
+          {notifications.map((notification) => (
+            <li
+              key={notification.id}
+              className={`flex items-start justify-between gap-4 p-3 rounded-lg ${
+                notification.acknowledged ? "bg-gray-900/50 text-gray-500" : "bg-gray-900"
+              }`}
+            >
+              <div>
+                <p className="font-medium">{notification.title}</p>
+                <p className="text-sm">{notification.message}</p>
+                <p className="text-xs text-gray-500 mt-1">
+                  {new Date(notification.timestamp * 1000).toLocaleString()}
+                  {accountName(notification.account_id) && ` • ${accountName(notification.account_id)}`}
+                </p>
+              </div>
+              {!notification.acknowledged && (
+                <button
+                  onClick={() => handleAcknowledge(notification.id)}
+                  title="Mark as read"
+                  className="p-1 hover:bg-gray-700 rounded transition-colors"
+                >
+                  <Check className="w-4 h-4" />
+                </button>
+              )}
+            </li>
+          ))}
+        </ul>
+      )}
+    </div>
+  );
+}
@@ -1,19 +1,219 @@
 import { useState } from "react";
 import { invoke } from "@tauri-apps/api/core";
-import { Trash2, ChevronDown, ChevronUp } from "lucide-react";
+import { useQueryClient } from "@tanstack/react-query";
+import { Star, KeyRound, Pencil, Copy, Trash2, ChevronDown, ChevronUp, BellOff, AlertTriangle } from "lucide-react";
 import TrendChart from "./TrendChart";
-import type { QuotaData, AccountResponse } from "../types";
+import Sparkline from "./Sparkline";
+import { useAccountErrors, useAccountBackoff, useAccountStatus, useAccountSettings, useAccountNotificationPrefs, useBudgets } from "../hooks/useQuotaData";
+import type { CachedQuota, AccountResponse, AccountUpdate, ProviderStatus, BudgetPeriod, AccountNotificationPrefs, NotificationChannel } from "../types";
 
 interface QuotaCardProps {
-  quota: QuotaData;
+  quota: CachedQuota;
   account?: AccountResponse;
   onDelete: () => void;
+  onUpdateCredentials?: () => void;
 }
 
-export default function QuotaCard({ quota, account, onDelete }: QuotaCardProps) {
+export default function QuotaCard({ quota, account, onDelete, onUpdateCredentials }: QuotaCardProps) {
   const [showModels, setShowModels] = useState(false);
+  const [showKeys, setShowKeys] = useState(false);
   const [showChart, setShowChart] = useState(false);
   const [isDeleting, setIsDeleting] = useState(false);
+  const queryClient = useQueryClient();
+  const [status, setStatus] = useState<ProviderStatus | null>(null);
+  const { data: errors = [] } = useAccountErrors(quota.account_id, 1);
+  // Only errors since the data shown here was fetched mean the card is stale
+  const lastError = errors.find((e) => e.timestamp > quota.timestamp);
+  const { data: backoff, refetch: refetchBackoff } = useAccountBackoff(quota.account_id);
+  const { data: fetchStatus, refetch: refetchStatus } = useAccountStatus(quota.account_id);
+  const [isRetrying, setIsRetrying] = useState(false);
+  const { data: allBudgets = [], refetch: refetchBudgets } = useBudgets();
+  // Alerts use these when the provider reports no quota limit
+  const budgets = allBudgets.filter((b) => b.scope === "account" && b.target === quota.account_id);
+  const [budgetPeriod, setBudgetPeriod] = useState<BudgetPeriod>("monthly");
+  const [budgetAmount, setBudgetAmount] = useState("");
+  const [showNotifications, setShowNotifications] = useState(false);
+  const { data: savedPrefs, refetch: refetchPrefs } = useAccountNotificationPrefs(quota.account_id);
+  const [prefsDraft, setPrefsDraft] = useState<AccountNotificationPrefs | null>(null);
+  const prefs = prefsDraft ?? savedPrefs;
+  const snoozedUntil = savedPrefs?.snoozed_until && savedPrefs.snoozed_until * 1000 > Date.now()
+    ? savedPrefs.snoozed_until
+    : null;
+  const isSilenced = savedPrefs !== undefined && (!savedPrefs.enabled || snoozedUntil !== null);
+  const { data: accountSettings, refetch: refetchAccountSettings } = useAccountSettings(quota.account_id);
+  const [pushTargets, setPushTargets] = useState<string | null>(null);
+
+  const handleRetry = async () => {
+    setIsRetrying(true);
+    try {
+      await invoke("refresh_account", { accountId: quota.account_id });
+    } catch (error) {
+      console.error("Failed to refresh account:", error);
+    } finally {
+      setIsRetrying(false);
+      refetchBackoff();
+      refetchStatus();
+    }
+  };
+
+  // One budget per period: setting it again replaces the amount
+  const handleSetBudget = async () => {
+    const amount = parseFloat(budgetAmount);
+    if (!(amount > 0)) return;
+    const existing = budgets.find((b) => b.period === budgetPeriod);
+    try {
+      await invoke("set_budget", {
+        request: {
+          id: existing?.id ?? null,
+          name: `${account?.name || quota.account_id} ${budgetPeriod}`,
+          scope: "account",
+          target: quota.account_id,
+          amount_usd: amount,
+          period: budgetPeriod,
+          reset_anchor: existing?.reset_anchor ?? null,
+        },
+      });
+      setBudgetAmount("");
+      refetchBudgets();
+    } catch (error) {
+      console.error("Failed to set budget:", error);
+      alert(`Failed to set budget: ${error}`);
+    }
+  };
+
+  const handleRemoveBudget = async (id: string) => {
+    try {
+      await invoke("remove_budget", { id });
+      refetchBudgets();
+    } catch (error) {
+      console.error("Failed to remove budget:", error);
+    }
+  };
+
+  const updatePrefs = (patch: Partial<AccountNotificationPrefs>) => {
+    if (prefs) setPrefsDraft({ ...prefs, ...patch });
+  };
+
+  const togglePrefsChannel = (channel: NotificationChannel) => {
+    const channels = prefs?.channels ?? [];
+    updatePrefs({
+      channels: channels.includes(channel) ? channels.filter((c) => c !== channel) : [...channels, channel],
+    });
+  };
+
+  // Mute and snooze take effect at once, and are carried into any unsaved edits
+  const handleMute = async (muted: boolean) => {
+    try {
+      await invoke("mute_account", { accountId: quota.account_id, muted });
+      setPrefsDraft((draft) => draft && { ...draft, enabled: !muted });
+      refetchPrefs();
+    } catch (error) {
+      console.error("Failed to mute account:", error);
+    }
+  };
+
+  const handleSnooze = async (durationMinutes: number) => {
+    try {
+      const until = await invoke<number | null>("snooze_notifications", {
+        accountId: quota.account_id,
+        durationMinutes,
+      });
+      setPrefsDraft((draft) => draft && { ...draft, snoozed_until: until });
+      refetchPrefs();
+    } catch (error) {
+      console.error("Failed to snooze notifications:", error);
+    }
+  };
+
+  const minutesToMonthEnd = () => {
+    const now = new Date();
+    const nextMonth = new Date(now.getFullYear(), now.getMonth() + 1, 1);
+    return Math.ceil((nextMonth.getTime() - now.getTime()) / 60000);
+  };
+
+  const handleResume = async () => {
+    if (snoozedUntil !== null) await handleSnooze(0);
+    if (savedPrefs && !savedPrefs.enabled) await handleMute(false);
+  };
+
+  const handleSavePrefs = async () => {
+    try {
+      await invoke("set_account_notification_prefs", { prefs: prefsDraft });
+      setPrefsDraft(null);
+      refetchPrefs();
+    } catch (error) {
+      console.error("Failed to save notification preferences:", error);
+      alert(`Failed to save notification preferences: ${error}`);
+    }
+  };
+
+  // Empty falls back to the push targets in the settings
+  const handleSavePushTargets = async () => {
+    try {
+      await invoke("set_account_setting", {
+        accountId: quota.account_id,
+        key: "push_targets",
+        value: pushTargets ?? "",
+      });
+      setPushTargets(null);
+      refetchAccountSettings();
+    } catch (error) {
+      console.error("Failed to save push targets:", error);
+      alert(`Failed to save push targets: ${error}`);
+    }
+  };
+
+  const handleCheckStatus = async () => {
+    if (!account) return;
+    try {
+      setStatus(await invoke<ProviderStatus>("check_provider_status", { provider: account.provider }));
+    } catch (error) {
+      console.error("Failed to check provider status:", error);
+    }
+  };
+
+  const handleRename = async () => {
+    const name = prompt("Account name", account?.name || "");
+    if (name === null || name.trim() === "" || name.trim() === account?.name) {
+      return;
+    }
+
+    try {
+      const update: AccountUpdate = { name };
+      await invoke("update_account", { accountId: quota.account_id, update });
+      queryClient.invalidateQueries({ queryKey: ["accounts"] });
+    } catch (error) {
+      alert(`Failed to rename account: ${error}`);
+    }
+  };
+
+  const handleDuplicate = async () => {
+    const name = prompt("Name for the copy", `${account?.name || quota.account_id} (copy)`);
+    if (name === null || name.trim() === "") {
+      return;
+    }
+
+    try {
+      await invoke("duplicate_account", { accountId: quota.account_id, newName: name });
+      queryClient.invalidateQueries({ queryKey: ["accounts"] });
+      // The copy shows up once its first fetch lands
+      setTimeout(() => queryClient.invalidateQueries({ queryKey: ["quotas"] }), 2000);
+    } catch (error) {
+      alert(`Failed to duplicate account: ${error}`);
+    }
+  };
+
+  const handleToggleFavorite = async () => {
+    if (!account) return;
+
+    try {
+      const update: AccountUpdate = { favorite: !account.favorite };
+      await invoke("update_account", { accountId: quota.account_id, update });
+      queryClient.invalidateQueries({ queryKey: ["accounts"] });
+    } catch (error) {
+      console.error("Failed to update favorite:", error);
+    }
+  };
 
   const handleDelete = async () => {
     if (!confirm(`Delete account "${account?.name || quota.account_id}"?`)) {
@@ -59,6 +259,7 @@ export default function QuotaCard({ quota, account, onDelete }: QuotaCardProps)
   };
 
   const hasModelBreakdown = quota.model_breakdown && quota.model_breakdown.length > 0;
+  const hasKeyBreakdown = quota.api_key_breakdown && quota.api_key_breakdown.length > 0;
 
   return (
     <div className="bg-gray-800 rounded-lg border border-gray-700 overflow-hidden">
@@ -66,19 +267,110 @@ export default function QuotaCard({ quota, account, onDelete }: QuotaCardProps)
       <div className={`${getProviderColor(account?.provider || "")} px-4 py-3 flex items-center justify-between`}>
         <div>
           <h3 className="font-semibold text-white">{account?.name || quota.account_id}</h3>
-          <p className="text-xs text-white/80 uppercase">{account?.provider || "Unknown"}</p>
+          <p className="text-xs text-white/80 uppercase">
+            {account?.provider || "Unknown"}
+            {quota.is_stale && (
+              <span
+                className="ml-2 normal-case"
+                title={quota.source === "restored"
+                  ? `Last stored ${formatDate(quota.fetched_at)}; not fetched yet`
+                  : `Last fetched ${formatDate(quota.fetched_at)}`}
+              >
+                (stale)
+              </span>
+            )}
+          </p>
+        </div>
+        <div className="flex items-center">
+          <Sparkline accountId={quota.account_id} />
+          {fetchStatus && fetchStatus.failure_count > 0 && (
+            <span
+              className="p-2"
+              title={`${fetchStatus.failure_count} failed fetch${fetchStatus.failure_count === 1 ? "" : "es"} in a row: ${fetchStatus.last_error}${
+                fetchStatus.last_success_at ? `\nLast success ${formatDate(fetchStatus.last_success_at)}` : ""
+              }`}
+            >
+              <AlertTriangle className="w-4 h-4 text-yellow-300" />
+            </span>
+          )}
+          {isSilenced && (
+            <button
+              onClick={handleResume}
+              title={snoozedUntil !== null ? `Snoozed until ${formatDate(snoozedUntil)}; click to resume` : "Muted; click to unmute"}
+              className="p-2 hover:bg-white/10 rounded transition-colors"
+            >
+              <BellOff className="w-4 h-4 text-white" />
+            </button>
+          )}
+          {account && (
+            <button
+              onClick={handleToggleFavorite}
+              title={account.favorite ? "Remove from favorites" : "Pin to the top"}
+              className="p-2 hover:bg-white/10 rounded transition-colors"
+            >
+              <Star className={`w-4 h-4 text-white ${account.favorite ? "fill-current" : ""}`} />
+            </button>
+          )}
+          {onUpdateCredentials && (
+            <button
+              onClick={onUpdateCredentials}
+              title={account?.needs_reauth ? "Credentials need updating" : "Update credentials"}
+              className="p-2 hover:bg-white/10 rounded transition-colors"
+            >
+              <KeyRound className={`w-4 h-4 ${account?.needs_reauth ? "text-yellow-300" : "text-white"}`} />
+            </button>
+          )}
+          <button
+            onClick={handleRename}
+            title="Rename"
+            className="p-2 hover:bg-white/10 rounded transition-colors"
+          >
+            <Pencil className="w-4 h-4 text-white" />
+          </button>
+          <button
+            onClick={handleDuplicate}
+            title="Duplicate"
+            className="p-2 hover:bg-white/10 rounded transition-colors"
+          >
+            <Copy className="w-4 h-4 text-white" />
+          </button>
+          <button
+            onClick={handleDelete}
+            disabled={isDeleting}
+            className="p-2 hover:bg-white/10 rounded transition-colors disabled:opacity-50"
+          >
+            <Trash2 className="w-4 h-4 text-white" />
+          </button>
         </div>
-        <button
-          onClick={handleDelete}
-          disabled={isDeleting}
-          className="p-2 hover:bg-white/10 rounded transition-colors disabled:opacity-50"
-        >
-          <Trash2 className="w-4 h-4 text-white" />
-        </button>
       </div>
 
       {/* Content */}
-      <div className="p-4 space-y-4">
+      <div className={`p-4 space-y-4 ${quota.is_stale ? "opacity-60" : ""}`}>
+        {lastError && (
+          <div className="text-xs bg-red-900/40 border border-red-800 rounded p-2">
+            <p className="text-red-300 font-medium">
+              Last fetch failed {formatDate(lastError.timestamp)} ({lastError.error_kind}
+              {lastError.http_status !== null && ` ${lastError.http_status}`})
+            </p>
+            <p className="text-red-200/80 mt-1 break-words">{lastError.message}</p>
+          </div>
+        )}
+
+        {backoff?.tripped_at != null && (
+          <div className="text-xs bg-yellow-900/40 border border-yellow-800 rounded p-2 flex items-center justify-between gap-2">
+            <p className="text-yellow-300">
+              Paused after {backoff.consecutive_failures} failed fetches until {formatDate(backoff.next_attempt_at)}
+            </p>
+            <button
+              onClick={handleRetry}
+              disabled={isRetrying}
+              className="px-2 py-1 bg-gray-700 hover:bg-gray-600 rounded text-white disabled:opacity-50"
+            >
+              Retry now
+            </button>
+          </div>
+        )}
+
         {/* Token Usage */}
         <div className="grid grid-cols-2 gap-3">
           <div>
@@ -122,6 +414,62 @@ export default function QuotaCard({ quota, account, onDelete }: QuotaCardProps)
           </div>
         )}
 
+        {/* Budgets */}
+        <div className="space-y-2">
+          {budgets.map((budget) => (
+            <div key={budget.id}>
+              <div className="flex justify-between text-xs text-gray-400 mb-1">
+                <span className="capitalize">{budget.period} budget</span>
+                <span className="flex items-center gap-2">
+                  ${budget.spent_usd.toFixed(2)} / ${budget.amount_usd.toFixed(2)}
+                  <button onClick={() => handleRemoveBudget(budget.id)} className="hover:text-white">
+                    <Trash2 className="w-3 h-3" />
+                  </button>
+                </span>
+              </div>
+              <div className="w-full bg-gray-700 rounded-full h-2">
+                <div
+                  className={`${budget.percent_used >= 90 ? "bg-red-500" : budget.percent_used >= 75 ? "bg-yellow-500" : "bg-green-500"} h-2 rounded-full transition-all`}
+                  style={{ width: `${Math.min(budget.percent_used, 100)}%` }}
+                />
+              </div>
+              {budget.projected_usd !== null && budget.projected_usd > budget.amount_usd && (
+                <p className="text-xs text-yellow-400 mt-1">
+                  On track for ${budget.projected_usd.toFixed(2)} (
+                  {Math.round((budget.projected_usd / budget.amount_usd) * 100)}%) by the end of the period
+                </p>
+              )}
+            </div>
+          ))}
+          <div className="flex gap-2 text-xs">
+            <select
+              value={budgetPeriod}
+              onChange={(e) => setBudgetPeriod(e.target.value as BudgetPeriod)}
+              className="px-2 py-1 bg-gray-700 border border-gray-600 rounded"
+            >
+              <option value="daily">Daily</option>
+              <option value="weekly">Weekly</option>
+              <option value="monthly">Monthly</option>
+            </select>
+            <input
+              type="number"
+              min="0"
+              step="0.01"
+              value={budgetAmount}
+              onChange={(e) => setBudgetAmount(e.target.value)}
+              placeholder="Budget (USD)"
+              className="flex-1 min-w-0 px-2 py-1 bg-gray-700 border border-gray-600 rounded"
+            />
+            <button
+              onClick={handleSetBudget}
+              disabled={!(parseFloat(budgetAmount) > 0)}
+              className="px-2 py-1 bg-gray-700 hover:bg-gray-600 rounded disabled:opacity-50"
+            >
+              Set
+            </button>
+          </div>
+        </div>
+
         {/* Model Breakdown */}
         {hasModelBreakdown && (
           <div>
@@ -162,6 +510,181 @@ export default function QuotaCard({ quota, account, onDelete }: QuotaCardProps)
           </div>
         )}
 
+        {/* API Key Breakdown */}
+        {hasKeyBreakdown && (
+          <div>
+            <button
+              onClick={() => setShowKeys(!showKeys)}
+              className="flex items-center justify-between w-full text-sm text-gray-300 hover:text-white transition-colors"
+            >
+              <span>API Keys ({quota.api_key_breakdown.length})</span>
+              {showKeys ? <ChevronUp className="w-4 h-4" /> : <ChevronDown className="w-4 h-4" />}
+            </button>
+
+            {showKeys && (
+              <div className="mt-3 space-y-2">
+                {[...quota.api_key_breakdown]
+                  .sort((a, b) => b.cost_usd - a.cost_usd)
+                  .map((key) => (
+                    <div key={key.api_key_id} className="bg-gray-700/50 rounded p-3 flex justify-between text-sm">
+                      <span className="font-mono text-xs truncate">{key.api_key_id}</span>
+                      <span className="text-gray-400">
+                        {key.request_count} req · <span className="text-green-400">${key.cost_usd.toFixed(4)}</span>
+                      </span>
+                    </div>
+                  ))}
+              </div>
+            )}
+          </div>
+        )}
+
+        {/* Notifications */}
+        <div>
+          <button
+            onClick={() => setShowNotifications(!showNotifications)}
+            className="flex items-center justify-between w-full text-sm text-gray-300 hover:text-white transition-colors"
+          >
+            <span>Notifications</span>
+            {showNotifications ? <ChevronUp className="w-4 h-4" /> : <ChevronDown className="w-4 h-4" />}
+          </button>
+
+          {showNotifications && prefs && (
+            <div className="mt-3 space-y-2 text-xs text-gray-400">
+              <label className="flex items-center gap-2">
+                <input
+                  type="checkbox"
+                  checked={prefs.enabled}
+                  onChange={(e) => handleMute(!e.target.checked)}
+                  className="w-4 h-4"
+                />
+                Alerts for this account
+              </label>
+              {prefs.enabled && (
+                <>
+                  {snoozedUntil !== null ? (
+                    <div className="flex items-center justify-between">
+                      <span>Snoozed until {formatDate(snoozedUntil)}</span>
+                      <button
+                        onClick={() => handleSnooze(0)}
+                        className="px-2 py-1 bg-gray-700 hover:bg-gray-600 rounded text-white"
+                      >
+                        Resume
+                      </button>
+                    </div>
+                  ) : (
+                    <div className="flex items-center gap-2">
+                      <span>Snooze</span>
+                      {([
+                        ["1 hour", 60],
+                        ["1 day", 1440],
+                        ["1 week", 10080],
+                      ] as const).map(([label, minutes]) => (
+                        <button
+                          key={label}
+                          onClick={() => handleSnooze(minutes)}
+                          className="px-2 py-1 bg-gray-700 hover:bg-gray-600 rounded text-white"
+                        >
+                          {label}
+                        </button>
+                      ))}
+                      <button
+                        onClick={() => handleSnooze(minutesToMonthEnd())}
+                        className="px-2 py-1 bg-gray-700 hover:bg-gray-600 rounded text-white"
+                      >
+                        Rest of month
+                      </button>
+                    </div>
+                  )}
+                  <div className="grid grid-cols-3 gap-2">
+                    {([
+                      ["warning_percent", "Warning", 75],
+                      ["caution_percent", "Caution", 90],
+                      ["critical_percent", "Critical", 95],
+                    ] as const).map(([key, label, fallback]) => (
+                      <label key={key}>
+                        {label} %
+                        <input
+                          type="number"
+                          value={prefs[key] ?? ""}
+                          onChange={(e) => updatePrefs({ [key]: e.target.value ? Number(e.target.value) : null })}
+                          placeholder={String(fallback)}
+                          min="1"
+                          max="100"
+                          className="w-full mt-1 px-2 py-1 bg-gray-700 border border-gray-600 rounded text-white"
+                        />
+                      </label>
+                    ))}
+                  </div>
+                  <label className="block">
+                    Alert above $ per hour
+                    <input
+                      type="number"
+                      value={prefs.burn_rate_usd_per_hour ?? ""}
+                      onChange={(e) => updatePrefs({ burn_rate_usd_per_hour: e.target.value ? Number(e.target.value) : null })}
+                      placeholder="Global setting"
+                      min="0"
+                      step="0.5"
+                      className="w-full mt-1 px-2 py-1 bg-gray-700 border border-gray-600 rounded text-white"
+                    />
+                  </label>
+                  <label className="flex items-center gap-2">
+                    <input
+                      type="checkbox"
+                      checked={prefs.channels !== null}
+                      onChange={(e) => updatePrefs({ channels: e.target.checked ? ["desktop"] : null })}
+                      className="w-4 h-4"
+                    />
+                    Choose channels instead of the routing rules
+                  </label>
+                  {prefs.channels !== null && (
+                    <div className="flex gap-3 ml-6">
+                      {(["desktop", "webhook", "email", "push"] as const).map((channel) => (
+                        <label key={channel} className="flex items-center gap-1 capitalize">
+                          <input
+                            type="checkbox"
+                            checked={prefs.channels?.includes(channel) ?? false}
+                            onChange={() => togglePrefsChannel(channel)}
+                            className="w-4 h-4"
+                          />
+                          {channel}
+                        </label>
+                      ))}
+                    </div>
+                  )}
+                </>
+              )}
+              <div className="flex justify-end">
+                <button
+                  onClick={handleSavePrefs}
+                  disabled={prefsDraft === null}
+                  className="px-2 py-1 bg-gray-700 hover:bg-gray-600 rounded text-white disabled:opacity-50"
+                >
+                  Save
+                </button>
+              </div>
+
+              <p className="pt-2 border-t border-gray-700">Push targets</p>
+              <textarea
+                value={pushTargets ?? accountSettings?.push_targets ?? ""}
+                onChange={(e) => setPushTargets(e.target.value)}
+                placeholder="ntfy https://ntfy.sh/my-topic"
+                rows={2}
+                className="w-full px-2 py-1 bg-gray-700 border border-gray-600 rounded font-mono text-xs text-white"
+              />
+              <div className="flex justify-between items-center">
+                <span>Empty uses the targets in Settings</span>
+                <button
+                  onClick={handleSavePushTargets}
+                  disabled={pushTargets === null}
+                  className="px-2 py-1 bg-gray-700 hover:bg-gray-600 rounded text-white disabled:opacity-50"
+                >
+                  Save
+                </button>
+              </div>
+            </div>
+          )}
+        </div>
+
         {/* Chart Toggle */}
         <div>
           <button
@@ -180,8 +703,17 @@ export default function QuotaCard({ quota, account, onDelete }: QuotaCardProps)
         </div>
 
         {/* Last Updated */}
-        <div className="text-xs text-gray-500 border-t border-gray-700 pt-3">
-          Last updated: {formatDate(quota.timestamp)}
+        <div className="text-xs text-gray-500 border-t border-gray-700 pt-3 flex justify-between gap-2">
+          <span>Last updated: {formatDate(quota.timestamp)}</span>
+          {status ? (
+            <span className={status.state === "operational" ? "text-green-400" : "text-yellow-400"}>
+              {status.description || status.state}
+            </span>
+          ) : (
+            <button onClick={handleCheckStatus} className="hover:text-white transition-colors">
+              Check provider status
+            </button>
+          )}
         </div>
       </div>
     </div>
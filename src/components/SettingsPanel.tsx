@@ -1,68 +1,429 @@
-import { useState, useEffect } from "react";
+import { Fragment, useState, useEffect } from "react";
 import { invoke } from "@tauri-apps/api/core";
-import { X } from "lucide-react";
+import { X, Download, Upload, Archive, RotateCcw, Lock } from "lucide-react";
+import { useQuotaData, useNotificationRoutes, useProviders } from "../hooks/useQuotaData";
+import type { EncryptionState, Settings, ProviderOverrides, ProfileList, DataLocation, LockState, KeyStorage, CredentialImport, AccountImport, SnapshotInfo, MaintenanceReport, ImportSummary, NotificationChannel, NotificationUrgency } from "../types";
+
+const NO_OVERRIDES: ProviderOverrides = {
+  connect_timeout_seconds: null,
+  read_timeout_seconds: null,
+  rate_limit_per_minute: null,
+};
 
 interface SettingsPanelProps {
   onClose: () => void;
 }
 
 export default function SettingsPanel({ onClose }: SettingsPanelProps) {
-  const [refreshInterval, setRefreshInterval] = useState("300");
-  const [notificationsEnabled, setNotificationsEnabled] = useState(true);
-  const [threshold75, setThreshold75] = useState(true);
-  const [threshold90, setThreshold90] = useState(true);
-  const [threshold95, setThreshold95] = useState(true);
-  const [dataRetention, setDataRetention] = useState("90");
+  const [settings, setSettings] = useState<Settings | null>(null);
   const [isSaving, setIsSaving] = useState(false);
+  const { accounts } = useQuotaData();
+  const [exportFrom, setExportFrom] = useState(() =>
+    new Date(Date.now() - 30 * 86400 * 1000).toISOString().slice(0, 10)
+  );
+  const [exportTo, setExportTo] = useState(() => new Date().toISOString().slice(0, 10));
+  const [exportAccounts, setExportAccounts] = useState<string[]>([]);
+  const [isExporting, setIsExporting] = useState(false);
+  // Last month by default, the usual one to file
+  const [reportPeriod, setReportPeriod] = useState(() => {
+    const now = new Date();
+    return new Date(Date.UTC(now.getUTCFullYear(), now.getUTCMonth() - 1, 1)).toISOString().slice(0, 7);
+  });
+  const [reportFormat, setReportFormat] = useState<"html" | "csv" | "json">("html");
+  const [importAccount, setImportAccount] = useState("");
+  const [backupPassphrase, setBackupPassphrase] = useState("");
+  const [isBackingUp, setIsBackingUp] = useState(false);
+  const [transferPassphrase, setTransferPassphrase] = useState("");
+  const [encryption, setEncryption] = useState<EncryptionState>("unavailable");
+  const [profiles, setProfiles] = useState<ProfileList | null>(null);
+  const [snapshots, setSnapshots] = useState<SnapshotInfo[]>([]);
+  const [maintenance, setMaintenance] = useState<MaintenanceReport | null>(null);
+  const [isMaintaining, setIsMaintaining] = useState(false);
+  const [newProfileName, setNewProfileName] = useState("");
+  const [dataLocation, setDataLocation] = useState<DataLocation | null>(null);
+  const [moveData, setMoveData] = useState(true);
+  const [lockState, setLockState] = useState<LockState | null>(null);
+  const [keyStorage, setKeyStorage] = useState<KeyStorage | null>(null);
+  const [currentPassword, setCurrentPassword] = useState("");
+  const [newPassword, setNewPassword] = useState("");
+  const [hasSmtpPassword, setHasSmtpPassword] = useState(false);
+  const [smtpPassword, setSmtpPassword] = useState("");
+  const [hasWebhookSecret, setHasWebhookSecret] = useState(false);
+  const [webhookSecret, setWebhookSecret] = useState("");
+  const [isSendingTest, setIsSendingTest] = useState(false);
+  const { data: routes = [], refetch: refetchRoutes } = useNotificationRoutes();
+  const { data: providers } = useProviders();
+  const [routeName, setRouteName] = useState("");
+  const [routeMin, setRouteMin] = useState("");
+  const [routeMax, setRouteMax] = useState("");
+  const [routeAccount, setRouteAccount] = useState("");
+  const [routeTag, setRouteTag] = useState("");
+  const [routeChannels, setRouteChannels] = useState<NotificationChannel[]>(["desktop"]);
 
   useEffect(() => {
     loadSettings();
+    invoke<EncryptionState>("get_database_encryption").then(setEncryption).catch(console.error);
+    invoke<ProfileList>("get_profiles").then(setProfiles).catch(console.error);
+    invoke<SnapshotInfo[]>("list_backups").then(setSnapshots).catch(console.error);
+    invoke<DataLocation>("get_data_location").then(setDataLocation).catch(console.error);
+    invoke<LockState>("get_lock_state").then(setLockState).catch(console.error);
+    invoke<KeyStorage>("get_key_storage").then(setKeyStorage).catch(console.error);
+    invoke<boolean>("has_smtp_password").then(setHasSmtpPassword).catch(console.error);
+    invoke<boolean>("has_webhook_secret").then(setHasWebhookSecret).catch(console.error);
   }, []);
 
   const loadSettings = async () => {
     try {
-      const [interval, notifs, t75, t90, t95, retention] = await Promise.all([
-        invoke<string>("get_setting", { key: "refresh_interval_seconds" }),
-        invoke<string>("get_setting", { key: "notifications_enabled" }),
-        invoke<string>("get_setting", { key: "threshold_75_enabled" }),
-        invoke<string>("get_setting", { key: "threshold_90_enabled" }),
-        invoke<string>("get_setting", { key: "threshold_95_enabled" }),
-        invoke<string>("get_setting", { key: "data_retention_days" }),
-      ]);
-
-      if (interval) setRefreshInterval(interval);
-      if (notifs) setNotificationsEnabled(notifs === "true");
-      if (t75) setThreshold75(t75 === "true");
-      if (t90) setThreshold90(t90 === "true");
-      if (t95) setThreshold95(t95 === "true");
-      if (retention) setDataRetention(retention);
+      setSettings(await invoke<Settings>("get_all_settings"));
     } catch (error) {
       console.error("Failed to load settings:", error);
     }
   };
 
+  const update = (patch: Partial<Settings>) => {
+    setSettings((current) => (current ? { ...current, ...patch } : current));
+  };
+
+  // Drops the provider's entry once none of its values are set
+  const updateOverride = (provider: string, patch: Partial<ProviderOverrides>) => {
+    setSettings((current) => {
+      if (!current) return current;
+      const next = { ...(current.provider_overrides[provider] ?? NO_OVERRIDES), ...patch };
+      const overrides = { ...current.provider_overrides };
+      if (Object.values(next).every((value) => value === null)) {
+        delete overrides[provider];
+      } else {
+        overrides[provider] = next;
+      }
+      return { ...current, provider_overrides: overrides };
+    });
+  };
+
   const handleSave = async () => {
+    if (!settings) return;
     setIsSaving(true);
 
     try {
-      await Promise.all([
-        invoke("set_setting", { key: "refresh_interval_seconds", value: refreshInterval }),
-        invoke("set_setting", { key: "notifications_enabled", value: notificationsEnabled.toString() }),
-        invoke("set_setting", { key: "threshold_75_enabled", value: threshold75.toString() }),
-        invoke("set_setting", { key: "threshold_90_enabled", value: threshold90.toString() }),
-        invoke("set_setting", { key: "threshold_95_enabled", value: threshold95.toString() }),
-        invoke("set_setting", { key: "data_retention_days", value: dataRetention }),
-      ]);
-
+      await invoke("update_settings", { settings });
       onClose();
     } catch (error) {
       console.error("Failed to save settings:", error);
-      alert("Failed to save settings");
+      alert(`Failed to save settings: ${error}`);
     } finally {
       setIsSaving(false);
     }
   };
 
+  // Stored encrypted on its own, so it's saved here rather than with the settings
+  const handleSetSmtpPassword = async (password: string | null) => {
+    try {
+      await invoke("set_smtp_password", { password });
+      setHasSmtpPassword(!!password);
+      setSmtpPassword("");
+    } catch (error) {
+      console.error("Failed to set SMTP password:", error);
+      alert(`Failed to set SMTP password: ${error}`);
+    }
+  };
+
+  // Stored encrypted like the SMTP password
+  const handleSetWebhookSecret = async (secret: string | null) => {
+    try {
+      await invoke("set_webhook_secret", { secret });
+      setHasWebhookSecret(!!secret);
+      setWebhookSecret("");
+    } catch (error) {
+      console.error("Failed to set webhook secret:", error);
+      alert(`Failed to set webhook secret: ${error}`);
+    }
+  };
+
+  const handleTestEmail = async () => {
+    setIsSendingTest(true);
+    try {
+      await invoke("send_test_email");
+      alert("Test email sent");
+    } catch (error) {
+      console.error("Failed to send test email:", error);
+      alert(`Failed to send test email: ${error}`);
+    } finally {
+      setIsSendingTest(false);
+    }
+  };
+
+  const toggleRouteChannel = (channel: NotificationChannel) => {
+    setRouteChannels((selected) =>
+      selected.includes(channel) ? selected.filter((c) => c !== channel) : [...selected, channel]
+    );
+  };
+
+  const handleAddRoute = async () => {
+    try {
+      await invoke("save_notification_route", {
+        request: {
+          name: routeName,
+          min_threshold: routeMin ? Number(routeMin) : null,
+          max_threshold: routeMax ? Number(routeMax) : null,
+          account_id: routeAccount || null,
+          account_tag: routeTag || null,
+          channels: routeChannels,
+        },
+      });
+      setRouteName("");
+      setRouteMin("");
+      setRouteMax("");
+      setRouteAccount("");
+      setRouteTag("");
+      refetchRoutes();
+    } catch (error) {
+      console.error("Failed to save routing rule:", error);
+      alert(`Failed to save routing rule: ${error}`);
+    }
+  };
+
+  const handleRemoveRoute = async (id: string) => {
+    try {
+      await invoke("remove_notification_route", { id });
+      refetchRoutes();
+    } catch (error) {
+      console.error("Failed to remove routing rule:", error);
+    }
+  };
+
+  const toggleExportAccount = (id: string) => {
+    setExportAccounts((selected) =>
+      selected.includes(id) ? selected.filter((a) => a !== id) : [...selected, id]
+    );
+  };
+
+  const handleExport = async () => {
+    setIsExporting(true);
+
+    try {
+      // The end date is inclusive in the UI, so export up to the following midnight
+      const start = Math.floor(new Date(exportFrom).getTime() / 1000);
+      const end = Math.floor(new Date(exportTo).getTime() / 1000) + 86400;
+      const path = await invoke<string | null>("export_usage_csv", {
+        accountIds: exportAccounts,
+        start,
+        end,
+      });
+      if (path) alert(`Exported usage to ${path}`);
+    } catch (error) {
+      console.error("Failed to export usage:", error);
+      alert(`Failed to export usage: ${error}`);
+    } finally {
+      setIsExporting(false);
+    }
+  };
+
+  const handleReport = async () => {
+    setIsExporting(true);
+
+    try {
+      const path = await invoke<string | null>("generate_report", {
+        period: reportPeriod,
+        accountIds: exportAccounts,
+        format: reportFormat,
+      });
+      if (path) alert(`Statement written to ${path}`);
+    } catch (error) {
+      console.error("Failed to generate statement:", error);
+      alert(`Failed to generate statement: ${error}`);
+    } finally {
+      setIsExporting(false);
+    }
+  };
+
+  const handleImport = async () => {
+    try {
+      const summary = await invoke<ImportSummary | null>("import_usage", { accountId: importAccount });
+      if (summary) {
+        alert(
+          `Imported ${summary.days_imported} days from ${summary.rows_read} rows` +
+            (summary.days_skipped ? `; ${summary.days_skipped} days already had data` : "") +
+            (summary.rows_skipped ? `; ${summary.rows_skipped} rows had no readable date` : "")
+        );
+      }
+    } catch (error) {
+      console.error("Failed to import usage:", error);
+      alert(`Failed to import usage: ${error}`);
+    }
+  };
+
+  const handleBackup = async () => {
+    setIsBackingUp(true);
+
+    try {
+      const path = await invoke<string | null>("backup_database", { passphrase: backupPassphrase });
+      if (path) alert(`Backup written to ${path}`);
+    } catch (error) {
+      console.error("Failed to back up:", error);
+      alert(`Failed to back up: ${error}`);
+    } finally {
+      setIsBackingUp(false);
+    }
+  };
+
+  const handleRestore = async () => {
+    if (!confirm("Restoring replaces all accounts, history and settings. Continue?")) return;
+    setIsBackingUp(true);
+
+    try {
+      const accounts = await invoke<number | null>("restore_database", { passphrase: backupPassphrase });
+      if (accounts !== null) {
+        alert(`Restored ${accounts} account(s)`);
+        loadSettings();
+      }
+    } catch (error) {
+      console.error("Failed to restore:", error);
+      alert(`Failed to restore: ${error}`);
+    } finally {
+      setIsBackingUp(false);
+    }
+  };
+
+  const handleExportCredentials = async () => {
+    try {
+      const path = await invoke<string | null>("export_credentials", { passphrase: transferPassphrase });
+      if (path) alert(`Accounts written to ${path}`);
+    } catch (error) {
+      console.error("Failed to export accounts:", error);
+      alert(`Failed to export accounts: ${error}`);
+    }
+  };
+
+  const handleImportCredentials = async () => {
+    try {
+      const summary = await invoke<CredentialImport | null>("import_credentials", { passphrase: transferPassphrase });
+      if (summary) {
+        alert(
+          `Imported ${summary.imported} account(s)` +
+            (summary.skipped ? `; ${summary.skipped} already existed` : "")
+        );
+      }
+    } catch (error) {
+      console.error("Failed to import accounts:", error);
+      alert(`Failed to import accounts: ${error}`);
+    }
+  };
+
+  const handleImportAccountList = async () => {
+    try {
+      const report = await invoke<AccountImport | null>("import_accounts");
+      if (!report) return;
+
+      const failed = report.results.filter((r) => r.error !== null);
+      if (failed.length === 0) {
+        alert(`Imported ${report.imported} account(s)`);
+      } else {
+        alert(
+          `Imported nothing; ${failed.length} of ${report.results.length} entries failed:\n` +
+            failed.map((r) => `#${r.index + 1} ${r.name} (${r.provider}): ${r.error}`).join("\n")
+        );
+      }
+    } catch (error) {
+      console.error("Failed to import account list:", error);
+      alert(`Failed to import account list: ${error}`);
+    }
+  };
+
+  const handleMaintain = async () => {
+    setIsMaintaining(true);
+    try {
+      setMaintenance(await invoke<MaintenanceReport>("maintain_database"));
+    } catch (error) {
+      console.error("Failed to maintain database:", error);
+      alert(`Database maintenance failed: ${error}`);
+    } finally {
+      setIsMaintaining(false);
+    }
+  };
+
+  const handleRestoreSnapshot = async (name: string) => {
+    if (!confirm(`Replace all current data with the backup ${name}?`)) return;
+
+    try {
+      await invoke("restore_backup", { name });
+      alert("Backup restored");
+    } catch (error) {
+      console.error("Failed to restore backup:", error);
+      alert(`Failed to restore backup: ${error}`);
+    }
+  };
+
+  const handleSwitchProfile = async (profileId: string) => {
+    if (!confirm("Quonitor will restart to open the selected profile. Continue?")) return;
+
+    try {
+      await invoke("switch_profile", { profileId });
+    } catch (error) {
+      console.error("Failed to switch profile:", error);
+      alert(`Failed to switch profile: ${error}`);
+    }
+  };
+
+  const handleCreateProfile = async () => {
+    try {
+      await invoke("create_profile", { name: newProfileName });
+      setNewProfileName("");
+      setProfiles(await invoke<ProfileList>("get_profiles"));
+    } catch (error) {
+      console.error("Failed to create profile:", error);
+      alert(`Failed to create profile: ${error}`);
+    }
+  };
+
+  const handleChangeDataLocation = async (reset: boolean) => {
+    const action = moveData ? "move your data to" : "switch to";
+    if (!confirm(`Quonitor will restart to ${action} the ${reset ? "default" : "selected"} folder. Continue?`)) return;
+
+    try {
+      await invoke("set_data_location", { reset, moveData });
+    } catch (error) {
+      console.error("Failed to change data location:", error);
+      alert(`Failed to change data location: ${error}`);
+    }
+  };
+
+  const handleSetMasterPassword = async (remove: boolean) => {
+    if (remove && !confirm("Remove the master password? Credentials will be protected by the system keyring only.")) return;
+
+    try {
+      setLockState(
+        await invoke<LockState>("set_master_password", {
+          current: lockState?.enabled ? currentPassword : null,
+          newPassphrase: remove ? null : newPassword,
+        })
+      );
+      setCurrentPassword("");
+      setNewPassword("");
+    } catch (error) {
+      console.error("Failed to update master password:", error);
+      alert(`Failed to update master password: ${error}`);
+    }
+  };
+
+  const handleKeyringOnly = async (enabled: boolean) => {
+    try {
+      setKeyStorage(await invoke<KeyStorage>("set_keyring_only", { enabled }));
+    } catch (error) {
+      console.error("Failed to change key storage:", error);
+      alert(`Failed to change key storage: ${error}`);
+    }
+  };
+
+  const handleEnableEncryption = async () => {
+    if (!confirm("Encrypt the database? This happens on the next start and cannot be undone.")) return;
+
+    try {
+      setEncryption(await invoke<EncryptionState>("enable_database_encryption"));
+    } catch (error) {
+      console.error("Failed to enable encryption:", error);
+      alert(`Failed to enable encryption: ${error}`);
+    }
+  };
+
   return (
     <div className="bg-gray-800 rounded-lg border border-gray-700 p-6">
       <div className="flex items-center justify-between mb-4">
@@ -76,6 +437,95 @@ export default function SettingsPanel({ onClose }: SettingsPanelProps) {
       </div>
 
       <div className="space-y-6">
+        {/* Profiles */}
+        {profiles && (
+          <div>
+            <label className="block text-sm font-medium text-gray-300 mb-2">
+              Profile
+            </label>
+            <div className="flex gap-2">
+              <select
+                value={profiles.active}
+                onChange={(e) => handleSwitchProfile(e.target.value)}
+                className="flex-1 bg-gray-700 border border-gray-600 rounded px-3 py-2 text-white"
+              >
+                {profiles.profiles.map((profile) => (
+                  <option key={profile.id} value={profile.id}>
+                    {profile.name}
+                  </option>
+                ))}
+              </select>
+              <input
+                type="text"
+                value={newProfileName}
+                onChange={(e) => setNewProfileName(e.target.value)}
+                placeholder="New profile"
+                className="flex-1 bg-gray-700 border border-gray-600 rounded px-3 py-2 text-white"
+              />
+              <button
+                onClick={handleCreateProfile}
+                disabled={!newProfileName.trim()}
+                className="px-4 py-2 bg-gray-700 hover:bg-gray-600 disabled:opacity-50 rounded transition-colors"
+              >
+                Create
+              </button>
+            </div>
+            <p className="text-xs text-gray-400 mt-1">
+              Each profile has its own accounts, settings and history
+            </p>
+          </div>
+        )}
+
+        {/* Data Location */}
+        {dataLocation && (
+          <div>
+            <label className="block text-sm font-medium text-gray-300 mb-2">
+              Data Location
+            </label>
+            <p className="text-sm text-gray-300 break-all mb-2">{dataLocation.path}</p>
+            {dataLocation.source === "flag" || dataLocation.source === "portable" ? (
+              <p className="text-xs text-gray-400">
+                {dataLocation.source === "flag"
+                  ? "Set with --data-dir for this run"
+                  : "Portable mode: data is kept next to the app"}
+              </p>
+            ) : (
+              <>
+                <div className="flex items-center gap-2">
+                  <button
+                    onClick={() => handleChangeDataLocation(false)}
+                    className="px-4 py-2 bg-gray-700 hover:bg-gray-600 rounded transition-colors"
+                  >
+                    Change...
+                  </button>
+                  {dataLocation.source === "custom" && (
+                    <button
+                      onClick={() => handleChangeDataLocation(true)}
+                      className="px-4 py-2 bg-gray-700 hover:bg-gray-600 rounded transition-colors"
+                    >
+                      Use Default
+                    </button>
+                  )}
+                  <label className="flex items-center gap-2 text-sm text-gray-300">
+                    <input
+                      type="checkbox"
+                      checked={moveData}
+                      onChange={(e) => setMoveData(e.target.checked)}
+                      className="w-4 h-4"
+                    />
+                    Move existing data
+                  </label>
+                </div>
+                <p className="text-xs text-gray-400 mt-1">
+                  {dataLocation.pending
+                    ? `Switching to ${dataLocation.pending} on next start`
+                    : "Database, key file and backups; uncheck to open data already in the new folder, e.g. a synced one"}
+                </p>
+              </>
+            )}
+          </div>
+        )}
+
         {/* Refresh Interval */}
         <div>
           <label className="block text-sm font-medium text-gray-300 mb-2">
@@ -83,14 +533,258 @@ export default function SettingsPanel({ onClose }: SettingsPanelProps) {
           </label>
           <input
             type="number"
-            value={refreshInterval}
-            onChange={(e) => setRefreshInterval(e.target.value)}
-            min="60"
-            max="3600"
+            value={settings?.refresh_interval_seconds ?? ""}
+            onChange={(e) => update({ refresh_interval_seconds: Number(e.target.value) })}
+            min="30"
+            max="86400"
             className="w-full bg-gray-700 border border-gray-600 rounded px-3 py-2 text-white"
           />
           <p className="text-xs text-gray-400 mt-1">
-            How often to check quota usage (minimum 60 seconds)
+            How often to check quota usage (minimum 30 seconds)
+          </p>
+          <input
+            type="text"
+            value={settings?.refresh_schedule ?? ""}
+            onChange={(e) => update({ refresh_schedule: e.target.value || null })}
+            placeholder="Cron schedule, e.g. */10 8-20 * * MON-FRI"
+            className="w-full mt-2 bg-gray-700 border border-gray-600 rounded px-3 py-2 text-white font-mono text-sm"
+          />
+          <p className="text-xs text-gray-400 mt-1">
+            When set, replaces the interval; evaluated in local time
+          </p>
+          <label className="flex items-center gap-2 text-sm text-gray-400 mt-2">
+            At most
+            <input
+              type="number"
+              value={settings?.rate_limit_per_minute ?? ""}
+              onChange={(e) => update({ rate_limit_per_minute: Number(e.target.value) })}
+              min="0"
+              max="600"
+              className="w-20 bg-gray-700 border border-gray-600 rounded px-2 py-1 text-white"
+            />
+            fetches per minute to each provider (0 for no limit)
+          </label>
+          <label className="flex items-center gap-2 text-sm text-gray-400 mt-2">
+            <input
+              type="checkbox"
+              checked={settings?.adaptive_polling ?? false}
+              onChange={(e) => update({ adaptive_polling: e.target.checked })}
+              className="w-4 h-4"
+            />
+            Adaptive: check busy accounts more often and idle ones less
+          </label>
+          {settings?.adaptive_polling && (
+            <div className="ml-6 mt-2 flex items-center gap-2 text-sm text-gray-400">
+              Between
+              <input
+                type="number"
+                value={settings.adaptive_min_interval_seconds}
+                onChange={(e) => update({ adaptive_min_interval_seconds: Number(e.target.value) })}
+                min="30"
+                max="86400"
+                className="w-24 bg-gray-700 border border-gray-600 rounded px-2 py-1 text-white"
+              />
+              and
+              <input
+                type="number"
+                value={settings.adaptive_max_interval_seconds}
+                onChange={(e) => update({ adaptive_max_interval_seconds: Number(e.target.value) })}
+                min="30"
+                max="86400"
+                className="w-24 bg-gray-700 border border-gray-600 rounded px-2 py-1 text-white"
+              />
+              seconds
+            </div>
+          )}
+          <div className="flex items-center gap-4 text-sm text-gray-400 mt-2">
+            <label className="flex items-center gap-2">
+              First refresh after launch waits
+              <input
+                type="number"
+                value={settings?.startup_delay_seconds ?? ""}
+                onChange={(e) => update({ startup_delay_seconds: Number(e.target.value) })}
+                min="0"
+                max="3600"
+                disabled={settings?.lazy_startup}
+                className="w-20 bg-gray-700 border border-gray-600 rounded px-2 py-1 text-white disabled:opacity-50"
+              />
+              seconds
+            </label>
+            <label className="flex items-center gap-2">
+              <input
+                type="checkbox"
+                checked={settings?.lazy_startup ?? false}
+                onChange={(e) => update({ lazy_startup: e.target.checked })}
+                className="w-4 h-4"
+              />
+              Show stored data and wait for the schedule
+            </label>
+          </div>
+          <input
+            type="text"
+            value={settings?.blackout_windows ?? ""}
+            onChange={(e) => update({ blackout_windows: e.target.value || null })}
+            placeholder="Don't refresh during, e.g. 23:00-07:00, 12:00-12:30"
+            className="w-full mt-2 bg-gray-700 border border-gray-600 rounded px-3 py-2 text-white font-mono text-sm"
+          />
+          <p className="text-xs text-gray-400 mt-1">
+            Everything is refreshed when a window ends
+          </p>
+          <label className="flex items-center gap-2 text-sm text-gray-400 mt-2">
+            <input
+              type="checkbox"
+              checked={settings?.pause_when_offline ?? true}
+              onChange={(e) => update({ pause_when_offline: e.target.checked })}
+              className="w-4 h-4"
+            />
+            Pause fetching while offline
+          </label>
+          <div className="flex items-center gap-4 text-sm text-gray-400 mt-2">
+            <label className="flex items-center gap-2">
+              <input
+                type="checkbox"
+                checked={settings?.slow_on_battery ?? false}
+                onChange={(e) => update({ slow_on_battery: e.target.checked })}
+                className="w-4 h-4"
+              />
+              On battery
+            </label>
+            <label className="flex items-center gap-2">
+              <input
+                type="checkbox"
+                checked={settings?.slow_on_metered ?? false}
+                onChange={(e) => update({ slow_on_metered: e.target.checked })}
+                className="w-4 h-4"
+              />
+              On metered connections
+            </label>
+            <label className="flex items-center gap-2">
+              refresh
+              <input
+                type="number"
+                value={settings?.slow_polling_factor ?? ""}
+                onChange={(e) => update({ slow_polling_factor: Number(e.target.value) })}
+                min="2"
+                max="20"
+                className="w-16 bg-gray-700 border border-gray-600 rounded px-2 py-1 text-white"
+              />
+              x less often
+            </label>
+          </div>
+        </div>
+
+        {/* Failing Accounts */}
+        <div>
+          <label className="block text-sm font-medium text-gray-300 mb-2">
+            Failing Accounts
+          </label>
+          <div className="grid grid-cols-2 gap-2">
+            <label className="text-xs text-gray-400">
+              Pause after consecutive failures
+              <input
+                type="number"
+                value={settings?.breaker_failure_threshold ?? ""}
+                onChange={(e) => update({ breaker_failure_threshold: Number(e.target.value) })}
+                min="1"
+                max="50"
+                className="w-full mt-1 bg-gray-700 border border-gray-600 rounded px-3 py-2 text-white"
+              />
+            </label>
+            <label className="text-xs text-gray-400">
+              Pause for (minutes)
+              <input
+                type="number"
+                value={settings?.breaker_cooldown_minutes ?? ""}
+                onChange={(e) => update({ breaker_cooldown_minutes: Number(e.target.value) })}
+                min="1"
+                max="10080"
+                className="w-full mt-1 bg-gray-700 border border-gray-600 rounded px-3 py-2 text-white"
+              />
+            </label>
+          </div>
+          <p className="text-xs text-gray-400 mt-1">
+            Failed accounts are retried less and less often, then paused until refreshed manually
+          </p>
+        </div>
+
+        {/* Network Limits */}
+        <div>
+          <label className="block text-sm font-medium text-gray-300 mb-2">
+            Network Limits
+          </label>
+          <div className="grid grid-cols-4 gap-2 items-center text-xs text-gray-400">
+            <span />
+            <span>Connect timeout (s)</span>
+            <span>Read timeout (s)</span>
+            <span>Fetches per minute</span>
+            <span>All providers</span>
+            <input
+              type="number"
+              value={settings?.connect_timeout_seconds ?? ""}
+              onChange={(e) => update({ connect_timeout_seconds: Number(e.target.value) })}
+              min="1"
+              max="600"
+              className="bg-gray-700 border border-gray-600 rounded px-2 py-1 text-white"
+            />
+            <input
+              type="number"
+              value={settings?.read_timeout_seconds ?? ""}
+              onChange={(e) => update({ read_timeout_seconds: Number(e.target.value) })}
+              min="1"
+              max="600"
+              className="bg-gray-700 border border-gray-600 rounded px-2 py-1 text-white"
+            />
+            <span className="px-2">{settings?.rate_limit_per_minute}</span>
+            {providers?.map((provider) => {
+              const overrides = settings?.provider_overrides[provider.id] ?? NO_OVERRIDES;
+              return (
+                <Fragment key={provider.id}>
+                  <span>{provider.name}</span>
+                  <input
+                    type="number"
+                    value={overrides.connect_timeout_seconds ?? ""}
+                    onChange={(e) =>
+                      updateOverride(provider.id, {
+                        connect_timeout_seconds: e.target.value === "" ? null : Number(e.target.value),
+                      })
+                    }
+                    min="1"
+                    max="600"
+                    placeholder="Default"
+                    className="bg-gray-700 border border-gray-600 rounded px-2 py-1 text-white"
+                  />
+                  <input
+                    type="number"
+                    value={overrides.read_timeout_seconds ?? ""}
+                    onChange={(e) =>
+                      updateOverride(provider.id, {
+                        read_timeout_seconds: e.target.value === "" ? null : Number(e.target.value),
+                      })
+                    }
+                    min="1"
+                    max="600"
+                    placeholder="Default"
+                    className="bg-gray-700 border border-gray-600 rounded px-2 py-1 text-white"
+                  />
+                  <input
+                    type="number"
+                    value={overrides.rate_limit_per_minute ?? ""}
+                    onChange={(e) =>
+                      updateOverride(provider.id, {
+                        rate_limit_per_minute: e.target.value === "" ? null : Number(e.target.value),
+                      })
+                    }
+                    min="0"
+                    max="600"
+                    placeholder="Default"
+                    className="bg-gray-700 border border-gray-600 rounded px-2 py-1 text-white"
+                  />
+                </Fragment>
+              );
+            })}
+          </div>
+          <p className="text-xs text-gray-400 mt-1">
+            Leave a provider blank to use the defaults; timeouts apply after restarting
           </p>
         </div>
 
@@ -99,20 +793,20 @@ export default function SettingsPanel({ onClose }: SettingsPanelProps) {
           <label className="flex items-center gap-2 text-sm font-medium text-gray-300 mb-3">
             <input
               type="checkbox"
-              checked={notificationsEnabled}
-              onChange={(e) => setNotificationsEnabled(e.target.checked)}
+              checked={settings?.notifications_enabled ?? true}
+              onChange={(e) => update({ notifications_enabled: e.target.checked })}
               className="w-4 h-4"
             />
             Enable Desktop Notifications
           </label>
 
-          {notificationsEnabled && (
+          {settings?.notifications_enabled && (
             <div className="ml-6 space-y-2">
               <label className="flex items-center gap-2 text-sm text-gray-400">
                 <input
                   type="checkbox"
-                  checked={threshold75}
-                  onChange={(e) => setThreshold75(e.target.checked)}
+                  checked={settings.threshold_75_enabled}
+                  onChange={(e) => update({ threshold_75_enabled: e.target.checked })}
                   className="w-4 h-4"
                 />
                 Notify at 75% usage
@@ -120,8 +814,8 @@ export default function SettingsPanel({ onClose }: SettingsPanelProps) {
               <label className="flex items-center gap-2 text-sm text-gray-400">
                 <input
                   type="checkbox"
-                  checked={threshold90}
-                  onChange={(e) => setThreshold90(e.target.checked)}
+                  checked={settings.threshold_90_enabled}
+                  onChange={(e) => update({ threshold_90_enabled: e.target.checked })}
                   className="w-4 h-4"
                 />
                 Notify at 90% usage
@@ -129,34 +823,893 @@ export default function SettingsPanel({ onClose }: SettingsPanelProps) {
               <label className="flex items-center gap-2 text-sm text-gray-400">
                 <input
                   type="checkbox"
-                  checked={threshold95}
-                  onChange={(e) => setThreshold95(e.target.checked)}
+                  checked={settings.threshold_95_enabled}
+                  onChange={(e) => update({ threshold_95_enabled: e.target.checked })}
                   className="w-4 h-4"
                 />
                 Notify at 95% usage (Critical)
               </label>
+              <div className="grid grid-cols-4 gap-2 text-xs text-gray-400">
+                <span />
+                <span>Urgency</span>
+                <span>On screen (s, 0 = until closed)</span>
+                <span>Sound</span>
+                {([
+                  ["75%", "threshold_75_urgency", "threshold_75_timeout_seconds", "threshold_75_sound"],
+                  ["90%", "threshold_90_urgency", "threshold_90_timeout_seconds", "threshold_90_sound"],
+                  ["95%", "threshold_95_urgency", "threshold_95_timeout_seconds", "threshold_95_sound"],
+                ] as const).map(([label, urgency, timeout, sound]) => (
+                  <Fragment key={label}>
+                    <span className="self-center">{label}</span>
+                    <select
+                      value={settings[urgency]}
+                      onChange={(e) => update({ [urgency]: e.target.value as NotificationUrgency })}
+                      className="bg-gray-700 border border-gray-600 rounded px-2 py-1 text-white"
+                    >
+                      <option value="low">Low</option>
+                      <option value="normal">Normal</option>
+                      <option value="critical">Critical</option>
+                    </select>
+                    <input
+                      type="number"
+                      value={settings[timeout]}
+                      onChange={(e) => update({ [timeout]: parseInt(e.target.value) || 0 })}
+                      min="0"
+                      max="3600"
+                      className="bg-gray-700 border border-gray-600 rounded px-2 py-1 text-white"
+                    />
+                    <input
+                      type="text"
+                      value={settings[sound] ?? ""}
+                      onChange={(e) => update({ [sound]: e.target.value || null })}
+                      placeholder="None"
+                      className="bg-gray-700 border border-gray-600 rounded px-2 py-1 text-white"
+                    />
+                  </Fragment>
+                ))}
+              </div>
+              <label className="flex items-center gap-2 text-sm text-gray-400">
+                <input
+                  type="checkbox"
+                  checked={settings.anomaly_detection}
+                  onChange={(e) => update({ anomaly_detection: e.target.checked })}
+                  className="w-4 h-4"
+                />
+                Notify on unusual spend
+              </label>
+              {settings.anomaly_detection && (
+                <div className="ml-6 flex items-center gap-2 text-sm text-gray-400">
+                  Over
+                  <input
+                    type="number"
+                    value={settings.anomaly_multiple}
+                    onChange={(e) => update({ anomaly_multiple: parseFloat(e.target.value) || 0 })}
+                    min="1.5"
+                    max="100"
+                    step="0.5"
+                    className="w-20 bg-gray-700 border border-gray-600 rounded px-2 py-1 text-white"
+                  />
+                  x the usual hourly spend, and at least $
+                  <input
+                    type="number"
+                    value={settings.anomaly_min_usd}
+                    onChange={(e) => update({ anomaly_min_usd: parseFloat(e.target.value) || 0 })}
+                    min="0"
+                    step="0.5"
+                    className="w-20 bg-gray-700 border border-gray-600 rounded px-2 py-1 text-white"
+                  />
+                </div>
+              )}
+              <label className="flex items-center gap-2 text-sm text-gray-400">
+                <input
+                  type="checkbox"
+                  checked={settings.forecast_alerts}
+                  onChange={(e) => update({ forecast_alerts: e.target.checked })}
+                  className="w-4 h-4"
+                />
+                Notify when a budget is on track to be exceeded
+              </label>
+              <div className="flex items-center gap-2 text-sm text-gray-400">
+                Notify when a failing account hasn't synced for
+                <input
+                  type="number"
+                  value={settings.stale_alert_hours}
+                  onChange={(e) => update({ stale_alert_hours: parseInt(e.target.value) || 0 })}
+                  min="0"
+                  max="720"
+                  className="w-20 bg-gray-700 border border-gray-600 rounded px-2 py-1 text-white"
+                />
+                hours (0 to turn off)
+              </div>
+              <div className="flex items-center gap-2 text-sm text-gray-400">
+                Notify when an account spends more than $
+                <input
+                  type="number"
+                  value={settings.burn_rate_usd_per_hour}
+                  onChange={(e) => update({ burn_rate_usd_per_hour: parseFloat(e.target.value) || 0 })}
+                  min="0"
+                  step="0.5"
+                  className="w-20 bg-gray-700 border border-gray-600 rounded px-2 py-1 text-white"
+                />
+                in an hour (0 to turn off)
+              </div>
+              <div className="flex items-center gap-2 text-sm text-gray-400">
+                Quiet hours
+                <input
+                  type="time"
+                  value={settings.quiet_hours_start ?? ""}
+                  onChange={(e) => update({ quiet_hours_start: e.target.value || null })}
+                  className="bg-gray-700 border border-gray-600 rounded px-2 py-1 text-white"
+                />
+                to
+                <input
+                  type="time"
+                  value={settings.quiet_hours_end ?? ""}
+                  onChange={(e) => update({ quiet_hours_end: e.target.value || null })}
+                  className="bg-gray-700 border border-gray-600 rounded px-2 py-1 text-white"
+                />
+                in
+                <input
+                  type="text"
+                  value={settings.quiet_hours_timezone ?? ""}
+                  onChange={(e) => update({ quiet_hours_timezone: e.target.value || null })}
+                  placeholder="Local time"
+                  className="w-40 bg-gray-700 border border-gray-600 rounded px-2 py-1 text-white"
+                />
+              </div>
+              <label className="block text-sm text-gray-400">
+                Quiet hours by weekday
+                <textarea
+                  value={settings.quiet_hours_weekdays ?? ""}
+                  onChange={(e) => update({ quiet_hours_weekdays: e.target.value || null })}
+                  placeholder={"mon-fri 22:00-07:00\nsat-sun 00:00-00:00"}
+                  rows={2}
+                  className="w-full mt-1 bg-gray-700 border border-gray-600 rounded px-3 py-2 text-white font-mono text-xs"
+                />
+              </label>
+              <p className="text-xs text-gray-400">
+                Listed days use their own ranges instead of the daily ones; a range that passes midnight
+                belongs to the day it starts, 00:00-00:00 is the whole day and "off" none of it. The timezone is
+                an IANA name such as Europe/Berlin.
+              </p>
+              <label className="flex items-center gap-2 text-sm text-gray-400">
+                <input
+                  type="checkbox"
+                  checked={settings.critical_bypasses_quiet_hours}
+                  onChange={(e) => update({ critical_bypasses_quiet_hours: e.target.checked })}
+                  className="w-4 h-4"
+                />
+                Critical alerts come during quiet hours too
+              </label>
+              <label className="block text-sm text-gray-400">
+                Webhook URLs
+                <textarea
+                  value={settings.webhook_urls ?? ""}
+                  onChange={(e) => update({ webhook_urls: e.target.value || null })}
+                  placeholder="https://hooks.example.com/quonitor"
+                  rows={2}
+                  className="w-full mt-1 bg-gray-700 border border-gray-600 rounded px-3 py-2 text-white font-mono text-xs"
+                />
+              </label>
+              <label className="block text-sm text-gray-400">
+                Webhook signing secret {hasWebhookSecret && "(saved)"}
+                <div className="flex gap-2 mt-1">
+                  <input
+                    type="password"
+                    value={webhookSecret}
+                    onChange={(e) => setWebhookSecret(e.target.value)}
+                    placeholder="Optional"
+                    className="flex-1 min-w-0 bg-gray-700 border border-gray-600 rounded px-3 py-2 text-white"
+                  />
+                  <button
+                    onClick={() => handleSetWebhookSecret(webhookSecret)}
+                    disabled={!webhookSecret}
+                    className="px-3 py-2 bg-gray-700 hover:bg-gray-600 rounded text-white disabled:opacity-50"
+                  >
+                    Set
+                  </button>
+                  {hasWebhookSecret && (
+                    <button
+                      onClick={() => handleSetWebhookSecret(null)}
+                      className="px-3 py-2 bg-gray-700 hover:bg-gray-600 rounded text-white"
+                    >
+                      Clear
+                    </button>
+                  )}
+                </div>
+              </label>
+              <p className="text-xs text-gray-400">
+                Alerts are POSTed as JSON to each URL (one per line), signed in the X-Quonitor-Signature
+                header with HMAC-SHA256 when a secret is set
+              </p>
+              <label className="block text-sm text-gray-400">
+                Push targets
+                <textarea
+                  value={settings.push_targets ?? ""}
+                  onChange={(e) => update({ push_targets: e.target.value || null })}
+                  placeholder={"ntfy https://ntfy.sh/my-topic\ngotify https://gotify.example.com APP_TOKEN\npushover USER_KEY APP_TOKEN"}
+                  rows={3}
+                  className="w-full mt-1 bg-gray-700 border border-gray-600 rounded px-3 py-2 text-white font-mono text-xs"
+                />
+              </label>
+              <p className="text-xs text-gray-400">
+                ntfy, Gotify and Pushover, one per line; priority follows the alert's urgency.
+                Accounts can have their own targets instead.
+              </p>
             </div>
           )}
         </div>
 
+        {/* Usage Digest */}
+        <div>
+          <label className="block text-sm font-medium text-gray-300 mb-2">
+            Usage Digest
+          </label>
+          <div className="flex items-center gap-4 text-sm text-gray-400">
+            <label className="flex items-center gap-2">
+              <input
+                type="checkbox"
+                checked={settings?.digest_daily ?? false}
+                onChange={(e) => update({ digest_daily: e.target.checked })}
+                className="w-4 h-4"
+              />
+              Daily
+            </label>
+            <label className="flex items-center gap-2">
+              <input
+                type="checkbox"
+                checked={settings?.digest_weekly ?? false}
+                onChange={(e) => update({ digest_weekly: e.target.checked })}
+                className="w-4 h-4"
+              />
+              Weekly (Mondays)
+            </label>
+            <label className="flex items-center gap-2">
+              at
+              <input
+                type="time"
+                value={settings?.digest_time ?? "08:00"}
+                onChange={(e) => update({ digest_time: e.target.value })}
+                className="bg-gray-700 border border-gray-600 rounded px-2 py-1 text-white"
+              />
+            </label>
+          </div>
+          <p className="text-xs text-gray-400 mt-1">
+            Spend per account, top models and biggest movers, sent through the alert channels
+          </p>
+        </div>
+
+        {/* Alert Routing */}
+        <div>
+          <label className="block text-sm font-medium text-gray-300 mb-2">
+            Alert Routing
+          </label>
+          {routes.length > 0 && (
+            <div className="space-y-1 mb-2">
+              {routes.map((route) => (
+                <div key={route.id} className="flex items-center justify-between bg-gray-700/50 rounded px-3 py-2 text-xs">
+                  <span className="text-gray-300">
+                    <span className="font-medium text-white">{route.name}</span>
+                    {route.min_threshold !== null && ` · ≥${route.min_threshold}%`}
+                    {route.max_threshold !== null && ` · ≤${route.max_threshold}%`}
+                    {route.account_id && ` · ${accounts.find((a) => a.id === route.account_id)?.name ?? route.account_id}`}
+                    {route.account_tag && ` · tag ${route.account_tag}`}
+                    {" → "}
+                    {route.channels.join(", ")}
+                  </span>
+                  <button
+                    onClick={() => handleRemoveRoute(route.id)}
+                    className="text-gray-400 hover:text-white"
+                  >
+                    <X className="w-3 h-3" />
+                  </button>
+                </div>
+              ))}
+            </div>
+          )}
+          <div className="grid grid-cols-2 gap-2 text-xs">
+            <input
+              type="text"
+              value={routeName}
+              onChange={(e) => setRouteName(e.target.value)}
+              placeholder="Rule name"
+              className="col-span-2 bg-gray-700 border border-gray-600 rounded px-3 py-2 text-white"
+            />
+            <input
+              type="number"
+              value={routeMin}
+              onChange={(e) => setRouteMin(e.target.value)}
+              placeholder="From threshold %"
+              min="0"
+              className="bg-gray-700 border border-gray-600 rounded px-3 py-2 text-white"
+            />
+            <input
+              type="number"
+              value={routeMax}
+              onChange={(e) => setRouteMax(e.target.value)}
+              placeholder="To threshold %"
+              min="0"
+              className="bg-gray-700 border border-gray-600 rounded px-3 py-2 text-white"
+            />
+            <select
+              value={routeAccount}
+              onChange={(e) => setRouteAccount(e.target.value)}
+              className="bg-gray-700 border border-gray-600 rounded px-3 py-2 text-white"
+            >
+              <option value="">Any account</option>
+              {accounts.map((account) => (
+                <option key={account.id} value={account.id}>{account.name}</option>
+              ))}
+            </select>
+            <input
+              type="text"
+              value={routeTag}
+              onChange={(e) => setRouteTag(e.target.value)}
+              placeholder="Account tag"
+              className="bg-gray-700 border border-gray-600 rounded px-3 py-2 text-white"
+            />
+            <div className="col-span-2 flex items-center gap-3 text-gray-400">
+              {(["desktop", "webhook", "email", "push"] as const).map((channel) => (
+                <label key={channel} className="flex items-center gap-1 capitalize">
+                  <input
+                    type="checkbox"
+                    checked={routeChannels.includes(channel)}
+                    onChange={() => toggleRouteChannel(channel)}
+                    className="w-4 h-4"
+                  />
+                  {channel}
+                </label>
+              ))}
+              <button
+                onClick={handleAddRoute}
+                disabled={!routeName || routeChannels.length === 0}
+                className="ml-auto px-3 py-1 bg-gray-700 hover:bg-gray-600 rounded text-white disabled:opacity-50"
+              >
+                Add rule
+              </button>
+            </div>
+          </div>
+          <p className="text-xs text-gray-400 mt-1">
+            Alerts go to the channels of every rule they match; alerts matching no rule go everywhere
+          </p>
+        </div>
+
+        {/* Email Alerts */}
+        <div>
+          <label className="block text-sm font-medium text-gray-300 mb-2">
+            Email Alerts
+          </label>
+          <div className="grid grid-cols-3 gap-2">
+            <label className="col-span-2 text-xs text-gray-400">
+              SMTP server
+              <input
+                type="text"
+                value={settings?.smtp_host ?? ""}
+                onChange={(e) => update({ smtp_host: e.target.value || null })}
+                placeholder="smtp.example.com"
+                className="w-full mt-1 bg-gray-700 border border-gray-600 rounded px-3 py-2 text-white"
+              />
+            </label>
+            <label className="text-xs text-gray-400">
+              Port
+              <input
+                type="number"
+                value={settings?.smtp_port ?? ""}
+                onChange={(e) => update({ smtp_port: Number(e.target.value) })}
+                min="1"
+                max="65535"
+                className="w-full mt-1 bg-gray-700 border border-gray-600 rounded px-3 py-2 text-white"
+              />
+            </label>
+            <label className="text-xs text-gray-400">
+              Username
+              <input
+                type="text"
+                value={settings?.smtp_username ?? ""}
+                onChange={(e) => update({ smtp_username: e.target.value || null })}
+                className="w-full mt-1 bg-gray-700 border border-gray-600 rounded px-3 py-2 text-white"
+              />
+            </label>
+            <label className="col-span-2 text-xs text-gray-400">
+              Password {hasSmtpPassword && "(saved)"}
+              <div className="flex gap-2 mt-1">
+                <input
+                  type="password"
+                  value={smtpPassword}
+                  onChange={(e) => setSmtpPassword(e.target.value)}
+                  className="flex-1 min-w-0 bg-gray-700 border border-gray-600 rounded px-3 py-2 text-white"
+                />
+                <button
+                  onClick={() => handleSetSmtpPassword(smtpPassword)}
+                  disabled={!smtpPassword}
+                  className="px-3 py-2 bg-gray-700 hover:bg-gray-600 rounded text-white disabled:opacity-50"
+                >
+                  Set
+                </button>
+                {hasSmtpPassword && (
+                  <button
+                    onClick={() => handleSetSmtpPassword(null)}
+                    className="px-3 py-2 bg-gray-700 hover:bg-gray-600 rounded text-white"
+                  >
+                    Clear
+                  </button>
+                )}
+              </div>
+            </label>
+            <label className="col-span-3 flex items-center gap-2 text-xs text-gray-400">
+              <input
+                type="checkbox"
+                checked={settings?.smtp_implicit_tls ?? false}
+                onChange={(e) => update({ smtp_implicit_tls: e.target.checked })}
+                className="w-4 h-4"
+              />
+              Implicit TLS (usually port 465) instead of STARTTLS
+            </label>
+            <label className="text-xs text-gray-400">
+              From
+              <input
+                type="email"
+                value={settings?.smtp_from ?? ""}
+                onChange={(e) => update({ smtp_from: e.target.value || null })}
+                className="w-full mt-1 bg-gray-700 border border-gray-600 rounded px-3 py-2 text-white"
+              />
+            </label>
+            <label className="col-span-2 text-xs text-gray-400">
+              To (comma separated)
+              <input
+                type="text"
+                value={settings?.email_to ?? ""}
+                onChange={(e) => update({ email_to: e.target.value || null })}
+                className="w-full mt-1 bg-gray-700 border border-gray-600 rounded px-3 py-2 text-white"
+              />
+            </label>
+            <label className="col-span-2 text-xs text-gray-400">
+              Email alerts at
+              <select
+                value={settings?.email_min_threshold ?? 0}
+                onChange={(e) => update({ email_min_threshold: Number(e.target.value) })}
+                className="w-full mt-1 bg-gray-700 border border-gray-600 rounded px-3 py-2 text-white"
+              >
+                <option value={0}>Every threshold</option>
+                <option value={90}>90% and above</option>
+                <option value={95}>95% and above</option>
+                <option value={100}>Exceeded budgets only</option>
+              </select>
+            </label>
+            <div className="flex items-end">
+              <button
+                onClick={handleTestEmail}
+                disabled={isSendingTest || !settings?.smtp_host}
+                className="w-full px-3 py-2 bg-gray-700 hover:bg-gray-600 rounded text-white text-xs disabled:opacity-50"
+              >
+                Send test
+              </button>
+            </div>
+          </div>
+          <p className="text-xs text-gray-400 mt-1">
+            Alerts are emailed as well as shown, for a record of them; save before sending a test
+          </p>
+        </div>
+
         {/* Data Retention */}
         <div>
           <label className="block text-sm font-medium text-gray-300 mb-2">
             Data Retention (days)
           </label>
+          <div className="grid grid-cols-2 gap-2">
+            {([
+              ["retention_snapshot_days", "Raw snapshots", 32],
+              ["retention_model_usage_days", "Model & key usage", 1],
+              ["retention_rollup_days", "Hourly/daily rollups", 1],
+              ["retention_notification_days", "Notifications & errors", 1],
+            ] as const).map(([key, label, min]) => (
+              <label key={key} className="text-xs text-gray-400">
+                {label}
+                <input
+                  type="number"
+                  value={settings?.[key] ?? ""}
+                  onChange={(e) => update({ [key]: Number(e.target.value) })}
+                  min={min}
+                  max="3650"
+                  className="w-full mt-1 bg-gray-700 border border-gray-600 rounded px-3 py-2 text-white"
+                />
+              </label>
+            ))}
+          </div>
+          <p className="text-xs text-gray-400 mt-1">
+            Older data is deleted automatically every hour
+          </p>
+        </div>
+
+        {/* Export */}
+        <div>
+          <label className="block text-sm font-medium text-gray-300 mb-2">
+            Export Usage (CSV)
+          </label>
+          <div className="flex gap-2 mb-2">
+            <input
+              type="date"
+              value={exportFrom}
+              onChange={(e) => setExportFrom(e.target.value)}
+              className="flex-1 bg-gray-700 border border-gray-600 rounded px-3 py-2 text-white"
+            />
+            <input
+              type="date"
+              value={exportTo}
+              onChange={(e) => setExportTo(e.target.value)}
+              className="flex-1 bg-gray-700 border border-gray-600 rounded px-3 py-2 text-white"
+            />
+          </div>
+          {accounts.length > 0 && (
+            <div className="space-y-1 mb-2">
+              {accounts.map((account) => (
+                <label key={account.id} className="flex items-center gap-2 text-sm text-gray-400">
+                  <input
+                    type="checkbox"
+                    checked={exportAccounts.includes(account.id)}
+                    onChange={() => toggleExportAccount(account.id)}
+                    className="w-4 h-4"
+                  />
+                  {account.name} ({account.provider})
+                </label>
+              ))}
+            </div>
+          )}
+          <button
+            onClick={handleExport}
+            disabled={isExporting || !exportFrom || !exportTo}
+            className="flex items-center gap-2 px-4 py-2 bg-gray-700 hover:bg-gray-600 disabled:bg-gray-600 rounded transition-colors"
+          >
+            <Download className="w-4 h-4" />
+            {isExporting ? "Exporting..." : "Export CSV"}
+          </button>
+          <p className="text-xs text-gray-400 mt-1">
+            Quota snapshots and per-model usage; leave all accounts unchecked to export everything
+          </p>
+        </div>
+
+        {/* Monthly Statement */}
+        <div>
+          <label className="block text-sm font-medium text-gray-300 mb-2">
+            Monthly Statement
+          </label>
+          <div className="flex gap-2">
+            <input
+              type="month"
+              value={reportPeriod}
+              onChange={(e) => setReportPeriod(e.target.value)}
+              className="flex-1 bg-gray-700 border border-gray-600 rounded px-3 py-2 text-white"
+            />
+            <select
+              value={reportFormat}
+              onChange={(e) => setReportFormat(e.target.value as "html" | "csv" | "json")}
+              className="bg-gray-700 border border-gray-600 rounded px-3 py-2 text-white"
+            >
+              <option value="html">HTML</option>
+              <option value="csv">CSV</option>
+              <option value="json">JSON</option>
+            </select>
+            <button
+              onClick={handleReport}
+              disabled={isExporting || !reportPeriod}
+              className="flex items-center gap-2 px-4 py-2 bg-gray-700 hover:bg-gray-600 disabled:bg-gray-600 rounded transition-colors"
+            >
+              <Download className="w-4 h-4" />
+              Save
+            </button>
+          </div>
+          <p className="text-xs text-gray-400 mt-1">
+            Cost by account, model and day for a UTC month, covering the accounts checked above
+          </p>
+        </div>
+
+        {/* Import */}
+        <div>
+          <label className="block text-sm font-medium text-gray-300 mb-2">
+            Import Usage History
+          </label>
+          <div className="flex gap-2">
+            <select
+              value={importAccount}
+              onChange={(e) => setImportAccount(e.target.value)}
+              className="flex-1 bg-gray-700 border border-gray-600 rounded px-3 py-2 text-white"
+            >
+              <option value="">Select account...</option>
+              {accounts.map((account) => (
+                <option key={account.id} value={account.id}>
+                  {account.name} ({account.provider})
+                </option>
+              ))}
+            </select>
+            <button
+              onClick={handleImport}
+              disabled={!importAccount}
+              className="flex items-center gap-2 px-4 py-2 bg-gray-700 hover:bg-gray-600 disabled:opacity-50 rounded transition-colors"
+            >
+              <Upload className="w-4 h-4" />
+              Import CSV/JSON
+            </button>
+          </div>
+          <p className="text-xs text-gray-400 mt-1">
+            Daily usage exported from the OpenAI or Anthropic consoles; days that already have data are skipped
+          </p>
+        </div>
+
+        {/* Backup & Restore */}
+        <div>
+          <label className="block text-sm font-medium text-gray-300 mb-2">
+            Backup & Restore
+          </label>
           <input
-            type="number"
-            value={dataRetention}
-            onChange={(e) => setDataRetention(e.target.value)}
-            min="7"
-            max="365"
-            className="w-full bg-gray-700 border border-gray-600 rounded px-3 py-2 text-white"
+            type="password"
+            value={backupPassphrase}
+            onChange={(e) => setBackupPassphrase(e.target.value)}
+            placeholder="Backup passphrase"
+            className="w-full bg-gray-700 border border-gray-600 rounded px-3 py-2 text-white mb-2"
+          />
+          <div className="flex gap-2">
+            <button
+              onClick={handleBackup}
+              disabled={isBackingUp || backupPassphrase.length < 8}
+              className="flex items-center gap-2 px-4 py-2 bg-gray-700 hover:bg-gray-600 disabled:bg-gray-600 rounded transition-colors"
+            >
+              <Archive className="w-4 h-4" />
+              Back Up
+            </button>
+            <button
+              onClick={handleRestore}
+              disabled={isBackingUp || !backupPassphrase}
+              className="flex items-center gap-2 px-4 py-2 bg-gray-700 hover:bg-gray-600 disabled:bg-gray-600 rounded transition-colors"
+            >
+              <RotateCcw className="w-4 h-4" />
+              Restore
+            </button>
+          </div>
+          <p className="text-xs text-gray-400 mt-1">
+            Credentials in the backup are encrypted with this passphrase (at least 8 characters)
+          </p>
+        </div>
+
+        {/* Move Accounts */}
+        <div>
+          <label className="block text-sm font-medium text-gray-300 mb-2">
+            Move Accounts
+          </label>
+          <input
+            type="password"
+            value={transferPassphrase}
+            onChange={(e) => setTransferPassphrase(e.target.value)}
+            placeholder="Transfer passphrase"
+            className="w-full bg-gray-700 border border-gray-600 rounded px-3 py-2 text-white mb-2"
           />
+          <div className="flex gap-2">
+            <button
+              onClick={handleExportCredentials}
+              disabled={transferPassphrase.length < 8}
+              className="flex items-center gap-2 px-4 py-2 bg-gray-700 hover:bg-gray-600 disabled:bg-gray-600 rounded transition-colors"
+            >
+              <Download className="w-4 h-4" />
+              Export Accounts
+            </button>
+            <button
+              onClick={handleImportCredentials}
+              disabled={!transferPassphrase}
+              className="flex items-center gap-2 px-4 py-2 bg-gray-700 hover:bg-gray-600 disabled:bg-gray-600 rounded transition-colors"
+            >
+              <Upload className="w-4 h-4" />
+              Import Accounts
+            </button>
+          </div>
           <p className="text-xs text-gray-400 mt-1">
-            Historical data older than this will be automatically deleted
+            Accounts and their keys, without history, encrypted with this passphrase for another machine
           </p>
         </div>
 
+        {/* Import Account List */}
+        <div>
+          <label className="block text-sm font-medium text-gray-300 mb-2">
+            Import Account List
+          </label>
+          <button
+            onClick={handleImportAccountList}
+            className="flex items-center gap-2 px-4 py-2 bg-gray-700 hover:bg-gray-600 rounded transition-colors"
+          >
+            <Upload className="w-4 h-4" />
+            Import JSON
+          </button>
+          <p className="text-xs text-gray-400 mt-1">
+            A plain JSON list of {"{"}provider, name, credentials{"}"} entries. Each is checked with its
+            provider, and nothing is added unless they all pass.
+          </p>
+        </div>
+
+        {/* Automatic Backups */}
+        <div>
+          <label className="block text-sm font-medium text-gray-300 mb-2">
+            Automatic Backups
+          </label>
+          <div className="flex items-center gap-2 text-sm text-gray-400 mb-2">
+            Every
+            <input
+              type="number"
+              value={settings?.backup_interval_hours ?? ""}
+              onChange={(e) => update({ backup_interval_hours: Number(e.target.value) })}
+              min="0"
+              max="720"
+              className="w-20 bg-gray-700 border border-gray-600 rounded px-2 py-1 text-white"
+            />
+            hours, keeping
+            <input
+              type="number"
+              value={settings?.backups_to_keep ?? ""}
+              onChange={(e) => update({ backups_to_keep: Number(e.target.value) })}
+              min="1"
+              max="100"
+              className="w-20 bg-gray-700 border border-gray-600 rounded px-2 py-1 text-white"
+            />
+            copies (0 hours disables)
+          </div>
+          {snapshots.length > 0 && (
+            <ul className="space-y-1 max-h-40 overflow-y-auto">
+              {snapshots.map((snapshot) => (
+                <li key={snapshot.name} className="flex items-center justify-between text-sm text-gray-400">
+                  <span>
+                    {new Date(snapshot.created_at * 1000).toLocaleString()} •{" "}
+                    {(snapshot.size_bytes / 1048576).toFixed(1)} MB
+                  </span>
+                  <button
+                    onClick={() => handleRestoreSnapshot(snapshot.name)}
+                    className="flex items-center gap-1 px-2 py-1 bg-gray-700 hover:bg-gray-600 rounded transition-colors"
+                  >
+                    <RotateCcw className="w-3 h-3" />
+                    Restore
+                  </button>
+                </li>
+              ))}
+            </ul>
+          )}
+        </div>
+
+        {/* Database Maintenance */}
+        <div>
+          <label className="block text-sm font-medium text-gray-300 mb-2">
+            Database Maintenance
+          </label>
+          <div className="flex items-center gap-4">
+            <button
+              onClick={handleMaintain}
+              disabled={isMaintaining}
+              className="px-4 py-2 bg-gray-700 hover:bg-gray-600 disabled:opacity-50 rounded transition-colors"
+            >
+              {isMaintaining ? "Running..." : "Check & Compact Now"}
+            </button>
+            <label className="flex items-center gap-2 text-sm text-gray-400">
+              <input
+                type="checkbox"
+                checked={settings?.weekly_maintenance ?? false}
+                onChange={(e) => update({ weekly_maintenance: e.target.checked })}
+                className="w-4 h-4"
+              />
+              Run weekly
+            </label>
+          </div>
+          {maintenance && (
+            <p className={`text-xs mt-1 ${maintenance.integrity_ok ? "text-gray-400" : "text-red-400"}`}>
+              {maintenance.integrity_ok
+                ? "Integrity check passed"
+                : `Integrity check found ${maintenance.integrity_errors.length} problems; compaction skipped`}
+              {" • "}
+              {(maintenance.size_before / 1048576).toFixed(1)} MB → {(maintenance.size_after / 1048576).toFixed(1)} MB
+            </p>
+          )}
+        </div>
+
+        {/* Master Password */}
+        {lockState && (
+          <div>
+            <label className="block text-sm font-medium text-gray-300 mb-2">
+              Master Password
+            </label>
+            <div className="flex gap-2">
+              {lockState.enabled && (
+                <input
+                  type="password"
+                  value={currentPassword}
+                  onChange={(e) => setCurrentPassword(e.target.value)}
+                  placeholder="Current password"
+                  className="flex-1 bg-gray-700 border border-gray-600 rounded px-3 py-2 text-white"
+                />
+              )}
+              <input
+                type="password"
+                value={newPassword}
+                onChange={(e) => setNewPassword(e.target.value)}
+                placeholder={lockState.enabled ? "New password" : "Master password"}
+                className="flex-1 bg-gray-700 border border-gray-600 rounded px-3 py-2 text-white"
+              />
+              <button
+                onClick={() => handleSetMasterPassword(false)}
+                disabled={newPassword.length < 8 || (lockState.enabled && !currentPassword)}
+                className="px-4 py-2 bg-gray-700 hover:bg-gray-600 disabled:opacity-50 rounded transition-colors"
+              >
+                {lockState.enabled ? "Change" : "Set"}
+              </button>
+              {lockState.enabled && (
+                <button
+                  onClick={() => handleSetMasterPassword(true)}
+                  disabled={!currentPassword}
+                  className="px-4 py-2 bg-gray-700 hover:bg-gray-600 disabled:opacity-50 rounded transition-colors"
+                >
+                  Remove
+                </button>
+              )}
+            </div>
+            <p className="text-xs text-gray-400 mt-1">
+              Credentials are locked on every start until the password is entered; it can't be recovered if forgotten
+            </p>
+          </div>
+        )}
+
+        {/* Key Storage */}
+        {keyStorage && (
+          <div>
+            <label className="flex items-center gap-2 text-sm font-medium text-gray-300">
+              <input
+                type="checkbox"
+                checked={keyStorage.keyring_only}
+                onChange={(e) => handleKeyringOnly(e.target.checked)}
+                className="w-4 h-4"
+              />
+              Keep the encryption key in the system keyring only
+            </label>
+            <p className="text-xs text-gray-400 mt-1">
+              {keyStorage.key_file
+                ? "A copy of the key is also stored as master.key in the data directory"
+                : "No key file is stored; Quonitor won't start without the system keyring"}
+            </p>
+          </div>
+        )}
+
+        {/* OS Authentication */}
+        <div>
+          <label className="flex items-center gap-2 text-sm font-medium text-gray-300">
+            <input
+              type="checkbox"
+              checked={settings?.require_os_auth ?? false}
+              onChange={(e) => update({ require_os_auth: e.target.checked })}
+              className="w-4 h-4"
+            />
+            Require system authentication for exports
+          </label>
+          <p className="text-xs text-gray-400 mt-1">
+            Windows Hello, Touch ID or your system password before backups and account exports; they're refused if no prompt is available
+          </p>
+        </div>
+
+        {/* Database Encryption */}
+        {encryption !== "unavailable" && (
+          <div>
+            <label className="block text-sm font-medium text-gray-300 mb-2">
+              Database Encryption
+            </label>
+            {encryption === "disabled" ? (
+              <button
+                onClick={handleEnableEncryption}
+                className="flex items-center gap-2 px-4 py-2 bg-gray-700 hover:bg-gray-600 rounded transition-colors"
+              >
+                <Lock className="w-4 h-4" />
+                Encrypt Database
+              </button>
+            ) : (
+              <p className="text-sm text-gray-400">
+                {encryption === "pending"
+                  ? "The database will be encrypted the next time Quonitor starts"
+                  : "Usage history and account data are encrypted at rest"}
+              </p>
+            )}
+          </div>
+        )}
+
         {/* Save Button */}
         <div className="flex gap-3 pt-4 border-t border-gray-700">
           <button
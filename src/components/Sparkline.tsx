@@ -0,0 +1,33 @@
+import { LineChart, Line, ResponsiveContainer } from "recharts";
+import { useSparkline } from "../hooks/useQuotaData";
+import type { UsageDelta } from "../types";
+
+interface SparklineProps {
+  accountId: string;
+}
+
+// Spend per fetch over the last few dozen fetches; tokens for accounts without cost data
+export default function Sparkline({ accountId }: SparklineProps) {
+  const { data: deltas = [] } = useSparkline(accountId);
+
+  if (deltas.length < 2) {
+    return null;
+  }
+
+  const hasCost = deltas.some((delta) => delta.cost_usd !== null);
+  const chartData = deltas.map((delta: UsageDelta) => ({
+    value: hasCost
+      ? delta.cost_usd || 0
+      : (delta.tokens_input || 0) + (delta.tokens_output || 0),
+  }));
+
+  return (
+    <div className="w-20 h-6" title={hasCost ? "Recent spend per fetch" : "Recent tokens per fetch"}>
+      <ResponsiveContainer width="100%" height="100%">
+        <LineChart data={chartData}>
+          <Line type="monotone" dataKey="value" stroke="#fff" strokeWidth={1.5} dot={false} isAnimationActive={false} />
+        </LineChart>
+      </ResponsiveContainer>
+    </div>
+  );
+}
@@ -0,0 +1,60 @@
+import { useState } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import { Lock } from "lucide-react";
+
+interface UnlockPanelProps {
+  onUnlocked: () => void;
+}
+
+export default function UnlockPanel({ onUnlocked }: UnlockPanelProps) {
+  const [passphrase, setPassphrase] = useState("");
+  const [error, setError] = useState<string | null>(null);
+  const [isUnlocking, setIsUnlocking] = useState(false);
+
+  const handleUnlock = async (e: React.FormEvent) => {
+    e.preventDefault();
+    setIsUnlocking(true);
+    setError(null);
+
+    try {
+      await invoke("unlock", { passphrase });
+      setPassphrase("");
+      onUnlocked();
+    } catch (error) {
+      setError(String(error));
+    } finally {
+      setIsUnlocking(false);
+    }
+  };
+
+  return (
+    <form
+      onSubmit={handleUnlock}
+      className="max-w-sm mx-auto mt-16 bg-gray-800 rounded-lg border border-gray-700 p-6 space-y-4"
+    >
+      <div className="flex items-center gap-2">
+        <Lock className="w-5 h-5" />
+        <h2 className="text-xl font-semibold">Locked</h2>
+      </div>
+      <p className="text-sm text-gray-400">
+        Enter your master password to decrypt account credentials. Quotas aren't refreshed while locked.
+      </p>
+      <input
+        type="password"
+        value={passphrase}
+        onChange={(e) => setPassphrase(e.target.value)}
+        placeholder="Master password"
+        autoFocus
+        className="w-full bg-gray-700 border border-gray-600 rounded px-3 py-2 text-white"
+      />
+      {error && <p className="text-sm text-red-400">{error}</p>}
+      <button
+        type="submit"
+        disabled={isUnlocking || !passphrase}
+        className="w-full px-4 py-2 bg-blue-600 hover:bg-blue-700 disabled:bg-gray-600 rounded transition-colors"
+      >
+        {isUnlocking ? "Unlocking..." : "Unlock"}
+      </button>
+    </form>
+  );
+}
@@ -1,16 +1,22 @@
 import { useQuery } from "@tanstack/react-query";
 import { invoke } from "@tauri-apps/api/core";
-import type { QuotaData, AccountResponse, QuotaSnapshot, ModelUsage } from "../types";
+import type { CachedQuota, QuotaSummary, AccountStatus, AccountResponse, QuotaSnapshot, ModelUsage, ApiKeyUsage, ProviderInfo, UsageSummary, BudgetStatus, TagSpend, AccountGroup, GroupSummary, NotificationRecord, FetchError, FetchBackoff, UsageDelta, HistoryPage, LockState, KeyStorage, NotificationRoute, AccountNotificationPrefs } from "../types";
 
 export function useQuotaData() {
   const {
     data: quotas = [],
     isLoading: quotasLoading,
     refetch: refetchQuotas,
-  } = useQuery<QuotaData[]>({
+  } = useQuery<CachedQuota[]>({
     queryKey: ["quotas"],
-    queryFn: () => invoke<QuotaData[]>("get_all_quotas"),
-    refetchInterval: 60000, // Refetch every minute
+    queryFn: () => invoke<CachedQuota[]>("get_all_quotas"),
+    // Kept current by quota-updated events; polled only so staleness gets marked
+    refetchInterval: 300000,
+  });
+
+  const { data: summary, refetch: refetchSummary } = useQuery<QuotaSummary>({
+    queryKey: ["quota-summary"],
+    queryFn: () => invoke<QuotaSummary>("get_quota_summary"),
   });
 
   const {
@@ -24,24 +30,159 @@ export function useQuotaData() {
 
   return {
     quotas,
+    summary,
     accounts,
     isLoading: quotasLoading || accountsLoading,
     refetch: () => {
       refetchQuotas();
+      refetchSummary();
       refetchAccounts();
     },
   };
 }
 
+// Charts don't need more points than they have pixels
+const CHART_MAX_POINTS = 500;
+
 export function useHistoricalData(accountId: string, days: number = 7) {
+  const page: HistoryPage = { max_points: CHART_MAX_POINTS };
   return useQuery<QuotaSnapshot[]>({
     queryKey: ["historical", accountId, days],
     queryFn: () =>
-      invoke<QuotaSnapshot[]>("get_historical_snapshots", { accountId, days }),
+      invoke<QuotaSnapshot[]>("get_historical_snapshots", { accountId, days, page }),
+    enabled: !!accountId,
+  });
+}
+
+export function useUsageDeltas(accountId: string, days: number = 7) {
+  return useQuery<UsageDelta[]>({
+    queryKey: ["usage-deltas", accountId, days],
+    queryFn: () => invoke<UsageDelta[]>("get_usage_deltas", { accountId, days }),
     enabled: !!accountId,
   });
 }
 
+export function useDashboardSummary(accountIds: string[] = [], days: number = 30) {
+  return useQuery<UsageSummary>({
+    queryKey: ["dashboard-summary", accountIds, days],
+    queryFn: () =>
+      invoke<UsageSummary>("get_dashboard_summary", { accountIds, days }),
+  });
+}
+
+export function useAccountErrors(accountId: string, limit: number = 20) {
+  return useQuery<FetchError[]>({
+    queryKey: ["account-errors", accountId, limit],
+    queryFn: () => invoke<FetchError[]>("get_account_errors", { accountId, limit }),
+    refetchInterval: 60000,
+  });
+}
+
+export function useAccountBackoff(accountId: string) {
+  return useQuery<FetchBackoff | null>({
+    queryKey: ["account-backoff", accountId],
+    queryFn: () => invoke<FetchBackoff | null>("get_account_backoff", { accountId }),
+    refetchInterval: 60000,
+  });
+}
+
+// Kept in memory by the backend; refreshed on quota-updated events
+export function useSparkline(accountId: string) {
+  return useQuery<UsageDelta[]>({
+    queryKey: ["sparkline", accountId],
+    queryFn: () => invoke<UsageDelta[]>("get_sparkline", { accountId }),
+  });
+}
+
+export function useAccountStatus(accountId: string) {
+  return useQuery<AccountStatus>({
+    queryKey: ["account-status", accountId],
+    queryFn: () => invoke<AccountStatus>("get_account_status", { accountId }),
+    refetchInterval: 60000,
+  });
+}
+
+export function useAccountSettings(accountId: string) {
+  return useQuery<Record<string, string>>({
+    queryKey: ["account-settings", accountId],
+    queryFn: () => invoke<Record<string, string>>("get_account_settings", { accountId }),
+  });
+}
+
+export function useAccountNotificationPrefs(accountId: string) {
+  return useQuery<AccountNotificationPrefs>({
+    queryKey: ["account-notification-prefs", accountId],
+    queryFn: () => invoke<AccountNotificationPrefs>("get_account_notification_prefs", { accountId }),
+  });
+}
+
+export function useNotificationHistory(unacknowledgedOnly: boolean = false) {
+  return useQuery<NotificationRecord[]>({
+    queryKey: ["notifications", unacknowledgedOnly],
+    queryFn: () => invoke<NotificationRecord[]>("get_notification_history", { unacknowledgedOnly }),
+    refetchInterval: 60000,
+  });
+}
+
+export function useLockState() {
+  return useQuery<LockState>({
+    queryKey: ["lock-state"],
+    queryFn: () => invoke<LockState>("get_lock_state"),
+  });
+}
+
+export function useKeyStorage() {
+  return useQuery<KeyStorage>({
+    queryKey: ["key-storage"],
+    queryFn: () => invoke<KeyStorage>("get_key_storage"),
+  });
+}
+
+export function useAccountTags() {
+  return useQuery<Record<string, string[]>>({
+    queryKey: ["account-tags"],
+    queryFn: () => invoke<Record<string, string[]>>("get_account_tags"),
+  });
+}
+
+export function useSpendByTag(days: number = 30) {
+  return useQuery<TagSpend[]>({
+    queryKey: ["spend-by-tag", days],
+    queryFn: () => invoke<TagSpend[]>("get_spend_by_tag", { days }),
+  });
+}
+
+export function useAccountGroups() {
+  return useQuery<AccountGroup[]>({
+    queryKey: ["account-groups"],
+    queryFn: () => invoke<AccountGroup[]>("get_account_groups"),
+  });
+}
+
+export function useGroupSummary(groupId: string) {
+  return useQuery<GroupSummary>({
+    queryKey: ["group-summary", groupId],
+    queryFn: () => invoke<GroupSummary>("get_group_summary", { groupId }),
+    enabled: !!groupId,
+    refetchInterval: 60000,
+  });
+}
+
+export function useNotificationRoutes() {
+  return useQuery<NotificationRoute[]>({
+    queryKey: ["notification-routes"],
+    queryFn: () => invoke<NotificationRoute[]>("get_notification_routes"),
+  });
+}
+
+export function useBudgets() {
+  return useQuery<BudgetStatus[]>({
+    queryKey: ["budgets"],
+    queryFn: () => invoke<BudgetStatus[]>("get_budgets"),
+    refetchInterval: 60000,
+  });
+}
+
 export function useModelUsageHistory(accountId: string, days: number = 7) {
   return useQuery<ModelUsage[]>({
     queryKey: ["model-usage", accountId, days],
@@ -50,3 +191,20 @@ export function useModelUsageHistory(accountId: string, days: number = 7) {
     enabled: !!accountId,
   });
 }
+
+export function useApiKeyUsageHistory(accountId: string, days: number = 7) {
+  return useQuery<ApiKeyUsage[]>({
+    queryKey: ["api-key-usage", accountId, days],
+    queryFn: () =>
+      invoke<ApiKeyUsage[]>("get_api_key_usage_history", { accountId, days }),
+    enabled: !!accountId,
+  });
+}
+
+export function useProviders() {
+  return useQuery<ProviderInfo[]>({
+    queryKey: ["providers"],
+    queryFn: () => invoke<ProviderInfo[]>("list_providers"),
+    staleTime: Infinity,
+  });
+}
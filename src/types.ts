@@ -4,6 +4,10 @@ export interface AccountResponse {
   name: string;
   created_at: number;
   last_synced: number | null;
+  needs_reauth: boolean;
+  // Favorites come first, then accounts by sort_order
+  sort_order: number;
+  favorite: boolean;
 }
 
 export interface QuotaData {
@@ -16,6 +20,61 @@ export interface QuotaData {
   quota_remaining: number | null;
   model_breakdown: ModelData[];
   metadata: string | null;
+  rpm_limit: number | null;
+  rpm_remaining: number | null;
+  tpm_limit: number | null;
+  tpm_remaining: number | null;
+  requests_limit: number | null;
+  requests_remaining: number | null;
+  requests_reset_at: number | null;
+  rpm_reset_at: number | null;
+  tpm_reset_at: number | null;
+  api_key_breakdown: ApiKeyData[];
+  images_generated: number | null;
+  audio_seconds: number | null;
+  video_seconds: number | null;
+}
+
+// A quota as cached by the backend. Restored data is the last stored snapshot, loaded at
+// launch before the account was fetched; it's always stale.
+export interface CachedQuota extends QuotaData {
+  fetched_at: number;
+  source: "live" | "restored";
+  // Older than two refresh intervals
+  is_stale: boolean;
+}
+
+// Result of test_credentials; nothing is stored either way
+export interface CredentialTest {
+  success: boolean;
+  error_kind: string | null;
+  http_status: number | null;
+  message: string | null;
+  quota: QuotaData | null;
+}
+
+// Changes for update_account; omitted parts are left as they are
+export interface AccountUpdate {
+  name?: string;
+  favorite?: boolean;
+  // Replace the account's tags and group memberships
+  tags?: string[];
+  group_ids?: string[];
+  settings?: Record<string, string>;
+}
+
+// Totals over every cached account, computed by the backend
+export interface QuotaSummary {
+  account_count: number;
+  total_cost_usd: number;
+  total_tokens_input: number;
+  total_tokens_output: number;
+  // The account closest to one of its limits; null when no account reports limits
+  worst_percentage: number | null;
+  worst_account_id: string | null;
+  // Accounts past their own warning (but not critical) and critical thresholds
+  warning_count: number;
+  critical_count: number;
 }
 
 export interface ModelData {
@@ -24,6 +83,11 @@ export interface ModelData {
   tokens_output: number;
   cost_usd: number;
   request_count: number;
+  tokens_cached_input: number;
+  tokens_reasoning: number;
+  images_generated: number | null;
+  audio_seconds: number | null;
+  video_seconds: number | null;
 }
 
 export interface QuotaSnapshot {
@@ -36,6 +100,18 @@ export interface QuotaSnapshot {
   quota_limit: number | null;
   quota_remaining: number | null;
   metadata: string | null;
+  rpm_limit: number | null;
+  rpm_remaining: number | null;
+  tpm_limit: number | null;
+  tpm_remaining: number | null;
+  requests_limit: number | null;
+  requests_remaining: number | null;
+  requests_reset_at: number | null;
+  rpm_reset_at: number | null;
+  tpm_reset_at: number | null;
+  images_generated: number | null;
+  audio_seconds: number | null;
+  video_seconds: number | null;
 }
 
 export interface ModelUsage {
@@ -47,10 +123,400 @@ export interface ModelUsage {
   tokens_output: number;
   cost_usd: number;
   request_count: number;
+  tokens_cached_input: number;
+  tokens_reasoning: number;
+  images_generated: number | null;
+  audio_seconds: number | null;
+  video_seconds: number | null;
+}
+
+export interface ApiKeyData {
+  api_key_id: string;
+  tokens_input: number;
+  tokens_output: number;
+  cost_usd: number;
+  request_count: number;
+}
+
+export interface ApiKeyUsage {
+  id: number | null;
+  account_id: string;
+  api_key_id: string;
+  timestamp: number;
+  tokens_input: number;
+  tokens_output: number;
+  cost_usd: number;
+  request_count: number;
 }
 
 export interface Credentials {
   api_key?: string;
   oauth_token?: string;
   oauth_refresh_token?: string;
+  admin_key?: string;
+  organization?: string;
+  service_account_json?: string;
+  billing_export_table?: string;
+  oauth_expires_at?: number;
+  oauth_client_id?: string;
+  oauth_client_secret?: string;
+}
+
+export interface PricingOverride {
+  provider: string;
+  model: string;
+  input_price: number;
+  output_price: number;
+  per_minute_price: number;
+}
+
+export type AuthMethod = "api_key" | "oauth" | "service_account";
+
+export interface ProviderInfo {
+  id: string;
+  name: string;
+  auth_methods: AuthMethod[];
+  reports_limits: boolean;
+  required_fields: (keyof Credentials)[];
+  optional_fields: (keyof Credentials)[];
+  // Further fields needed only with a given auth method
+  method_fields: Partial<Record<AuthMethod, (keyof Credentials)[]>>;
+}
+
+export type ServiceState = "operational" | "degraded" | "outage" | "unreachable" | "unknown";
+
+export interface ProviderStatus {
+  provider: string;
+  state: ServiceState;
+  description: string | null;
+  checked_at: number;
+}
+
+export interface DailyUsage {
+  day: string;
+  tokens_input: number;
+  tokens_output: number;
+  cost_usd: number;
+  account_count: number;
+}
+
+export interface UsageSummary {
+  since: number;
+  until: number;
+  days: number;
+  total_tokens_input: number;
+  total_tokens_output: number;
+  total_cost_usd: number;
+  avg_daily_tokens_input: number;
+  avg_daily_tokens_output: number;
+  avg_daily_cost_usd: number;
+  daily: DailyUsage[];
+  // Highest spend first
+  by_provider: ProviderSpend[];
+  top_models: ModelSpend[];
+  // Month to date (UTC) against the same days of the month before
+  month_to_date_cost_usd: number;
+  previous_month_cost_usd: number;
+  month_over_month_percent: number | null;
+}
+
+export interface ProviderSpend {
+  provider: string;
+  account_count: number;
+  cost_usd: number;
+}
+
+export interface ModelSpend {
+  model_name: string;
+  cost_usd: number;
+}
+
+export type EncryptionState = "unavailable" | "disabled" | "pending" | "enabled";
+
+export type BudgetScope = "global" | "account" | "provider" | "model";
+export type BudgetPeriod = "daily" | "weekly" | "monthly";
+
+export interface Budget {
+  id: string;
+  name: string;
+  scope: BudgetScope;
+  target: string | null;
+  amount_usd: number;
+  period: BudgetPeriod;
+  reset_anchor: number;
+  created_at: number;
+}
+
+export interface BudgetStatus extends Budget {
+  period_start: number;
+  period_end: number;
+  spent_usd: number;
+  percent_used: number;
+  // At the run rate so far; null early in the period
+  projected_usd: number | null;
+}
+
+export interface TagSpend {
+  tag: string;
+  account_count: number;
+  tokens_input: number;
+  tokens_output: number;
+  cost_usd: number;
+}
+
+export interface AccountGroup {
+  id: string;
+  name: string;
+  account_ids: string[];
+  quota_threshold_percent: number | null;
+  cost_threshold_usd: number | null;
+  last_notified_at: number | null;
+  created_at: number;
+}
+
+export type NotificationUrgency = "low" | "normal" | "critical";
+
+export type NotificationChannel = "desktop" | "webhook" | "email" | "push";
+
+export interface AccountNotificationPrefs {
+  account_id: string;
+  enabled: boolean;
+  warning_percent: number | null;
+  caution_percent: number | null;
+  critical_percent: number | null;
+  // Replaces the routing rules when set
+  channels: NotificationChannel[] | null;
+  // Unix seconds; alerts resume by themselves afterwards
+  snoozed_until: number | null;
+  // Replaces the global burn rate alert; 0 turns it off for the account
+  burn_rate_usd_per_hour: number | null;
+}
+
+export interface NotificationRoute {
+  id: string;
+  name: string;
+  min_threshold: number | null;
+  max_threshold: number | null;
+  account_id: string | null;
+  account_tag: string | null;
+  channels: NotificationChannel[];
+  created_at: number;
+}
+
+export interface GroupSummary {
+  group_id: string;
+  account_count: number;
+  tokens_input: number;
+  tokens_output: number;
+  cost_usd: number;
+  quota_limit: number | null;
+  quota_remaining: number | null;
+  percent_used: number | null;
+}
+
+export interface NotificationRecord {
+  id: number;
+  account_id: string | null;
+  threshold: number | null;
+  title: string;
+  message: string;
+  timestamp: number;
+  acknowledged: boolean;
+}
+
+export interface FetchError {
+  id: number | null;
+  account_id: string;
+  provider: string;
+  error_kind: string;
+  http_status: number | null;
+  message: string;
+  timestamp: number;
+}
+
+export interface FetchBackoff {
+  account_id: string;
+  consecutive_failures: number;
+  next_attempt_at: number;
+  tripped_at: number | null;
+}
+
+// How the account's fetches have gone this run
+export interface AccountStatus {
+  account_id: string;
+  last_error: string | null;
+  error_kind: string | null;
+  last_error_at: number | null;
+  // Consecutive failures; 0 once a fetch succeeds
+  failure_count: number;
+  last_success_at: number | null;
+}
+
+export interface ConnectivityStatus {
+  online: boolean;
+  since: number;
+}
+
+// Per-provider values for the settings of the same names; null uses the global one
+export interface ProviderOverrides {
+  connect_timeout_seconds: number | null;
+  read_timeout_seconds: number | null;
+  rate_limit_per_minute: number | null;
+}
+
+export interface Settings {
+  refresh_interval_seconds: number;
+  refresh_schedule: string | null;
+  startup_delay_seconds: number;
+  lazy_startup: boolean;
+  blackout_windows: string | null;
+  pause_when_offline: boolean;
+  slow_on_battery: boolean;
+  slow_on_metered: boolean;
+  slow_polling_factor: number;
+  adaptive_polling: boolean;
+  adaptive_min_interval_seconds: number;
+  adaptive_max_interval_seconds: number;
+  notifications_enabled: boolean;
+  threshold_75_enabled: boolean;
+  threshold_90_enabled: boolean;
+  threshold_95_enabled: boolean;
+  threshold_75_urgency: NotificationUrgency;
+  threshold_90_urgency: NotificationUrgency;
+  threshold_95_urgency: NotificationUrgency;
+  // 0 keeps the notification until dismissed
+  threshold_75_timeout_seconds: number;
+  threshold_90_timeout_seconds: number;
+  threshold_95_timeout_seconds: number;
+  threshold_75_sound: string | null;
+  threshold_90_sound: string | null;
+  threshold_95_sound: string | null;
+  critical_bypasses_quiet_hours: boolean;
+  anomaly_detection: boolean;
+  anomaly_multiple: number;
+  anomaly_min_usd: number;
+  forecast_alerts: boolean;
+  stale_alert_hours: number;
+  burn_rate_usd_per_hour: number;
+  quiet_hours_start: string | null;
+  quiet_hours_end: string | null;
+  // e.g. "mon-fri 22:00-07:00; sat off"
+  quiet_hours_weekdays: string | null;
+  quiet_hours_timezone: string | null;
+  webhook_urls: string | null;
+  smtp_host: string | null;
+  smtp_port: number;
+  smtp_implicit_tls: boolean;
+  smtp_username: string | null;
+  smtp_from: string | null;
+  email_to: string | null;
+  email_min_threshold: number;
+  push_targets: string | null;
+  digest_daily: boolean;
+  digest_weekly: boolean;
+  digest_time: string;
+  retention_snapshot_days: number;
+  retention_model_usage_days: number;
+  retention_rollup_days: number;
+  retention_notification_days: number;
+  retry_max_attempts: number;
+  rate_limit_per_minute: number;
+  breaker_failure_threshold: number;
+  breaker_cooldown_minutes: number;
+  connect_timeout_seconds: number;
+  read_timeout_seconds: number;
+  // Keyed by provider id
+  provider_overrides: Record<string, ProviderOverrides>;
+  pricing_url: string | null;
+  backup_interval_hours: number;
+  backups_to_keep: number;
+  weekly_maintenance: boolean;
+  require_os_auth: boolean;
+}
+
+export interface MaintenanceReport {
+  ran_at: number;
+  integrity_ok: boolean;
+  integrity_errors: string[];
+  size_before: number;
+  size_after: number;
+  duration_ms: number;
+}
+
+export interface SnapshotInfo {
+  name: string;
+  created_at: number;
+  size_bytes: number;
+}
+
+export interface Profile {
+  id: string;
+  name: string;
+  created_at: number;
+}
+
+export interface ProfileList {
+  active: string;
+  profiles: Profile[];
+}
+
+export type LocationSource = "default" | "custom" | "flag" | "portable";
+
+export interface DataLocation {
+  path: string;
+  source: LocationSource;
+  pending: string | null;
+}
+
+// Paging and downsampling for the history commands
+export interface HistoryPage {
+  limit?: number;
+  offset?: number;
+  max_points?: number;
+}
+
+export interface CredentialImport {
+  imported: number;
+  skipped: number;
+}
+
+export interface AccountImportResult {
+  index: number;
+  provider: string;
+  name: string;
+  // null when the entry checked out
+  error: string | null;
+}
+
+// Nothing is imported unless every entry checks out
+export interface AccountImport {
+  imported: number;
+  results: AccountImportResult[];
+}
+
+export interface KeyStorage {
+  keyring_only: boolean;
+  key_file: boolean;
+  chosen: boolean;
+}
+
+export interface LockState {
+  enabled: boolean;
+  locked: boolean;
+}
+
+export interface UsageDelta {
+  account_id: string;
+  timestamp: number;
+  interval_seconds: number;
+  tokens_input: number | null;
+  tokens_output: number | null;
+  cost_usd: number | null;
+}
+
+export interface ImportSummary {
+  rows_read: number;
+  rows_skipped: number;
+  days_imported: number;
+  days_skipped: number;
 }